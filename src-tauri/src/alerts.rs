@@ -0,0 +1,208 @@
+use crate::db::AlertRule;
+use crate::metrics::Metric;
+use crate::notifications::Category;
+use crate::ssh::ServerStatus;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Comparison between a reading and a rule's threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+impl Operator {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            ">" => Ok(Operator::Above),
+            ">=" => Ok(Operator::AtLeast),
+            "<" => Ok(Operator::Below),
+            "<=" => Ok(Operator::AtMost),
+            other => Err(format!("Unknown alert operator: {}", other)),
+        }
+    }
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Operator::Above => value > threshold,
+            Operator::AtLeast => value >= threshold,
+            Operator::Below => value < threshold,
+            Operator::AtMost => value <= threshold,
+        }
+    }
+}
+
+/// Payload of the `ssh-alert-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEvent {
+    pub session_id: String,
+    /// Id in the alert history; `None` when it could not be recorded
+    pub alert_id: Option<i64>,
+    pub rule_id: String,
+    pub name: String,
+    pub metric: String,
+    pub operator: String,
+    pub threshold: f64,
+    pub value: f64,
+    /// "triggered" or "resolved"
+    pub state: String,
+}
+
+struct WatchedRule {
+    rule: AlertRule,
+    metric: Metric,
+    operator: Operator,
+    duration: Duration,
+    /// When the condition started to hold without interruption
+    breached_since: Option<Instant>,
+    /// Set while the alert is firing; holds its history id once recorded
+    firing: Option<Option<i64>>,
+}
+
+/// Evaluates a session's alert rules against its status readings.
+///
+/// A rule fires once its condition has held for its whole duration and is
+/// re-armed when the condition no longer holds, which resolves the alert.
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<WatchedRule>,
+}
+
+impl AlertEngine {
+    /// Builds an engine from stored rules. Disabled rules and rules with an
+    /// unknown metric or operator are skipped.
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter(|r| r.enabled)
+            .filter_map(|rule| {
+                Some(WatchedRule {
+                    metric: Metric::parse(&rule.metric).ok()?,
+                    operator: Operator::parse(&rule.operator).ok()?,
+                    duration: Duration::from_millis(rule.duration_ms.max(0) as u64),
+                    breached_since: None,
+                    firing: None,
+                    rule,
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Replaces the rules; rules that still exist keep their state so a
+    /// reload neither re-fires nor forgets a firing alert
+    pub fn replace_rules(&mut self, rules: Vec<AlertRule>) {
+        let mut fresh = Self::new(rules);
+        for watched in &mut fresh.rules {
+            if let Some(old) = self.rules.iter().find(|o| o.rule.id == watched.rule.id) {
+                watched.breached_since = old.breached_since;
+                watched.firing = old.firing;
+            }
+        }
+        *self = fresh;
+    }
+
+    /// Compares a reading against every rule, records fired and resolved
+    /// alerts in the history and returns the events to emit. Blocking.
+    pub fn check(
+        &mut self,
+        app: &AppHandle,
+        session_id: &str,
+        saved_session_id: Option<&str>,
+        host: &str,
+        status: &ServerStatus,
+    ) -> Vec<AlertEvent> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for watched in &mut self.rules {
+            let value = watched.metric.value(status);
+            let (state, alert_id) = if watched.operator.holds(value, watched.rule.threshold) {
+                let since = *watched.breached_since.get_or_insert(now);
+                if watched.firing.is_some() || now.duration_since(since) < watched.duration {
+                    continue;
+                }
+                let alert_id = crate::db::record_alert(
+                    &watched.rule,
+                    saved_session_id,
+                    host,
+                    value,
+                    now_millis(),
+                )
+                .map_err(|e| tracing::warn!("{}", e))
+                .ok();
+                watched.firing = Some(alert_id);
+                crate::notifications::notify(
+                    app,
+                    Category::Thresholds,
+                    alert_title(&watched.rule),
+                    format!(
+                        "{}: {} {} {} (now {:.1})",
+                        host,
+                        watched.rule.metric,
+                        watched.rule.operator,
+                        watched.rule.threshold,
+                        value
+                    ),
+                );
+                ("triggered", alert_id)
+            } else {
+                watched.breached_since = None;
+                let Some(alert_id) = watched.firing.take() else {
+                    continue;
+                };
+                if let Some(id) = alert_id {
+                    if let Err(e) = crate::db::resolve_alert(id, now_millis()) {
+                        tracing::warn!("{}", e);
+                    }
+                }
+                ("resolved", alert_id)
+            };
+            events.push(AlertEvent {
+                session_id: session_id.to_string(),
+                alert_id,
+                rule_id: watched.rule.id.clone(),
+                name: watched.rule.name.clone(),
+                metric: watched.rule.metric.clone(),
+                operator: watched.rule.operator.clone(),
+                threshold: watched.rule.threshold,
+                value,
+                state: state.to_string(),
+            });
+        }
+        events
+    }
+
+    /// Resolves the firing alerts in the history once the session is gone
+    /// and their conditions can no longer be watched
+    pub fn resolve_all(&mut self) {
+        for watched in &mut self.rules {
+            watched.breached_since = None;
+            if let Some(Some(id)) = watched.firing.take() {
+                if let Err(e) = crate::db::resolve_alert(id, now_millis()) {
+                    tracing::warn!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+fn alert_title(rule: &AlertRule) -> String {
+    if rule.name.is_empty() {
+        format!("Alert: {}", rule.metric)
+    } else {
+        rule.name.clone()
+    }
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
@@ -0,0 +1,340 @@
+use crate::ssh::{shell_quote, SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Time allowed for counting the entries an operation will process
+const COUNT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Minimum pause between two `archive-progress` events of a task
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Cancellation flags of running archive operations by task id
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `archive-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveProgress {
+    pub task_id: String,
+    pub session_id: String,
+    /// "compress" or "extract"
+    pub operation: String,
+    /// Entries processed so far
+    pub processed: u64,
+    /// Entries in total; 0 when they could not be counted
+    pub total: u64,
+    /// Entry being processed
+    pub current: Option<String>,
+    /// "running", "success", "error" or "cancelled"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    /// tar with its compression flag ("", "z", "j" or "J")
+    Tar(&'static str),
+    Zip,
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Self, SshError> {
+        match name.to_lowercase().as_str() {
+            "tar" => Ok(Format::Tar("")),
+            "tar.gz" | "tgz" => Ok(Format::Tar("z")),
+            "tar.bz2" | "tbz2" => Ok(Format::Tar("j")),
+            "tar.xz" | "txz" => Ok(Format::Tar("J")),
+            "zip" => Ok(Format::Zip),
+            _ => Err(SshError::OperationFailed(format!(
+                "Unsupported archive format: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Format of an archive, from its file name
+    fn of_archive(path: &str) -> Result<Self, SshError> {
+        let lower = path.to_lowercase();
+        [
+            "tar.gz", "tgz", "tar.bz2", "tbz2", "tar.xz", "txz", "tar", "zip",
+        ]
+        .into_iter()
+        .find(|ext| lower.ends_with(&format!(".{}", ext)))
+        .map(Format::parse)
+        .unwrap_or_else(|| {
+            Err(SshError::OperationFailed(format!(
+                "Unknown archive type: {}",
+                path
+            )))
+        })
+    }
+
+    fn tool(self) -> &'static str {
+        match self {
+            Format::Tar(_) => "tar",
+            Format::Zip => "zip",
+        }
+    }
+
+    /// Entry named by a line of verbose output; `None` for messages
+    fn entry(self, line: &str) -> Option<String> {
+        match self {
+            Format::Tar(_) if line.is_empty() || line.starts_with("tar: ") => None,
+            Format::Tar(_) => Some(line.to_string()),
+            Format::Zip => {
+                let line = line.trim_start();
+                [
+                    "adding:",
+                    "updating:",
+                    "inflating:",
+                    "extracting:",
+                    "creating:",
+                ]
+                .into_iter()
+                .find_map(|prefix| line.strip_prefix(prefix))
+                .map(|rest| {
+                    // zip appends the compression ratio
+                    let rest = rest.trim();
+                    rest.rsplit_once(" (")
+                        .map_or(rest, |(name, _)| name)
+                        .to_string()
+                })
+            }
+        }
+    }
+}
+
+/// Runs an archive command, reporting each processed entry. `count` prints
+/// the number of entries to expect. Blocking; meant for a worker thread.
+#[allow(clippy::too_many_arguments)]
+fn run_archive(
+    app_handle: &AppHandle,
+    handle: &SessionHandle,
+    session_id: &str,
+    task_id: &str,
+    operation: &str,
+    format: Format,
+    count: &str,
+    command: &str,
+) -> Result<u64, SshError> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(task_id.to_string(), cancel.clone());
+
+    let mut progress = ArchiveProgress {
+        task_id: task_id.to_string(),
+        session_id: session_id.to_string(),
+        operation: operation.to_string(),
+        processed: 0,
+        total: handle
+            .exec_interruptible(count, COUNT_TIMEOUT, &cancel)
+            .ok()
+            .and_then(|o| o.stdout.trim().parse().ok())
+            .unwrap_or(0),
+        current: None,
+        status: "running".to_string(),
+        error: None,
+    };
+    let _ = app_handle.emit("archive-progress", &progress);
+
+    let mut pending = Vec::new();
+    let mut last_message = None;
+    let mut last_emit = Instant::now();
+    let result = handle.exec_streaming(command, None, &cancel, |chunk| {
+        pending.extend_from_slice(chunk);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim_end().to_string();
+            match format.entry(&line) {
+                Some(entry) => {
+                    progress.processed += 1;
+                    progress.current = Some(entry);
+                }
+                None if !line.trim().is_empty() => last_message = Some(line),
+                None => {}
+            }
+        }
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            last_emit = Instant::now();
+            let _ = app_handle.emit("archive-progress", &progress);
+        }
+    });
+
+    if let Ok(mut running) = RUNNING.lock() {
+        running.remove(task_id);
+    }
+
+    let result = match result {
+        Ok(output) if output.exit_status == Some(0) => Ok(progress.processed),
+        Ok(output) if output.exit_status == Some(127) => Err(SshError::OperationFailed(format!(
+            "{} is not installed on the server",
+            if operation == "extract" && format.tool() == "zip" {
+                "unzip"
+            } else {
+                format.tool()
+            }
+        ))),
+        Ok(output) => Err(SshError::OperationFailed(
+            last_message.unwrap_or_else(|| output.error_message(format.tool())),
+        )),
+        Err(e) => Err(e),
+    };
+    (progress.status, progress.error) = match &result {
+        Ok(_) => ("success".to_string(), None),
+        Err(_) if cancel.load(Ordering::SeqCst) => ("cancelled".to_string(), None),
+        Err(e) => ("error".to_string(), Some(e.to_string())),
+    };
+    progress.current = None;
+    let _ = app_handle.emit("archive-progress", &progress);
+    result
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Packs remote paths into an archive on the server with tar or zip.
+/// `format` is "tar", "tar.gz", "tar.bz2", "tar.xz" or "zip"; the paths
+/// must share a parent directory and are stored relative to it. Progress
+/// is reported through `archive-progress` events. Returns the number of
+/// entries written.
+#[tauri::command]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub async fn compress_remote(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    taskId: String,
+    paths: Vec<String>,
+    archivePath: String,
+    format: String,
+) -> Result<u64, SshError> {
+    let format = Format::parse(&format)?;
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+
+    let mut parent = None;
+    let mut names = Vec::new();
+    for path in &paths {
+        let (dir, name) = match path.trim_end_matches('/').rsplit_once('/') {
+            Some(("", name)) => ("/", name),
+            Some((dir, name)) => (dir, name),
+            None => (".", path.as_str()),
+        };
+        if name.is_empty() {
+            return Err(SshError::OperationFailed(format!(
+                "Cannot archive {}",
+                path
+            )));
+        }
+        if parent.is_some_and(|p| p != dir) {
+            return Err(SshError::OperationFailed(
+                "All paths must be in the same directory".to_string(),
+            ));
+        }
+        parent = Some(dir);
+        names.push(shell_quote(name));
+    }
+    let Some(parent) = parent else {
+        return Err(SshError::OperationFailed("No paths selected".to_string()));
+    };
+    let names = names.join(" ");
+    let parent = shell_quote(parent);
+    let archive = shell_quote(&archivePath);
+
+    let count = format!("cd {} && find {} 2>/dev/null | wc -l", parent, names);
+    let command = match format {
+        Format::Tar(flag) => format!(
+            "cd {} && tar -c{}vf {} -- {} 2>&1",
+            parent, flag, archive, names
+        ),
+        Format::Zip => format!("cd {} && zip -r {} {} 2>&1", parent, archive, names),
+    };
+    tokio::task::spawn_blocking(move || {
+        run_archive(
+            &app_handle,
+            &handle,
+            &sessionId,
+            &taskId,
+            "compress",
+            format,
+            &count,
+            &command,
+        )
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Unpacks a tar or zip archive on the server into `destination`, creating
+/// it if needed. The format follows the archive's extension. Progress is
+/// reported through `archive-progress` events. Returns the number of
+/// entries extracted.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn extract_remote(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    taskId: String,
+    archivePath: String,
+    destination: String,
+) -> Result<u64, SshError> {
+    let format = Format::of_archive(&archivePath)?;
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let archive = shell_quote(&archivePath);
+    let destination = shell_quote(&destination);
+
+    let (count, command) = match format {
+        Format::Tar(flag) => (
+            format!("tar -t{}f {} 2>/dev/null | wc -l", flag, archive),
+            format!(
+                "mkdir -p {} && tar -x{}vf {} -C {} 2>&1",
+                destination, flag, archive, destination
+            ),
+        ),
+        Format::Zip => (
+            format!("unzip -Z1 {} 2>/dev/null | wc -l", archive),
+            format!(
+                "mkdir -p {} && unzip -o {} -d {} 2>&1",
+                destination, archive, destination
+            ),
+        ),
+    };
+    tokio::task::spawn_blocking(move || {
+        run_archive(
+            &app_handle,
+            &handle,
+            &sessionId,
+            &taskId,
+            "extract",
+            format,
+            &count,
+            &command,
+        )
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Cancels a running `compress_remote` or `extract_remote`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_remote_archive(taskId: String) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(cancel) = running.get(&taskId) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// Most stderr kept from the Session Manager plugin for error messages
+const MAX_STDERR_BYTES: usize = 8 * 1024;
+
+/// Reaching an EC2 instance through AWS instead of a direct TCP connection.
+/// Both modes use the local AWS CLI and its credentials.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AwsConnectOptions {
+    /// `i-...` id of the instance
+    pub instance_id: String,
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    /// Tunnel the SSH connection through Session Manager
+    /// (`AWS-StartSSHSession`), so no inbound port 22 is needed
+    pub session_manager: bool,
+    /// Authenticate with a one-time key pushed through EC2 Instance Connect
+    /// instead of the session's credentials
+    pub instance_connect: bool,
+}
+
+impl AwsConnectOptions {
+    fn cli_args(&self, args: &[&str]) -> Vec<String> {
+        let mut all: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        if let Some(profile) = self.profile.as_deref().filter(|p| !p.is_empty()) {
+            all.extend(["--profile".to_string(), profile.to_string()]);
+        }
+        if let Some(region) = self.region.as_deref().filter(|r| !r.is_empty()) {
+            all.extend(["--region".to_string(), region.to_string()]);
+        }
+        all
+    }
+}
+
+fn spawn_error(program: &str, e: std::io::Error) -> String {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        format!("{} is not installed or not on PATH", program)
+    } else {
+        format!("Failed to run {}: {}", program, e)
+    }
+}
+
+/// A Session Manager session carrying an SSH connection. `stream` is the
+/// local end handed to libssh2; the AWS CLI is relayed to it over loopback
+/// because libssh2 needs a real socket.
+pub struct SessionManagerTunnel {
+    pub stream: TcpStream,
+    pub local_addr: SocketAddr,
+    pub stderr: CliStderr,
+}
+
+/// Stderr collected from a running AWS CLI
+#[derive(Clone, Default)]
+pub struct CliStderr(Arc<Mutex<String>>);
+
+impl CliStderr {
+    /// Last non-empty line, to explain a failed handshake
+    pub fn last_line(&self) -> Option<String> {
+        let stderr = self.0.lock().ok()?;
+        stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| l.trim().to_string())
+    }
+}
+
+/// Starts `aws ssm start-session` to the instance's SSH port and relays
+/// it to a loopback socket. The CLI is killed when the SSH session closes
+/// its end, and the socket is closed when the CLI exits.
+pub fn open_session_manager(
+    options: &AwsConnectOptions,
+    port: u16,
+) -> Result<SessionManagerTunnel, String> {
+    if options.instance_id.is_empty() {
+        return Err("No instance id configured".to_string());
+    }
+    let args = options.cli_args(&[
+        "ssm",
+        "start-session",
+        "--target",
+        &options.instance_id,
+        "--document-name",
+        "AWS-StartSSHSession",
+        "--parameters",
+        &format!("portNumber={}", port),
+    ]);
+    let mut child = Command::new("aws")
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_error("aws", e))?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let stream = TcpStream::connect(local_addr).map_err(|e| e.to_string())?;
+    let (relay, _) = listener.accept().map_err(|e| e.to_string())?;
+
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr_pipe = child.stderr.take();
+    let stderr = CliStderr::default();
+
+    if let Some(mut pipe) = stderr_pipe {
+        let stderr = Arc::clone(&stderr.0);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 1024];
+            while let Ok(n) = pipe.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                if let Ok(mut stderr) = stderr.lock() {
+                    if stderr.len() < MAX_STDERR_BYTES {
+                        stderr.push_str(&String::from_utf8_lossy(&buf[..n]));
+                    }
+                }
+            }
+        });
+    }
+    if let (Some(mut stdout), Ok(mut to_ssh)) = (stdout, relay.try_clone()) {
+        std::thread::spawn(move || {
+            let _ = std::io::copy(&mut stdout, &mut to_ssh);
+            let _ = to_ssh.shutdown(Shutdown::Both);
+        });
+    }
+    if let Some(stdin) = stdin {
+        std::thread::spawn(move || relay_to_cli(relay, stdin, child));
+    }
+
+    Ok(SessionManagerTunnel {
+        stream,
+        local_addr,
+        stderr,
+    })
+}
+
+fn relay_to_cli(mut from_ssh: TcpStream, mut stdin: std::process::ChildStdin, mut child: Child) {
+    let mut buf = [0u8; 16 * 1024];
+    loop {
+        match from_ssh.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if stdin
+                    .write_all(&buf[..n])
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// A one-time key pair registered with EC2 Instance Connect. The files are
+/// deleted on drop; the instance forgets the key after 60 seconds.
+pub struct EphemeralKey {
+    dir: PathBuf,
+}
+
+impl EphemeralKey {
+    pub fn private_key_path(&self) -> PathBuf {
+        self.dir.join("id_ed25519")
+    }
+}
+
+impl Drop for EphemeralKey {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn run(program: &str, args: &[String]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| spawn_error(program, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(format!("{} failed: {}", program, stderr.trim()))
+}
+
+/// Generates a key pair and pushes its public half to the instance for
+/// `username` with `aws ec2-instance-connect send-ssh-public-key`.
+/// Authenticate with it right away.
+pub fn push_ephemeral_key(
+    options: &AwsConnectOptions,
+    username: &str,
+) -> Result<EphemeralKey, String> {
+    if options.instance_id.is_empty() {
+        return Err("No instance id configured".to_string());
+    }
+    let key = EphemeralKey {
+        dir: std::env::temp_dir().join(format!("nexashell-eic-{}", uuid::Uuid::new_v4())),
+    };
+    std::fs::create_dir_all(&key.dir).map_err(|e| e.to_string())?;
+    let private_key = key.private_key_path();
+    let path = |p: &Path| p.to_string_lossy().into_owned();
+    run(
+        "ssh-keygen",
+        &[
+            "-q".to_string(),
+            "-t".to_string(),
+            "ed25519".to_string(),
+            "-N".to_string(),
+            String::new(),
+            "-C".to_string(),
+            "nexashell-instance-connect".to_string(),
+            "-f".to_string(),
+            path(&private_key),
+        ],
+    )?;
+    let public_key = std::fs::read_to_string(private_key.with_extension("pub"))
+        .map_err(|e| format!("Failed to read generated key: {}", e))?;
+    run(
+        "aws",
+        &options.cli_args(&[
+            "ec2-instance-connect",
+            "send-ssh-public-key",
+            "--instance-id",
+            &options.instance_id,
+            "--instance-os-user",
+            username,
+            "--ssh-public-key",
+            public_key.trim(),
+        ]),
+    )?;
+    Ok(key)
+}
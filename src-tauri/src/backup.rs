@@ -0,0 +1,156 @@
+use crate::ssh::ExecOutput;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Emitter};
+
+/// A stored snapshot of a task's command output
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSnapshot {
+    /// Snapshot id (the capture time in Unix milliseconds)
+    pub id: String,
+    pub taken_at: i64,
+    pub size: u64,
+}
+
+/// Payload of the `backup-snapshot` event, sent after each run of a
+/// scheduled "backup" task
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRunResult {
+    pub task_id: String,
+    pub session_id: String,
+    /// New snapshot id; `None` when the output did not change or the run failed
+    pub snapshot_id: Option<String>,
+    pub changed: bool,
+    pub error: Option<String>,
+}
+
+/// Directory holding a task's snapshots
+fn task_dir(task_id: &str) -> Result<PathBuf, String> {
+    if task_id.is_empty()
+        || !task_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid task id: {}", task_id));
+    }
+    Ok(crate::db::app_data_dir()?.join("backups").join(task_id))
+}
+
+fn snapshot_path(task_id: &str, snapshot_id: &str) -> Result<PathBuf, String> {
+    if snapshot_id.is_empty() || !snapshot_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid snapshot id: {}", snapshot_id));
+    }
+    Ok(task_dir(task_id)?.join(format!("{}.txt", snapshot_id)))
+}
+
+/// Lists a task's snapshots, newest first
+fn snapshots(task_id: &str) -> Result<Vec<BackupSnapshot>, String> {
+    let dir = task_dir(task_id)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut list = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let Some(stem) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|_| path.extension().is_some_and(|e| e == "txt"))
+        else {
+            continue;
+        };
+        let Ok(taken_at) = stem.parse::<i64>() else {
+            continue;
+        };
+        list.push(BackupSnapshot {
+            id: stem.to_string(),
+            taken_at,
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+    list.sort_by_key(|s| std::cmp::Reverse(s.taken_at));
+    Ok(list)
+}
+
+/// Stores `output` as a new snapshot of a task unless it equals the latest
+/// one; returns the new snapshot id
+fn store_snapshot(task_id: &str, output: &str, taken_at: i64) -> Result<Option<String>, String> {
+    let latest = snapshots(task_id)?.into_iter().next();
+    if let Some(latest) = latest {
+        let previous =
+            std::fs::read_to_string(snapshot_path(task_id, &latest.id)?).unwrap_or_default();
+        if previous == output {
+            return Ok(None);
+        }
+    }
+
+    let dir = task_dir(task_id)?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let snapshot_id = taken_at.to_string();
+    std::fs::write(snapshot_path(task_id, &snapshot_id)?, output).map_err(|e| e.to_string())?;
+    Ok(Some(snapshot_id))
+}
+
+/// Records the run of a scheduled "backup" task on a session: stores the
+/// command's output as a snapshot when it changed and sends a
+/// `backup-snapshot` event. Returns the new snapshot id. Blocking.
+pub fn record_run(
+    app: &AppHandle,
+    task_id: &str,
+    session_id: &str,
+    ran_at: i64,
+    output: Result<ExecOutput, String>,
+) -> Result<Option<String>, String> {
+    let stored = output.and_then(|output| {
+        if output.exit_status != Some(0) {
+            return Err(output.error_message("Command"));
+        }
+        store_snapshot(task_id, &output.stdout, ran_at)
+    });
+    let snapshot_id = stored.as_ref().ok().cloned().flatten();
+    let _ = app.emit(
+        "backup-snapshot",
+        &BackupRunResult {
+            task_id: task_id.to_string(),
+            session_id: session_id.to_string(),
+            changed: snapshot_id.is_some(),
+            snapshot_id,
+            error: stored.as_ref().err().cloned(),
+        },
+    );
+    stored
+}
+
+/// Lists a task's stored snapshots, newest first.
+#[command]
+pub fn list_backup_snapshots(task_id: String) -> Result<Vec<BackupSnapshot>, String> {
+    snapshots(&task_id)
+}
+
+/// Returns the content of a snapshot.
+#[command]
+pub fn read_backup_snapshot(task_id: String, snapshot_id: String) -> Result<String, String> {
+    std::fs::read_to_string(snapshot_path(&task_id, &snapshot_id)?).map_err(|e| e.to_string())
+}
+
+/// Returns a unified diff between two snapshots of a task.
+#[command]
+pub fn diff_backup_snapshots(
+    task_id: String,
+    from_snapshot_id: String,
+    to_snapshot_id: String,
+) -> Result<String, String> {
+    let old = std::fs::read_to_string(snapshot_path(&task_id, &from_snapshot_id)?)
+        .map_err(|e| e.to_string())?;
+    let new = std::fs::read_to_string(snapshot_path(&task_id, &to_snapshot_id)?)
+        .map_err(|e| e.to_string())?;
+    Ok(similar::TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&from_snapshot_id, &to_snapshot_id)
+        .to_string())
+}
@@ -0,0 +1,251 @@
+use crate::encryption::EncryptionManager;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::command;
+
+/// When the user last passed a biometric check
+static LAST_VERIFIED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether credential retrieval requires biometric confirmation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BiometricOptions {
+    pub enabled: bool,
+    /// Minutes after a successful check during which no new prompt is shown
+    pub grace_minutes: i64,
+    /// Whether Touch ID / Windows Hello is usable on this machine
+    #[serde(default)]
+    pub available: bool,
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil, YES};
+    use cocoa::foundation::NSString;
+    use objc::runtime::BOOL;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    /// `LAPolicyDeviceOwnerAuthenticationWithBiometrics`
+    const POLICY_BIOMETRICS: i64 = 1;
+
+    pub fn is_available() -> bool {
+        unsafe {
+            let context: id = msg_send![class!(LAContext), new];
+            let error: *mut id = std::ptr::null_mut();
+            let ok: BOOL = msg_send![context, canEvaluatePolicy: POLICY_BIOMETRICS error: error];
+            let _: () = msg_send![context, release];
+            ok == YES
+        }
+    }
+
+    pub fn authenticate(reason: &str) -> Result<(), String> {
+        let (tx, rx) = mpsc::channel();
+        unsafe {
+            let context: id = msg_send![class!(LAContext), new];
+            let reason = NSString::alloc(nil).init_str(reason);
+            let reply = ConcreteBlock::new(move |success: BOOL, _error: id| {
+                let _ = tx.send(success == YES);
+            })
+            .copy();
+            let _: () = msg_send![context, evaluatePolicy: POLICY_BIOMETRICS localizedReason: reason reply: &*reply];
+            let result = rx.recv();
+            let _: () = msg_send![reason, release];
+            let _: () = msg_send![context, release];
+            match result {
+                Ok(true) => Ok(()),
+                _ => Err("Touch ID verification failed".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    pub fn is_available() -> bool {
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|op| op.get())
+            .is_ok_and(|a| a == UserConsentVerifierAvailability::Available)
+    }
+
+    pub fn authenticate(reason: &str) -> Result<(), String> {
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|op| op.get())
+            .map_err(|e| e.to_string())?;
+        if result == UserConsentVerificationResult::Verified {
+            Ok(())
+        } else {
+            Err(format!("Windows Hello verification failed: {:?}", result))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn authenticate(_reason: &str) -> Result<(), String> {
+        Err("Biometric authentication is not supported on this platform".to_string())
+    }
+}
+
+/// Asks for biometric confirmation when the option is enabled, unless the
+/// user already confirmed within the grace period. Blocking.
+pub fn confirm(reason: &str) -> Result<(), String> {
+    let options = crate::db::load_biometric_options()?;
+    if !options.enabled {
+        return Ok(());
+    }
+    let grace = Duration::from_secs(options.grace_minutes.max(0) as u64 * 60);
+    let mut last = LAST_VERIFIED.lock().map_err(|e| e.to_string())?;
+    if last.is_some_and(|t| t.elapsed() < grace) {
+        return Ok(());
+    }
+    platform::authenticate(reason)?;
+    *last = Some(Instant::now());
+    Ok(())
+}
+
+/// Returns the biometric gating options and whether biometrics are
+/// available on this machine.
+#[command]
+pub fn get_biometric_options() -> Result<BiometricOptions, String> {
+    let mut options = crate::db::load_biometric_options()?;
+    options.available = platform::is_available();
+    Ok(options)
+}
+
+/// Enables or disables biometric confirmation before stored passwords are
+/// returned. Enabling requires a successful check first so an unusable
+/// sensor cannot lock the user out.
+#[command]
+pub async fn set_biometric_options(
+    enabled: bool,
+    grace_minutes: Option<i64>,
+) -> Result<(), String> {
+    if enabled {
+        tokio::task::spawn_blocking(|| {
+            if !platform::is_available() {
+                return Err("Biometric authentication is not available".to_string());
+            }
+            platform::authenticate("enable biometric unlock for saved credentials")
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        if let Ok(mut last) = LAST_VERIFIED.lock() {
+            *last = Some(Instant::now());
+        }
+    } else {
+        tokio::task::spawn_blocking(|| confirm("disable biometric unlock for saved credentials"))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    crate::db::save_biometric_options(enabled, grace_minutes.unwrap_or(5).max(0))
+}
+
+/// Asks for the master password or, without one, a biometric check. Unlike
+/// `confirm` there is no grace period and it applies whether or not
+/// biometric gating is enabled. Blocking.
+pub fn reauthenticate(reason: &str, master_password: Option<&str>) -> Result<(), String> {
+    if let Some(password) = master_password {
+        let hash = crate::db::load_master_password_hash()?
+            .ok_or_else(|| "No master password is set".to_string())?;
+        if !EncryptionManager::verify_password(password, &hash) {
+            return Err("Incorrect master password".to_string());
+        }
+        return Ok(());
+    }
+    if !platform::is_available() {
+        return Err(format!("Master password required to {}", reason));
+    }
+    platform::authenticate(reason)?;
+    if let Ok(mut last) = LAST_VERIFIED.lock() {
+        *last = Some(Instant::now());
+    }
+    Ok(())
+}
+
+/// Re-authenticates before a protected saved session is connected, even
+/// when its credentials are cached. Blocking.
+pub fn confirm_protected_session(
+    saved_session_id: &str,
+    master_password: Option<&str>,
+) -> Result<(), String> {
+    if !crate::db::load_session_protected(saved_session_id)? {
+        return Ok(());
+    }
+    reauthenticate("connect to a protected session", master_password)
+}
+
+/// Re-authenticates once before several saved sessions are connected
+/// together, when any of them is protected. Blocking.
+pub fn confirm_protected_sessions(
+    saved_session_ids: &[String],
+    master_password: Option<&str>,
+) -> Result<(), String> {
+    for id in saved_session_ids {
+        if crate::db::load_session_protected(id)? {
+            return reauthenticate("connect to protected sessions", master_password);
+        }
+    }
+    Ok(())
+}
+
+/// Marks a saved session as protected. Removing the protection requires
+/// re-authentication.
+#[command]
+pub async fn set_session_protected(
+    session_id: String,
+    protected: bool,
+    master_password: Option<String>,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        if !protected && crate::db::load_session_protected(&session_id)? {
+            reauthenticate(
+                "remove the protection of a session",
+                master_password.as_deref(),
+            )?;
+        }
+        crate::db::save_session_protected(&session_id, protected)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Sets, changes or (with `None`) removes the master password used to
+/// open protected sessions. Changing an existing one requires it.
+#[command]
+pub async fn set_master_password(
+    current_password: Option<String>,
+    new_password: Option<String>,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(hash) = crate::db::load_master_password_hash()? {
+            let current = current_password.unwrap_or_default();
+            if !EncryptionManager::verify_password(&current, &hash) {
+                return Err("Incorrect master password".to_string());
+            }
+        }
+        let hash = match new_password.as_deref() {
+            Some("") => return Err("Master password cannot be empty".to_string()),
+            Some(password) => Some(EncryptionManager::hash_password(password)),
+            None => None,
+        };
+        crate::db::save_master_password_hash(hash)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
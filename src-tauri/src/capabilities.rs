@@ -0,0 +1,222 @@
+use crate::ssh::{SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Tools looked up on the server's PATH
+const PROBED_TOOLS: &[&str] = &[
+    "docker",
+    "podman",
+    "systemctl",
+    "journalctl",
+    "rg",
+    "grep",
+    "find",
+    "ss",
+    "netstat",
+    "free",
+    "df",
+    "tmux",
+    "screen",
+    "zellij",
+    "git",
+    "python3",
+    "sudo",
+    "apt",
+    "dnf",
+    "yum",
+    "apk",
+    "pacman",
+    "brew",
+];
+
+/// Time allowed for the probe command
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Capabilities of connected sessions by session id
+static CAPABILITIES: Lazy<RwLock<HashMap<String, SessionCapabilities>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// What a server runs and which tools it offers, probed once per
+/// connection
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCapabilities {
+    /// Kernel name from `uname -s`, e.g. "Linux", "Darwin" or "FreeBSD"
+    pub os: String,
+    pub kernel_release: String,
+    pub arch: String,
+    /// `ID` from /etc/os-release, e.g. "ubuntu"; `None` without the file
+    pub distro_id: Option<String>,
+    pub distro_version: Option<String>,
+    /// `PRETTY_NAME` from /etc/os-release, e.g. "Ubuntu 24.04 LTS"
+    pub distro_name: Option<String>,
+    /// Probed tools found on the PATH
+    pub tools: Vec<String>,
+}
+
+impl SessionCapabilities {
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|tool| tool == name)
+    }
+
+    /// Whether the server metrics of the monitoring task can be read; they
+    /// come from Linux's /proc
+    pub fn supports_monitoring(&self) -> bool {
+        self.os == "Linux"
+    }
+}
+
+/// Payload of the `session-capabilities` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitiesProbed {
+    pub session_id: String,
+    pub capabilities: SessionCapabilities,
+}
+
+/// One shell command printing `uname`, /etc/os-release and the tools
+/// found, in sections started by `--name` lines
+fn probe_command() -> String {
+    format!(
+        "echo --uname; uname -s; uname -r; uname -m; \
+         echo --os-release; cat /etc/os-release 2>/dev/null; \
+         echo --tools; for t in {}; do command -v \"$t\" >/dev/null 2>&1 && echo \"$t\"; done",
+        PROBED_TOOLS.join(" ")
+    )
+}
+
+/// Value of an os-release assignment, without its quotes
+fn os_release_value(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+        .replace("\\\"", "\"")
+}
+
+fn parse_probe(output: &str) -> SessionCapabilities {
+    let mut capabilities = SessionCapabilities::default();
+    let mut section = "";
+    let mut uname = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("--") {
+            section = match name {
+                "uname" | "os-release" | "tools" => name,
+                _ => section,
+            };
+            continue;
+        }
+        match section {
+            "uname" => uname.push(line.to_string()),
+            "os-release" => {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                let value = Some(os_release_value(value)).filter(|v| !v.is_empty());
+                match key {
+                    "ID" => capabilities.distro_id = value,
+                    "VERSION_ID" => capabilities.distro_version = value,
+                    "PRETTY_NAME" => capabilities.distro_name = value,
+                    _ => {}
+                }
+            }
+            "tools" if PROBED_TOOLS.contains(&line) => capabilities.tools.push(line.to_string()),
+            _ => {}
+        }
+    }
+    let mut uname = uname.into_iter();
+    capabilities.os = uname.next().unwrap_or_default();
+    capabilities.kernel_release = uname.next().unwrap_or_default();
+    capabilities.arch = uname.next().unwrap_or_default();
+    capabilities
+}
+
+/// Runs the probe on an exec channel and caches the result. Blocking.
+fn probe(
+    session_id: &str,
+    handle: &SessionHandle,
+    cancel: &AtomicBool,
+) -> Result<SessionCapabilities, SshError> {
+    let output = handle.exec_interruptible(&probe_command(), PROBE_TIMEOUT, cancel)?;
+    let capabilities = parse_probe(&output.stdout);
+    if capabilities.os.is_empty() {
+        return Err(SshError::OperationFailed(format!(
+            "Capability probe returned no system name: {}",
+            output.stderr.trim()
+        )));
+    }
+    if let Ok(mut cache) = CAPABILITIES.write() {
+        cache.insert(session_id.to_string(), capabilities.clone());
+    }
+    Ok(capabilities)
+}
+
+/// Probes a newly connected session in the background and announces the
+/// result with a `session-capabilities` event
+pub fn spawn_probe(
+    app_handle: Option<AppHandle>,
+    session_id: SessionId,
+    handle: SessionHandle,
+    stop: Arc<AtomicBool>,
+) {
+    tokio::task::spawn_blocking(move || match probe(&session_id.0, &handle, &stop) {
+        Ok(capabilities) => {
+            if let Some(h) = app_handle {
+                let _ = h.emit(
+                    "session-capabilities",
+                    &CapabilitiesProbed {
+                        session_id: session_id.0,
+                        capabilities,
+                    },
+                );
+            }
+        }
+        // Failures while the connection closes are expected
+        Err(e) if !stop.load(Ordering::SeqCst) => {
+            crate::error_bus::report("capabilities", Some(&session_id.0), e);
+        }
+        Err(_) => {}
+    });
+}
+
+/// Capabilities of a session, once probed
+pub fn cached(session_id: &str) -> Option<SessionCapabilities> {
+    CAPABILITIES.read().ok()?.get(session_id).cloned()
+}
+
+/// Drops the capabilities of a session; called when it disconnects
+pub fn remove_session(session_id: &str) {
+    if let Ok(mut cache) = CAPABILITIES.write() {
+        cache.remove(session_id);
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Returns the operating system, distribution and available tools of a
+/// connected server. They are probed once after connecting and announced
+/// with `session-capabilities`; before that, the probe runs on demand.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_session_capabilities(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<SessionCapabilities, SshError> {
+    if let Some(capabilities) = cached(&sessionId) {
+        return Ok(capabilities);
+    }
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    tokio::task::spawn_blocking(move || probe(&sessionId, &handle, &AtomicBool::new(false)))
+        .await
+        .map_err(|e| SshError::TaskError(e.to_string()))?
+}
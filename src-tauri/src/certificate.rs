@@ -0,0 +1,308 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+/// Value of `valid_before` for certificates that never expire
+const FOREVER: u64 = u64::MAX;
+
+/// Fields of an OpenSSH user certificate relevant before connecting
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    /// e.g. `ssh-ed25519-cert-v01@openssh.com`
+    pub key_type: String,
+    pub serial: u64,
+    pub key_id: String,
+    pub principals: Vec<String>,
+    /// Unix seconds; 0 means valid from the beginning of time
+    pub valid_after: u64,
+    /// Unix seconds; `None` when the certificate never expires
+    pub valid_before: Option<u64>,
+    /// Whether the certificate is valid right now
+    pub valid: bool,
+}
+
+/// Reader for the SSH wire encoding (RFC 4251 §5)
+struct WireReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err("Certificate is truncated".to_string());
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let b = self.take(8)?;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(b);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+}
+
+/// Number of length-prefixed public key fields between the nonce and the
+/// serial for each certificate type (see OpenSSH PROTOCOL.certkeys)
+fn public_key_fields(key_type: &str) -> Option<usize> {
+    let base = key_type.strip_suffix("-cert-v01@openssh.com")?;
+    match base {
+        "ssh-rsa" => Some(2),
+        "ssh-dss" => Some(4),
+        "ssh-ed25519" => Some(1),
+        "sk-ssh-ed25519@openssh.com" => Some(2),
+        "sk-ecdsa-sha2-nistp256@openssh.com" => Some(3),
+        b if b.starts_with("ecdsa-sha2-") => Some(2),
+        _ => None,
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Parses the content of a `*-cert.pub` file
+pub fn parse_certificate(content: &str) -> Result<CertificateInfo, String> {
+    let mut parts = content.split_whitespace();
+    let declared_type = parts
+        .next()
+        .ok_or_else(|| "Certificate file is empty".to_string())?;
+    let blob = parts
+        .next()
+        .ok_or_else(|| "Certificate data is missing".to_string())?;
+    let blob = general_purpose::STANDARD
+        .decode(blob)
+        .map_err(|e| format!("Invalid certificate encoding: {}", e))?;
+
+    let mut reader = WireReader { data: &blob };
+    let key_type = reader.string()?;
+    if key_type != declared_type {
+        return Err(format!(
+            "Certificate type mismatch: {} vs {}",
+            declared_type, key_type
+        ));
+    }
+    let fields = public_key_fields(&key_type)
+        .ok_or_else(|| format!("Not an OpenSSH certificate: {}", key_type))?;
+    reader.bytes()?; // nonce
+    for _ in 0..fields {
+        reader.bytes()?;
+    }
+    let serial = reader.u64()?;
+    let cert_type = reader.u32()?;
+    if cert_type != 1 {
+        return Err("Certificate is a host certificate, not a user certificate".to_string());
+    }
+    let key_id = reader.string()?;
+    let mut principals_reader = WireReader {
+        data: reader.bytes()?,
+    };
+    let mut principals = Vec::new();
+    while !principals_reader.data.is_empty() {
+        principals.push(principals_reader.string()?);
+    }
+    let valid_after = reader.u64()?;
+    let valid_before = reader.u64()?;
+
+    let now = now_secs();
+    Ok(CertificateInfo {
+        key_type,
+        serial,
+        key_id,
+        principals,
+        valid_after,
+        valid_before: (valid_before != FOREVER).then_some(valid_before),
+        valid: valid_after <= now && now < valid_before,
+    })
+}
+
+/// Reads and parses a certificate file
+pub fn read_certificate(path: &Path) -> Result<CertificateInfo, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read certificate {}: {}", path.display(), e))?;
+    parse_certificate(&content)
+}
+
+/// Certificate to offer for a key: the configured path, otherwise
+/// `<key>-cert.pub` next to the private key when it exists (as OpenSSH does)
+pub fn certificate_for_key(key_path: &str, certificate_path: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = certificate_path.filter(|p| !p.trim().is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let default = PathBuf::from(format!("{}-cert.pub", key_path));
+    default.is_file().then_some(default)
+}
+
+/// Fails with a readable reason when a certificate is expired, not yet
+/// valid or does not list `username` as a principal
+pub fn validate_certificate(path: &Path, username: &str) -> Result<CertificateInfo, String> {
+    let info = read_certificate(path)?;
+    let now = now_secs();
+    if now < info.valid_after {
+        return Err(format!(
+            "Certificate {} is not valid yet (valid in {})",
+            info.key_id,
+            describe_span(info.valid_after - now)
+        ));
+    }
+    if let Some(before) = info.valid_before.filter(|b| now >= *b) {
+        return Err(format!(
+            "Certificate {} expired {} ago",
+            info.key_id,
+            describe_span(now - before)
+        ));
+    }
+    if !info.principals.is_empty() && !info.principals.iter().any(|p| p == username) {
+        return Err(format!(
+            "Certificate {} is not valid for user {} (principals: {})",
+            info.key_id,
+            username,
+            info.principals.join(", ")
+        ));
+    }
+    Ok(info)
+}
+
+/// Describes a span of seconds coarsely, e.g. `3 days`
+fn describe_span(secs: u64) -> String {
+    let (value, unit) = match secs {
+        s if s >= 86_400 => (s / 86_400, "day"),
+        s if s >= 3_600 => (s / 3_600, "hour"),
+        s if s >= 60 => (s / 60, "minute"),
+        s => (s, "second"),
+    };
+    format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+}
+
+/// Reads an OpenSSH user certificate so the session form can show its
+/// principals and validity window.
+#[command]
+pub fn inspect_certificate(path: String) -> Result<CertificateInfo, String> {
+    read_certificate(Path::new(&path))
+}
+
+/// What `validate_private_key` found in a private key file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateKeyInfo {
+    /// "openssh" or "pem"
+    pub format: String,
+    /// e.g. `ssh-ed25519`; `None` for PKCS#8 keys without a `.pub` file
+    pub key_type: Option<String>,
+    /// `SHA256:...`, as printed by `ssh-keygen -l`
+    pub fingerprint: Option<String>,
+    pub comment: Option<String>,
+    pub encrypted: bool,
+    /// Whether the given passphrase decrypts the key; `None` when it was not
+    /// checked
+    pub passphrase_valid: Option<bool>,
+}
+
+/// Type, fingerprint and comment from the `.pub` file next to a key
+fn public_key_beside(path: &Path) -> Option<ssh_key::PublicKey> {
+    let content = std::fs::read_to_string(format!("{}.pub", path.display())).ok()?;
+    ssh_key::PublicKey::from_openssh(content.trim()).ok()
+}
+
+/// Inspects a private key before it is saved with a session: its type,
+/// fingerprint and whether it is encrypted. When a passphrase is given for
+/// an OpenSSH key it is checked too; legacy PEM keys cannot be checked.
+#[command]
+pub async fn validate_private_key(
+    path: String,
+    passphrase: Option<String>,
+) -> Result<PrivateKeyInfo, String> {
+    // Decrypting runs the key's KDF, which takes a noticeable time
+    tokio::task::spawn_blocking(move || inspect_private_key(Path::new(&path), passphrase))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn inspect_private_key(path: &Path, passphrase: Option<String>) -> Result<PrivateKeyInfo, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    if metadata.len() > 64 * 1024 {
+        return Err("File is too large to be a private key".to_string());
+    }
+    let content = std::fs::read_to_string(path).map_err(|_| "Not a private key".to_string())?;
+
+    if content.contains("BEGIN OPENSSH PRIVATE KEY") {
+        let key = ssh_key::PrivateKey::from_openssh(&content)
+            .map_err(|e| format!("Invalid OpenSSH key: {}", e))?;
+        let encrypted = key.is_encrypted();
+        let decrypted = match passphrase.as_deref().filter(|p| !p.is_empty()) {
+            Some(passphrase) if encrypted => Some(key.decrypt(passphrase).ok()),
+            _ => None,
+        };
+        // The comment of an encrypted key is stored encrypted
+        let comment = match (encrypted, &decrypted) {
+            (false, _) => Some(key.comment().to_string()),
+            (true, Some(Some(plain))) => Some(plain.comment().to_string()),
+            _ => public_key_beside(path).map(|public| public.comment().to_string()),
+        };
+        return Ok(PrivateKeyInfo {
+            format: "openssh".to_string(),
+            key_type: Some(key.algorithm().as_str().to_string()),
+            fingerprint: Some(key.fingerprint(ssh_key::HashAlg::Sha256).to_string()),
+            comment: comment.filter(|c| !c.is_empty()),
+            encrypted,
+            passphrase_valid: decrypted.map(|d| d.is_some()),
+        });
+    }
+
+    if content.starts_with("PuTTY-User-Key-File-") {
+        return Err("PuTTY keys are not supported; convert it with \
+             `puttygen key.ppk -O private-openssh -o key`"
+            .to_string());
+    }
+
+    let pem_type = [
+        ("RSA PRIVATE KEY", Some("ssh-rsa")),
+        ("EC PRIVATE KEY", Some("ecdsa")),
+        ("DSA PRIVATE KEY", Some("ssh-dss")),
+        ("ENCRYPTED PRIVATE KEY", None),
+        ("PRIVATE KEY", None),
+    ]
+    .into_iter()
+    .find(|(label, _)| content.contains(&format!("-----BEGIN {}-----", label)));
+    let Some((label, key_type)) = pem_type else {
+        return Err("Not a private key".to_string());
+    };
+    let public = public_key_beside(path);
+    Ok(PrivateKeyInfo {
+        format: "pem".to_string(),
+        key_type: public
+            .as_ref()
+            .map(|p| p.algorithm().as_str().to_string())
+            .or(key_type.map(str::to_string)),
+        fingerprint: public
+            .as_ref()
+            .map(|p| p.fingerprint(ssh_key::HashAlg::Sha256).to_string()),
+        comment: public
+            .as_ref()
+            .map(|p| p.comment().to_string())
+            .filter(|c| !c.is_empty()),
+        encrypted: label == "ENCRYPTED PRIVATE KEY" || content.contains("Proc-Type: 4,ENCRYPTED"),
+        passphrase_valid: None,
+    })
+}
@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use tauri::command;
+
+/// A machine reported by a cloud provider
+#[derive(Debug, Clone)]
+pub struct CloudHost {
+    /// Provider-side id, stable across renames and IP changes
+    pub external_id: String,
+    pub name: String,
+    pub public_ip: Option<String>,
+    pub private_ip: Option<String>,
+    /// Name of the provider key pair the machine was created with
+    pub key_name: Option<String>,
+}
+
+/// Account settings of a provider, stored for `refresh_cloud_hosts`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProviderOptions {
+    /// CLI profile (AWS)
+    pub profile: Option<String>,
+    /// Region to list (AWS); other providers list every region
+    pub region: Option<String>,
+    /// Project to list (GCP)
+    pub project: Option<String>,
+    /// Login user for imported sessions; defaults per provider
+    pub username: Option<String>,
+    /// Directory searched for private keys named after the key pair
+    /// (`<name>`, `<name>.pem`)
+    pub key_dir: Option<String>,
+    /// Connect to the private address even when a public one exists
+    pub use_private_ip: bool,
+}
+
+/// A source of hosts. Providers that need an API token receive the one
+/// stored with the account; CLI-based ones use the CLI's own credentials.
+pub trait CloudProvider: Sync {
+    /// Identifier stored with imported sessions
+    fn id(&self) -> &'static str;
+    /// Display name, also used as the tag of imported sessions
+    fn label(&self) -> &'static str;
+    fn needs_token(&self) -> bool;
+    /// Login user when the account does not set one
+    fn default_username(&self) -> String;
+    /// Lists the provider's running machines. Blocking.
+    fn list_hosts(
+        &self,
+        options: &ProviderOptions,
+        token: Option<&str>,
+    ) -> Result<Vec<CloudHost>, String>;
+}
+
+/// Runs a provider CLI and parses its JSON output
+fn run_cli(program: &str, args: &[String]) -> Result<Value, String> {
+    let program = if cfg!(windows) && program == "gcloud" {
+        "gcloud.cmd"
+    } else {
+        program
+    };
+    let output = Command::new(program).args(args).output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!("{} is not installed or not on PATH", program)
+        } else {
+            format!("Failed to run {}: {}", program, e)
+        }
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", program, stderr.trim()));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected {} output: {}", program, e))
+}
+
+/// GETs a provider API endpoint with a bearer token
+fn get_json(url: &str, token: Option<&str>) -> Result<Value, String> {
+    let token = token.ok_or("An API token is required")?;
+    let body = ureq::get(url)
+        .set("Authorization", &format!("Bearer {}", token))
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(401, _) => "The API token was rejected".to_string(),
+            e => format!("Request failed: {}", e),
+        })?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected API response: {}", e))
+}
+
+fn string(value: &Value, pointer: &str) -> Option<String> {
+    match value.pointer(pointer)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn array<'a>(value: &'a Value, pointer: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+struct Aws;
+
+impl CloudProvider for Aws {
+    fn id(&self) -> &'static str {
+        "aws"
+    }
+
+    fn label(&self) -> &'static str {
+        "AWS"
+    }
+
+    fn needs_token(&self) -> bool {
+        false
+    }
+
+    fn default_username(&self) -> String {
+        "ec2-user".to_string()
+    }
+
+    fn list_hosts(
+        &self,
+        options: &ProviderOptions,
+        _token: Option<&str>,
+    ) -> Result<Vec<CloudHost>, String> {
+        let mut args: Vec<String> = [
+            "ec2",
+            "describe-instances",
+            "--output",
+            "json",
+            "--filters",
+            "Name=instance-state-name,Values=running",
+        ]
+        .map(String::from)
+        .to_vec();
+        if let Some(profile) = &options.profile {
+            args.extend(["--profile".to_string(), profile.clone()]);
+        }
+        if let Some(region) = &options.region {
+            args.extend(["--region".to_string(), region.clone()]);
+        }
+        let json = run_cli("aws", &args)?;
+        Ok(array(&json, "/Reservations")
+            .flat_map(|r| array(r, "/Instances"))
+            .filter_map(|i| {
+                let external_id = string(i, "/InstanceId")?;
+                let name = array(i, "/Tags")
+                    .find(|t| t["Key"] == "Name")
+                    .and_then(|t| string(t, "/Value"))
+                    .unwrap_or_else(|| external_id.clone());
+                Some(CloudHost {
+                    name,
+                    public_ip: string(i, "/PublicIpAddress"),
+                    private_ip: string(i, "/PrivateIpAddress"),
+                    key_name: string(i, "/KeyName"),
+                    external_id,
+                })
+            })
+            .collect())
+    }
+}
+
+struct Gcp;
+
+impl CloudProvider for Gcp {
+    fn id(&self) -> &'static str {
+        "gcp"
+    }
+
+    fn label(&self) -> &'static str {
+        "GCP"
+    }
+
+    fn needs_token(&self) -> bool {
+        false
+    }
+
+    fn default_username(&self) -> String {
+        // gcloud provisions the local user name on the instances
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "root".to_string())
+    }
+
+    fn list_hosts(
+        &self,
+        options: &ProviderOptions,
+        _token: Option<&str>,
+    ) -> Result<Vec<CloudHost>, String> {
+        let mut args: Vec<String> = [
+            "compute",
+            "instances",
+            "list",
+            "--format=json",
+            "--filter=status=RUNNING",
+        ]
+        .map(String::from)
+        .to_vec();
+        if let Some(project) = &options.project {
+            args.push(format!("--project={}", project));
+        }
+        let json = run_cli("gcloud", &args)?;
+        Ok(array(&json, "")
+            .filter_map(|i| {
+                let interface = i.pointer("/networkInterfaces/0")?;
+                Some(CloudHost {
+                    external_id: string(i, "/id")?,
+                    name: string(i, "/name")?,
+                    public_ip: string(interface, "/accessConfigs/0/natIP"),
+                    private_ip: string(interface, "/networkIP"),
+                    key_name: None,
+                })
+            })
+            .collect())
+    }
+}
+
+struct Hetzner;
+
+impl CloudProvider for Hetzner {
+    fn id(&self) -> &'static str {
+        "hetzner"
+    }
+
+    fn label(&self) -> &'static str {
+        "Hetzner"
+    }
+
+    fn needs_token(&self) -> bool {
+        true
+    }
+
+    fn default_username(&self) -> String {
+        "root".to_string()
+    }
+
+    fn list_hosts(
+        &self,
+        _options: &ProviderOptions,
+        token: Option<&str>,
+    ) -> Result<Vec<CloudHost>, String> {
+        let mut hosts = Vec::new();
+        let mut page = Some(1);
+        while let Some(current) = page {
+            let json = get_json(
+                &format!(
+                    "https://api.hetzner.cloud/v1/servers?status=running&per_page=50&page={}",
+                    current
+                ),
+                token,
+            )?;
+            hosts.extend(array(&json, "/servers").filter_map(|s| {
+                Some(CloudHost {
+                    external_id: string(s, "/id")?,
+                    name: string(s, "/name")?,
+                    public_ip: string(s, "/public_net/ipv4/ip"),
+                    private_ip: string(s, "/private_net/0/ip"),
+                    key_name: None,
+                })
+            }));
+            page = json
+                .pointer("/meta/pagination/next_page")
+                .and_then(Value::as_u64);
+        }
+        Ok(hosts)
+    }
+}
+
+struct DigitalOcean;
+
+impl CloudProvider for DigitalOcean {
+    fn id(&self) -> &'static str {
+        "digitalocean"
+    }
+
+    fn label(&self) -> &'static str {
+        "DigitalOcean"
+    }
+
+    fn needs_token(&self) -> bool {
+        true
+    }
+
+    fn default_username(&self) -> String {
+        "root".to_string()
+    }
+
+    fn list_hosts(
+        &self,
+        _options: &ProviderOptions,
+        token: Option<&str>,
+    ) -> Result<Vec<CloudHost>, String> {
+        let mut hosts = Vec::new();
+        let mut url = Some("https://api.digitalocean.com/v2/droplets?per_page=200".to_string());
+        while let Some(current) = url {
+            let json = get_json(&current, token)?;
+            hosts.extend(
+                array(&json, "/droplets")
+                    .filter(|d| d["status"] == "active")
+                    .filter_map(|d| {
+                        let address = |kind: &str| {
+                            array(d, "/networks/v4")
+                                .find(|n| n["type"] == kind)
+                                .and_then(|n| string(n, "/ip_address"))
+                        };
+                        Some(CloudHost {
+                            external_id: string(d, "/id")?,
+                            name: string(d, "/name")?,
+                            public_ip: address("public"),
+                            private_ip: address("private"),
+                            key_name: None,
+                        })
+                    }),
+            );
+            url = string(&json, "/links/pages/next");
+        }
+        Ok(hosts)
+    }
+}
+
+static PROVIDERS: [&dyn CloudProvider; 4] = [&Aws, &Gcp, &Hetzner, &DigitalOcean];
+
+fn provider(id: &str) -> Result<&'static dyn CloudProvider, String> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .find(|p| p.id() == id)
+        .ok_or_else(|| format!("Unknown cloud provider: {}", id))
+}
+
+/// A host mapped to session fields
+pub struct CloudSessionRecord {
+    /// `provider:external_id`, matched against `sessions.cloud_source`
+    pub source: String,
+    pub server_name: String,
+    pub addr: String,
+    pub username: String,
+    pub private_key_path: Option<String>,
+}
+
+fn to_record(
+    provider: &dyn CloudProvider,
+    options: &ProviderOptions,
+    host: CloudHost,
+) -> Option<CloudSessionRecord> {
+    let addr = if options.use_private_ip {
+        host.private_ip.or(host.public_ip)
+    } else {
+        host.public_ip.or(host.private_ip)
+    }?;
+    let private_key_path = options
+        .key_dir
+        .as_deref()
+        .zip(host.key_name.as_deref())
+        .and_then(|(dir, key)| {
+            [key.to_string(), format!("{}.pem", key)]
+                .into_iter()
+                .map(|name| Path::new(dir).join(name))
+                .find(|path| path.is_file())
+        })
+        .map(|path| path.to_string_lossy().into_owned());
+    Some(CloudSessionRecord {
+        source: format!("{}:{}", provider.id(), host.external_id),
+        server_name: host.name,
+        addr,
+        username: options
+            .username
+            .clone()
+            .unwrap_or_else(|| provider.default_username()),
+        private_key_path,
+    })
+}
+
+/// Outcome of an import or refresh
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudSyncResult {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Imported sessions whose machine no longer exists or is stopped.
+    /// They are kept; the user decides whether to delete them.
+    pub stale: Vec<String>,
+    /// Machines skipped because they have no IP address
+    pub skipped: usize,
+}
+
+/// Lists the provider's hosts and merges them into the session library.
+/// Blocking.
+fn sync_provider(
+    provider: &dyn CloudProvider,
+    options: &ProviderOptions,
+    token: Option<&str>,
+) -> Result<CloudSyncResult, String> {
+    let hosts = provider.list_hosts(options, token)?;
+    let total = hosts.len();
+    let records: Vec<CloudSessionRecord> = hosts
+        .into_iter()
+        .filter_map(|host| to_record(provider, options, host))
+        .collect();
+    let mut result = crate::db::sync_cloud_sessions(provider.id(), provider.label(), &records)?;
+    result.skipped = total - records.len();
+    Ok(result)
+}
+
+/// A provider as listed for the import dialog
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudProviderInfo {
+    pub id: String,
+    pub label: String,
+    pub needs_token: bool,
+    /// Whether an account was saved by an earlier import
+    pub configured: bool,
+    pub last_synced_at: Option<String>,
+}
+
+/// Lists the supported cloud providers
+#[command]
+pub fn list_cloud_providers() -> Result<Vec<CloudProviderInfo>, String> {
+    PROVIDERS
+        .iter()
+        .map(|p| {
+            let account = crate::db::load_cloud_account(p.id())?;
+            Ok(CloudProviderInfo {
+                id: p.id().to_string(),
+                label: p.label().to_string(),
+                needs_token: p.needs_token(),
+                configured: account.is_some(),
+                last_synced_at: account.and_then(|a| a.last_synced_at),
+            })
+        })
+        .collect()
+}
+
+/// Imports the running machines of a provider as sessions tagged with the
+/// provider's name, and saves the account for `refresh_cloud_hosts`. The
+/// token (Hetzner, DigitalOcean) is stored encrypted; `None` keeps the
+/// stored one. AWS and GCP use the local CLI and its credentials.
+#[command]
+pub async fn import_cloud_hosts(
+    provider: String,
+    options: ProviderOptions,
+    token: Option<String>,
+) -> Result<CloudSyncResult, String> {
+    let provider = self::provider(&provider)?;
+    tokio::task::spawn_blocking(move || {
+        let stored = crate::db::load_cloud_account(provider.id())?.and_then(|a| a.token);
+        let token = token.filter(|t| !t.is_empty()).or(stored);
+        let result = sync_provider(provider, &options, token.as_deref())?;
+        crate::db::save_cloud_account(provider.id(), &options, token.as_deref())?;
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Re-reads a previously imported provider: new machines are added,
+/// renamed or re-addressed ones updated, and vanished ones reported as
+/// stale.
+#[command]
+pub async fn refresh_cloud_hosts(provider: String) -> Result<CloudSyncResult, String> {
+    let provider = self::provider(&provider)?;
+    tokio::task::spawn_blocking(move || {
+        let account = crate::db::load_cloud_account(provider.id())?
+            .ok_or_else(|| format!("{} has not been imported yet", provider.label()))?;
+        let result = sync_provider(provider, &account.options, account.token.as_deref())?;
+        crate::db::save_cloud_account(provider.id(), &account.options, None)?;
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
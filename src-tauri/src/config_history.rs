@@ -0,0 +1,318 @@
+use crate::db::ExportData;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+/// File holding the library in the repository
+const LIBRARY_FILE: &str = "library.json";
+
+/// Changes within this window are committed together
+const COMMIT_DELAY: Duration = Duration::from_secs(2);
+
+/// Session fields that change without a configuration edit and would only
+/// add noise to the history
+const VOLATILE_SESSION_FIELDS: &[&str] = &[
+    "updated_at",
+    "last_connected_at",
+    "health_status",
+    "health_checked_at",
+];
+
+/// Serializes git operations on the repository
+static REPO_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Set while a delayed commit is scheduled
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// One entry of the configuration history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCommit {
+    pub id: String,
+    pub message: String,
+    /// One line per edited record when a commit covers several edits
+    pub details: Vec<String>,
+    /// Unix seconds
+    pub committed_at: i64,
+}
+
+fn repo_dir() -> Result<PathBuf, String> {
+    Ok(crate::db::app_data_dir()?.join("config-history"))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=NexaShell",
+            "-c",
+            "user.email=nexashell@localhost",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "git is not installed".to_string(),
+            _ => format!("Failed to run git: {}", e),
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Creates the repository on first use
+fn ensure_repo() -> Result<PathBuf, String> {
+    let dir = repo_dir()?;
+    if !dir.join(".git").exists() {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        git(&dir, &["init", "--quiet"])?;
+    }
+    Ok(dir)
+}
+
+/// The library as stored in the repository: records sorted by id, without
+/// credentials or volatile fields
+fn snapshot(library: &ExportData) -> Result<Value, String> {
+    let mut value = serde_json::to_value(library).map_err(|e| e.to_string())?;
+    for (collection, volatile) in [
+        ("sessions", VOLATILE_SESSION_FIELDS),
+        ("groups", &["updated_at"][..]),
+        ("tags", &["updated_at"][..]),
+    ] {
+        let Some(records) = value.get_mut(collection).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for record in records.iter_mut() {
+            let fields = if record.get("metadata").is_some() {
+                &mut record["metadata"]
+            } else {
+                &mut *record
+            };
+            if let Some(fields) = fields.as_object_mut() {
+                for key in volatile {
+                    fields.remove(*key);
+                }
+            }
+            if let Some(fields) = record.as_object_mut() {
+                fields.remove("encrypted_credentials");
+            }
+        }
+        records.sort_by(|a, b| record_id(a).cmp(record_id(b)));
+    }
+    Ok(value)
+}
+
+/// Parses a stored snapshot back into library records
+fn parse_snapshot(json: &str) -> Result<ExportData, String> {
+    let mut value: Value =
+        serde_json::from_str(json).map_err(|e| format!("Snapshot is unreadable: {}", e))?;
+    for collection in ["sessions", "groups", "tags"] {
+        let Some(records) = value.get_mut(collection).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for record in records.iter_mut() {
+            let fields = if record.get("metadata").is_some() {
+                &mut record["metadata"]
+            } else {
+                &mut *record
+            };
+            if let Some(fields) = fields.as_object_mut() {
+                fields
+                    .entry("updated_at")
+                    .or_insert_with(|| Value::String(String::new()));
+            }
+        }
+    }
+    serde_json::from_value(value).map_err(|e| format!("Snapshot is unreadable: {}", e))
+}
+
+fn record_id(record: &Value) -> &str {
+    record
+        .get("metadata")
+        .unwrap_or(record)
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn record_label(kind: &str, record: &Value) -> String {
+    let fields = record.get("metadata").unwrap_or(record);
+    let name = fields
+        .get("server_name")
+        .or_else(|| fields.get("name"))
+        .and_then(Value::as_str)
+        .filter(|n| !n.is_empty())
+        .or_else(|| fields.get("addr").and_then(Value::as_str))
+        .unwrap_or_else(|| record_id(record));
+    format!("{} \"{}\"", kind, name)
+}
+
+/// Describes the edits between two snapshots, one line per record
+fn describe(old: &Value, new: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (collection, kind) in [
+        ("sessions", "session"),
+        ("groups", "group"),
+        ("tags", "tag"),
+    ] {
+        let index = |v: &Value| -> BTreeMap<String, Value> {
+            v.get(collection)
+                .and_then(Value::as_array)
+                .map(|records| {
+                    records
+                        .iter()
+                        .map(|r| (record_id(r).to_string(), r.clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let (before, after) = (index(old), index(new));
+        for (id, record) in &after {
+            match before.get(id) {
+                None => lines.push(format!("Add {}", record_label(kind, record))),
+                Some(previous) if previous != record => {
+                    lines.push(format!("Edit {}", record_label(kind, record)))
+                }
+                _ => {}
+            }
+        }
+        for (id, record) in &before {
+            if !after.contains_key(id) {
+                lines.push(format!("Delete {}", record_label(kind, record)));
+            }
+        }
+    }
+    lines
+}
+
+/// Writes the current library to the repository and commits it if it
+/// changed. `subject` overrides the generated message. Blocking.
+fn commit_change(subject: Option<String>) -> Result<Option<String>, String> {
+    let _guard = REPO_LOCK.lock().map_err(|e| e.to_string())?;
+    let dir = ensure_repo()?;
+    let path = dir.join(LIBRARY_FILE);
+    let previous = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Value>(&json).ok());
+
+    let current = snapshot(&crate::db::load_library(None)?)?;
+    let json = serde_json::to_string_pretty(&current).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json + "\n").map_err(|e| e.to_string())?;
+    git(&dir, &["add", LIBRARY_FILE])?;
+    if git(&dir, &["status", "--porcelain"])?.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let lines = match &previous {
+        Some(previous) => describe(previous, &current),
+        None => Vec::new(),
+    };
+    let subject = subject.unwrap_or_else(|| match (previous.is_some(), lines.as_slice()) {
+        (false, _) => "Initial session library".to_string(),
+        (true, [line]) => line.clone(),
+        (true, lines) => format!("Update {} records", lines.len()),
+    });
+    let body = lines.join("\n");
+    if body.is_empty() || body == subject {
+        git(&dir, &["commit", "--quiet", "-m", &subject])?;
+    } else {
+        git(&dir, &["commit", "--quiet", "-m", &subject, "-m", &body])?;
+    }
+    git(&dir, &["rev-parse", "HEAD"]).map(|id| Some(id.trim().to_string()))
+}
+
+/// Commits the library shortly after an edit when versioning is enabled.
+/// Edits made in quick succession (bulk edits, imports) share one commit.
+pub fn schedule_commit() {
+    if !crate::settings::current().versioning.enabled || PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        std::thread::sleep(COMMIT_DELAY);
+        PENDING.store(false, Ordering::SeqCst);
+        if let Err(e) = commit_change(None) {
+            tracing::warn!("commit failed: {}", e);
+        }
+    });
+}
+
+/// Lists library commits, newest first.
+#[command]
+pub async fn config_history(limit: Option<usize>) -> Result<Vec<ConfigCommit>, String> {
+    tokio::task::spawn_blocking(move || {
+        let dir = repo_dir()?;
+        if !dir.join(".git").exists() {
+            return Ok(Vec::new());
+        }
+        // An empty repository has no HEAD yet
+        if git(&dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_err() {
+            return Ok(Vec::new());
+        }
+        let limit = format!("--max-count={}", limit.unwrap_or(100));
+        let log = git(&dir, &["log", &limit, "--format=%H%x1f%ct%x1f%s%x1f%b%x1e"])?;
+        Ok(log
+            .split('\x1e')
+            .filter_map(|entry| {
+                let mut fields = entry.trim_start_matches('\n').splitn(4, '\x1f');
+                let id = fields.next().filter(|id| !id.is_empty())?.to_string();
+                let committed_at = fields.next()?.parse().ok()?;
+                let message = fields.next()?.to_string();
+                let details = fields
+                    .next()
+                    .unwrap_or_default()
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(str::to_string)
+                    .collect();
+                Some(ConfigCommit {
+                    id,
+                    message,
+                    details,
+                    committed_at,
+                })
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Restores the session library as of `commit` and records the rollback as
+/// a new commit. Credentials of sessions that still exist are kept. Emits
+/// `config-rolled-back` so the frontend reloads.
+#[command]
+pub async fn config_rollback(app: AppHandle, commit: String) -> Result<Option<String>, String> {
+    if commit.is_empty() || commit.len() > 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid commit: {}", commit));
+    }
+    let id = tokio::task::spawn_blocking(move || {
+        let snapshot = {
+            let _guard = REPO_LOCK.lock().map_err(|e| e.to_string())?;
+            let dir = repo_dir()?;
+            let spec = format!("{}:{}", commit, LIBRARY_FILE);
+            parse_snapshot(&git(&dir, &["show", &spec])?)?
+        };
+        crate::db::restore_library(&snapshot)?;
+        let short = &commit[..commit.len().min(8)];
+        commit_change(Some(format!("Roll back to {}", short)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let _ = app.emit("config-rolled-back", &id);
+    Ok(id)
+}
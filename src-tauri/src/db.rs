@@ -2,6 +2,7 @@ use once_cell::sync::Lazy;
 use rusqlite::types::ToSql;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use uuid::Uuid;
 
@@ -16,10 +17,18 @@ static DB_PATH: Lazy<Result<PathBuf, String>> = Lazy::new(|| {
 });
 
 /// Get the cached database path, creating the app data directory if needed.
-fn db_path() -> Result<&'static PathBuf, String> {
+pub fn db_path() -> Result<&'static PathBuf, String> {
     DB_PATH.as_ref().map_err(|e| e.clone())
 }
 
+/// App data directory holding the database and other persisted files.
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    db_path()?
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or_else(|| "Failed to determine app data directory".to_string())
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Session {
     pub id: String,
@@ -33,6 +42,67 @@ pub struct Session {
     pub last_connected_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// MAC address used to wake the host with a Wake-on-LAN packet
+    #[serde(default)]
+    pub mac_address: Option<String>,
+    /// Last health check result: "online" or "offline"
+    #[serde(default)]
+    pub health_status: Option<String>,
+    /// When the last health check ran
+    #[serde(default)]
+    pub health_checked_at: Option<String>,
+    /// OpenSSH user certificate (`*-cert.pub`) offered with the private key
+    #[serde(default)]
+    pub certificate_path: Option<String>,
+    /// Template sessions prefill new sessions created with `duplicate_session`
+    #[serde(default)]
+    pub is_template: bool,
+    #[serde(default)]
+    pub appearance: Appearance,
+}
+
+/// How a session or group is set apart in the UI, e.g. a red tab for
+/// production hosts. Unset fields fall back to the session's group, then
+/// to the defaults.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Appearance {
+    /// Tab and list color as `#rrggbb`
+    pub color: Option<String>,
+    /// Name of an icon of the frontend's icon set
+    pub icon: Option<String>,
+    /// Terminal theme used instead of the global one
+    pub terminal_theme: Option<String>,
+}
+
+/// Columns selected for a `Session`, in the order read by `session_from_row`.
+/// Queries alias the sessions table as `s`.
+const SESSION_COLUMNS: &str = "s.id, s.addr, s.port, s.server_name, s.username, s.auth_type, s.private_key_path, s.is_favorite, s.last_connected_at, s.created_at, s.updated_at, s.mac_address, s.health_status, s.health_checked_at, s.certificate_path, s.is_template, s.color, s.icon, s.terminal_theme";
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: row.get("id")?,
+        addr: row.get("addr")?,
+        port: row.get("port")?,
+        server_name: row.get("server_name")?,
+        username: row.get("username")?,
+        auth_type: row.get("auth_type")?,
+        private_key_path: row.get("private_key_path")?,
+        is_favorite: row.get::<_, i64>("is_favorite")? != 0,
+        last_connected_at: row.get("last_connected_at")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        mac_address: row.get("mac_address")?,
+        health_status: row.get("health_status")?,
+        health_checked_at: row.get("health_checked_at")?,
+        certificate_path: row.get("certificate_path")?,
+        is_template: row.get::<_, i64>("is_template")? != 0,
+        appearance: Appearance {
+            color: row.get("color")?,
+            icon: row.get("icon")?,
+            terminal_theme: row.get("terminal_theme")?,
+        },
+    })
 }
 
 /// Represents a persisted group for organizing sessions.
@@ -48,6 +118,29 @@ pub struct Group {
     pub created_at: String,
     /// Last update timestamp (set by SQLite DEFAULT CURRENT_TIMESTAMP)
     pub updated_at: String,
+    /// Appearance of the group's sessions that do not set their own
+    #[serde(default)]
+    pub appearance: Appearance,
+}
+
+/// Columns selected for a `Group`, in the order read by `group_from_row`.
+/// Queries alias the groups table as `g`.
+const GROUP_COLUMNS: &str =
+    "g.id, g.name, g.sort, g.created_at, g.updated_at, g.color, g.icon, g.terminal_theme";
+
+fn group_from_row(row: &rusqlite::Row) -> rusqlite::Result<Group> {
+    Ok(Group {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        sort: row.get("sort")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        appearance: Appearance {
+            color: row.get("color")?,
+            icon: row.get("icon")?,
+            terminal_theme: row.get("terminal_theme")?,
+        },
+    })
 }
 
 /// Represents a persisted tag.
@@ -67,7 +160,296 @@ pub struct Tag {
     pub updated_at: String,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Adds new and imported sessions whose address or name matches a pattern
+/// to a group and/or tag, e.g. `\.prod\.example\.com$` to "Production".
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssignmentRule {
+    /// UUID primary key (string)
+    pub id: String,
+    pub name: String,
+    /// Field matched: "addr", "server_name" or "any" (either of them)
+    pub field: String,
+    /// Regular expression, matched case-insensitively
+    pub pattern: String,
+    pub group_id: Option<String>,
+    pub tag_id: Option<String>,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// An expect-style automation rule evaluated against session output.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Trigger {
+    /// UUID primary key (string)
+    pub id: String,
+    /// Owning session; `None` applies the trigger to every session
+    pub session_id: Option<String>,
+    pub name: String,
+    /// Regular expression matched against output lines
+    pub pattern: String,
+    /// Action kind: "send" (text as-is), "run" (text followed by Enter) or "notify"
+    pub action: String,
+    /// Text sent by "send"/"run", message for "notify"
+    pub action_value: String,
+    pub enabled: bool,
+    /// Minimum time between two firings of this trigger
+    pub cooldown_ms: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A highlight rule tagging matching output so the terminal can style it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighlightRule {
+    /// UUID primary key (string)
+    pub id: String,
+    /// Owning session; `None` applies the rule to every session
+    pub session_id: Option<String>,
+    pub name: String,
+    /// Regular expression matched against the visible output text
+    pub pattern: String,
+    /// Style tag reported with each match (e.g. "error", "warning")
+    pub tag: String,
+    /// Count the output lines the rule matches
+    pub count: bool,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A step of a workflow. Which fields are used depends on `kind`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WorkflowStep {
+    pub name: String,
+    /// "command", "upload", "wait" or "prompt"
+    pub kind: String,
+    /// Command to run ("command"), or to re-run until its output matches
+    /// `pattern` ("wait")
+    pub command: String,
+    /// Local file to upload ("upload")
+    pub local_path: String,
+    /// Destination on the server ("upload")
+    pub remote_path: String,
+    /// Regex the output of `command` must match ("wait")
+    pub pattern: String,
+    /// Question the user confirms before the workflow goes on ("prompt")
+    pub message: String,
+    /// Seconds before the step fails; 0 uses the default of its kind
+    pub timeout_secs: u64,
+}
+
+/// An ordered list of steps run against one or more sessions.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Workflow {
+    /// UUID primary key (string)
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub steps: Vec<WorkflowStep>,
+    /// Stop on every session once a step fails on one; otherwise only the
+    /// failing session stops
+    pub abort_on_failure: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A snippet command, workflow or config backup run on a cron schedule
+/// while the app is running.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduledTask {
+    /// UUID primary key (string)
+    pub id: String,
+    pub name: String,
+    /// "command", "workflow" or "backup", whose output is kept as a
+    /// snapshot when it changed (e.g. `show running-config`)
+    pub kind: String,
+    /// Command run on an exec channel ("command" and "backup")
+    pub command: String,
+    /// Workflow to run ("workflow")
+    pub workflow_id: String,
+    /// Saved sessions the task runs on, each over a fresh connection
+    pub session_ids: Vec<String>,
+    /// Five-field cron expression in local time, e.g. `30 2 * * *`
+    pub schedule: String,
+    pub enabled: bool,
+    /// Unix timestamp (ms) of the last run
+    pub last_run_at: Option<i64>,
+    /// "ok" or a summary of the failures of the last run
+    pub last_status: Option<String>,
+    /// Unix timestamp (ms) of the next run; `None` when disabled
+    pub next_run_at: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// The outcome of a scheduled task on one session.
+#[derive(Serialize, Clone)]
+pub struct ScheduledRun {
+    pub id: i64,
+    pub task_id: String,
+    pub session_id: String,
+    /// Unix timestamps (ms)
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// "ok" or "error"
+    pub status: String,
+    /// Command output (truncated); empty for workflows, and whether a
+    /// snapshot was stored for backups
+    pub output: String,
+    pub error: Option<String>,
+}
+
+/// A monitoring alert rule compared against every server status reading.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AlertRule {
+    /// UUID primary key (string)
+    pub id: String,
+    /// Owning session; `None` applies the rule to every session
+    pub session_id: Option<String>,
+    pub name: String,
+    /// "cpu", "memory", "swap", "disk", "load", "netUp", "netDown" or
+    /// "latency"
+    pub metric: String,
+    /// ">", ">=", "<" or "<="
+    pub operator: String,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires
+    pub duration_ms: i64,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A fired monitoring alert.
+#[derive(Serialize, Clone)]
+pub struct Alert {
+    pub id: i64,
+    pub rule_id: String,
+    /// Saved session the connection was opened from, if any
+    pub session_id: Option<String>,
+    /// `user@host` of the connection
+    pub host: String,
+    pub name: String,
+    pub metric: String,
+    pub operator: String,
+    pub threshold: f64,
+    /// Reading that completed the breach
+    pub value: f64,
+    /// Unix timestamps (ms)
+    pub triggered_at: i64,
+    /// Set once the condition no longer holds
+    pub resolved_at: Option<i64>,
+    pub acknowledged_at: Option<i64>,
+}
+
+/// Environment applied to the remote shell when a saved session connects.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionEnvironment {
+    /// Variables requested with `setenv` before the shell starts. Servers
+    /// only accept names allowed by their `AcceptEnv` setting.
+    pub env_vars: std::collections::BTreeMap<String, String>,
+    /// Commands typed into the shell once it has started
+    pub startup_commands: Vec<String>,
+    /// Existing tmux/screen sessions on connect: "attach" resumes the most
+    /// recently used detached one, "ask" emits `resume-available`; empty
+    /// leaves them alone
+    pub resume: String,
+    /// Character set of the terminal: empty for UTF-8, "auto" to detect it
+    /// from the output, or a label such as "gbk", "big5" or "latin1"
+    pub encoding: String,
+    /// TERM requested with the PTY; empty uses the `terminal.term` setting
+    pub term: String,
+    /// Reply sent when the server sends ENQ (0x05); empty sends nothing
+    pub answerback: String,
+    /// PTY size used while the terminal view has no size yet; 0 uses the
+    /// `terminal.defaultCols` / `terminal.defaultRows` settings
+    pub cols: u16,
+    pub rows: u16,
+    /// Minutes without input before the session is locked or disconnected;
+    /// `None` uses the `security.idleTimeoutMinutes` setting, 0 never
+    pub idle_timeout_minutes: Option<u32>,
+    /// "lock" or "disconnect"; empty uses the `security.idleAction` setting
+    pub idle_action: String,
+}
+
+/// Field overrides applied to a copy made by `duplicate_session`.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionOverrides {
+    pub addr: Option<String>,
+    pub port: Option<i64>,
+    pub server_name: Option<String>,
+    pub username: Option<String>,
+    pub auth_type: Option<String>,
+    pub private_key_path: Option<String>,
+    pub certificate_path: Option<String>,
+    pub mac_address: Option<String>,
+    pub is_favorite: Option<bool>,
+    /// Keep the copy as a template; copies are regular sessions by default
+    pub is_template: Option<bool>,
+}
+
+/// Metadata of a named secret stored for a session. The value itself is
+/// only returned by `get_session_secret`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionSecret {
+    pub session_id: String,
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// When the value was last read with `get_session_secret`
+    pub last_accessed_at: Option<String>,
+}
+
+/// Group summary embedded in `SessionDetailed`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GroupRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// Tag summary embedded in `SessionDetailed`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TagRef {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+}
+
+/// A session with its groups and tags, returned by `get_sessions_detailed`.
+#[derive(Serialize, Clone)]
+pub struct SessionDetailed {
+    #[serde(flatten)]
+    pub session: Session,
+    pub groups: Vec<GroupRef>,
+    pub tags: Vec<TagRef>,
+}
+
+/// Changes applied by `bulk_edit_sessions`. Unset fields are left alone.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionPatch {
+    /// Move the sessions to this group, replacing their current groups
+    pub move_to_group_id: Option<String>,
+    pub add_tag_ids: Vec<String>,
+    pub remove_tag_ids: Vec<String>,
+    pub username: Option<String>,
+    pub port: Option<i64>,
+    pub is_favorite: Option<bool>,
+}
+
+/// Outcome of a bulk operation for one session.
+#[derive(Serialize, Clone)]
+pub struct BulkResult {
+    pub id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ExportSession {
     pub metadata: Session,
     pub encrypted_credentials: Option<String>,
@@ -118,6 +500,31 @@ pub fn init_db() -> Result<String, String> {
         [],
     );
     let _ = conn.execute("ALTER TABLE sessions ADD COLUMN last_connected_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN mac_address TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN health_status TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN health_checked_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN knock_sequence TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN advanced_options TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN certificate_path TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE sessions ADD COLUMN is_template INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN environment TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE sessions ADD COLUMN credential_backend TEXT NOT NULL DEFAULT 'vault'",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN clipboard_access TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN cloud_source TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE sessions ADD COLUMN protected INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN color TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN icon TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN terminal_theme TEXT", []);
+    let _ = conn.execute("ALTER TABLE sessions ADD COLUMN pin_position INTEGER", []);
 
     // Data migration: fill last_connected_at with updated_at for existing sessions that were never connected
     let _ = conn.execute(
@@ -127,6 +534,33 @@ pub fn init_db() -> Result<String, String> {
 
     // Ensure groups/tags and junction tables exist.
     ensure_groups_and_tags(&conn)?;
+    ensure_triggers(&conn)?;
+    ensure_assignment_rules(&conn)?;
+    ensure_highlight_rules(&conn)?;
+    ensure_connection_history(&conn)?;
+    ensure_usage_history(&conn)?;
+    ensure_session_secrets(&conn)?;
+    ensure_credential_gate(&conn)?;
+    ensure_settings(&conn)?;
+    ensure_sync(&conn)?;
+    ensure_clipboard_history(&conn)?;
+    ensure_recent_remote_paths(&conn)?;
+    ensure_cloud_accounts(&conn)?;
+    ensure_workflows(&conn)?;
+    ensure_scheduled_tasks(&conn)?;
+    ensure_alerts(&conn)?;
+    ensure_metrics_history(&conn)?;
+    prune_metrics_history(&conn)?;
+
+    // Seed the history once from existing connect timestamps so suggestions
+    // are useful right after upgrading
+    let _ = conn.execute(
+        "INSERT INTO connection_history (session_id, connected_at)
+         SELECT id, CAST(strftime('%s', last_connected_at) AS INTEGER) FROM sessions
+         WHERE last_connected_at IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM connection_history)",
+        [],
+    );
 
     // Create useful indexes to speed up common queries (no foreign-key
     // constraints; indexes only).
@@ -177,6 +611,10 @@ pub fn add_session(
         params![id, addr, port, server_name, username, auth_type, private_key_path],
     )
     .map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let rules = load_assignment_rules(&conn)?;
+    assign_by_rules(&conn, &rules, &id, &addr, &server_name)?;
+    crate::config_history::schedule_commit();
     Ok(id)
 }
 
@@ -193,10 +631,15 @@ pub fn add_session(
 /// * `is_favorite` - Whether the session is favorited (optional)
 /// * `group_ids` - List of group IDs to associate with this session (optional)
 /// * `tag_ids` - List of tag IDs to associate with this session (optional)
+/// * `mac_address` - MAC address for Wake-on-LAN (optional; kept on update
+///   when absent, cleared when empty)
+/// * `certificate_path` - OpenSSH user certificate for key auth (optional;
+///   kept on update when absent, cleared when empty)
 ///
 /// # Returns
 /// The UUID of the newly created session
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn save_session_with_credentials(
     id: Option<String>,
     addr: String,
@@ -210,7 +653,17 @@ pub fn save_session_with_credentials(
     is_favorite: Option<bool>,
     group_ids: Option<Vec<String>>,
     tag_ids: Option<Vec<String>>,
+    mac_address: Option<String>,
+    certificate_path: Option<String>,
 ) -> Result<String, String> {
+    // Like the MAC address: absent keeps, empty clears
+    let certificate_path = certificate_path.map(|p| Some(p).filter(|p| !p.trim().is_empty()));
+    // Absent keeps the stored address on update; empty clears it
+    let mac_address = match mac_address {
+        Some(m) if m.trim().is_empty() => Some(None),
+        Some(m) => Some(Some(crate::network::normalize_mac(&m)?)),
+        None => None,
+    };
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     ensure_groups_and_tags(&conn)?;
@@ -218,19 +671,36 @@ pub fn save_session_with_credentials(
     let is_update = id.is_some();
     let session_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
-    // 0. Encrypt sensitive information if present
-    let encrypted_credentials = if password.is_some() || key_passphrase.is_some() {
-        let sensitive = crate::encryption::SensitiveData {
+    // 0. Encrypt sensitive information if present, or hand it to the
+    // keychain for sessions stored there
+    let sensitive = (password.is_some() || key_passphrase.is_some()).then(|| {
+        crate::encryption::SensitiveData {
             password: password.clone(),
             key_passphrase: key_passphrase.clone(),
-        };
-        Some(crate::encryption::EncryptionManager::encrypt(&sensitive)?)
+        }
+    });
+    let backend = if is_update {
+        credential_backend(&conn, &session_id)?
     } else {
-        None
+        CredentialBackend::Vault
+    };
+    let encrypted_credentials = match (backend, &sensitive) {
+        (CredentialBackend::Keychain, Some(data)) => {
+            crate::keychain::write(&session_id, data)?;
+            None
+        }
+        (CredentialBackend::Keychain, None) => {
+            crate::keychain::delete(&session_id)?;
+            None
+        }
+        (CredentialBackend::Vault, Some(data)) => {
+            Some(crate::encryption::EncryptionManager::encrypt(data)?)
+        }
+        (CredentialBackend::Vault, None) => None,
     };
 
-    println!(
-        "[save_session_with_credentials] {} session: {}",
+    tracing::debug!(
+        "{} session: {}",
         if is_update { "Updating" } else { "Saving new" },
         session_id
     );
@@ -248,6 +718,18 @@ pub fn save_session_with_credentials(
             Box::new(encrypted_credentials),
         ];
 
+        if let Some(mac) = mac_address {
+            sql.push_str(", mac_address = ?");
+            sql.push_str(&(params_vec.len() + 1).to_string());
+            params_vec.push(Box::new(mac));
+        }
+
+        if let Some(path) = certificate_path {
+            sql.push_str(", certificate_path = ?");
+            sql.push_str(&(params_vec.len() + 1).to_string());
+            params_vec.push(Box::new(path));
+        }
+
         if let Some(fav) = is_favorite {
             sql.push_str(", is_favorite = ?");
             sql.push_str(&(params_vec.len() + 1).to_string());
@@ -275,10 +757,14 @@ pub fn save_session_with_credentials(
         .map_err(|e| e.to_string())?;
     } else {
         conn.execute(
-            "INSERT INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![session_id, addr, port, server_name, username, auth_type, private_key_path, if is_favorite.unwrap_or(false) { 1 } else { 0 }, encrypted_credentials],
+            "INSERT INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials, mac_address, certificate_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![session_id, addr, port, server_name, username, auth_type, private_key_path, if is_favorite.unwrap_or(false) { 1 } else { 0 }, encrypted_credentials, mac_address.flatten(), certificate_path.flatten()],
         ).map_err(|e| e.to_string())?;
+
+        // 2. Organize by assignment rules; edits keep the user's choice
+        let rules = load_assignment_rules(&conn)?;
+        assign_by_rules(&conn, &rules, &session_id, &addr, &server_name)?;
     }
 
     // 3. Associate with groups
@@ -303,6 +789,7 @@ pub fn save_session_with_credentials(
         }
     }
 
+    crate::config_history::schedule_commit();
     Ok(session_id)
 }
 
@@ -313,28 +800,215 @@ pub fn save_session_with_credentials(
 ///
 /// # Returns
 /// Tuple of (session_id, password_option, key_passphrase_option)
+///
+/// When biometric unlock is enabled, stored secrets are only returned after
+/// a Touch ID / Windows Hello confirmation (or within its grace period).
+/// Protected sessions always require re-authentication.
 #[tauri::command]
 #[allow(non_snake_case)]
-pub fn get_session_credentials(
+pub async fn get_session_credentials(
     sessionId: String,
+    masterPassword: Option<String>,
 ) -> Result<(String, Option<String>, Option<String>), String> {
+    tokio::task::spawn_blocking(move || match load_session_credentials(&sessionId)? {
+        Some(credentials) => {
+            crate::biometric::confirm_protected_session(&sessionId, masterPassword.as_deref())?;
+            crate::biometric::confirm("access saved credentials")?;
+            Ok((sessionId, credentials.password, credentials.key_passphrase))
+        }
+        None => Ok((sessionId, None, None)),
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Load and decrypt the stored credentials of a saved session.
+/// Backend-only helper; callers must never log the result.
+pub fn load_session_credentials(
+    session_id: &str,
+) -> Result<Option<crate::encryption::SensitiveData>, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    read_credentials(&conn, session_id)
+}
+
+/// Where a session's credentials are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialBackend {
+    /// Encrypted in the `encrypted_credentials` column
+    Vault,
+    /// In the OS keychain, keyed by session id
+    Keychain,
+}
+
+impl CredentialBackend {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "vault" => Ok(Self::Vault),
+            "keychain" => Ok(Self::Keychain),
+            other => Err(format!("Unknown credential backend: {}", other)),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Vault => "vault",
+            Self::Keychain => "keychain",
+        }
+    }
+}
 
-    let encrypted_credentials: Option<String> = conn
+fn credential_backend(conn: &Connection, session_id: &str) -> Result<CredentialBackend, String> {
+    let backend: String = conn
         .query_row(
-            "SELECT encrypted_credentials FROM sessions WHERE id = ?1",
-            params![sessionId],
+            "SELECT credential_backend FROM sessions WHERE id = ?1",
+            params![session_id],
             |row| row.get(0),
         )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Session not found: {}", session_id),
+            e => e.to_string(),
+        })?;
+    CredentialBackend::parse(&backend)
+}
+
+/// Whether a session keeps its credentials in the OS keychain
+fn uses_keychain(conn: &Connection, session_id: &str) -> bool {
+    credential_backend(conn, session_id).ok() == Some(CredentialBackend::Keychain)
+}
+
+/// Remove the keychain entries of deleted sessions. Called only once the
+/// deletion is committed, so a rolled back one keeps working credentials; a
+/// failure merely leaves an orphaned entry behind.
+fn delete_keychain_entries(session_ids: &[String]) {
+    for id in session_ids {
+        if let Err(e) = crate::keychain::delete(id) {
+            tracing::warn!(
+                "failed to remove keychain entry of deleted session {}: {}",
+                id,
+                e
+            );
+        }
+    }
+}
+
+/// Read a session's credentials from whichever backend holds them.
+fn read_credentials(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Option<crate::encryption::SensitiveData>, String> {
+    let (encrypted_credentials, backend): (Option<String>, String) = conn
+        .query_row(
+            "SELECT encrypted_credentials, credential_backend FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
         .map_err(|e| e.to_string())?;
 
-    if let Some(encrypted) = encrypted_credentials {
-        let credentials = crate::encryption::EncryptionManager::decrypt(&encrypted)?;
-        Ok((sessionId, credentials.password, credentials.key_passphrase))
-    } else {
-        Ok((sessionId, None, None))
+    match CredentialBackend::parse(&backend)? {
+        CredentialBackend::Keychain => crate::keychain::read(session_id),
+        CredentialBackend::Vault => encrypted_credentials
+            .map(|encrypted| crate::encryption::EncryptionManager::decrypt(&encrypted))
+            .transpose(),
+    }
+}
+
+/// Result of checking one session's stored credentials.
+#[derive(Serialize, Clone)]
+pub struct CredentialCheck {
+    pub session_id: String,
+    pub server_name: String,
+    /// "vault" or "keychain"
+    pub backend: String,
+    /// "ok", "missing" (password auth without a stored password) or
+    /// "unreadable"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// Check every session's stored credentials so broken entries (deleted
+/// keychain items, data encrypted on another machine) can be fixed before
+/// connecting. Only sessions with a problem are returned.
+#[tauri::command]
+pub fn verify_credentials() -> Result<Vec<CredentialCheck>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut problems = Vec::new();
+    for session in list_sessions()? {
+        let backend = credential_backend(&conn, &session.id)?;
+        let (status, error) = match read_credentials(&conn, &session.id) {
+            Ok(Some(_)) => continue,
+            Ok(None) if session.auth_type == "password" => ("missing", None),
+            Ok(None) => continue,
+            Err(e) => ("unreadable", Some(e)),
+        };
+        problems.push(CredentialCheck {
+            session_id: session.id,
+            server_name: session.server_name,
+            backend: backend.as_str().to_string(),
+            status: status.to_string(),
+            error,
+        });
+    }
+    Ok(problems)
+}
+
+/// Move all stored credentials to `target_backend` ("keychain" or "vault").
+///
+/// Each session is moved independently: its credentials are written to the
+/// target first and only then removed from the source, so a failure leaves
+/// that session on its current backend.
+#[tauri::command]
+pub fn migrate_credentials(target_backend: String) -> Result<Vec<BulkResult>, String> {
+    let target = CredentialBackend::parse(&target_backend)?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut results = Vec::new();
+    for session in list_sessions()? {
+        if credential_backend(&conn, &session.id)? == target {
+            continue;
+        }
+        let outcome = (|| -> Result<(), String> {
+            let credentials = read_credentials(&conn, &session.id)?;
+            match target {
+                CredentialBackend::Keychain => {
+                    if let Some(data) = &credentials {
+                        crate::keychain::write(&session.id, data)?;
+                    }
+                    conn.execute(
+                        "UPDATE sessions SET credential_backend = 'keychain', encrypted_credentials = NULL WHERE id = ?1",
+                        params![session.id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                CredentialBackend::Vault => {
+                    let encrypted = credentials
+                        .as_ref()
+                        .map(crate::encryption::EncryptionManager::encrypt)
+                        .transpose()?;
+                    conn.execute(
+                        "UPDATE sessions SET credential_backend = 'vault', encrypted_credentials = ?1 WHERE id = ?2",
+                        params![encrypted, session.id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    if let Err(e) = crate::keychain::delete(&session.id) {
+                        tracing::warn!(
+                            "failed to remove migrated keychain entry of {}: {}",
+                            session.id,
+                            e
+                        );
+                    }
+                }
+            }
+            Ok(())
+        })();
+        results.push(BulkResult {
+            id: session.id,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
     }
+    Ok(results)
 }
 
 /// Save a new SSH session with groups and tags associations.
@@ -379,6 +1053,8 @@ pub fn save_session(
         params![id, addr, port, server_name, username, auth_type, private_key_path, if is_favorite.unwrap_or(false) { 1 } else { 0 }],
     )
     .map_err(|e| e.to_string())?;
+    let rules = load_assignment_rules(&conn)?;
+    assign_by_rules(&conn, &rules, &id, &addr, &server_name)?;
 
     // Associate with groups
     if let Some(groups) = group_ids {
@@ -402,6 +1078,7 @@ pub fn save_session(
         }
     }
 
+    crate::config_history::schedule_commit();
     Ok(id)
 }
 
@@ -414,6 +1091,7 @@ pub fn toggle_favorite(id: String, is_favorite: bool) -> Result<(), String> {
         params![if is_favorite { 1 } else { 0 }, id],
     )
     .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
@@ -425,6 +1103,20 @@ pub fn update_session_timestamp(id: String) -> Result<(), String> {
         "UPDATE sessions SET last_connected_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
         params![id],
     ).map_err(|e| e.to_string())?;
+    ensure_connection_history(&conn)?;
+    conn.execute(
+        "INSERT INTO connection_history (session_id, connected_at) VALUES (?1, CAST(strftime('%s', 'now') AS INTEGER))",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM connection_history WHERE session_id = ?1 AND id NOT IN (
+            SELECT id FROM connection_history WHERE session_id = ?1
+            ORDER BY connected_at DESC, id DESC LIMIT ?2
+        )",
+        params![id, MAX_CONNECTION_HISTORY],
+    )
+    .map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -434,25 +1126,11 @@ pub fn list_sessions() -> Result<Vec<Session>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     let mut stmt = conn
         .prepare(
-            "SELECT id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, last_connected_at, created_at, updated_at FROM sessions",
+            &format!("SELECT {} FROM sessions s", SESSION_COLUMNS),
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([], |row| {
-            Ok(Session {
-                id: row.get(0)?,
-                addr: row.get(1)?,
-                port: row.get(2)?,
-                server_name: row.get(3)?,
-                username: row.get(4)?,
-                auth_type: row.get(5)?,
-                private_key_path: row.get(6)?,
-                is_favorite: row.get::<_, i64>(7)? != 0,
-                last_connected_at: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })
+        .query_map([], session_from_row)
         .map_err(|e| e.to_string())?;
     let mut v = Vec::new();
     for r in rows {
@@ -461,52 +1139,120 @@ pub fn list_sessions() -> Result<Vec<Session>, String> {
     Ok(v)
 }
 
-/// Retrieve sessions with optional filters.
+/// Filters shared by `get_sessions` and `count_sessions`.
 ///
-/// All parameters are optional; when none are provided the full table is
-/// returned. Filters:
-/// - `group_id`: returns sessions belonging to the specified group
-/// - `tag_id`: returns sessions tagged with the specified tag
-/// - `id`: filter by primary key
-/// - `server_name`: partial match on `server_name` (LIKE)
-/// - `host_addr`: partial match on `addr` (LIKE)
-#[tauri::command]
-pub fn get_sessions(
-    group_id: Option<String>,
-    tag_id: Option<String>,
-    id: Option<String>,
-    server_name: Option<String>,
-    host_addr: Option<String>,
-) -> Result<Vec<Session>, String> {
-    let db_path = db_path()?;
-    let mut sql = String::from("SELECT DISTINCT s.id, s.addr, s.port, s.server_name, s.username, s.auth_type, s.private_key_path, s.is_favorite, s.last_connected_at, s.created_at, s.updated_at FROM sessions s");
-    if group_id.is_some() {
-        sql.push_str(" JOIN session_groups sg ON s.id = sg.session_id");
+/// Group and tag conditions are combined with AND. Within each, `*_match`
+/// selects whether a session needs "any" (default) or "all" of the listed
+/// ids. `ungrouped` / `untagged` select sessions without any group / tag;
+/// with "any" they widen the listed ids (OR), with "all" they narrow them.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionFilter {
+    pub group_id: Option<String>,
+    pub tag_id: Option<String>,
+    pub id: Option<String>,
+    pub server_name: Option<String>,
+    pub host_addr: Option<String>,
+    pub group_ids: Vec<String>,
+    pub tag_ids: Vec<String>,
+    /// "any" or "all"
+    pub group_match: Option<String>,
+    /// "any" or "all"
+    pub tag_match: Option<String>,
+    pub ungrouped: bool,
+    pub untagged: bool,
+}
+
+/// Build the condition for a group or tag filter against a junction table.
+fn membership_clause(
+    table: &str,
+    column: &str,
+    ids: &[String],
+    match_mode: Option<&str>,
+    without_any: bool,
+    params_vec: &mut Vec<Box<dyn ToSql>>,
+) -> Result<Option<String>, String> {
+    let match_all = match match_mode.unwrap_or("any") {
+        "any" => false,
+        "all" => true,
+        other => return Err(format!("Unknown match mode: {}", other)),
+    };
+    let mut parts: Vec<String> = Vec::new();
+    if !ids.is_empty() {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        parts.push(if match_all {
+            format!(
+                "(SELECT COUNT(DISTINCT j.{col}) FROM {table} j WHERE j.session_id = s.id AND j.{col} IN ({ph})) = {n}",
+                col = column,
+                table = table,
+                ph = placeholders,
+                n = ids.len()
+            )
+        } else {
+            format!(
+                "EXISTS (SELECT 1 FROM {table} j WHERE j.session_id = s.id AND j.{col} IN ({ph}))",
+                col = column,
+                table = table,
+                ph = placeholders
+            )
+        });
+        for id in ids {
+            params_vec.push(Box::new(id.clone()));
+        }
     }
-    if tag_id.is_some() {
-        sql.push_str(" JOIN session_tags st ON s.id = st.session_id");
+    if without_any {
+        parts.push(format!(
+            "NOT EXISTS (SELECT 1 FROM {} j WHERE j.session_id = s.id)",
+            table
+        ));
     }
+    if parts.is_empty() {
+        return Ok(None);
+    }
+    let joiner = if match_all { " AND " } else { " OR " };
+    Ok(Some(format!("({})", parts.join(joiner))))
+}
 
+/// Build the `FROM ... WHERE ...` part of a session query for `filter`.
+fn session_filter_sql(filter: &SessionFilter) -> Result<(String, Vec<Box<dyn ToSql>>), String> {
+    let mut sql = "FROM sessions s".to_string();
     let mut where_clauses: Vec<String> = Vec::new();
     let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
 
-    if let Some(gid) = group_id {
-        where_clauses.push("sg.group_id = ?".to_string());
-        params_vec.push(Box::new(gid));
+    let mut group_ids = filter.group_ids.clone();
+    group_ids.extend(filter.group_id.clone());
+    let mut tag_ids = filter.tag_ids.clone();
+    tag_ids.extend(filter.tag_id.clone());
+
+    if let Some(clause) = membership_clause(
+        "session_groups",
+        "group_id",
+        &group_ids,
+        filter.group_match.as_deref(),
+        filter.ungrouped,
+        &mut params_vec,
+    )? {
+        where_clauses.push(clause);
     }
-    if let Some(tid) = tag_id {
-        where_clauses.push("st.tag_id = ?".to_string());
-        params_vec.push(Box::new(tid));
+    if let Some(clause) = membership_clause(
+        "session_tags",
+        "tag_id",
+        &tag_ids,
+        filter.tag_match.as_deref(),
+        filter.untagged,
+        &mut params_vec,
+    )? {
+        where_clauses.push(clause);
     }
-    if let Some(pid) = id {
+    if let Some(pid) = &filter.id {
         where_clauses.push("s.id = ?".to_string());
-        params_vec.push(Box::new(pid));
+        params_vec.push(Box::new(pid.clone()));
     }
-    if let Some(name) = server_name {
+    if let Some(name) = &filter.server_name {
         where_clauses.push("s.server_name LIKE ?".to_string());
         params_vec.push(Box::new(format!("%{}%", name)));
     }
-    if let Some(addr) = host_addr {
+    if let Some(addr) = &filter.host_addr {
         where_clauses.push("s.addr LIKE ?".to_string());
         params_vec.push(Box::new(format!("%{}%", addr)));
     }
@@ -515,6 +1261,107 @@ pub fn get_sessions(
         sql.push_str(" WHERE ");
         sql.push_str(&where_clauses.join(" AND "));
     }
+    Ok((sql, params_vec))
+}
+
+/// `ORDER BY` clause for a `get_sessions` sort key.
+fn session_order_sql(order_by: &str) -> Result<&'static str, String> {
+    match order_by {
+        "name" => Ok(" ORDER BY s.server_name COLLATE NOCASE, s.id"),
+        "last_used" => Ok(
+            " ORDER BY s.last_connected_at IS NULL, s.last_connected_at DESC, s.server_name COLLATE NOCASE, s.id",
+        ),
+        "created" => Ok(" ORDER BY s.created_at DESC, s.id"),
+        "favorite" => Ok(" ORDER BY s.is_favorite DESC, s.server_name COLLATE NOCASE, s.id"),
+        other => Err(format!("Unknown sort order: {}", other)),
+    }
+}
+
+/// `ORDER BY` clause for the "manual" sort key: the order set with
+/// `reorder_sessions` within the one group filtered on, or favorites first
+/// in their pinned order otherwise. Sessions without a position follow by
+/// name.
+fn manual_order_sql(filter: &SessionFilter, params_vec: &mut Vec<Box<dyn ToSql>>) -> String {
+    let groups: Vec<&String> = filter.group_ids.iter().chain(&filter.group_id).collect();
+    match groups.as_slice() {
+        [group_id] => {
+            params_vec.push(Box::new((*group_id).clone()));
+            " ORDER BY COALESCE(
+                (SELECT sg.position FROM session_groups sg WHERE sg.session_id = s.id AND sg.group_id = ?),
+                9223372036854775807
+             ), s.server_name COLLATE NOCASE, s.id"
+        }
+        _ => " ORDER BY s.is_favorite DESC, s.pin_position IS NULL, s.pin_position, s.server_name COLLATE NOCASE, s.id",
+    }
+    .to_string()
+}
+
+/// Build a session listing query selecting `columns`, with filtering,
+/// ordering and paging as described on `get_sessions`.
+fn session_query_sql(
+    columns: &str,
+    filter: &SessionFilter,
+    order_by: Option<&str>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<(String, Vec<Box<dyn ToSql>>), String> {
+    let (from_where, mut params_vec) = session_filter_sql(filter)?;
+    let mut sql = format!("SELECT {} {}", columns, from_where);
+
+    let paged = limit.is_some() || offset.is_some();
+    match order_by {
+        Some("manual") => sql.push_str(&manual_order_sql(filter, &mut params_vec)),
+        Some(order) => sql.push_str(session_order_sql(order)?),
+        None if paged => sql.push_str(session_order_sql("name")?),
+        None => {}
+    }
+    if paged {
+        sql.push_str(" LIMIT ? OFFSET ?");
+        params_vec.push(Box::new(limit.filter(|l| *l >= 0).unwrap_or(-1)));
+        params_vec.push(Box::new(offset.unwrap_or(0).max(0)));
+    }
+    Ok((sql, params_vec))
+}
+
+/// Retrieve sessions with optional filters.
+///
+/// All parameters are optional; when none are provided the full table is
+/// returned. Filters:
+/// - `group_id`: returns sessions belonging to the specified group
+/// - `tag_id`: returns sessions tagged with the specified tag
+/// - `id`: filter by primary key
+/// - `server_name`: partial match on `server_name` (LIKE)
+/// - `host_addr`: partial match on `addr` (LIKE)
+/// - `filter`: further filters, including multiple groups/tags with AND/OR
+///   matching and ungrouped/untagged sessions (see `SessionFilter`)
+///
+/// Paging and sorting:
+/// - `order_by`: "name", "last_used", "created", "favorite" (favorites
+///   first, then by name) or "manual" (the order set with
+///   `reorder_sessions`); paged queries default to "name"
+/// - `limit` / `offset`: page window; use `count_sessions` for the total
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn get_sessions(
+    group_id: Option<String>,
+    tag_id: Option<String>,
+    id: Option<String>,
+    server_name: Option<String>,
+    host_addr: Option<String>,
+    filter: Option<SessionFilter>,
+    order_by: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Session>, String> {
+    let db_path = db_path()?;
+    let mut filter = filter.unwrap_or_default();
+    filter.group_id = group_id.or(filter.group_id);
+    filter.tag_id = tag_id.or(filter.tag_id);
+    filter.id = id.or(filter.id);
+    filter.server_name = server_name.or(filter.server_name);
+    filter.host_addr = host_addr.or(filter.host_addr);
+    let (sql, params_vec) =
+        session_query_sql(SESSION_COLUMNS, &filter, order_by.as_deref(), limit, offset)?;
 
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
@@ -522,21 +1369,7 @@ pub fn get_sessions(
     // Convert boxed params to &[&dyn ToSql]
     let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
     let rows = stmt
-        .query_map(param_refs.as_slice(), |row| {
-            Ok(Session {
-                id: row.get(0)?,
-                addr: row.get(1)?,
-                port: row.get(2)?,
-                server_name: row.get(3)?,
-                username: row.get(4)?,
-                auth_type: row.get(5)?,
-                private_key_path: row.get(6)?,
-                is_favorite: row.get::<_, i64>(7)? != 0,
-                last_connected_at: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })
+        .query_map(param_refs.as_slice(), session_from_row)
         .map_err(|e| e.to_string())?;
 
     let mut v = Vec::new();
@@ -546,6 +1379,73 @@ pub fn get_sessions(
     Ok(v)
 }
 
+/// Retrieve sessions like `get_sessions`, each with its groups and tags
+/// inline, in a single query.
+#[tauri::command]
+pub fn get_sessions_detailed(
+    filter: Option<SessionFilter>,
+    order_by: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<SessionDetailed>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let columns = format!(
+        "{},
+         (SELECT json_group_array(json_object('id', x.id, 'name', x.name))
+            FROM (SELECT g.id, g.name FROM groups g
+                  JOIN session_groups sg ON g.id = sg.group_id
+                  WHERE sg.session_id = s.id ORDER BY g.sort, g.created_at) x) AS groups_json,
+         (SELECT json_group_array(json_object('id', x.id, 'name', x.name, 'color', x.color))
+            FROM (SELECT t.id, t.name, t.color FROM tags t
+                  JOIN session_tags st ON t.id = st.tag_id
+                  WHERE st.session_id = s.id ORDER BY t.sort, t.created_at) x) AS tags_json",
+        SESSION_COLUMNS
+    );
+    let (sql, params_vec) = session_query_sql(
+        &columns,
+        &filter.unwrap_or_default(),
+        order_by.as_deref(),
+        limit,
+        offset,
+    )?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    let rows = stmt
+        .query_map(param_refs.as_slice(), |row| {
+            Ok((
+                session_from_row(row)?,
+                row.get::<_, String>("groups_json")?,
+                row.get::<_, String>("tags_json")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut v = Vec::new();
+    for r in rows {
+        let (session, groups_json, tags_json) = r.map_err(|e| e.to_string())?;
+        v.push(SessionDetailed {
+            session,
+            groups: serde_json::from_str(&groups_json).map_err(|e| e.to_string())?,
+            tags: serde_json::from_str(&tags_json).map_err(|e| e.to_string())?,
+        });
+    }
+    Ok(v)
+}
+
+/// Count the sessions matching a filter (same semantics as `get_sessions`).
+#[tauri::command]
+pub fn count_sessions(filter: Option<SessionFilter>) -> Result<i64, String> {
+    let db_path = db_path()?;
+    let (from_where, params_vec) = session_filter_sql(&filter.unwrap_or_default())?;
+    let sql = format!("SELECT COUNT(*) {}", from_where);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.query_row(&sql, param_refs.as_slice(), |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
 /// Edit an existing group. Only provided fields are updated.
 #[tauri::command]
 pub fn edit_group(id: String, name: Option<String>, sort: Option<i64>) -> Result<(), String> {
@@ -572,6 +1472,7 @@ pub fn edit_group(id: String, name: Option<String>, sort: Option<i64>) -> Result
     let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
     conn.execute(&sql, param_refs.as_slice())
         .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
@@ -585,8 +1486,10 @@ pub fn delete_group(id: String) -> Result<(), String> {
         params![id.clone()],
     )
     .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM groups WHERE id = ?1", params![id])
+    conn.execute("DELETE FROM groups WHERE id = ?1", params![id.clone()])
         .map_err(|e| e.to_string())?;
+    record_tombstone(&conn, "group", &id)?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
@@ -624,6 +1527,7 @@ pub fn edit_tag(
     let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
     conn.execute(&sql, param_refs.as_slice())
         .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
@@ -637,13 +1541,16 @@ pub fn delete_tag(id: String) -> Result<(), String> {
         params![id.clone()],
     )
     .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM tags WHERE id = ?1", params![id])
+    conn.execute("DELETE FROM tags WHERE id = ?1", params![id.clone()])
         .map_err(|e| e.to_string())?;
+    record_tombstone(&conn, "tag", &id)?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
 /// Edit an existing session record. Only provided fields are updated.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn edit_session(
     id: String,
     addr: Option<String>,
@@ -653,6 +1560,8 @@ pub fn edit_session(
     auth_type: Option<String>,
     private_key_path: Option<Option<String>>,
     is_favorite: Option<bool>,
+    mac_address: Option<Option<String>>,
+    certificate_path: Option<Option<String>>,
 ) -> Result<(), String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
@@ -686,6 +1595,18 @@ pub fn edit_session(
         sets.push("is_favorite = ?".to_string());
         params_vec.push(Box::new(if fav { 1 } else { 0 }));
     }
+    if let Some(mac_opt) = mac_address {
+        let mac_opt = match mac_opt.filter(|m| !m.trim().is_empty()) {
+            Some(m) => Some(crate::network::normalize_mac(&m)?),
+            None => None,
+        };
+        sets.push("mac_address = ?".to_string());
+        params_vec.push(Box::new(mac_opt));
+    }
+    if let Some(cert_opt) = certificate_path {
+        sets.push("certificate_path = ?".to_string());
+        params_vec.push(Box::new(cert_opt.filter(|p| !p.trim().is_empty())));
+    }
     if sets.is_empty() {
         return Ok(());
     }
@@ -695,199 +1616,385 @@ pub fn edit_session(
     let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
     conn.execute(&sql, param_refs.as_slice())
         .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
-/// Delete a session and its logical associations.
-#[tauri::command]
-pub fn delete_session(id: String) -> Result<(), String> {
-    println!("delete_session called with id: {}", id);
+/// Load a single session by id (backend use).
+pub fn load_session(id: &str) -> Result<Session, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM sessions s WHERE s.id = ?1", SESSION_COLUMNS),
+        params![id],
+        session_from_row,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Session not found: {}", id),
+        e => e.to_string(),
+    })
+}
 
-    // Delete session_groups
-    let rows1 = conn
-        .execute(
-            "DELETE FROM session_groups WHERE session_id = ?1",
-            params![id.clone()],
-        )
-        .map_err(|e| e.to_string())?;
-    println!("Deleted {} rows from session_groups", rows1);
+/// Find a session by address, port and optionally username (backend use).
+/// Addresses compare case-insensitively; templates never match. The most
+/// recently used session wins when several match.
+pub fn find_session_by_address(
+    addr: &str,
+    port: i64,
+    username: Option<&str>,
+) -> Result<Option<Session>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let result = conn.query_row(
+        &format!(
+            "SELECT {} FROM sessions s
+             WHERE LOWER(s.addr) = LOWER(?1) AND s.port = ?2 AND (?3 IS NULL OR s.username = ?3)
+               AND s.is_template = 0
+             ORDER BY s.last_connected_at IS NULL, s.last_connected_at DESC, s.created_at
+             LIMIT 1",
+            SESSION_COLUMNS
+        ),
+        params![addr, port, username],
+        session_from_row,
+    );
+    match result {
+        Ok(session) => Ok(Some(session)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
 
-    // Delete session_tags
-    let rows2 = conn
-        .execute(
-            "DELETE FROM session_tags WHERE session_id = ?1",
-            params![id.clone()],
-        )
-        .map_err(|e| e.to_string())?;
-    println!("Deleted {} rows from session_tags", rows2);
+/// Record the result of a health check (backend use).
+pub fn update_session_health(id: &str, status: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE sessions SET health_status = ?1, health_checked_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![status, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Delete session
-    let rows3 = conn
-        .execute("DELETE FROM sessions WHERE id = ?1", params![id.clone()])
-        .map_err(|e| e.to_string())?;
-    println!("Deleted {} rows from sessions table", rows3);
+/// Get the port knocking sequence of a session (empty when none).
+#[tauri::command]
+pub fn get_knock_sequence(session_id: String) -> Result<Vec<crate::network::KnockStep>, String> {
+    load_knock_sequence(&session_id)
+}
 
-    println!("Session {} deleted successfully", id);
-    Ok(())
+/// Load the port knocking sequence of a session (backend use).
+pub fn load_knock_sequence(session_id: &str) -> Result<Vec<crate::network::KnockStep>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT knock_sequence FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
 }
 
-/// Create the `groups` and `tags` tables if they do not exist.
-fn ensure_groups_and_tags(conn: &Connection) -> Result<(), String> {
+/// Set the port knocking sequence performed before connecting to a session.
+/// An empty list disables knocking.
+#[tauri::command]
+pub fn set_knock_sequence(
+    session_id: String,
+    steps: Vec<crate::network::KnockStep>,
+) -> Result<(), String> {
+    let json = if steps.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&steps).map_err(|e| e.to_string())?)
+    };
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS groups (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL DEFAULT '默认分组',
-            sort INTEGER NOT NULL DEFAULT 1,
-            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
-            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
-        )",
-        [],
+        "UPDATE sessions SET knock_sequence = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![json, session_id],
     )
     .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Get the advanced SSH options of a session (defaults when none are stored).
+#[tauri::command]
+pub fn get_advanced_options(session_id: String) -> Result<crate::ssh::SshAdvancedOptions, String> {
+    load_advanced_options(&session_id)
+}
+
+/// Load the advanced SSH options of a session (backend use).
+pub fn load_advanced_options(session_id: &str) -> Result<crate::ssh::SshAdvancedOptions, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT advanced_options FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Default::default()),
+    }
+}
 
+/// Set the advanced SSH options (connect timeout, algorithm preferences,
+/// compression) of a session.
+#[tauri::command]
+pub fn set_advanced_options(
+    session_id: String,
+    options: crate::ssh::SshAdvancedOptions,
+) -> Result<(), String> {
+    let json = serde_json::to_string(&options).map_err(|e| e.to_string())?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS tags (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL DEFAULT '',
-            color TEXT,
-            sort INTEGER NOT NULL DEFAULT 1,
-            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
-            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
-        )",
-        [],
+        "UPDATE sessions SET advanced_options = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![json, session_id],
     )
     .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Try to add color column if it doesn't exist (for existing databases)
-    let _ = conn.execute("ALTER TABLE tags ADD COLUMN color TEXT", []);
+/// Get the environment variables and startup commands of a session.
+#[tauri::command]
+pub fn get_session_environment(session_id: String) -> Result<SessionEnvironment, String> {
+    load_session_environment(&session_id)
+}
 
-    // Junction table for sessions <-> groups (logical association only)
+/// Load the environment of a session (backend use).
+pub fn load_session_environment(session_id: &str) -> Result<SessionEnvironment, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT environment FROM sessions WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Default::default()),
+    }
+}
+
+/// Set the environment variables and startup commands of a session.
+#[tauri::command]
+pub fn set_session_environment(
+    session_id: String,
+    environment: SessionEnvironment,
+) -> Result<(), String> {
+    if let Some(name) = environment.env_vars.keys().find(|name| {
+        name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }) {
+        return Err(format!("Invalid environment variable name: {:?}", name));
+    }
+    if !["", "attach", "ask"].contains(&environment.resume.as_str()) {
+        return Err(format!("Invalid resume mode: {}", environment.resume));
+    }
+    crate::encoding::SessionEncoding::parse(&environment.encoding)?;
+    if !environment.term.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(format!("Invalid terminal type: {:?}", environment.term));
+    }
+    if !["", "lock", "disconnect"].contains(&environment.idle_action.as_str()) {
+        return Err(format!("Invalid idle action: {}", environment.idle_action));
+    }
+    let json = serde_json::to_string(&environment).map_err(|e| e.to_string())?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS session_groups (
-            session_id TEXT NOT NULL,
-            group_id TEXT NOT NULL,
-            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
-            UNIQUE(session_id, group_id)
-        )",
-        [],
+        "UPDATE sessions SET environment = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![json, session_id],
     )
     .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Junction table for sessions <-> tags (logical association only)
+/// Get whether connecting to a session requires re-authentication.
+#[tauri::command]
+pub fn get_session_protected(session_id: String) -> Result<bool, String> {
+    load_session_protected(&session_id)
+}
+
+/// Load a session's protected flag (backend use).
+pub fn load_session_protected(session_id: &str) -> Result<bool, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT protected FROM sessions WHERE id = ?1",
+        params![session_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|protected| protected != 0)
+    .map_err(|e| e.to_string())
+}
+
+/// Ids of the protected sessions (backend use).
+pub fn load_protected_session_ids() -> Result<Vec<String>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM sessions WHERE protected != 0")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+/// Save a session's protected flag (backend use; changing it goes through
+/// `biometric::set_session_protected`).
+pub fn save_session_protected(session_id: &str, protected: bool) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS session_tags (
-            session_id TEXT NOT NULL,
-            tag_id TEXT NOT NULL,
-            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
-            UNIQUE(session_id, tag_id)
-        )",
-        [],
+        "UPDATE sessions SET protected = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![if protected { 1 } else { 0 }, session_id],
     )
     .map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
-/// Create a new group and return its UUID.
+/// Get whether programs on a session's server may set the local clipboard
+/// (OSC 52): "deny", "ask" or "allow"; `None` follows the global
+/// `clipboard.osc52` setting.
 #[tauri::command]
-pub fn add_group(name: Option<String>, sort: Option<i64>) -> Result<String, String> {
+pub fn get_clipboard_access(session_id: String) -> Result<Option<String>, String> {
+    load_clipboard_access(&session_id)
+}
+
+/// Load a session's OSC 52 clipboard access (backend use).
+pub fn load_clipboard_access(session_id: &str) -> Result<Option<String>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT clipboard_access FROM sessions WHERE id = ?1",
+        params![session_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Set a session's OSC 52 clipboard access; `None` follows the global
+/// setting.
+#[tauri::command]
+pub fn set_clipboard_access(session_id: String, access: Option<String>) -> Result<(), String> {
+    if let Some(access) = access.as_deref() {
+        if !["deny", "ask", "allow"].contains(&access) {
+            return Err(format!("Invalid clipboard access: {}", access));
+        }
+    }
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    ensure_groups_and_tags(&conn)?;
-    let id = Uuid::new_v4().to_string();
-    let name = name.unwrap_or_else(|| "默认分组".to_string());
-    let sort = sort.unwrap_or(1);
     conn.execute(
-        "INSERT INTO groups (id, name, sort) VALUES (?1, ?2, ?3)",
-        params![id, name, sort],
+        "UPDATE sessions SET clipboard_access = ?1 WHERE id = ?2",
+        params![access, session_id],
     )
     .map_err(|e| e.to_string())?;
-    Ok(id)
+    Ok(())
 }
 
-/// Return all groups ordered by `sort` then `created_at`.
+/// Check an `Appearance` before it is stored: colors are `#rrggbb`, icon
+/// and theme are short names.
+fn validate_appearance(appearance: &Appearance) -> Result<(), String> {
+    if let Some(color) = appearance.color.as_deref() {
+        let hex = color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color: {}", color));
+        }
+    }
+    for (what, value) in [
+        ("icon", &appearance.icon),
+        ("terminal theme", &appearance.terminal_theme),
+    ] {
+        if let Some(value) = value {
+            if value.is_empty() || value.len() > 64 || value.chars().any(char::is_control) {
+                return Err(format!("Invalid {}: {:?}", what, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Set the color, icon and terminal theme of a session; unset fields fall
+/// back to its group's.
 #[tauri::command]
-pub fn list_groups() -> Result<Vec<Group>, String> {
+pub fn set_session_appearance(session_id: String, appearance: Appearance) -> Result<(), String> {
+    validate_appearance(&appearance)?;
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    ensure_groups_and_tags(&conn)?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, sort, created_at, updated_at FROM groups ORDER BY sort, created_at",
-        )
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                sort: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    let mut v = Vec::new();
-    for r in rows {
-        v.push(r.map_err(|e| e.to_string())?);
-    }
-    Ok(v)
+    conn.execute(
+        "UPDATE sessions SET color = ?1, icon = ?2, terminal_theme = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
+        params![
+            appearance.color,
+            appearance.icon,
+            appearance.terminal_theme,
+            session_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
 }
 
-/// Associate a session with a group (logical join). Duplicate associations
-/// are ignored.
+/// Set the color, icon and terminal theme of a group, used by its sessions
+/// that do not set their own.
 #[tauri::command]
-pub fn link_session_group(session_id: String, group_id: String) -> Result<(), String> {
+pub fn set_group_appearance(group_id: String, appearance: Appearance) -> Result<(), String> {
+    validate_appearance(&appearance)?;
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     ensure_groups_and_tags(&conn)?;
     conn.execute(
-        "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
-        params![session_id, group_id],
+        "UPDATE groups SET color = ?1, icon = ?2, terminal_theme = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
+        params![
+            appearance.color,
+            appearance.icon,
+            appearance.terminal_theme,
+            group_id
+        ],
     )
     .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
-/// Remove the association between a session and a group.
+/// Mark a session as a template (or back to a regular session).
 #[tauri::command]
-pub fn unlink_session_group(session_id: String, group_id: String) -> Result<(), String> {
+pub fn set_session_template(id: String, is_template: bool) -> Result<(), String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     conn.execute(
-        "DELETE FROM session_groups WHERE session_id = ?1 AND group_id = ?2",
-        params![session_id, group_id],
+        "UPDATE sessions SET is_template = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![if is_template { 1 } else { 0 }, id],
     )
     .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
     Ok(())
 }
 
-/// List groups associated with a given session.
+/// List the sessions flagged as templates.
 #[tauri::command]
-pub fn list_groups_for_session(session_id: String) -> Result<Vec<Group>, String> {
+pub fn list_session_templates() -> Result<Vec<Session>, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare(
-            "SELECT g.id, g.name, g.sort, g.created_at, g.updated_at
-             FROM groups g
-             JOIN session_groups sg ON g.id = sg.group_id
-             WHERE sg.session_id = ?1
-             ORDER BY g.sort, g.created_at",
-        )
+        .prepare(&format!(
+            "SELECT {} FROM sessions s WHERE s.is_template = 1 ORDER BY s.server_name",
+            SESSION_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![session_id], |row| {
-            Ok(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                sort: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        })
+        .query_map([], session_from_row)
         .map_err(|e| e.to_string())?;
     let mut v = Vec::new();
     for r in rows {
@@ -896,45 +2003,3720 @@ pub fn list_groups_for_session(session_id: String) -> Result<Vec<Group>, String>
     Ok(v)
 }
 
-/// Create a new tag and return its UUID.
+/// Copy a session (or instantiate a template) and return the new UUID.
+///
+/// The copy keeps the source's connection settings, stored credentials,
+/// port knocking, advanced options, environment, groups, tags,
+/// session-specific triggers and highlight rules; `overrides` replaces
+/// individual fields. The
+/// copy is a regular session unless `overrides.is_template` says otherwise,
+/// and its name gets a " (copy)" suffix when no name is given.
+#[tauri::command]
+pub fn duplicate_session(
+    id: String,
+    overrides: Option<SessionOverrides>,
+) -> Result<String, String> {
+    let overrides = overrides.unwrap_or_default();
+    let source = load_session(&id)?;
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    ensure_triggers(&conn)?;
+    ensure_highlight_rules(&conn)?;
+    let new_id = Uuid::new_v4().to_string();
+    let server_name = overrides.server_name.clone().unwrap_or_else(|| {
+        if source.is_template {
+            source.server_name.clone()
+        } else {
+            format!("{} (copy)", source.server_name)
+        }
+    });
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials, credential_backend, mac_address, knock_sequence, advanced_options, certificate_path, is_template, environment, color, icon, terminal_theme)
+         SELECT ?1, addr, port, ?2, username, auth_type, private_key_path, ?3, encrypted_credentials, credential_backend, mac_address, knock_sequence, advanced_options, certificate_path, ?4, environment, color, icon, terminal_theme
+         FROM sessions WHERE id = ?5",
+        params![
+            new_id,
+            server_name,
+            if overrides.is_favorite.unwrap_or(false) { 1 } else { 0 },
+            if overrides.is_template.unwrap_or(false) { 1 } else { 0 },
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR IGNORE INTO session_groups (session_id, group_id)
+         SELECT ?1, group_id FROM session_groups WHERE session_id = ?2",
+        params![new_id, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR IGNORE INTO session_tags (session_id, tag_id)
+         SELECT ?1, tag_id FROM session_tags WHERE session_id = ?2",
+        params![new_id, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let trigger_ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM triggers WHERE session_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    for trigger_id in trigger_ids {
+        tx.execute(
+            "INSERT INTO triggers (id, session_id, name, pattern, action, action_value, enabled, cooldown_ms)
+             SELECT ?1, ?2, name, pattern, action, action_value, enabled, cooldown_ms
+             FROM triggers WHERE id = ?3",
+            params![Uuid::new_v4().to_string(), new_id, trigger_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    let rule_ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM highlight_rules WHERE session_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    for rule_id in rule_ids {
+        tx.execute(
+            "INSERT INTO highlight_rules (id, session_id, name, pattern, tag, count, enabled)
+             SELECT ?1, ?2, name, pattern, tag, count, enabled
+             FROM highlight_rules WHERE id = ?3",
+            params![Uuid::new_v4().to_string(), new_id, rule_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if credential_backend(&conn, &id)? == CredentialBackend::Keychain {
+        if let Some(data) = crate::keychain::read(&id)? {
+            crate::keychain::write(&new_id, &data)?;
+        }
+    }
+
+    edit_session(
+        new_id.clone(),
+        overrides.addr,
+        overrides.port,
+        None,
+        overrides.username,
+        overrides.auth_type,
+        overrides.private_key_path.map(Some),
+        None,
+        overrides.mac_address.map(Some),
+        overrides.certificate_path.map(Some),
+    )?;
+    crate::config_history::schedule_commit();
+    Ok(new_id)
+}
+
+/// Delete a session and its logical associations.
+#[tauri::command]
+pub fn delete_session(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let keychain = uses_keychain(&conn, &id);
+
+    // Delete session_groups
+    let rows1 = conn
+        .execute(
+            "DELETE FROM session_groups WHERE session_id = ?1",
+            params![id.clone()],
+        )
+        .map_err(|e| e.to_string())?;
+
+    // Delete session_tags
+    let rows2 = conn
+        .execute(
+            "DELETE FROM session_tags WHERE session_id = ?1",
+            params![id.clone()],
+        )
+        .map_err(|e| e.to_string())?;
+
+    // Delete connection history and secrets
+    ensure_connection_history(&conn)?;
+    conn.execute(
+        "DELETE FROM connection_history WHERE session_id = ?1",
+        params![id.clone()],
+    )
+    .map_err(|e| e.to_string())?;
+    ensure_usage_history(&conn)?;
+    for table in ["connection_failures", "transfer_history"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE session_id = ?1", table),
+            params![id.clone()],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    ensure_session_secrets(&conn)?;
+    conn.execute(
+        "DELETE FROM session_secrets WHERE session_id = ?1",
+        params![id.clone()],
+    )
+    .map_err(|e| e.to_string())?;
+    ensure_clipboard_history(&conn)?;
+    conn.execute(
+        "DELETE FROM clipboard_history WHERE session_id = ?1",
+        params![id.clone()],
+    )
+    .map_err(|e| e.to_string())?;
+    ensure_recent_remote_paths(&conn)?;
+    conn.execute(
+        "DELETE FROM recent_remote_paths WHERE session_id = ?1",
+        params![id.clone()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Delete session
+    let rows3 = conn
+        .execute("DELETE FROM sessions WHERE id = ?1", params![id.clone()])
+        .map_err(|e| e.to_string())?;
+    record_tombstone(&conn, "session", &id)?;
+
+    // Delete keychain entry once the row is gone
+    if keychain {
+        delete_keychain_entries(std::slice::from_ref(&id));
+    }
+
+    tracing::debug!(
+        "deleted session {} ({} rows, {} group and {} tag links)",
+        id,
+        rows3,
+        rows1,
+        rows2
+    );
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Apply one patch to a session inside a bulk transaction.
+fn apply_session_patch(conn: &Connection, id: &str, patch: &SessionPatch) -> Result<(), String> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Session not found: {}", id));
+    }
+
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(u) = &patch.username {
+        sets.push("username = ?".to_string());
+        params_vec.push(Box::new(u.clone()));
+    }
+    if let Some(p) = patch.port {
+        sets.push("port = ?".to_string());
+        params_vec.push(Box::new(p));
+    }
+    if let Some(fav) = patch.is_favorite {
+        sets.push("is_favorite = ?".to_string());
+        params_vec.push(Box::new(if fav { 1 } else { 0 }));
+    }
+    if !sets.is_empty() {
+        sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+        let sql = format!("UPDATE sessions SET {} WHERE id = ?", sets.join(", "));
+        params_vec.push(Box::new(id.to_string()));
+        let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+        conn.execute(&sql, param_refs.as_slice())
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(group_id) = &patch.move_to_group_id {
+        conn.execute(
+            "DELETE FROM session_groups WHERE session_id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
+            params![id, group_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for tag_id in &patch.add_tag_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+            params![id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for tag_id in &patch.remove_tag_ids {
+        conn.execute(
+            "DELETE FROM session_tags WHERE session_id = ?1 AND tag_id = ?2",
+            params![id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Delete a session's row and associations inside a bulk transaction.
+/// Keychain entries are left to the caller, to be removed once the
+/// transaction is committed.
+fn delete_session_rows(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM session_groups WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM connection_history WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    for table in ["connection_failures", "transfer_history"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE session_id = ?1", table),
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "DELETE FROM session_secrets WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM clipboard_history WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM recent_remote_paths WHERE session_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM sessions WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Session not found: {}", id));
+    }
+    record_tombstone(conn, "session", id)
+}
+
+/// Run `op` for each id in one transaction. Each id runs in its own
+/// savepoint, so a failing id is rolled back and reported while the others
+/// are committed together.
+fn bulk_apply(
+    ids: Vec<String>,
+    op: impl Fn(&Connection, &str) -> Result<(), String>,
+) -> Result<Vec<BulkResult>, String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    ensure_connection_history(&conn)?;
+    ensure_session_secrets(&conn)?;
+
+    let mut tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut sp = tx.savepoint().map_err(|e| e.to_string())?;
+        let outcome = op(&sp, &id);
+        match outcome {
+            Ok(()) => sp.commit().map_err(|e| e.to_string())?,
+            Err(_) => sp.rollback().map_err(|e| e.to_string())?,
+        }
+        results.push(BulkResult {
+            id,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(results)
+}
+
+/// Apply the same changes (group move, tag add/remove, username, port,
+/// favorite) to many sessions in a single transaction.
+#[tauri::command]
+pub fn bulk_edit_sessions(
+    ids: Vec<String>,
+    patch: SessionPatch,
+) -> Result<Vec<BulkResult>, String> {
+    let results = bulk_apply(ids, |conn, id| apply_session_patch(conn, id, &patch))?;
+    crate::config_history::schedule_commit();
+    Ok(results)
+}
+
+/// Delete many sessions and their associations in a single transaction.
+#[tauri::command]
+pub fn bulk_delete_sessions(ids: Vec<String>) -> Result<Vec<BulkResult>, String> {
+    let keychain_ids: HashSet<String> = {
+        let conn = Connection::open(db_path()?).map_err(|e| e.to_string())?;
+        ids.iter()
+            .filter(|id| uses_keychain(&conn, id))
+            .cloned()
+            .collect()
+    };
+    let results = bulk_apply(ids, delete_session_rows)?;
+    let deleted: Vec<String> = results
+        .iter()
+        .filter(|r| r.ok && keychain_ids.contains(&r.id))
+        .map(|r| r.id.clone())
+        .collect();
+    delete_keychain_entries(&deleted);
+    crate::config_history::schedule_commit();
+    Ok(results)
+}
+
+/// Set the manual order of sessions, used by the "manual" sort key. With a
+/// `group_id` the sessions are ordered within that group; without one the
+/// favorites are pinned in the given order. Sessions left out lose their
+/// position and follow the ordered ones by name.
+#[tauri::command]
+pub fn reorder_sessions(group_id: Option<String>, ordered_ids: Vec<String>) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    match &group_id {
+        Some(group_id) => {
+            tx.execute(
+                "UPDATE session_groups SET position = NULL WHERE group_id = ?1",
+                params![group_id],
+            )
+            .map_err(|e| e.to_string())?;
+            for (position, id) in ordered_ids.iter().enumerate() {
+                let updated = tx
+                    .execute(
+                        "UPDATE session_groups SET position = ?1 WHERE group_id = ?2 AND session_id = ?3",
+                        params![position as i64, group_id, id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                if updated == 0 {
+                    return Err(format!("Session {} is not in group {}", id, group_id));
+                }
+            }
+        }
+        None => {
+            tx.execute("UPDATE sessions SET pin_position = NULL", [])
+                .map_err(|e| e.to_string())?;
+            for (position, id) in ordered_ids.iter().enumerate() {
+                let updated = tx
+                    .execute(
+                        "UPDATE sessions SET pin_position = ?1 WHERE id = ?2 AND is_favorite = 1",
+                        params![position as i64, id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                if updated == 0 {
+                    return Err(format!("Session {} is not a favorite", id));
+                }
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Saved sessions pointing at the same account: same address (compared
+/// case-insensitively), port and username.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSessions {
+    pub addr: String,
+    pub port: i64,
+    pub username: String,
+    /// Most recently used first, which is the usual one to keep
+    pub sessions: Vec<Session>,
+}
+
+/// Find saved sessions that share address, port and username, typically
+/// left behind by importing the same hosts from several sources. Templates
+/// are not included.
+#[tauri::command]
+pub fn find_duplicate_sessions() -> Result<Vec<DuplicateSessions>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM sessions s
+             WHERE s.is_template = 0 AND EXISTS (
+                SELECT 1 FROM sessions d
+                WHERE d.id != s.id AND d.is_template = 0 AND LOWER(d.addr) = LOWER(s.addr)
+                  AND d.port = s.port AND d.username = s.username
+             )
+             ORDER BY LOWER(s.addr), s.port, s.username,
+                      s.last_connected_at IS NULL, s.last_connected_at DESC, s.created_at",
+            SESSION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let sessions = stmt
+        .query_map([], session_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut duplicates: Vec<DuplicateSessions> = Vec::new();
+    for session in sessions {
+        match duplicates.last_mut() {
+            Some(last)
+                if last.addr.eq_ignore_ascii_case(&session.addr)
+                    && last.port == session.port
+                    && last.username == session.username =>
+            {
+                last.sessions.push(session)
+            }
+            _ => duplicates.push(DuplicateSessions {
+                addr: session.addr.clone(),
+                port: session.port,
+                username: session.username.clone(),
+                sessions: vec![session],
+            }),
+        }
+    }
+    Ok(duplicates)
+}
+
+/// Tables whose rows belong to a session and move to the kept one on merge
+const MERGED_SESSION_TABLES: &[&str] = &[
+    "connection_history",
+    "connection_failures",
+    "transfer_history",
+    "clipboard_history",
+    "scheduled_runs",
+    "alerts",
+    "alert_rules",
+    "triggers",
+    "highlight_rules",
+    "open_tabs",
+];
+
+/// Merge duplicate sessions into `keep_id` and delete them. The kept
+/// session gains their groups, tags, connection history, per-session rules,
+/// named secrets, metrics and recent remote paths; it keeps its own values
+/// on conflict. Stored
+/// credentials of a removed session are moved over only if the kept one has
+/// none, and the favorite flag and last connection time are combined.
+#[tauri::command]
+pub fn merge_sessions(keep_id: String, remove_ids: Vec<String>) -> Result<Session, String> {
+    let remove_ids: Vec<String> = remove_ids.into_iter().filter(|id| *id != keep_id).collect();
+    if remove_ids.is_empty() {
+        return Err("No sessions to merge".to_string());
+    }
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_open_tabs(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let exists: bool = tx
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sessions WHERE id = ?1)",
+            params![keep_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Session not found: {}", keep_id));
+    }
+
+    let mut has_credentials = read_credentials(&tx, &keep_id)?.is_some();
+    for id in &remove_ids {
+        for (table, column) in [("session_groups", "group_id"), ("session_tags", "tag_id")] {
+            tx.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO {table} (session_id, {column})
+                     SELECT ?1, {column} FROM {table} WHERE session_id = ?2",
+                    table = table,
+                    column = column,
+                ),
+                params![keep_id, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        for table in MERGED_SESSION_TABLES {
+            tx.execute(
+                &format!("UPDATE {} SET session_id = ?1 WHERE session_id = ?2", table),
+                params![keep_id, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        // Keyed by session, so rows the kept session already has win
+        for table in ["session_secrets", "metrics_history", "recent_remote_paths"] {
+            tx.execute(
+                &format!(
+                    "UPDATE OR IGNORE {} SET session_id = ?1 WHERE session_id = ?2",
+                    table
+                ),
+                params![keep_id, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.execute(
+            "DELETE FROM metrics_history WHERE session_id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        if !has_credentials {
+            if let Some(credentials) = read_credentials(&tx, id)? {
+                write_credentials(&tx, &keep_id, Some(&credentials))?;
+                has_credentials = true;
+            }
+        }
+        tx.execute(
+            "UPDATE sessions SET
+                is_favorite = MAX(is_favorite, (SELECT is_favorite FROM sessions WHERE id = ?2)),
+                last_connected_at = (
+                    SELECT MAX(last_connected_at) FROM sessions WHERE id IN (?1, ?2)
+                ),
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        delete_session_rows(&tx, id)?;
+    }
+    merge_scheduled_task_sessions(&tx, &keep_id, &remove_ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    tracing::debug!("merged {} sessions into {}", remove_ids.len(), keep_id);
+    crate::config_history::schedule_commit();
+    load_session(&keep_id)
+}
+
+/// Point scheduled tasks that ran on merged sessions at the kept one
+fn merge_scheduled_task_sessions(
+    conn: &Connection,
+    keep_id: &str,
+    remove_ids: &[String],
+) -> Result<(), String> {
+    let tasks = {
+        let mut stmt = conn
+            .prepare("SELECT id, session_ids FROM scheduled_tasks")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+    for (id, session_ids) in tasks {
+        let old: Vec<String> = serde_json::from_str(&session_ids).unwrap_or_default();
+        if !old.iter().any(|s| remove_ids.contains(s)) {
+            continue;
+        }
+        let mut new: Vec<String> = Vec::with_capacity(old.len());
+        for session_id in old {
+            let session_id = if remove_ids.contains(&session_id) {
+                keep_id.to_string()
+            } else {
+                session_id
+            };
+            if !new.contains(&session_id) {
+                new.push(session_id);
+            }
+        }
+        conn.execute(
+            "UPDATE scheduled_tasks SET session_ids = ?1 WHERE id = ?2",
+            params![serde_json::to_string(&new).map_err(|e| e.to_string())?, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Create the `groups` and `tags` tables if they do not exist.
+fn ensure_groups_and_tags(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS groups (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT '默认分组',
+            sort INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT '',
+            color TEXT,
+            sort INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Try to add color column if it doesn't exist (for existing databases)
+    let _ = conn.execute("ALTER TABLE tags ADD COLUMN color TEXT", []);
+    let _ = conn.execute("ALTER TABLE groups ADD COLUMN color TEXT", []);
+    let _ = conn.execute("ALTER TABLE groups ADD COLUMN icon TEXT", []);
+    let _ = conn.execute("ALTER TABLE groups ADD COLUMN terminal_theme TEXT", []);
+
+    // Junction table for sessions <-> groups (logical association only)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_groups (
+            session_id TEXT NOT NULL,
+            group_id TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            UNIQUE(session_id, group_id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    // Manual order of the sessions within a group; NULL sorts by name
+    let _ = conn.execute("ALTER TABLE session_groups ADD COLUMN position INTEGER", []);
+
+    // Junction table for sessions <-> tags (logical association only)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_tags (
+            session_id TEXT NOT NULL,
+            tag_id TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            UNIQUE(session_id, tag_id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Create a new group and return its UUID.
+#[tauri::command]
+pub fn add_group(name: Option<String>, sort: Option<i64>) -> Result<String, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    let name = name.unwrap_or_else(|| "默认分组".to_string());
+    let sort = sort.unwrap_or(1);
+    conn.execute(
+        "INSERT INTO groups (id, name, sort) VALUES (?1, ?2, ?3)",
+        params![id, name, sort],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(id)
+}
+
+/// Return all groups ordered by `sort` then `created_at`.
+#[tauri::command]
+pub fn list_groups() -> Result<Vec<Group>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM groups g ORDER BY g.sort, g.created_at",
+            GROUP_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], group_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Associate a session with a group (logical join). Duplicate associations
+/// are ignored.
+#[tauri::command]
+pub fn link_session_group(session_id: String, group_id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
+        params![session_id, group_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Remove the association between a session and a group.
+#[tauri::command]
+pub fn unlink_session_group(session_id: String, group_id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM session_groups WHERE session_id = ?1 AND group_id = ?2",
+        params![session_id, group_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// List groups associated with a given session.
+#[tauri::command]
+pub fn list_groups_for_session(session_id: String) -> Result<Vec<Group>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM groups g
+             JOIN session_groups sg ON g.id = sg.group_id
+             WHERE sg.session_id = ?1
+             ORDER BY g.sort, g.created_at",
+            GROUP_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id], group_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Create a new tag and return its UUID.
 #[tauri::command]
 pub fn add_tag(
     name: Option<String>,
-    color: Option<String>,
-    sort: Option<i64>,
+    color: Option<String>,
+    sort: Option<i64>,
+) -> Result<String, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    let name = name.unwrap_or_default();
+    let sort = sort.unwrap_or(1);
+    conn.execute(
+        "INSERT INTO tags (id, name, color, sort) VALUES (?1, ?2, ?3, ?4)",
+        params![id, name, color, sort],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(id)
+}
+
+/// Return all tags ordered by `sort` then `created_at`.
+#[tauri::command]
+pub fn list_tags() -> Result<Vec<Tag>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, color, sort, created_at, updated_at FROM tags ORDER BY sort, created_at")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                sort: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Associate a session with a tag (logical join). Duplicate associations
+/// are ignored.
+#[tauri::command]
+pub fn link_session_tag(session_id: String, tag_id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+        params![session_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Remove the association between a session and a tag.
+#[tauri::command]
+pub fn unlink_session_tag(session_id: String, tag_id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1 AND tag_id = ?2",
+        params![session_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// List tags associated with a given session.
+#[tauri::command]
+pub fn list_tags_for_session(session_id: String) -> Result<Vec<Tag>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color, t.sort, t.created_at, t.updated_at
+             FROM tags t
+             JOIN session_tags st ON t.id = st.tag_id
+             WHERE st.session_id = ?1
+             ORDER BY t.sort, t.created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                sort: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Create the `assignment_rules` table if it does not exist.
+fn ensure_assignment_rules(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS assignment_rules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT '',
+            field TEXT NOT NULL DEFAULT 'any',
+            pattern TEXT NOT NULL,
+            group_id TEXT,
+            tag_id TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn assignment_regex(pattern: &str) -> Result<regex::Regex, String> {
+    regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+fn validate_assignment_field(field: &str) -> Result<(), String> {
+    match field {
+        "addr" | "server_name" | "any" => Ok(()),
+        other => Err(format!("Unknown rule field: {}", other)),
+    }
+}
+
+/// Load the enabled assignment rules with their compiled patterns. Rules
+/// whose pattern no longer compiles are skipped.
+fn load_assignment_rules(conn: &Connection) -> Result<Vec<(AssignmentRule, regex::Regex)>, String> {
+    ensure_assignment_rules(conn)?;
+    Ok(query_assignment_rules(conn)?
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| {
+            let regex = assignment_regex(&rule.pattern).ok()?;
+            Some((rule, regex))
+        })
+        .collect())
+}
+
+/// Add a session to the groups and tags of the rules it matches.
+fn assign_by_rules(
+    conn: &Connection,
+    rules: &[(AssignmentRule, regex::Regex)],
+    session_id: &str,
+    addr: &str,
+    server_name: &str,
+) -> Result<(), String> {
+    for (rule, regex) in rules {
+        let matched = match rule.field.as_str() {
+            "addr" => regex.is_match(addr),
+            "server_name" => regex.is_match(server_name),
+            _ => regex.is_match(addr) || regex.is_match(server_name),
+        };
+        if !matched {
+            continue;
+        }
+        if let Some(group_id) = &rule.group_id {
+            conn.execute(
+                "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
+                params![session_id, group_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        if let Some(tag_id) = &rule.tag_id {
+            conn.execute(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+                params![session_id, tag_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn query_assignment_rules(conn: &Connection) -> Result<Vec<AssignmentRule>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, field, pattern, group_id, tag_id, enabled, created_at, updated_at
+             FROM assignment_rules ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(AssignmentRule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                field: row.get(2)?,
+                pattern: row.get(3)?,
+                group_id: row.get(4)?,
+                tag_id: row.get(5)?,
+                enabled: row.get::<_, i64>(6)? != 0,
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Create an assignment rule and return its UUID. New and imported
+/// sessions matching `pattern` are added to `group_id` and/or `tag_id`.
+#[tauri::command]
+pub fn add_assignment_rule(
+    name: Option<String>,
+    field: Option<String>,
+    pattern: String,
+    group_id: Option<String>,
+    tag_id: Option<String>,
+) -> Result<String, String> {
+    let field = field.unwrap_or_else(|| "any".to_string());
+    validate_assignment_field(&field)?;
+    assignment_regex(&pattern)?;
+    if group_id.is_none() && tag_id.is_none() {
+        return Err("A rule needs a group or a tag".to_string());
+    }
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_assignment_rules(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO assignment_rules (id, name, field, pattern, group_id, tag_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            id,
+            name.unwrap_or_default(),
+            field,
+            pattern,
+            group_id,
+            tag_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(id)
+}
+
+/// List all assignment rules, oldest first.
+#[tauri::command]
+pub fn list_assignment_rules() -> Result<Vec<AssignmentRule>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_assignment_rules(&conn)?;
+    query_assignment_rules(&conn)
+}
+
+/// Edit an assignment rule. Only provided fields are updated; an empty
+/// `group_id` or `tag_id` clears it.
+#[tauri::command]
+pub fn edit_assignment_rule(
+    id: String,
+    name: Option<String>,
+    field: Option<String>,
+    pattern: Option<String>,
+    group_id: Option<String>,
+    tag_id: Option<String>,
+    enabled: Option<bool>,
+) -> Result<(), String> {
+    if let Some(f) = &field {
+        validate_assignment_field(f)?;
+    }
+    if let Some(p) = &pattern {
+        assignment_regex(p)?;
+    }
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_assignment_rules(&conn)?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(f) = field {
+        sets.push("field = ?".to_string());
+        params_vec.push(Box::new(f));
+    }
+    if let Some(p) = pattern {
+        sets.push("pattern = ?".to_string());
+        params_vec.push(Box::new(p));
+    }
+    if let Some(g) = group_id {
+        sets.push("group_id = ?".to_string());
+        params_vec.push(Box::new(Some(g).filter(|g| !g.is_empty())));
+    }
+    if let Some(t) = tag_id {
+        sets.push("tag_id = ?".to_string());
+        params_vec.push(Box::new(Some(t).filter(|t| !t.is_empty())));
+    }
+    if let Some(e) = enabled {
+        sets.push("enabled = ?".to_string());
+        params_vec.push(Box::new(if e { 1 } else { 0 }));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!(
+        "UPDATE assignment_rules SET {} WHERE id = ?",
+        sets.join(", ")
+    );
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Delete an assignment rule. Sessions it organized keep their groups and
+/// tags.
+#[tauri::command]
+pub fn delete_assignment_rule(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_assignment_rules(&conn)?;
+    conn.execute("DELETE FROM assignment_rules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Export every session with its credentials re-encrypted with `password`.
+/// Asks for biometric confirmation first when it is enabled, and for
+/// re-authentication when any session is protected.
+#[tauri::command]
+pub async fn export_sessions(
+    password: String,
+    master_password: Option<String>,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::biometric::confirm_protected_sessions(
+            &load_protected_session_ids()?,
+            master_password.as_deref(),
+        )?;
+        crate::biometric::confirm("export saved credentials")?;
+        let db_path = db_path()?;
+        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        let export_data = build_export(&conn, Some(&password))?;
+        serde_json::to_string(&export_data).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Collect all sessions, groups and tags, with credentials re-encrypted
+/// with `password`. Without a password credentials are left out.
+fn build_export(conn: &Connection, password: Option<&str>) -> Result<ExportData, String> {
+    // 1. Get all sessions
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM sessions s", SESSION_COLUMNS))
+        .map_err(|e| e.to_string())?;
+
+    let session_rows = stmt
+        .query_map([], session_from_row)
+        .map_err(|e| e.to_string())?;
+
+    let mut export_sessions = Vec::new();
+    for row in session_rows {
+        let metadata = row.map_err(|e| e.to_string())?;
+
+        // Read from the vault or keychain and re-encrypt with export password
+        let re_encrypted = match password {
+            Some(password) => match read_credentials(conn, &metadata.id)? {
+                Some(sensitive) => Some(crate::encryption::EncryptionManager::encrypt_with_key(
+                    &sensitive, password,
+                )?),
+                None => None,
+            },
+            None => None,
+        };
+
+        // Get groups for this session
+        let mut g_stmt = conn
+            .prepare("SELECT group_id FROM session_groups WHERE session_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let groups: Vec<String> = g_stmt
+            .query_map(params![metadata.id], |r| r.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        // Get tags for this session
+        let mut t_stmt = conn
+            .prepare("SELECT tag_id FROM session_tags WHERE session_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let tags: Vec<String> = t_stmt
+            .query_map(params![metadata.id], |r| r.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        export_sessions.push(ExportSession {
+            metadata,
+            encrypted_credentials: re_encrypted,
+            group_ids: groups,
+            tag_ids: tags,
+        });
+    }
+
+    // 2. Get all groups
+    let mut g_stmt = conn
+        .prepare(&format!("SELECT {} FROM groups g", GROUP_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let groups = g_stmt
+        .query_map([], group_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Group>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // 3. Get all tags
+    let mut t_stmt = conn
+        .prepare("SELECT id, name, color, sort, created_at, updated_at FROM tags")
+        .map_err(|e| e.to_string())?;
+    let tags = t_stmt
+        .query_map([], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                sort: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Tag>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ExportData {
+        sessions: export_sessions,
+        groups,
+        tags,
+    })
+}
+
+#[tauri::command]
+pub fn import_sessions(json_data: String, password: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let export_data: ExportData = serde_json::from_str(&json_data).map_err(|e| e.to_string())?;
+
+    ensure_groups_and_tags(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let rules = load_assignment_rules(&tx)?;
+
+    // 1. Import Groups
+    for group in export_data.groups {
+        tx.execute(
+            "INSERT OR IGNORE INTO groups (id, name, sort, created_at, updated_at, color, icon, terminal_theme) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                group.id, group.name, group.sort, group.created_at, group.updated_at,
+                group.appearance.color, group.appearance.icon, group.appearance.terminal_theme
+            ],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // 2. Import Tags
+    for tag in export_data.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO tags (id, name, color, sort, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![tag.id, tag.name, tag.color, tag.sort, tag.created_at, tag.updated_at],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // 3. Import Sessions
+    for session in export_data.sessions {
+        let metadata = session.metadata;
+
+        // Decrypt from export password and re-encrypt with machine ID
+        let re_encrypted = if let Some(creds) = session.encrypted_credentials {
+            let sensitive =
+                crate::encryption::EncryptionManager::decrypt_with_key(&creds, &password)?;
+            Some(crate::encryption::EncryptionManager::encrypt(&sensitive)?)
+        } else {
+            None
+        };
+
+        tx.execute(
+            "INSERT OR REPLACE INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials, created_at, updated_at, mac_address, certificate_path, is_template, color, icon, terminal_theme)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                metadata.id, metadata.addr, metadata.port, metadata.server_name,
+                metadata.username, metadata.auth_type, metadata.private_key_path,
+                if metadata.is_favorite { 1 } else { 0 }, re_encrypted, metadata.created_at, metadata.updated_at,
+                metadata.mac_address, metadata.certificate_path,
+                if metadata.is_template { 1 } else { 0 },
+                metadata.appearance.color, metadata.appearance.icon, metadata.appearance.terminal_theme
+            ],
+        ).map_err(|e| e.to_string())?;
+
+        // Restore group associations
+        tx.execute(
+            "DELETE FROM session_groups WHERE session_id = ?1",
+            params![metadata.id],
+        )
+        .ok();
+        for gid in session.group_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
+                params![metadata.id, gid],
+            )
+            .ok();
+        }
+
+        // Restore tag associations
+        tx.execute(
+            "DELETE FROM session_tags WHERE session_id = ?1",
+            params![metadata.id],
+        )
+        .ok();
+        for tid in session.tag_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+                params![metadata.id, tid],
+            )
+            .ok();
+        }
+        assign_by_rules(
+            &tx,
+            &rules,
+            &metadata.id,
+            &metadata.addr,
+            &metadata.server_name,
+        )?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(())
+}
+
+/// Create the `triggers` table if it does not exist.
+fn ensure_triggers(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS triggers (
+            id TEXT PRIMARY KEY,
+            session_id TEXT,
+            name TEXT NOT NULL DEFAULT '',
+            pattern TEXT NOT NULL,
+            action TEXT NOT NULL,
+            action_value TEXT NOT NULL DEFAULT '',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            cooldown_ms INTEGER NOT NULL DEFAULT 1000,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_triggers_session_id ON triggers(session_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn validate_trigger(pattern: &str, action: &str) -> Result<(), String> {
+    regex::Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    match action {
+        "send" | "run" | "notify" => Ok(()),
+        other => Err(format!("Unknown trigger action: {}", other)),
+    }
+}
+
+/// Create a trigger and return its UUID.
+#[tauri::command]
+pub fn add_trigger(
+    session_id: Option<String>,
+    name: Option<String>,
+    pattern: String,
+    action: String,
+    action_value: Option<String>,
+    cooldown_ms: Option<i64>,
+) -> Result<String, String> {
+    validate_trigger(&pattern, &action)?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_triggers(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO triggers (id, session_id, name, pattern, action, action_value, cooldown_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            session_id,
+            name.unwrap_or_default(),
+            pattern,
+            action,
+            action_value.unwrap_or_default(),
+            cooldown_ms.unwrap_or(1000)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// List triggers. With `session_id`, returns that session's triggers plus
+/// global ones; without it, returns every trigger.
+#[tauri::command]
+pub fn list_triggers(session_id: Option<String>) -> Result<Vec<Trigger>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_triggers(&conn)?;
+    query_triggers(&conn, session_id.as_deref(), false)
+}
+
+/// Load the enabled triggers that apply to a session: its own and global
+/// ones, or only global ones without a session id (backend use).
+pub fn load_triggers_for_session(session_id: Option<&str>) -> Result<Vec<Trigger>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_triggers(&conn)?;
+    query_triggers(&conn, session_id, true)
+}
+
+fn query_triggers(
+    conn: &Connection,
+    session_id: Option<&str>,
+    enabled_only: bool,
+) -> Result<Vec<Trigger>, String> {
+    let mut sql = String::from(
+        "SELECT id, session_id, name, pattern, action, action_value, enabled, cooldown_ms, created_at, updated_at FROM triggers",
+    );
+    let mut where_clauses: Vec<&str> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    match session_id {
+        Some(sid) => {
+            where_clauses.push("(session_id = ? OR session_id IS NULL)");
+            params_vec.push(Box::new(sid.to_string()));
+        }
+        // Sessions without a saved id only get the global triggers
+        None if enabled_only => where_clauses.push("session_id IS NULL"),
+        None => {}
+    }
+    if enabled_only {
+        where_clauses.push("enabled = 1");
+    }
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY created_at");
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    let rows = stmt
+        .query_map(param_refs.as_slice(), |row| {
+            Ok(Trigger {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                name: row.get(2)?,
+                pattern: row.get(3)?,
+                action: row.get(4)?,
+                action_value: row.get(5)?,
+                enabled: row.get::<_, i64>(6)? != 0,
+                cooldown_ms: row.get(7)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Edit an existing trigger. Only provided fields are updated.
+#[tauri::command]
+pub fn edit_trigger(
+    id: String,
+    name: Option<String>,
+    pattern: Option<String>,
+    action: Option<String>,
+    action_value: Option<String>,
+    enabled: Option<bool>,
+    cooldown_ms: Option<i64>,
+) -> Result<(), String> {
+    if let Some(p) = &pattern {
+        regex::Regex::new(p).map_err(|e| format!("Invalid pattern: {}", e))?;
+    }
+    if let Some(a) = &action {
+        validate_trigger(".", a)?;
+    }
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_triggers(&conn)?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(p) = pattern {
+        sets.push("pattern = ?".to_string());
+        params_vec.push(Box::new(p));
+    }
+    if let Some(a) = action {
+        sets.push("action = ?".to_string());
+        params_vec.push(Box::new(a));
+    }
+    if let Some(v) = action_value {
+        sets.push("action_value = ?".to_string());
+        params_vec.push(Box::new(v));
+    }
+    if let Some(e) = enabled {
+        sets.push("enabled = ?".to_string());
+        params_vec.push(Box::new(if e { 1 } else { 0 }));
+    }
+    if let Some(c) = cooldown_ms {
+        sets.push("cooldown_ms = ?".to_string());
+        params_vec.push(Box::new(c));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!("UPDATE triggers SET {} WHERE id = ?", sets.join(", "));
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete a trigger.
+#[tauri::command]
+pub fn delete_trigger(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_triggers(&conn)?;
+    conn.execute("DELETE FROM triggers WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create the `highlight_rules` table if it does not exist.
+fn ensure_highlight_rules(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS highlight_rules (
+            id TEXT PRIMARY KEY,
+            session_id TEXT,
+            name TEXT NOT NULL DEFAULT '',
+            pattern TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            count INTEGER NOT NULL DEFAULT 0,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_highlight_rules_session_id ON highlight_rules(session_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create a highlight rule and return its UUID.
+#[tauri::command]
+pub fn add_highlight_rule(
+    session_id: Option<String>,
+    name: Option<String>,
+    pattern: String,
+    tag: String,
+    count: Option<bool>,
+) -> Result<String, String> {
+    regex::Regex::new(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_highlight_rules(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO highlight_rules (id, session_id, name, pattern, tag, count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            id,
+            session_id,
+            name.unwrap_or_default(),
+            pattern,
+            tag,
+            if count.unwrap_or(false) { 1 } else { 0 }
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// List highlight rules. With `session_id`, returns that session's rules
+/// plus global ones; without it, returns every rule.
+#[tauri::command]
+pub fn list_highlight_rules(session_id: Option<String>) -> Result<Vec<HighlightRule>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_highlight_rules(&conn)?;
+    query_highlight_rules(&conn, session_id.as_deref(), false)
+}
+
+/// Load the enabled highlight rules that apply to a session: its own and
+/// global ones, or only global ones without a session id (backend use).
+pub fn load_highlight_rules_for_session(
+    session_id: Option<&str>,
+) -> Result<Vec<HighlightRule>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_highlight_rules(&conn)?;
+    query_highlight_rules(&conn, session_id, true)
+}
+
+fn query_highlight_rules(
+    conn: &Connection,
+    session_id: Option<&str>,
+    enabled_only: bool,
+) -> Result<Vec<HighlightRule>, String> {
+    let mut sql = String::from(
+        "SELECT id, session_id, name, pattern, tag, count, enabled, created_at, updated_at FROM highlight_rules",
+    );
+    let mut where_clauses: Vec<&str> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    match session_id {
+        Some(sid) => {
+            where_clauses.push("(session_id = ? OR session_id IS NULL)");
+            params_vec.push(Box::new(sid.to_string()));
+        }
+        // Sessions without a saved id only get the global rules
+        None if enabled_only => where_clauses.push("session_id IS NULL"),
+        None => {}
+    }
+    if enabled_only {
+        where_clauses.push("enabled = 1");
+    }
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY created_at");
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    let rows = stmt
+        .query_map(param_refs.as_slice(), |row| {
+            Ok(HighlightRule {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                name: row.get(2)?,
+                pattern: row.get(3)?,
+                tag: row.get(4)?,
+                count: row.get::<_, i64>(5)? != 0,
+                enabled: row.get::<_, i64>(6)? != 0,
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Edit an existing highlight rule. Only provided fields are updated.
+#[tauri::command]
+pub fn edit_highlight_rule(
+    id: String,
+    name: Option<String>,
+    pattern: Option<String>,
+    tag: Option<String>,
+    count: Option<bool>,
+    enabled: Option<bool>,
+) -> Result<(), String> {
+    if let Some(p) = &pattern {
+        regex::Regex::new(p).map_err(|e| format!("Invalid pattern: {}", e))?;
+    }
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_highlight_rules(&conn)?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(p) = pattern {
+        sets.push("pattern = ?".to_string());
+        params_vec.push(Box::new(p));
+    }
+    if let Some(t) = tag {
+        sets.push("tag = ?".to_string());
+        params_vec.push(Box::new(t));
+    }
+    if let Some(c) = count {
+        sets.push("count = ?".to_string());
+        params_vec.push(Box::new(if c { 1 } else { 0 }));
+    }
+    if let Some(e) = enabled {
+        sets.push("enabled = ?".to_string());
+        params_vec.push(Box::new(if e { 1 } else { 0 }));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!("UPDATE highlight_rules SET {} WHERE id = ?", sets.join(", "));
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete a highlight rule.
+#[tauri::command]
+pub fn delete_highlight_rule(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_highlight_rules(&conn)?;
+    conn.execute("DELETE FROM highlight_rules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Connections kept per saved session; older ones are dropped as new ones
+/// are recorded
+const MAX_CONNECTION_HISTORY: i64 = 1000;
+
+/// Create the `connection_history` table if it does not exist.
+fn ensure_connection_history(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS connection_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            connected_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_connection_history_session ON connection_history(session_id, connected_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = conn.execute(
+        "ALTER TABLE connection_history ADD COLUMN disconnected_at INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE connection_history ADD COLUMN bytes_in INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE connection_history ADD COLUMN bytes_out INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE connection_history ADD COLUMN server_banner TEXT",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE connection_history ADD COLUMN motd TEXT", []);
+    Ok(())
+}
+
+/// Close the latest open connection record of a saved session with its
+/// traffic totals and the banner and MOTD the server showed (backend use).
+pub fn record_disconnect(
+    session_id: &str,
+    bytes_in: u64,
+    bytes_out: u64,
+    server_banner: Option<&str>,
+    motd: Option<&str>,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_connection_history(&conn)?;
+    conn.execute(
+        "UPDATE connection_history
+         SET disconnected_at = CAST(strftime('%s', 'now') AS INTEGER), bytes_in = ?2, bytes_out = ?3,
+             server_banner = ?4, motd = ?5
+         WHERE id = (
+            SELECT id FROM connection_history
+            WHERE session_id = ?1 AND disconnected_at IS NULL
+            ORDER BY connected_at DESC, id DESC LIMIT 1
+         )",
+        params![session_id, bytes_in as i64, bytes_out as i64, server_banner, motd],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// One connection of a saved session.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionRecord {
+    pub id: i64,
+    /// Unix seconds
+    pub connected_at: i64,
+    /// Unix seconds; `None` while connected or when the app quit first
+    pub disconnected_at: Option<i64>,
+    pub bytes_in: Option<i64>,
+    pub bytes_out: Option<i64>,
+    /// Identification string the server sent before authentication
+    pub server_banner: Option<String>,
+    /// Text the server printed before the first prompt
+    pub motd: Option<String>,
+}
+
+/// List the connections of a saved session, newest first, with the banner
+/// and MOTD each one showed.
+#[tauri::command]
+pub fn list_connection_history(
+    session_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<ConnectionRecord>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_connection_history(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, connected_at, disconnected_at, bytes_in, bytes_out, server_banner, motd
+             FROM connection_history WHERE session_id = ?1
+             ORDER BY connected_at DESC, id DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id, limit.unwrap_or(-1)], |row| {
+            Ok(ConnectionRecord {
+                id: row.get(0)?,
+                connected_at: row.get(1)?,
+                disconnected_at: row.get(2)?,
+                bytes_in: row.get(3)?,
+                bytes_out: row.get(4)?,
+                server_banner: row.get(5)?,
+                motd: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Create the tables of failed connection attempts and finished transfers,
+/// kept for the usage statistics.
+fn ensure_usage_history(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS connection_failures (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            failed_at INTEGER NOT NULL,
+            error TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transfer_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            direction TEXT NOT NULL,
+            status TEXT NOT NULL,
+            bytes INTEGER NOT NULL,
+            finished_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record a failed connection attempt of a saved session (backend use).
+pub fn record_connect_failure(session_id: &str, error: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_usage_history(&conn)?;
+    conn.execute(
+        "INSERT INTO connection_failures (session_id, failed_at, error)
+         VALUES (?1, CAST(strftime('%s', 'now') AS INTEGER), ?2)",
+        params![session_id, error],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record a finished SFTP transfer of a saved session (backend use).
+/// `status` is "success", "error" or "cancelled".
+pub fn record_transfer(
+    session_id: &str,
+    direction: &str,
+    status: &str,
+    bytes: u64,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_usage_history(&conn)?;
+    conn.execute(
+        "INSERT INTO transfer_history (session_id, direction, status, bytes, finished_at)
+         VALUES (?1, ?2, ?3, ?4, CAST(strftime('%s', 'now') AS INTEGER))",
+        params![session_id, direction, status, bytes as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Usage of one host over the range of `get_usage_stats`.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HostUsage {
+    pub addr: String,
+    pub connections: i64,
+    /// Seconds connected, counting closed connections only
+    pub connected_secs: i64,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+    pub failures: i64,
+}
+
+/// Usage of one saved session over the range of `get_usage_stats`.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub server_name: String,
+    pub addr: String,
+    pub connections: i64,
+    pub connected_secs: i64,
+    /// Unix seconds
+    pub last_connected_at: Option<i64>,
+}
+
+/// Aggregated usage for the statistics panel.
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    /// Start of the range in Unix seconds; `None` for all time
+    pub since: Option<i64>,
+    pub connections: i64,
+    pub connected_secs: i64,
+    pub connect_failures: i64,
+    /// Terminal and forwarding traffic of closed connections
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+    pub uploads: i64,
+    pub downloads: i64,
+    pub failed_transfers: i64,
+    pub bytes_uploaded: i64,
+    pub bytes_downloaded: i64,
+    /// Hosts by time connected, longest first
+    pub top_hosts: Vec<HostUsage>,
+    /// Saved sessions by number of connections, most used first
+    pub top_sessions: Vec<SessionUsage>,
+}
+
+/// Hosts and sessions listed in `UsageStats`
+const USAGE_TOP_LIMIT: i64 = 10;
+
+/// Start of a `get_usage_stats` range in Unix seconds
+fn usage_range_start(range: &str, now: i64) -> Result<Option<i64>, String> {
+    let days = match range {
+        "day" => 1,
+        "week" => 7,
+        "month" => 30,
+        "year" => 365,
+        "all" => return Ok(None),
+        other => return Err(format!("Unknown range: {}", other)),
+    };
+    Ok(Some(now - days * 86_400))
+}
+
+/// Aggregate the connection history, failed connection attempts and
+/// transfers of saved sessions over `range`: "day", "week", "month",
+/// "year" (the default) or "all". Sessions deleted since are left out of
+/// the per-host and per-session lists but still count in the totals.
+#[tauri::command]
+pub fn get_usage_stats(range: Option<String>) -> Result<UsageStats, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    let since = usage_range_start(range.as_deref().unwrap_or("year"), now)?;
+    let start = since.unwrap_or(0);
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_connection_history(&conn)?;
+    ensure_usage_history(&conn)?;
+
+    let mut stats = UsageStats {
+        since,
+        ..UsageStats::default()
+    };
+    (
+        stats.connections,
+        stats.connected_secs,
+        stats.bytes_in,
+        stats.bytes_out,
+    ) = conn
+        .query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(COALESCE(disconnected_at, connected_at) - connected_at), 0),
+                    COALESCE(SUM(bytes_in), 0), COALESCE(SUM(bytes_out), 0)
+             FROM connection_history WHERE connected_at >= ?1",
+            params![start],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    stats.connect_failures = conn
+        .query_row(
+            "SELECT COUNT(*) FROM connection_failures WHERE failed_at >= ?1",
+            params![start],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT direction, status, COUNT(*), COALESCE(SUM(bytes), 0)
+                 FROM transfer_history WHERE finished_at >= ?1
+                 GROUP BY direction, status",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![start], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (direction, status, count, bytes) = row.map_err(|e| e.to_string())?;
+            if status == "error" {
+                stats.failed_transfers += count;
+            }
+            match direction.as_str() {
+                "upload" => {
+                    stats.uploads += count;
+                    stats.bytes_uploaded += bytes;
+                }
+                _ => {
+                    stats.downloads += count;
+                    stats.bytes_downloaded += bytes;
+                }
+            }
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT LOWER(s.addr) AS host,
+                    COUNT(h.id),
+                    COALESCE(SUM(COALESCE(h.disconnected_at, h.connected_at) - h.connected_at), 0) AS secs,
+                    COALESCE(SUM(h.bytes_in), 0), COALESCE(SUM(h.bytes_out), 0),
+                    (SELECT COUNT(*) FROM connection_failures f
+                     JOIN sessions fs ON fs.id = f.session_id
+                     WHERE LOWER(fs.addr) = LOWER(s.addr) AND f.failed_at >= ?1)
+             FROM connection_history h JOIN sessions s ON s.id = h.session_id
+             WHERE h.connected_at >= ?1
+             GROUP BY host ORDER BY secs DESC, COUNT(h.id) DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    stats.top_hosts = stmt
+        .query_map(params![start, USAGE_TOP_LIMIT], |row| {
+            Ok(HostUsage {
+                addr: row.get(0)?,
+                connections: row.get(1)?,
+                connected_secs: row.get(2)?,
+                bytes_in: row.get(3)?,
+                bytes_out: row.get(4)?,
+                failures: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.server_name, s.addr, COUNT(h.id) AS connections,
+                    COALESCE(SUM(COALESCE(h.disconnected_at, h.connected_at) - h.connected_at), 0),
+                    MAX(h.connected_at)
+             FROM connection_history h JOIN sessions s ON s.id = h.session_id
+             WHERE h.connected_at >= ?1
+             GROUP BY s.id ORDER BY connections DESC, MAX(h.connected_at) DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    stats.top_sessions = stmt
+        .query_map(params![start, USAGE_TOP_LIMIT], |row| {
+            Ok(SessionUsage {
+                session_id: row.get(0)?,
+                server_name: row.get(1)?,
+                addr: row.get(2)?,
+                connections: row.get(3)?,
+                connected_secs: row.get(4)?,
+                last_connected_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(stats)
+}
+
+/// A session suggested for the quick-connect palette.
+#[derive(Serialize, Clone)]
+pub struct SessionSuggestion {
+    #[serde(flatten)]
+    pub session: Session,
+    pub score: f64,
+    pub frecency: f64,
+}
+
+/// Number of most recent connections sampled for the frecency score
+const FRECENCY_SAMPLES: usize = 10;
+
+/// Weight of a single connection by its age, in the spirit of Firefox's
+/// frecency buckets
+fn visit_weight(age_secs: i64) -> f64 {
+    match age_secs / 86_400 {
+        0..=4 => 100.0,
+        5..=14 => 70.0,
+        15..=31 => 50.0,
+        32..=90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+/// Frecency from a session's total connection count and its most recent
+/// connection times (Unix seconds, newest first)
+fn frecency(total: usize, recent: &[i64], now: i64) -> f64 {
+    if recent.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = recent.iter().map(|t| visit_weight(now - t)).sum();
+    total as f64 * sum / recent.len() as f64
+}
+
+/// Scores `text` as a fuzzy (subsequence) match of `term`, rewarding
+/// consecutive characters, word starts and prefixes. `None` when some
+/// character of the term does not occur in order.
+fn fuzzy_score(term: &str, text: &str) -> Option<f64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0.0;
+    let mut pos = 0;
+    let mut prev: Option<usize> = None;
+    for c in term.chars() {
+        let found = (pos..text.len()).find(|&i| text[i] == c)?;
+        score += 1.0;
+        if prev.is_some_and(|p| p + 1 == found) {
+            score += 2.0;
+        }
+        if found == 0 || matches!(text[found - 1], ' ' | '.' | '-' | '_' | '@' | '/' | ':') {
+            score += 3.0;
+        }
+        prev = Some(found);
+        pos = found + 1;
+    }
+    let text: String = text.into_iter().collect();
+    if text.starts_with(term) {
+        score += 5.0;
+    } else if text.contains(term) {
+        score += 2.0;
+    }
+    Some(score)
+}
+
+/// Best weighted match of a term across the session's searchable fields
+fn session_match(term: &str, session: &Session) -> Option<f64> {
+    [
+        (&session.server_name, 1.0),
+        (&session.addr, 0.8),
+        (&session.username, 0.6),
+    ]
+    .into_iter()
+    .filter_map(|(field, weight)| fuzzy_score(term, field).map(|s| s * weight))
+    .max_by(|a, b| a.total_cmp(b))
+}
+
+/// Suggest sessions for the quick-connect palette.
+///
+/// Sessions are fuzzy-matched on `server_name`, `addr` and `username`
+/// (every whitespace-separated term must match) and ranked by match quality
+/// weighted with a frecency score built from the connection history. An
+/// empty query returns the most frecent sessions.
+#[tauri::command]
+pub fn suggest_sessions(
+    query: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<SessionSuggestion>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_connection_history(&conn)?;
+
+    let mut history: HashMap<String, (usize, Vec<i64>)> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT session_id, connected_at FROM connection_history ORDER BY connected_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let (session_id, connected_at) = r.map_err(|e| e.to_string())?;
+            let entry = history.entry(session_id).or_default();
+            entry.0 += 1;
+            if entry.1.len() < FRECENCY_SAMPLES {
+                entry.1.push(connected_at);
+            }
+        }
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let query = query.unwrap_or_default().to_lowercase();
+    let terms: Vec<&str> = query.split_whitespace().collect();
+
+    let mut suggestions = Vec::new();
+    for session in list_sessions()? {
+        let Some(match_score) = terms
+            .iter()
+            .map(|term| session_match(term, &session))
+            .sum::<Option<f64>>()
+        else {
+            continue;
+        };
+        let frecency = history
+            .get(&session.id)
+            .map(|(total, recent)| frecency(*total, recent, now))
+            .unwrap_or(0.0);
+        let mut score = if terms.is_empty() {
+            frecency
+        } else {
+            match_score * (1.0 + (1.0 + frecency).ln() / 5.0)
+        };
+        if session.is_favorite {
+            score *= 1.1;
+        }
+        suggestions.push(SessionSuggestion {
+            session,
+            score,
+            frecency,
+        });
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.session.server_name.cmp(&b.session.server_name))
+    });
+    suggestions.truncate(limit.unwrap_or(10).clamp(1, 100) as usize);
+    Ok(suggestions)
+}
+
+/// Create the `session_secrets` table if it does not exist.
+fn ensure_session_secrets(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_secrets (
+            session_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            encrypted_value TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            last_accessed_at TEXT,
+            PRIMARY KEY (session_id, name)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Store (or replace) a named secret for a session, e.g. an API token or a
+/// root password. The value is encrypted with the machine-specific key.
+#[tauri::command]
+pub fn save_session_secret(session_id: String, name: String, value: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Secret name must not be empty".to_string());
+    }
+    let encrypted = crate::encryption::EncryptionManager::encrypt_secret(&value)?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_session_secrets(&conn)?;
+    conn.execute(
+        "INSERT INTO session_secrets (session_id, name, encrypted_value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(session_id, name) DO UPDATE SET
+            encrypted_value = excluded.encrypted_value,
+            updated_at = CURRENT_TIMESTAMP",
+        params![session_id, name, encrypted],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Decrypt and return a session secret, recording the access time. Asks
+/// for biometric confirmation first when it is enabled, and for
+/// re-authentication when the session is protected.
+#[tauri::command]
+pub async fn get_session_secret(
+    session_id: String,
+    name: String,
+    master_password: Option<String>,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::biometric::confirm_protected_session(&session_id, master_password.as_deref())?;
+        crate::biometric::confirm("access a saved secret")?;
+        read_session_secret(&session_id, &name)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn read_session_secret(session_id: &str, name: &str) -> Result<String, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_session_secrets(&conn)?;
+    let encrypted: String = conn
+        .query_row(
+            "SELECT encrypted_value FROM session_secrets WHERE session_id = ?1 AND name = ?2",
+            params![session_id, name],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Secret not found: {}", name),
+            e => e.to_string(),
+        })?;
+    let value = crate::encryption::EncryptionManager::decrypt_secret(&encrypted)?;
+    conn.execute(
+        "UPDATE session_secrets SET last_accessed_at = CURRENT_TIMESTAMP WHERE session_id = ?1 AND name = ?2",
+        params![session_id, name],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(value)
+}
+
+/// List the secrets stored for a session (names and timestamps only).
+#[tauri::command]
+pub fn list_session_secrets(session_id: String) -> Result<Vec<SessionSecret>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_session_secrets(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, name, created_at, updated_at, last_accessed_at
+             FROM session_secrets WHERE session_id = ?1 ORDER BY name",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok(SessionSecret {
+                session_id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+                last_accessed_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Delete a session secret.
+#[tauri::command]
+pub fn delete_session_secret(session_id: String, name: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_session_secrets(&conn)?;
+    conn.execute(
+        "DELETE FROM session_secrets WHERE session_id = ?1 AND name = ?2",
+        params![session_id, name],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create the single-row `credential_gate` table if it does not exist.
+fn ensure_credential_gate(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS credential_gate (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            require_biometric INTEGER NOT NULL DEFAULT 0,
+            grace_minutes INTEGER NOT NULL DEFAULT 5
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = conn.execute(
+        "ALTER TABLE credential_gate ADD COLUMN master_password_hash TEXT",
+        [],
+    );
+    Ok(())
+}
+
+/// Load the biometric gating options (backend use).
+pub fn load_biometric_options() -> Result<crate::biometric::BiometricOptions, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_credential_gate(&conn)?;
+    let row = conn.query_row(
+        "SELECT require_biometric, grace_minutes FROM credential_gate WHERE id = 1",
+        [],
+        |row| Ok((row.get::<_, i64>(0)? != 0, row.get::<_, i64>(1)?)),
+    );
+    match row {
+        Ok((enabled, grace_minutes)) => Ok(crate::biometric::BiometricOptions {
+            enabled,
+            grace_minutes,
+            available: false,
+        }),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(crate::biometric::BiometricOptions {
+            enabled: false,
+            grace_minutes: 5,
+            available: false,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Save the biometric gating options (backend use).
+pub fn save_biometric_options(enabled: bool, grace_minutes: i64) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_credential_gate(&conn)?;
+    conn.execute(
+        "INSERT INTO credential_gate (id, require_biometric, grace_minutes) VALUES (1, ?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET require_biometric = excluded.require_biometric,
+                                       grace_minutes = excluded.grace_minutes",
+        params![if enabled { 1 } else { 0 }, grace_minutes],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Load the hash of the master password, if one is set (backend use).
+pub fn load_master_password_hash() -> Result<Option<String>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_credential_gate(&conn)?;
+    let row = conn.query_row(
+        "SELECT master_password_hash FROM credential_gate WHERE id = 1",
+        [],
+        |row| row.get(0),
+    );
+    match row {
+        Ok(hash) => Ok(hash),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Save or clear the hash of the master password (backend use).
+pub fn save_master_password_hash(hash: Option<String>) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_credential_gate(&conn)?;
+    conn.execute("INSERT OR IGNORE INTO credential_gate (id) VALUES (1)", [])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE credential_gate SET master_password_hash = ?1 WHERE id = 1",
+        params![hash],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create the `settings` table if it does not exist.
+fn ensure_settings(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Load all stored settings as (dotted key, JSON value) pairs (backend use).
+pub fn load_settings() -> Result<Vec<(String, String)>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_settings(&conn)?;
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Store one setting as JSON (backend use).
+pub fn save_setting(key: &str, json: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_settings(&conn)?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        params![key, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remove a stored setting so its default applies again (backend use).
+pub fn delete_setting(key: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_settings(&conn)?;
+    conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// A deleted session, group or tag, kept so the deletion reaches other
+/// devices through sync.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tombstone {
+    /// "session", "group" or "tag"
+    pub kind: String,
+    pub id: String,
+    pub deleted_at: String,
+}
+
+/// Create the `sync_tombstones` and single-row `sync_state` tables if they
+/// do not exist.
+fn ensure_sync(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_tombstones (
+            kind TEXT NOT NULL,
+            id TEXT NOT NULL,
+            deleted_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            PRIMARY KEY (kind, id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            encrypted_passphrase TEXT,
+            encrypted_webdav_password TEXT,
+            last_synced_at TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn record_tombstone(conn: &Connection, kind: &str, id: &str) -> Result<(), String> {
+    ensure_sync(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_tombstones (kind, id, deleted_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+        params![kind, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Stored sync secrets, decrypted (backend use).
+#[derive(Default)]
+pub struct SyncSecrets {
+    pub passphrase: Option<String>,
+    pub webdav_password: Option<String>,
+    pub last_synced_at: Option<String>,
+}
+
+/// Load the sync passphrase, WebDAV password and last sync time (backend use).
+pub fn load_sync_secrets() -> Result<SyncSecrets, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_sync(&conn)?;
+    let row = conn.query_row(
+        "SELECT encrypted_passphrase, encrypted_webdav_password, last_synced_at FROM sync_state WHERE id = 1",
+        [],
+        |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        },
+    );
+    let (passphrase, webdav_password, last_synced_at) = match row {
+        Ok(row) => row,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(SyncSecrets::default()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let decrypt = |value: Option<String>| {
+        value
+            .map(|v| crate::encryption::EncryptionManager::decrypt_secret(&v))
+            .transpose()
+    };
+    Ok(SyncSecrets {
+        passphrase: decrypt(passphrase)?,
+        webdav_password: decrypt(webdav_password)?,
+        last_synced_at,
+    })
+}
+
+/// Store the sync passphrase and/or WebDAV password, encrypted with the
+/// machine key. `None` keeps the stored value, an empty string clears it
+/// (backend use).
+pub fn save_sync_secrets(
+    passphrase: Option<String>,
+    webdav_password: Option<String>,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_sync(&conn)?;
+    conn.execute("INSERT OR IGNORE INTO sync_state (id) VALUES (1)", [])
+        .map_err(|e| e.to_string())?;
+    for (column, value) in [
+        ("encrypted_passphrase", passphrase),
+        ("encrypted_webdav_password", webdav_password),
+    ] {
+        let Some(value) = value else { continue };
+        let encrypted = if value.is_empty() {
+            None
+        } else {
+            Some(crate::encryption::EncryptionManager::encrypt_secret(
+                &value,
+            )?)
+        };
+        conn.execute(
+            &format!("UPDATE sync_state SET {} = ?1 WHERE id = 1", column),
+            params![encrypted],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Snapshot the library for sync: all sessions, groups and tags with
+/// credentials encrypted with `passphrase`, plus the recorded deletions
+/// (backend use).
+pub fn load_sync_snapshot(passphrase: &str) -> Result<(ExportData, Vec<Tombstone>), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    ensure_sync(&conn)?;
+    let data = build_export(&conn, Some(passphrase))?;
+    let mut stmt = conn
+        .prepare("SELECT kind, id, deleted_at FROM sync_tombstones")
+        .map_err(|e| e.to_string())?;
+    let tombstones = stmt
+        .query_map([], |row| {
+            Ok(Tombstone {
+                kind: row.get(0)?,
+                id: row.get(1)?,
+                deleted_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok((data, tombstones))
+}
+
+/// Insert or update a group from a library snapshot.
+fn upsert_library_group(conn: &Connection, group: &Group) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO groups (id, name, sort, created_at, updated_at, color, icon, terminal_theme) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, sort = excluded.sort, updated_at = excluded.updated_at,
+             color = excluded.color, icon = excluded.icon, terminal_theme = excluded.terminal_theme",
+        params![
+            group.id, group.name, group.sort, group.created_at, group.updated_at,
+            group.appearance.color, group.appearance.icon, group.appearance.terminal_theme
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Insert or update a tag from a library snapshot.
+fn upsert_library_tag(conn: &Connection, tag: &Tag) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO tags (id, name, color, sort, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, color = excluded.color, sort = excluded.sort, updated_at = excluded.updated_at",
+        params![tag.id, tag.name, tag.color, tag.sort, tag.created_at, tag.updated_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Insert or update a session and its group/tag links from a library
+/// snapshot. Stored credentials and per-device columns are left untouched.
+fn upsert_library_session(conn: &Connection, session: &ExportSession) -> Result<(), String> {
+    let metadata = &session.metadata;
+    conn.execute(
+        "INSERT INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, created_at, updated_at, mac_address, certificate_path, is_template, color, icon, terminal_theme)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT(id) DO UPDATE SET addr = excluded.addr, port = excluded.port, server_name = excluded.server_name,
+             username = excluded.username, auth_type = excluded.auth_type, private_key_path = excluded.private_key_path,
+             is_favorite = excluded.is_favorite, updated_at = excluded.updated_at, mac_address = excluded.mac_address,
+             certificate_path = excluded.certificate_path, is_template = excluded.is_template,
+             color = excluded.color, icon = excluded.icon, terminal_theme = excluded.terminal_theme",
+        params![
+            metadata.id, metadata.addr, metadata.port, metadata.server_name,
+            metadata.username, metadata.auth_type, metadata.private_key_path,
+            if metadata.is_favorite { 1 } else { 0 }, metadata.created_at, metadata.updated_at,
+            metadata.mac_address, metadata.certificate_path,
+            if metadata.is_template { 1 } else { 0 },
+            metadata.appearance.color, metadata.appearance.icon, metadata.appearance.terminal_theme
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM session_groups WHERE session_id = ?1",
+        params![metadata.id],
+    )
+    .map_err(|e| e.to_string())?;
+    for gid in &session.group_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
+            params![metadata.id, gid],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1",
+        params![metadata.id],
+    )
+    .map_err(|e| e.to_string())?;
+    for tid in &session.tag_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+            params![metadata.id, tid],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Store or clear a session's credentials in the backend this device uses
+/// for it.
+fn write_credentials(
+    conn: &Connection,
+    session_id: &str,
+    credentials: Option<&crate::encryption::SensitiveData>,
+) -> Result<(), String> {
+    match credential_backend(conn, session_id)? {
+        CredentialBackend::Keychain => match credentials {
+            Some(c) => crate::keychain::write(session_id, c),
+            None => crate::keychain::delete(session_id),
+        },
+        CredentialBackend::Vault => {
+            let encrypted = credentials
+                .map(crate::encryption::EncryptionManager::encrypt)
+                .transpose()?;
+            conn.execute(
+                "UPDATE sessions SET encrypted_credentials = ?1 WHERE id = ?2",
+                params![encrypted, session_id],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+/// Delete a session, group or tag and its associations. Keychain entries
+/// are left to the caller, see `delete_keychain_entries`.
+fn delete_library_record(conn: &Connection, kind: &str, id: &str) -> Result<(), String> {
+    let statements: &[&str] = match kind {
+        "session" => &[
+            "DELETE FROM session_groups WHERE session_id = ?1",
+            "DELETE FROM session_tags WHERE session_id = ?1",
+            "DELETE FROM connection_history WHERE session_id = ?1",
+            "DELETE FROM session_secrets WHERE session_id = ?1",
+            "DELETE FROM sessions WHERE id = ?1",
+        ],
+        "group" => &[
+            "DELETE FROM session_groups WHERE group_id = ?1",
+            "DELETE FROM groups WHERE id = ?1",
+        ],
+        "tag" => &[
+            "DELETE FROM session_tags WHERE tag_id = ?1",
+            "DELETE FROM tags WHERE id = ?1",
+        ],
+        other => return Err(format!("Unknown record kind: {}", other)),
+    };
+    for sql in statements {
+        conn.execute(sql, params![id]).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Apply the result of a sync merge in one transaction: upsert records that
+/// are newer remotely, delete records removed on another device and store
+/// the merged tombstones (backend use).
+pub fn apply_sync_changes(
+    incoming: ExportData,
+    deletions: &[Tombstone],
+    tombstones: &[Tombstone],
+    passphrase: &str,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    ensure_sync(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for group in &incoming.groups {
+        upsert_library_group(&tx, group)?;
+    }
+    for tag in &incoming.tags {
+        upsert_library_tag(&tx, tag)?;
+    }
+
+    for session in incoming.sessions {
+        let credentials = session
+            .encrypted_credentials
+            .as_ref()
+            .map(|c| crate::encryption::EncryptionManager::decrypt_with_key(c, passphrase))
+            .transpose()?;
+
+        upsert_library_session(&tx, &session)?;
+        write_credentials(&tx, &session.metadata.id, credentials.as_ref())?;
+    }
+
+    let mut keychain_ids = Vec::new();
+    for deletion in deletions {
+        if deletion.kind == "session" && uses_keychain(&tx, &deletion.id) {
+            keychain_ids.push(deletion.id.clone());
+        }
+        delete_library_record(&tx, &deletion.kind, &deletion.id)?;
+    }
+
+    for tombstone in tombstones {
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_tombstones (kind, id, deleted_at) VALUES (?1, ?2, ?3)",
+            params![tombstone.kind, tombstone.id, tombstone.deleted_at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    delete_keychain_entries(&keychain_ids);
+    Ok(())
+}
+
+/// Record that a sync round finished, once the merged library is stored
+/// remotely (backend use).
+pub fn mark_synced() -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_sync(&conn)?;
+    conn.execute("INSERT OR IGNORE INTO sync_state (id) VALUES (1)", [])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE sync_state SET last_synced_at = CURRENT_TIMESTAMP WHERE id = 1",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Load the whole session library, with credentials encrypted with
+/// `password` when given (backend use).
+pub fn load_library(password: Option<&str>) -> Result<ExportData, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    build_export(&conn, password)
+}
+
+/// Replace the session library with a snapshot in one transaction.
+/// Records missing from the snapshot are deleted; credentials of sessions
+/// that remain are kept. Restored records count as edited now so sync
+/// propagates the rollback (backend use).
+pub fn restore_library(snapshot: &ExportData) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    ensure_sync(&conn)?;
+    let current = build_export(&conn, None)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut keychain_ids = Vec::new();
+    for session in &current.sessions {
+        if !snapshot
+            .sessions
+            .iter()
+            .any(|s| s.metadata.id == session.metadata.id)
+        {
+            if uses_keychain(&tx, &session.metadata.id) {
+                keychain_ids.push(session.metadata.id.clone());
+            }
+            delete_library_record(&tx, "session", &session.metadata.id)?;
+            record_tombstone(&tx, "session", &session.metadata.id)?;
+        }
+    }
+    for group in &current.groups {
+        if !snapshot.groups.iter().any(|g| g.id == group.id) {
+            delete_library_record(&tx, "group", &group.id)?;
+            record_tombstone(&tx, "group", &group.id)?;
+        }
+    }
+    for tag in &current.tags {
+        if !snapshot.tags.iter().any(|t| t.id == tag.id) {
+            delete_library_record(&tx, "tag", &tag.id)?;
+            record_tombstone(&tx, "tag", &tag.id)?;
+        }
+    }
+
+    for group in &snapshot.groups {
+        upsert_library_group(&tx, group)?;
+    }
+    for tag in &snapshot.tags {
+        upsert_library_tag(&tx, tag)?;
+    }
+    for session in &snapshot.sessions {
+        upsert_library_session(&tx, session)?;
+    }
+    for table in ["sessions", "groups", "tags"] {
+        tx.execute(
+            &format!("UPDATE {} SET updated_at = CURRENT_TIMESTAMP", table),
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    delete_keychain_entries(&keychain_ids);
+    Ok(())
+}
+
+/// Import records from a shared bundle in one transaction. Group and tag
+/// ids of sessions must already point at the records they are imported as.
+/// Credentials are only written for sessions that carry them; imported
+/// records count as edited now (backend use).
+pub fn import_shared_records(
+    groups: &[Group],
+    tags: &[Tag],
+    sessions: &[ExportSession],
+    passphrase: &str,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for group in groups {
+        upsert_library_group(&tx, group)?;
+        tx.execute(
+            "UPDATE groups SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![group.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for tag in tags {
+        upsert_library_tag(&tx, tag)?;
+        tx.execute(
+            "UPDATE tags SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![tag.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for session in sessions {
+        upsert_library_session(&tx, session)?;
+        tx.execute(
+            "UPDATE sessions SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![session.metadata.id],
+        )
+        .map_err(|e| e.to_string())?;
+        if let Some(encrypted) = &session.encrypted_credentials {
+            let credentials =
+                crate::encryption::EncryptionManager::decrypt_with_key(encrypted, passphrase)?;
+            write_credentials(&tx, &session.metadata.id, Some(&credentials))?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn ensure_clipboard_history(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS clipboard_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            copied_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_clipboard_history_session ON clipboard_history(session_id, copied_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Text copied from a session's terminal.
+#[derive(Serialize, Clone)]
+pub struct ClipboardEntry {
+    pub id: i64,
+    pub session_id: String,
+    pub text: String,
+    /// Unix seconds
+    pub copied_at: i64,
+}
+
+/// Remember text copied from a saved session's terminal. Copying the same
+/// text again moves it to the top. Returns false when nothing was stored
+/// because the history is disabled, or the text is blank or too large.
+#[tauri::command]
+pub fn record_clipboard(session_id: String, text: String) -> Result<bool, String> {
+    let settings = crate::settings::current().clipboard;
+    if !settings.history_enabled || text.trim().is_empty() || text.len() > settings.max_entry_bytes
+    {
+        return Ok(false);
+    }
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_clipboard_history(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM clipboard_history WHERE session_id = ?1 AND text = ?2",
+        params![session_id, text],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO clipboard_history (session_id, text, copied_at) VALUES (?1, ?2, CAST(strftime('%s', 'now') AS INTEGER))",
+        params![session_id, text],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM clipboard_history WHERE session_id = ?1 AND id NOT IN (
+            SELECT id FROM clipboard_history WHERE session_id = ?1 ORDER BY id DESC LIMIT ?2
+        )",
+        params![session_id, settings.history_limit.max(1) as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// List text copied from a saved session's terminal, newest first.
+#[tauri::command]
+pub fn list_clipboard_history(session_id: String) -> Result<Vec<ClipboardEntry>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_clipboard_history(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, session_id, text, copied_at FROM clipboard_history
+             WHERE session_id = ?1 ORDER BY id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok(ClipboardEntry {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                text: row.get(2)?,
+                copied_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Clear the clipboard history of one saved session, or of all sessions
+/// when `session_id` is omitted.
+#[tauri::command]
+pub fn clear_clipboard_history(session_id: Option<String>) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_clipboard_history(&conn)?;
+    match session_id {
+        Some(id) => conn.execute(
+            "DELETE FROM clipboard_history WHERE session_id = ?1",
+            params![id],
+        ),
+        None => conn.execute("DELETE FROM clipboard_history", []),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remote paths remembered per saved session and kind
+const MAX_RECENT_REMOTE_PATHS: i64 = 20;
+
+fn ensure_recent_remote_paths(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recent_remote_paths (
+            session_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            use_count INTEGER NOT NULL DEFAULT 1,
+            used_at INTEGER NOT NULL,
+            PRIMARY KEY (session_id, kind, path)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// A remote path recently used over SFTP.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentRemotePath {
+    pub path: String,
+    /// "dir" for a browsed or upload destination directory, "upload" for
+    /// an uploaded file
+    pub kind: String,
+    pub use_count: i64,
+    /// Unix seconds
+    pub used_at: i64,
+}
+
+/// Remember a remote path used by a saved session (backend use). Only the
+/// most recent paths of each kind are kept.
+pub fn record_recent_remote_path(session_id: &str, path: &str, kind: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_recent_remote_paths(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO recent_remote_paths (session_id, path, kind, used_at)
+         VALUES (?1, ?2, ?3, CAST(strftime('%s', 'now') AS INTEGER))
+         ON CONFLICT (session_id, kind, path) DO UPDATE SET
+            use_count = use_count + 1, used_at = excluded.used_at",
+        params![session_id, path, kind],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM recent_remote_paths WHERE session_id = ?1 AND kind = ?2 AND path NOT IN (
+            SELECT path FROM recent_remote_paths WHERE session_id = ?1 AND kind = ?2
+            ORDER BY used_at DESC LIMIT ?3
+        )",
+        params![session_id, kind, MAX_RECENT_REMOTE_PATHS],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// List the remote directories and uploaded files a saved session used
+/// recently, newest first, so file dialogs can start somewhere useful.
+/// `kind` restricts the list to "dir" or "upload".
+#[tauri::command]
+pub fn list_recent_remote_paths(
+    session_id: String,
+    kind: Option<String>,
+) -> Result<Vec<RecentRemotePath>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_recent_remote_paths(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, kind, use_count, used_at FROM recent_remote_paths
+             WHERE session_id = ?1 AND (?2 IS NULL OR kind = ?2)
+             ORDER BY used_at DESC, use_count DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id, kind], |row| {
+            Ok(RecentRemotePath {
+                path: row.get(0)?,
+                kind: row.get(1)?,
+                use_count: row.get(2)?,
+                used_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn ensure_cloud_accounts(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cloud_accounts (
+            provider TEXT PRIMARY KEY,
+            options TEXT NOT NULL,
+            encrypted_token TEXT,
+            last_synced_at TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// A cloud provider account saved by an import, token decrypted (backend use).
+pub struct CloudAccount {
+    pub options: crate::cloud::ProviderOptions,
+    pub token: Option<String>,
+    pub last_synced_at: Option<String>,
+}
+
+/// Load the saved account of a cloud provider, if it was imported before
+/// (backend use).
+pub fn load_cloud_account(provider: &str) -> Result<Option<CloudAccount>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_cloud_accounts(&conn)?;
+    let row = conn.query_row(
+        "SELECT options, encrypted_token, last_synced_at FROM cloud_accounts WHERE provider = ?1",
+        params![provider],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        },
+    );
+    let (options, token, last_synced_at) = match row {
+        Ok(row) => row,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    Ok(Some(CloudAccount {
+        options: serde_json::from_str(&options).unwrap_or_default(),
+        token: token
+            .map(|t| crate::encryption::EncryptionManager::decrypt_secret(&t))
+            .transpose()?,
+        last_synced_at,
+    }))
+}
+
+/// Save a cloud provider account and mark it as just synced. The token is
+/// encrypted with the machine key; `None` keeps the stored one (backend use).
+pub fn save_cloud_account(
+    provider: &str,
+    options: &crate::cloud::ProviderOptions,
+    token: Option<&str>,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_cloud_accounts(&conn)?;
+    let options = serde_json::to_string(options).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO cloud_accounts (provider, options, last_synced_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(provider) DO UPDATE SET
+            options = excluded.options, last_synced_at = excluded.last_synced_at",
+        params![provider, options],
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(token) = token {
+        let encrypted = crate::encryption::EncryptionManager::encrypt_secret(token)?;
+        conn.execute(
+            "UPDATE cloud_accounts SET encrypted_token = ?1 WHERE provider = ?2",
+            params![encrypted, provider],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Merge the hosts listed by a cloud provider into the library, matching
+/// earlier imports by `cloud_source`. New hosts become sessions tagged with
+/// `label` (the tag is created when missing); known ones get their name and
+/// address updated, leaving user edits of login and key alone unless no
+/// key was set. Sessions of hosts that are gone are reported as stale, not
+/// deleted (backend use).
+pub fn sync_cloud_sessions(
+    provider: &str,
+    label: &str,
+    records: &[crate::cloud::CloudSessionRecord],
+) -> Result<crate::cloud::CloudSyncResult, String> {
+    let db_path = db_path()?;
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_groups_and_tags(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let rules = load_assignment_rules(&tx)?;
+
+    let tag_id = match tx.query_row(
+        "SELECT id FROM tags WHERE name = ?1 ORDER BY created_at LIMIT 1",
+        params![label],
+        |row| row.get::<_, String>(0),
+    ) {
+        Ok(id) => id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            let id = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO tags (id, name, sort) VALUES (?1, ?2, 1)",
+                params![id, label],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // cloud_source -> (id, server_name, addr, private_key_path)
+    let mut existing: HashMap<String, (String, String, String, Option<String>)> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT cloud_source, id, server_name, addr, private_key_path FROM sessions
+                 WHERE cloud_source LIKE ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![format!("{}:%", provider)], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?),
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut result = crate::cloud::CloudSyncResult::default();
+    for record in records {
+        match existing.remove(&record.source) {
+            Some((id, server_name, addr, key_path)) => {
+                let add_key = key_path.is_none() && record.private_key_path.is_some();
+                if server_name == record.server_name && addr == record.addr && !add_key {
+                    result.unchanged += 1;
+                    continue;
+                }
+                tx.execute(
+                    "UPDATE sessions SET server_name = ?1, addr = ?2, updated_at = CURRENT_TIMESTAMP
+                     WHERE id = ?3",
+                    params![record.server_name, record.addr, id],
+                )
+                .map_err(|e| e.to_string())?;
+                if add_key {
+                    tx.execute(
+                        "UPDATE sessions SET auth_type = 'key', private_key_path = ?1 WHERE id = ?2",
+                        params![record.private_key_path, id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                result.updated += 1;
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                let auth_type = if record.private_key_path.is_some() {
+                    "key"
+                } else {
+                    "password"
+                };
+                tx.execute(
+                    "INSERT INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, cloud_source)
+                     VALUES (?1, ?2, 22, ?3, ?4, ?5, ?6, 0, ?7)",
+                    params![
+                        id,
+                        record.addr,
+                        record.server_name,
+                        record.username,
+                        auth_type,
+                        record.private_key_path,
+                        record.source
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
+                    params![id, tag_id],
+                )
+                .map_err(|e| e.to_string())?;
+                assign_by_rules(&tx, &rules, &id, &record.addr, &record.server_name)?;
+                result.added += 1;
+            }
+        }
+    }
+    result.stale = existing.into_values().map(|(id, ..)| id).collect();
+
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::config_history::schedule_commit();
+    Ok(result)
+}
+
+/// Create the `workflows` table if it does not exist.
+fn ensure_workflows(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS workflows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT '',
+            description TEXT NOT NULL DEFAULT '',
+            steps TEXT NOT NULL DEFAULT '[]',
+            abort_on_failure INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn validate_workflow_steps(steps: &[WorkflowStep]) -> Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        let label = if step.name.is_empty() {
+            format!("Step {}", index + 1)
+        } else {
+            step.name.clone()
+        };
+        let missing = match step.kind.as_str() {
+            "command" if step.command.trim().is_empty() => Some("a command"),
+            "upload" if step.local_path.is_empty() || step.remote_path.is_empty() => {
+                Some("a local and a remote path")
+            }
+            "wait" if step.command.trim().is_empty() => Some("a command"),
+            "wait" => {
+                regex::Regex::new(&step.pattern)
+                    .map_err(|e| format!("{}: invalid pattern: {}", label, e))?;
+                None
+            }
+            "prompt" if step.message.trim().is_empty() => Some("a message"),
+            "command" | "upload" | "prompt" => None,
+            other => return Err(format!("{}: unknown step kind: {}", label, other)),
+        };
+        if let Some(missing) = missing {
+            return Err(format!("{} needs {}", label, missing));
+        }
+    }
+    Ok(())
+}
+
+/// Create a workflow and return its UUID.
+#[tauri::command]
+pub fn add_workflow(
+    name: String,
+    description: Option<String>,
+    steps: Vec<WorkflowStep>,
+    abort_on_failure: Option<bool>,
+) -> Result<String, String> {
+    validate_workflow_steps(&steps)?;
+    let steps = serde_json::to_string(&steps).map_err(|e| e.to_string())?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_workflows(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO workflows (id, name, description, steps, abort_on_failure)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            id,
+            name,
+            description.unwrap_or_default(),
+            steps,
+            abort_on_failure.unwrap_or(true)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// List all workflows by name.
+#[tauri::command]
+pub fn list_workflows() -> Result<Vec<Workflow>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_workflows(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, description, steps, abort_on_failure, created_at, updated_at
+             FROM workflows ORDER BY name, created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Workflow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                // A hand-edited, unreadable step list shows as empty
+                steps: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+                abort_on_failure: row.get::<_, i64>(4)? != 0,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut v = Vec::new();
+    for r in rows {
+        v.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(v)
+}
+
+/// Load a single workflow by id (backend use).
+pub fn load_workflow(id: &str) -> Result<Workflow, String> {
+    list_workflows()?
+        .into_iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("Workflow not found: {}", id))
+}
+
+/// Edit an existing workflow. Only provided fields are updated; `steps`
+/// replaces the whole list.
+#[tauri::command]
+pub fn edit_workflow(
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    steps: Option<Vec<WorkflowStep>>,
+    abort_on_failure: Option<bool>,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_workflows(&conn)?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(d) = description {
+        sets.push("description = ?".to_string());
+        params_vec.push(Box::new(d));
+    }
+    if let Some(steps) = steps {
+        validate_workflow_steps(&steps)?;
+        sets.push("steps = ?".to_string());
+        params_vec.push(Box::new(
+            serde_json::to_string(&steps).map_err(|e| e.to_string())?,
+        ));
+    }
+    if let Some(a) = abort_on_failure {
+        sets.push("abort_on_failure = ?".to_string());
+        params_vec.push(Box::new(if a { 1 } else { 0 }));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!("UPDATE workflows SET {} WHERE id = ?", sets.join(", "));
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete a workflow.
+#[tauri::command]
+pub fn delete_workflow(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_workflows(&conn)?;
+    conn.execute("DELETE FROM workflows WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Runs kept per scheduled task
+const MAX_SCHEDULED_RUNS: i64 = 200;
+
+/// Create the `scheduled_tasks` and `scheduled_runs` tables if they do not
+/// exist.
+fn ensure_scheduled_tasks(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_tasks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL DEFAULT '',
+            kind TEXT NOT NULL,
+            command TEXT NOT NULL DEFAULT '',
+            workflow_id TEXT NOT NULL DEFAULT '',
+            session_ids TEXT NOT NULL DEFAULT '[]',
+            schedule TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            last_run_at INTEGER,
+            last_status TEXT,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            output TEXT NOT NULL DEFAULT '',
+            error TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_scheduled_runs_task ON scheduled_runs(task_id, started_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn validate_scheduled_task(
+    kind: &str,
+    command: &str,
+    workflow_id: &str,
+    schedule: &str,
+    sessions: usize,
+) -> Result<(), String> {
+    crate::scheduler::CronSchedule::parse(schedule)?;
+    match kind {
+        "command" | "backup" if command.trim().is_empty() => {
+            Err("Command must not be empty".to_string())
+        }
+        // Snapshots are kept per task, so they compare one server's output
+        "backup" if sessions != 1 => Err("A backup task runs on exactly one session".to_string()),
+        "workflow" => load_workflow(workflow_id).map(|_| ()),
+        "command" | "backup" => Ok(()),
+        other => Err(format!("Unknown task kind: {}", other)),
+    }
+}
+
+/// Create a scheduled task and return its UUID.
+#[tauri::command]
+pub fn add_scheduled_task(
+    name: Option<String>,
+    kind: String,
+    command: Option<String>,
+    workflow_id: Option<String>,
+    session_ids: Vec<String>,
+    schedule: String,
 ) -> Result<String, String> {
+    let command = command.unwrap_or_default();
+    let workflow_id = workflow_id.unwrap_or_default();
+    validate_scheduled_task(&kind, &command, &workflow_id, &schedule, session_ids.len())?;
+    if session_ids.is_empty() {
+        return Err("No sessions selected".to_string());
+    }
+    let session_ids = serde_json::to_string(&session_ids).map_err(|e| e.to_string())?;
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    ensure_groups_and_tags(&conn)?;
+    ensure_scheduled_tasks(&conn)?;
     let id = Uuid::new_v4().to_string();
-    let name = name.unwrap_or_default();
-    let sort = sort.unwrap_or(1);
     conn.execute(
-        "INSERT INTO tags (id, name, color, sort) VALUES (?1, ?2, ?3, ?4)",
-        params![id, name, color, sort],
+        "INSERT INTO scheduled_tasks (id, name, kind, command, workflow_id, session_ids, schedule)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            name.unwrap_or_default(),
+            kind,
+            command,
+            workflow_id,
+            session_ids,
+            schedule.trim()
+        ],
     )
     .map_err(|e| e.to_string())?;
     Ok(id)
 }
 
-/// Return all tags ordered by `sort` then `created_at`.
+/// List scheduled tasks with their last result and next run time.
 #[tauri::command]
-pub fn list_tags() -> Result<Vec<Tag>, String> {
+pub fn list_scheduled_tasks() -> Result<Vec<ScheduledTask>, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    ensure_groups_and_tags(&conn)?;
+    ensure_scheduled_tasks(&conn)?;
     let mut stmt = conn
-        .prepare("SELECT id, name, color, sort, created_at, updated_at FROM tags ORDER BY sort, created_at")
+        .prepare(
+            "SELECT id, name, kind, command, workflow_id, session_ids, schedule, enabled, last_run_at, last_status, created_at, updated_at
+             FROM scheduled_tasks ORDER BY created_at",
+        )
         .map_err(|e| e.to_string())?;
     let rows = stmt
         .query_map([], |row| {
-            Ok(Tag {
+            let schedule: String = row.get(6)?;
+            let enabled = row.get::<_, i64>(7)? != 0;
+            Ok(ScheduledTask {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                color: row.get(2)?,
-                sort: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
+                kind: row.get(2)?,
+                command: row.get(3)?,
+                workflow_id: row.get(4)?,
+                session_ids: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+                next_run_at: enabled
+                    .then(|| crate::scheduler::next_run_millis(&schedule))
+                    .flatten(),
+                schedule,
+                enabled,
+                last_run_at: row.get(8)?,
+                last_status: row.get(9)?,
+                created_at: row.get(10)?,
+                updated_at: row.get(11)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -945,260 +5727,760 @@ pub fn list_tags() -> Result<Vec<Tag>, String> {
     Ok(v)
 }
 
-/// Associate a session with a tag (logical join). Duplicate associations
-/// are ignored.
-#[tauri::command]
-pub fn link_session_tag(session_id: String, tag_id: String) -> Result<(), String> {
+/// Load a single scheduled task by id (backend use).
+pub fn load_scheduled_task(id: &str) -> Result<ScheduledTask, String> {
+    list_scheduled_tasks()?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Scheduled task not found: {}", id))
+}
+
+/// Edit an existing scheduled task. Only provided fields are updated.
+#[tauri::command]
+pub fn edit_scheduled_task(
+    id: String,
+    name: Option<String>,
+    command: Option<String>,
+    workflow_id: Option<String>,
+    session_ids: Option<Vec<String>>,
+    schedule: Option<String>,
+) -> Result<(), String> {
+    let task = load_scheduled_task(&id)?;
+    validate_scheduled_task(
+        &task.kind,
+        command.as_deref().unwrap_or(&task.command),
+        workflow_id.as_deref().unwrap_or(&task.workflow_id),
+        schedule.as_deref().unwrap_or(&task.schedule),
+        session_ids
+            .as_ref()
+            .map_or(task.session_ids.len(), Vec::len),
+    )?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(c) = command {
+        sets.push("command = ?".to_string());
+        params_vec.push(Box::new(c));
+    }
+    if let Some(w) = workflow_id {
+        sets.push("workflow_id = ?".to_string());
+        params_vec.push(Box::new(w));
+    }
+    if let Some(ids) = session_ids {
+        if ids.is_empty() {
+            return Err("No sessions selected".to_string());
+        }
+        sets.push("session_ids = ?".to_string());
+        params_vec.push(Box::new(
+            serde_json::to_string(&ids).map_err(|e| e.to_string())?,
+        ));
+    }
+    if let Some(s) = schedule {
+        sets.push("schedule = ?".to_string());
+        params_vec.push(Box::new(s.trim().to_string()));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!("UPDATE scheduled_tasks SET {} WHERE id = ?", sets.join(", "));
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Enable or disable a scheduled task.
+#[tauri::command]
+pub fn set_scheduled_task_enabled(id: String, enabled: bool) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_scheduled_tasks(&conn)?;
+    conn.execute(
+        "UPDATE scheduled_tasks SET enabled = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![enabled, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete a scheduled task and its run history. Snapshots of a backup
+/// task are kept on disk.
+#[tauri::command]
+pub fn delete_scheduled_task(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_scheduled_tasks(&conn)?;
+    conn.execute("DELETE FROM scheduled_tasks WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM scheduled_runs WHERE task_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// List the run history of a scheduled task, newest first.
+#[tauri::command]
+pub fn list_scheduled_runs(
+    task_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<ScheduledRun>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_scheduled_tasks(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, task_id, session_id, started_at, finished_at, status, output, error
+             FROM scheduled_runs WHERE task_id = ?1 ORDER BY started_at DESC, id DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![task_id, limit.unwrap_or(MAX_SCHEDULED_RUNS)],
+            |row| {
+                Ok(ScheduledRun {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    session_id: row.get(2)?,
+                    started_at: row.get(3)?,
+                    finished_at: row.get(4)?,
+                    status: row.get(5)?,
+                    output: row.get(6)?,
+                    error: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Record the outcome of a scheduled task on one session, keeping the
+/// latest `MAX_SCHEDULED_RUNS` runs of the task (backend use).
+pub fn record_scheduled_run(
+    task_id: &str,
+    session_id: &str,
+    started_at: i64,
+    finished_at: i64,
+    status: &str,
+    output: &str,
+    error: Option<&str>,
+) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_scheduled_tasks(&conn)?;
+    conn.execute(
+        "INSERT INTO scheduled_runs (task_id, session_id, started_at, finished_at, status, output, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![task_id, session_id, started_at, finished_at, status, output, error],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM scheduled_runs WHERE task_id = ?1 AND id NOT IN (
+            SELECT id FROM scheduled_runs WHERE task_id = ?1
+            ORDER BY started_at DESC, id DESC LIMIT ?2
+         )",
+        params![task_id, MAX_SCHEDULED_RUNS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record the time and summary of a scheduled task's last run (backend use).
+pub fn mark_scheduled_task_run(id: &str, ran_at: i64, status: &str) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE scheduled_tasks SET last_run_at = ?1, last_status = ?2 WHERE id = ?3",
+        params![ran_at, status, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Alerts kept in the history
+const MAX_ALERTS: i64 = 500;
+
+/// Create the `alert_rules` and `alerts` tables if they do not exist.
+fn ensure_alerts(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alert_rules (
+            id TEXT PRIMARY KEY,
+            session_id TEXT,
+            name TEXT NOT NULL DEFAULT '',
+            metric TEXT NOT NULL,
+            operator TEXT NOT NULL,
+            threshold REAL NOT NULL,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+            updated_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_alert_rules_session_id ON alert_rules(session_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alerts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule_id TEXT NOT NULL,
+            session_id TEXT,
+            host TEXT NOT NULL DEFAULT '',
+            name TEXT NOT NULL DEFAULT '',
+            metric TEXT NOT NULL,
+            operator TEXT NOT NULL,
+            threshold REAL NOT NULL,
+            value REAL NOT NULL,
+            triggered_at INTEGER NOT NULL,
+            resolved_at INTEGER,
+            acknowledged_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_alerts_session ON alerts(session_id, triggered_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn validate_alert_rule(metric: &str, operator: &str, duration_ms: i64) -> Result<(), String> {
+    crate::metrics::Metric::parse(metric)?;
+    crate::alerts::Operator::parse(operator)?;
+    if duration_ms < 0 {
+        return Err("Duration must not be negative".to_string());
+    }
+    Ok(())
+}
+
+/// Create an alert rule and return its UUID.
+#[tauri::command]
+pub fn add_alert_rule(
+    session_id: Option<String>,
+    name: Option<String>,
+    metric: String,
+    operator: String,
+    threshold: f64,
+    duration_ms: Option<i64>,
+) -> Result<String, String> {
+    let duration_ms = duration_ms.unwrap_or(0);
+    validate_alert_rule(&metric, &operator, duration_ms)?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO alert_rules (id, session_id, name, metric, operator, threshold, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            session_id,
+            name.unwrap_or_default(),
+            metric,
+            operator,
+            threshold,
+            duration_ms
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// List alert rules. With `session_id`, returns that session's rules plus
+/// global ones; without it, returns every rule.
+#[tauri::command]
+pub fn list_alert_rules(session_id: Option<String>) -> Result<Vec<AlertRule>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    query_alert_rules(&conn, session_id.as_deref(), false)
+}
+
+/// Load the enabled alert rules that apply to a session: its own and global
+/// ones, or only global ones without a session id (backend use).
+pub fn load_alert_rules_for_session(session_id: Option<&str>) -> Result<Vec<AlertRule>, String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    query_alert_rules(&conn, session_id, true)
+}
+
+fn query_alert_rules(
+    conn: &Connection,
+    session_id: Option<&str>,
+    enabled_only: bool,
+) -> Result<Vec<AlertRule>, String> {
+    let mut sql = String::from(
+        "SELECT id, session_id, name, metric, operator, threshold, duration_ms, enabled, created_at, updated_at FROM alert_rules",
+    );
+    let mut where_clauses: Vec<&str> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    match session_id {
+        Some(sid) => {
+            where_clauses.push("(session_id = ? OR session_id IS NULL)");
+            params_vec.push(Box::new(sid.to_string()));
+        }
+        // Sessions without a saved id only get the global alert rules
+        None if enabled_only => where_clauses.push("session_id IS NULL"),
+        None => {}
+    }
+    if enabled_only {
+        where_clauses.push("enabled = 1");
+    }
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY created_at");
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    let rows = stmt
+        .query_map(param_refs.as_slice(), |row| {
+            Ok(AlertRule {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                name: row.get(2)?,
+                metric: row.get(3)?,
+                operator: row.get(4)?,
+                threshold: row.get(5)?,
+                duration_ms: row.get(6)?,
+                enabled: row.get::<_, i64>(7)? != 0,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Edit an existing alert rule. Only provided fields are updated.
+#[tauri::command]
+pub fn edit_alert_rule(
+    id: String,
+    name: Option<String>,
+    metric: Option<String>,
+    operator: Option<String>,
+    threshold: Option<f64>,
+    duration_ms: Option<i64>,
+    enabled: Option<bool>,
+) -> Result<(), String> {
+    validate_alert_rule(
+        metric.as_deref().unwrap_or("cpu"),
+        operator.as_deref().unwrap_or(">"),
+        duration_ms.unwrap_or(0),
+    )?;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    let mut sets: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(n) = name {
+        sets.push("name = ?".to_string());
+        params_vec.push(Box::new(n));
+    }
+    if let Some(m) = metric {
+        sets.push("metric = ?".to_string());
+        params_vec.push(Box::new(m));
+    }
+    if let Some(o) = operator {
+        sets.push("operator = ?".to_string());
+        params_vec.push(Box::new(o));
+    }
+    if let Some(t) = threshold {
+        sets.push("threshold = ?".to_string());
+        params_vec.push(Box::new(t));
+    }
+    if let Some(d) = duration_ms {
+        sets.push("duration_ms = ?".to_string());
+        params_vec.push(Box::new(d));
+    }
+    if let Some(e) = enabled {
+        sets.push("enabled = ?".to_string());
+        params_vec.push(Box::new(if e { 1 } else { 0 }));
+    }
+    if sets.is_empty() {
+        return Ok(());
+    }
+    sets.push("updated_at = CURRENT_TIMESTAMP".to_string());
+    let sql = format!("UPDATE alert_rules SET {} WHERE id = ?", sets.join(", "));
+    params_vec.push(Box::new(id));
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Delete an alert rule. Alerts it already fired stay in the history.
+#[tauri::command]
+pub fn delete_alert_rule(id: String) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    conn.execute("DELETE FROM alert_rules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record a fired alert and return its id, keeping the latest
+/// `MAX_ALERTS` alerts (backend use).
+pub fn record_alert(
+    rule: &AlertRule,
+    session_id: Option<&str>,
+    host: &str,
+    value: f64,
+    triggered_at: i64,
+) -> Result<i64, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    ensure_groups_and_tags(&conn)?;
+    ensure_alerts(&conn)?;
     conn.execute(
-        "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
-        params![session_id, tag_id],
+        "INSERT INTO alerts (rule_id, session_id, host, name, metric, operator, threshold, value, triggered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            rule.id,
+            session_id,
+            host,
+            rule.name,
+            rule.metric,
+            rule.operator,
+            rule.threshold,
+            value,
+            triggered_at
+        ],
     )
     .map_err(|e| e.to_string())?;
-    Ok(())
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM alerts WHERE id NOT IN (
+            SELECT id FROM alerts ORDER BY id DESC LIMIT ?1
+         )",
+        params![MAX_ALERTS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
 }
 
-/// Remove the association between a session and a tag.
-#[tauri::command]
-pub fn unlink_session_tag(session_id: String, tag_id: String) -> Result<(), String> {
+/// Record that the condition of a fired alert no longer holds (backend use).
+pub fn resolve_alert(id: i64, resolved_at: i64) -> Result<(), String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
     conn.execute(
-        "DELETE FROM session_tags WHERE session_id = ?1 AND tag_id = ?2",
-        params![session_id, tag_id],
+        "UPDATE alerts SET resolved_at = ?1 WHERE id = ?2 AND resolved_at IS NULL",
+        params![resolved_at, id],
     )
     .map_err(|e| e.to_string())?;
     Ok(())
 }
 
-/// List tags associated with a given session.
+/// List the alert history, newest first. `session_id` limits it to one saved
+/// session; `unacknowledged_only` hides acknowledged alerts.
 #[tauri::command]
-pub fn list_tags_for_session(session_id: String) -> Result<Vec<Tag>, String> {
+pub fn list_alerts(
+    session_id: Option<String>,
+    unacknowledged_only: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<Alert>, String> {
     let db_path = db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT t.id, t.name, t.color, t.sort, t.created_at, t.updated_at
-             FROM tags t
-             JOIN session_tags st ON t.id = st.tag_id
-             WHERE st.session_id = ?1
-             ORDER BY t.sort, t.created_at",
-        )
-        .map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    let mut sql = String::from(
+        "SELECT id, rule_id, session_id, host, name, metric, operator, threshold, value, triggered_at, resolved_at, acknowledged_at FROM alerts",
+    );
+    let mut where_clauses: Vec<&str> = Vec::new();
+    let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(sid) = session_id {
+        where_clauses.push("session_id = ?");
+        params_vec.push(Box::new(sid));
+    }
+    if unacknowledged_only.unwrap_or(false) {
+        where_clauses.push("acknowledged_at IS NULL");
+    }
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY id DESC LIMIT ?");
+    params_vec.push(Box::new(limit.unwrap_or(MAX_ALERTS)));
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
     let rows = stmt
-        .query_map(params![session_id], |row| {
-            Ok(Tag {
+        .query_map(param_refs.as_slice(), |row| {
+            Ok(Alert {
                 id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                sort: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
+                rule_id: row.get(1)?,
+                session_id: row.get(2)?,
+                host: row.get(3)?,
+                name: row.get(4)?,
+                metric: row.get(5)?,
+                operator: row.get(6)?,
+                threshold: row.get(7)?,
+                value: row.get(8)?,
+                triggered_at: row.get(9)?,
+                resolved_at: row.get(10)?,
+                acknowledged_at: row.get(11)?,
             })
         })
         .map_err(|e| e.to_string())?;
-    let mut v = Vec::new();
-    for r in rows {
-        v.push(r.map_err(|e| e.to_string())?);
-    }
-    Ok(v)
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
+/// Acknowledge alerts so they no longer count as new.
 #[tauri::command]
-pub fn export_sessions(password: String) -> Result<String, String> {
+pub fn acknowledge_alerts(ids: Vec<i64>) -> Result<(), String> {
     let db_path = db_path()?;
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-
-    // 1. Get all sessions
-    let mut stmt = conn.prepare("SELECT id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials, last_connected_at, created_at, updated_at FROM sessions")
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for id in ids {
+        tx.execute(
+            "UPDATE alerts SET acknowledged_at = CAST(strftime('%s', 'now') AS INTEGER) * 1000
+             WHERE id = ?1 AND acknowledged_at IS NULL",
+            params![id],
+        )
         .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
 
-    let session_rows = stmt
-        .query_map([], |row| {
-            let id: String = row.get(0)?;
-            let metadata = Session {
-                id: id.clone(),
-                addr: row.get(1)?,
-                port: row.get(2)?,
-                server_name: row.get(3)?,
-                username: row.get(4)?,
-                auth_type: row.get(5)?,
-                private_key_path: row.get(6)?,
-                is_favorite: row.get::<_, i64>(7)? != 0,
-                last_connected_at: row.get(9)?,
-                created_at: row.get(10)?,
-                updated_at: row.get(11)?,
-            };
-            let encrypted_creds: Option<String> = row.get(8)?;
-            Ok((metadata, encrypted_creds))
-        })
-        .map_err(|e| e.to_string())?;
+/// Clear the alert history of one saved session, or of all sessions when
+/// `session_id` is omitted.
+#[tauri::command]
+pub fn clear_alerts(session_id: Option<String>) -> Result<(), String> {
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_alerts(&conn)?;
+    match session_id {
+        Some(id) => conn.execute("DELETE FROM alerts WHERE session_id = ?1", params![id]),
+        None => conn.execute("DELETE FROM alerts", []),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let mut export_sessions = Vec::new();
-    for row in session_rows {
-        let (metadata, encrypted_creds) = row.map_err(|e| e.to_string())?;
+/// Create the `metrics_history` ring buffer if it does not exist. Each
+/// session has `MAX_SAMPLES` slots that are overwritten in turn.
+fn ensure_metrics_history(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metrics_history (
+            session_id TEXT NOT NULL,
+            slot INTEGER NOT NULL,
+            taken_at INTEGER NOT NULL,
+            cpu REAL NOT NULL,
+            memory REAL NOT NULL,
+            swap REAL NOT NULL,
+            disk REAL NOT NULL,
+            load REAL NOT NULL,
+            net_up REAL NOT NULL,
+            net_down REAL NOT NULL,
+            latency REAL NOT NULL,
+            PRIMARY KEY (session_id, slot)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-        // Decrypt from machine ID and re-encrypt with export password
-        let re_encrypted = if let Some(creds) = encrypted_creds {
-            let sensitive = crate::encryption::EncryptionManager::decrypt(&creds)?;
-            Some(crate::encryption::EncryptionManager::encrypt_with_key(
-                &sensitive, &password,
-            )?)
-        } else {
-            None
-        };
+/// Drop samples older than the ring buffer covers. Slots are only
+/// overwritten while a session keeps connecting, so connections that are
+/// never reopened, such as ad-hoc ones keyed by connection id, would
+/// otherwise stay forever.
+fn prune_metrics_history(conn: &Connection) -> Result<(), String> {
+    use crate::metrics::{MAX_SAMPLES, SAMPLE_INTERVAL_MS};
 
-        // Get groups for this session
-        let mut g_stmt = conn
-            .prepare("SELECT group_id FROM session_groups WHERE session_id = ?1")
-            .map_err(|e| e.to_string())?;
-        let groups: Vec<String> = g_stmt
-            .query_map(params![metadata.id], |r| r.get(0))
-            .map_err(|e| e.to_string())?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| e.to_string())?;
+    let cutoff = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+        - MAX_SAMPLES * SAMPLE_INTERVAL_MS;
+    conn.execute(
+        "DELETE FROM metrics_history WHERE taken_at < ?1",
+        params![cutoff],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-        // Get tags for this session
-        let mut t_stmt = conn
-            .prepare("SELECT tag_id FROM session_tags WHERE session_id = ?1")
-            .map_err(|e| e.to_string())?;
-        let tags: Vec<String> = t_stmt
-            .query_map(params![metadata.id], |r| r.get(0))
-            .map_err(|e| e.to_string())?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| e.to_string())?;
+/// Store a downsampled status sample in the session's ring buffer
+/// (backend use).
+pub fn record_metrics_sample(
+    session_id: &str,
+    sample: &crate::metrics::MetricsSample,
+) -> Result<(), String> {
+    use crate::metrics::{Metric, MAX_SAMPLES, SAMPLE_INTERVAL_MS};
 
-        export_sessions.push(ExportSession {
-            metadata,
-            encrypted_credentials: re_encrypted,
-            group_ids: groups,
-            tag_ids: tags,
-        });
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_metrics_history(&conn)?;
+    let columns: Vec<&str> = Metric::ALL.iter().map(|m| m.column()).collect();
+    let sql = format!(
+        "INSERT OR REPLACE INTO metrics_history (session_id, slot, taken_at, {}) VALUES (?, ?, ?{})",
+        columns.join(", "),
+        ", ?".repeat(columns.len())
+    );
+    let mut params_vec: Vec<Box<dyn ToSql>> = vec![
+        Box::new(session_id.to_string()),
+        Box::new((sample.taken_at / SAMPLE_INTERVAL_MS).rem_euclid(MAX_SAMPLES)),
+        Box::new(sample.taken_at),
+    ];
+    for value in sample.values {
+        params_vec.push(Box::new(value));
     }
-
-    // 2. Get all groups
-    let mut g_stmt = conn
-        .prepare("SELECT id, name, sort, created_at, updated_at FROM groups")
-        .map_err(|e| e.to_string())?;
-    let groups = g_stmt
-        .query_map([], |row| {
-            Ok(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                sort: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<Group>, _>>()
+    let param_refs: Vec<&dyn ToSql> = params_vec.iter().map(|b| &**b as &dyn ToSql).collect();
+    conn.execute(&sql, param_refs.as_slice())
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // 3. Get all tags
-    let mut t_stmt = conn
-        .prepare("SELECT id, name, color, sort, created_at, updated_at FROM tags")
+/// Get the recorded history of one metric over the last `range` seconds
+/// (default one hour), oldest first.
+///
+/// `session_id` is the saved session id, or the connection id for
+/// connections not opened from a saved session. `metric` is one of "cpu",
+/// "memory", "swap", "disk", "load", "netUp", "netDown" or "latency". Each
+/// point averages the readings of `SAMPLE_INTERVAL_MS`; the interval in
+/// progress is not included yet.
+#[tauri::command]
+pub fn get_metrics_history(
+    session_id: String,
+    metric: String,
+    range: Option<i64>,
+) -> Result<Vec<crate::metrics::MetricPoint>, String> {
+    let column = crate::metrics::Metric::parse(&metric)?.column();
+    let since = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+        - range.unwrap_or(3600).max(0) * 1000;
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_metrics_history(&conn)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT taken_at, {} FROM metrics_history
+             WHERE session_id = ?1 AND taken_at >= ?2 ORDER BY taken_at",
+            column
+        ))
         .map_err(|e| e.to_string())?;
-    let tags = t_stmt
-        .query_map([], |row| {
-            Ok(Tag {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                sort: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
+    let rows = stmt
+        .query_map(params![session_id, since], |row| {
+            Ok(crate::metrics::MetricPoint {
+                time: row.get(0)?,
+                value: row.get(1)?,
             })
         })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<Tag>, _>>()
         .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
 
-    let export_data = ExportData {
-        sessions: export_sessions,
-        groups,
-        tags,
-    };
+fn ensure_open_tabs(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS open_tabs (
+            position INTEGER PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            title TEXT,
+            cols INTEGER NOT NULL DEFAULT 0,
+            rows INTEGER NOT NULL DEFAULT 0,
+            active INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    serde_json::to_string(&export_data).map_err(|e| e.to_string())
+/// A terminal tab of a saved session, remembered so it can be reopened
+/// after the app quits or crashes.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpenTab {
+    pub session_id: String,
+    /// Title shown on the tab, when renamed
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub cols: u16,
+    #[serde(default)]
+    pub rows: u16,
+    /// The tab that had focus
+    #[serde(default)]
+    pub active: bool,
 }
 
+/// Record the tabs currently open, in tab order. Called by the frontend
+/// whenever tabs are opened, closed, moved or resized, so the list is
+/// current even if the app crashes.
 #[tauri::command]
-pub fn import_sessions(json_data: String, password: String) -> Result<(), String> {
+pub fn set_open_tabs(tabs: Vec<OpenTab>) -> Result<(), String> {
     let db_path = db_path()?;
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    let export_data: ExportData = serde_json::from_str(&json_data).map_err(|e| e.to_string())?;
-
+    ensure_open_tabs(&conn)?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    // 1. Import Groups
-    for group in export_data.groups {
-        tx.execute(
-            "INSERT OR IGNORE INTO groups (id, name, sort, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![group.id, group.name, group.sort, group.created_at, group.updated_at],
-        ).map_err(|e| e.to_string())?;
-    }
-
-    // 2. Import Tags
-    for tag in export_data.tags {
-        tx.execute(
-            "INSERT OR IGNORE INTO tags (id, name, color, sort, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![tag.id, tag.name, tag.color, tag.sort, tag.created_at, tag.updated_at],
-        ).map_err(|e| e.to_string())?;
-    }
-
-    // 3. Import Sessions
-    for session in export_data.sessions {
-        let metadata = session.metadata;
-
-        // Decrypt from export password and re-encrypt with machine ID
-        let re_encrypted = if let Some(creds) = session.encrypted_credentials {
-            let sensitive =
-                crate::encryption::EncryptionManager::decrypt_with_key(&creds, &password)?;
-            Some(crate::encryption::EncryptionManager::encrypt(&sensitive)?)
-        } else {
-            None
-        };
-
+    tx.execute("DELETE FROM open_tabs", [])
+        .map_err(|e| e.to_string())?;
+    for (position, tab) in tabs.iter().enumerate() {
         tx.execute(
-            "INSERT OR REPLACE INTO sessions (id, addr, port, server_name, username, auth_type, private_key_path, is_favorite, encrypted_credentials, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO open_tabs (position, session_id, title, cols, rows, active)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
-                metadata.id, metadata.addr, metadata.port, metadata.server_name,
-                metadata.username, metadata.auth_type, metadata.private_key_path,
-                if metadata.is_favorite { 1 } else { 0 }, re_encrypted, metadata.created_at, metadata.updated_at
+                position as i64,
+                tab.session_id,
+                tab.title,
+                tab.cols,
+                tab.rows,
+                tab.active
             ],
-        ).map_err(|e| e.to_string())?;
-
-        // Restore group associations
-        tx.execute(
-            "DELETE FROM session_groups WHERE session_id = ?1",
-            params![metadata.id],
-        )
-        .ok();
-        for gid in session.group_ids {
-            tx.execute(
-                "INSERT OR IGNORE INTO session_groups (session_id, group_id) VALUES (?1, ?2)",
-                params![metadata.id, gid],
-            )
-            .ok();
-        }
-
-        // Restore tag associations
-        tx.execute(
-            "DELETE FROM session_tags WHERE session_id = ?1",
-            params![metadata.id],
         )
-        .ok();
-        for tid in session.tag_ids {
-            tx.execute(
-                "INSERT OR IGNORE INTO session_tags (session_id, tag_id) VALUES (?1, ?2)",
-                params![metadata.id, tid],
-            )
-            .ok();
-        }
+        .map_err(|e| e.to_string())?;
     }
-
     tx.commit().map_err(|e| e.to_string())
 }
+
+/// Get the tabs that were open when the app last quit or crashed, in tab
+/// order, for reopening on startup. Tabs of sessions deleted since are
+/// left out; the list is empty when `restore.reopenSessions` is off. With
+/// `restore.autoReconnect` the frontend connects them without asking.
+#[tauri::command]
+pub fn get_restorable_sessions() -> Result<Vec<OpenTab>, String> {
+    if !crate::settings::current().restore.reopen_sessions {
+        return Ok(Vec::new());
+    }
+    let db_path = db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_open_tabs(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.session_id, t.title, t.cols, t.rows, t.active FROM open_tabs t
+             JOIN sessions s ON s.id = t.session_id ORDER BY t.position",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(OpenTab {
+                session_id: row.get(0)?,
+                title: row.get(1)?,
+                cols: row.get(2)?,
+                rows: row.get(3)?,
+                active: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
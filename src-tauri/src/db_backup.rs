@@ -0,0 +1,180 @@
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+/// How often the scheduler checks whether a backup is due
+const SCHEDULER_TICK: Duration = Duration::from_secs(60 * 60);
+
+/// File name prefix of automatic and manual backups
+const BACKUP_PREFIX: &str = "nexashell-";
+
+/// A stored database backup
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbBackup {
+    pub path: String,
+    /// Unix milliseconds
+    pub created_at: i64,
+    pub size: u64,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn backup_dir() -> Result<PathBuf, String> {
+    Ok(crate::db::app_data_dir()?.join("db-backups"))
+}
+
+/// Lists backups, newest first
+fn backups() -> Result<Vec<DbBackup>, String> {
+    let dir = backup_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut list = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let Some(created_at) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix(BACKUP_PREFIX))
+            .and_then(|n| n.strip_suffix(".db"))
+            .and_then(|n| n.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        list.push(DbBackup {
+            path: path.to_string_lossy().into_owned(),
+            created_at,
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+    list.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(list)
+}
+
+/// Writes a consistent copy of the database with `VACUUM INTO` and prunes
+/// old backups beyond the configured retention. Blocking.
+fn backup_now() -> Result<DbBackup, String> {
+    let dir = backup_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let created_at = now_millis();
+    let path = dir.join(format!("{}{}.db", BACKUP_PREFIX, created_at));
+
+    let conn = Connection::open(crate::db::db_path()?).map_err(|e| e.to_string())?;
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+        .map_err(|e| format!("Backup failed: {}", e))?;
+
+    let retention = crate::settings::current().database.backup_retention.max(1);
+    for old in backups()?.into_iter().skip(retention) {
+        let _ = std::fs::remove_file(&old.path);
+    }
+
+    Ok(DbBackup {
+        path: path.to_string_lossy().into_owned(),
+        created_at,
+        size: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+/// Checks that a file is a readable NexaShell database
+fn validate_database(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Cannot open backup: {}", e))?;
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Not a valid database: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Backup is corrupted: {}", integrity));
+    }
+    let has_sessions: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sessions')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !has_sessions {
+        return Err("Not a NexaShell database".to_string());
+    }
+    Ok(())
+}
+
+/// Starts the background task that backs up the database when the latest
+/// backup is older than the configured interval
+pub fn start_scheduler() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let result = tokio::task::spawn_blocking(|| -> Result<(), String> {
+                let hours = crate::settings::current().database.backup_interval_hours;
+                if hours == 0 {
+                    return Ok(());
+                }
+                let due = backups()?
+                    .first()
+                    .is_none_or(|latest| now_millis() - latest.created_at >= hours * 3_600_000);
+                if due {
+                    backup_now()?;
+                }
+                Ok(())
+            })
+            .await;
+            if let Ok(Err(e)) = result {
+                crate::error_bus::report(
+                    "db_backup",
+                    None,
+                    format!("scheduled backup failed: {}", e),
+                );
+            }
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    });
+}
+
+/// Backs up the database immediately.
+#[command]
+pub async fn backup_db_now() -> Result<DbBackup, String> {
+    tokio::task::spawn_blocking(backup_now)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Lists database backups, newest first.
+#[command]
+pub fn list_db_backups() -> Result<Vec<DbBackup>, String> {
+    backups()
+}
+
+/// Replaces the database with a backup file after validating it. The
+/// current database is backed up first so a restore can be undone. Emits
+/// `db-restored` so the frontend reloads its data.
+#[command]
+pub async fn restore_db(app: AppHandle, path: String) -> Result<DbBackup, String> {
+    let safety = tokio::task::spawn_blocking(move || -> Result<DbBackup, String> {
+        let source = PathBuf::from(&path);
+        validate_database(&source)?;
+
+        // Stage the copy first: the safety backup may prune `source`
+        let db_path = crate::db::db_path()?;
+        let staging = db_path.with_extension("db.restore");
+        std::fs::copy(&source, &staging).map_err(|e| e.to_string())?;
+        let safety = backup_now()?;
+        std::fs::rename(&staging, db_path).map_err(|e| e.to_string())?;
+        crate::db::init_db()?;
+        crate::settings::invalidate();
+        crate::config_history::schedule_commit();
+        Ok(safety)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let _ = app.emit("db-restored", &safety);
+    Ok(safety)
+}
@@ -0,0 +1,243 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// Links resolved before the frontend was ready, e.g. the link the app was
+/// launched with
+static PENDING: Lazy<Mutex<Vec<DeepLinkOpen>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set once the frontend has collected pending links; later links are
+/// emitted right away
+static FRONTEND_READY: AtomicBool = AtomicBool::new(false);
+
+/// What a deep link points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLinkTarget {
+    /// `ssh://[user@]host[:port]`
+    Address {
+        username: Option<String>,
+        host: String,
+        port: u16,
+    },
+    /// `nexashell://session/{id}`
+    Session(String),
+}
+
+/// Payload of the `deep-link-open` event: where a link points. Nothing is
+/// saved or connected for it; the frontend asks the user before opening a
+/// tab, and saves an unknown address only when asked to.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkOpen {
+    pub uri: String,
+    /// Saved session the link points at; `None` for an unknown address
+    pub session_id: Option<String>,
+    pub server_name: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+}
+
+/// Payload of the `deep-link-error` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkError {
+    pub uri: String,
+    pub error: String,
+}
+
+/// Splits `scheme://rest` with a case-insensitive scheme
+fn strip_scheme<'a>(uri: &'a str, scheme: &str) -> Option<&'a str> {
+    let (found, rest) = uri.split_once("://")?;
+    found.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+/// Parses an `ssh://` or `nexashell://session/{id}` URI. Passwords and
+/// fingerprint parameters in the user part are ignored.
+pub fn parse_uri(uri: &str) -> Result<DeepLinkTarget, String> {
+    let uri = uri.trim();
+    if let Some(rest) = strip_scheme(uri, "nexashell") {
+        let path = rest.split(['?', '#']).next().unwrap_or_default();
+        let mut parts = path.trim_matches('/').split('/');
+        return match (parts.next(), parts.next(), parts.next()) {
+            (Some("session"), Some(id), None) if !id.is_empty() => {
+                Ok(DeepLinkTarget::Session(crate::osc::percent_decode(id)))
+            }
+            _ => Err(format!("Unsupported link: {}", uri)),
+        };
+    }
+
+    let rest = strip_scheme(uri, "ssh").ok_or_else(|| format!("Unsupported link: {}", uri))?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let (user_info, host_port) = match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    let username = user_info
+        .and_then(|u| u.split([';', ':']).next())
+        .map(crate::osc::percent_decode)
+        .filter(|u| !u.is_empty());
+
+    let (host, port) = if let Some(v6) = host_port.strip_prefix('[') {
+        let (host, after) = v6
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid host in link: {}", uri))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+    let port = match port.filter(|p| !p.is_empty()) {
+        Some(p) => p
+            .parse::<u16>()
+            .ok()
+            .filter(|p| *p > 0)
+            .ok_or_else(|| format!("Invalid port in link: {}", uri))?,
+        None => 22,
+    };
+    let host = crate::osc::percent_decode(host);
+    if host.is_empty() || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("Invalid host in link: {}", uri));
+    }
+    Ok(DeepLinkTarget::Address {
+        username,
+        host,
+        port,
+    })
+}
+
+/// Finds the saved session a link points at; unknown addresses are
+/// returned as they are
+fn resolve(uri: &str) -> Result<DeepLinkOpen, String> {
+    let open = |session: crate::db::Session| -> Result<DeepLinkOpen, String> {
+        Ok(DeepLinkOpen {
+            uri: uri.to_string(),
+            port: u16::try_from(session.port).map_err(|e| e.to_string())?,
+            session_id: Some(session.id),
+            server_name: session.server_name,
+            host: session.addr,
+            username: session.username,
+        })
+    };
+    match parse_uri(uri)? {
+        DeepLinkTarget::Session(id) => open(crate::db::load_session(&id)?),
+        DeepLinkTarget::Address {
+            username,
+            host,
+            port,
+        } => {
+            if let Some(session) =
+                crate::db::find_session_by_address(&host, port as i64, username.as_deref())?
+            {
+                return open(session);
+            }
+            // Like ssh, fall back to the local user name
+            let username = username
+                .or_else(|| std::env::var("USER").ok())
+                .or_else(|| std::env::var("USERNAME").ok())
+                .unwrap_or_else(|| "root".to_string());
+            Ok(DeepLinkOpen {
+                uri: uri.to_string(),
+                session_id: None,
+                server_name: host.clone(),
+                host,
+                port,
+                username,
+            })
+        }
+    }
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Resolves received links and hands them to the frontend, or queues them
+/// until it is ready
+fn handle_urls(app: &AppHandle, urls: Vec<String>) {
+    if urls.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for uri in urls {
+            match resolve(&uri) {
+                Ok(open) => {
+                    // Checked under the lock so a link is never queued after
+                    // the frontend has taken the queue
+                    let Ok(mut pending) = PENDING.lock() else {
+                        continue;
+                    };
+                    if FRONTEND_READY.load(Ordering::SeqCst) {
+                        drop(pending);
+                        let _ = app.emit("deep-link-open", &open);
+                    } else {
+                        pending.push(open);
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!("{}: {}", uri, error);
+                    let _ = app.emit("deep-link-error", &DeepLinkError { uri, error });
+                }
+            }
+        }
+        focus_main_window(&app);
+    });
+}
+
+/// Registers the `ssh` and `nexashell` schemes and starts handling links.
+/// Must run after the deep-link plugin is initialized.
+pub fn init(app: &AppHandle) {
+    // Installed bundles register the schemes themselves; Linux (AppImage)
+    // and Windows development builds register at runtime
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("failed to register schemes: {}", e);
+    }
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        handle_urls(app, urls.iter().map(|u| u.to_string()).collect());
+    }
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        handle_urls(
+            &handle,
+            event.urls().iter().map(|u| u.to_string()).collect(),
+        );
+    });
+}
+
+/// Called by a second launch of the app (single instance): shows the
+/// running instance. Links passed to it arrive through the deep-link plugin.
+pub fn on_second_instance(app: &AppHandle) {
+    focus_main_window(app);
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Returns the links received before the frontend was ready and emits
+/// later ones as `deep-link-open` events. Call once on startup.
+#[command]
+pub fn take_pending_deep_links() -> Result<Vec<DeepLinkOpen>, String> {
+    let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+    FRONTEND_READY.store(true, Ordering::SeqCst);
+    Ok(std::mem::take(&mut *pending))
+}
+
+/// Resolves a link pasted by the user the same way as an opened one.
+#[command]
+pub async fn open_deep_link(uri: String) -> Result<DeepLinkOpen, String> {
+    tokio::task::spawn_blocking(move || resolve(&uri))
+        .await
+        .map_err(|e| e.to_string())?
+}
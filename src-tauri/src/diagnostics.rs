@@ -0,0 +1,60 @@
+use serde::Serialize;
+use std::time::Instant;
+
+/// One recorded step of a connection attempt
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEntry {
+    /// "knock", "resolve", "tcp", "handshake", "auth", "channel", ...
+    pub phase: String,
+    pub ok: bool,
+    pub message: String,
+    /// Time since the attempt started
+    pub elapsed_ms: u64,
+}
+
+/// Structured log of a connection attempt, similar to `ssh -vvv`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTrace {
+    pub entries: Vec<TraceEntry>,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+impl ConnectionTrace {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            started: Some(Instant::now()),
+        }
+    }
+
+    /// Appends an entry for `phase`
+    pub fn record(&mut self, phase: &str, ok: bool, message: impl Into<String>) {
+        let elapsed_ms = self
+            .started
+            .map(|s| s.elapsed().as_millis() as u64)
+            .unwrap_or_default();
+        self.entries.push(TraceEntry {
+            phase: phase.to_string(),
+            ok,
+            message: message.into(),
+            elapsed_ms,
+        });
+    }
+
+    /// Records the outcome of a fallible step and passes the result through
+    pub fn step<T, E: std::fmt::Display>(
+        &mut self,
+        phase: &str,
+        result: Result<T, E>,
+        success: impl FnOnce(&T) -> String,
+    ) -> Result<T, E> {
+        match &result {
+            Ok(value) => self.record(phase, true, success(value)),
+            Err(e) => self.record(phase, false, e.to_string()),
+        }
+        result
+    }
+}
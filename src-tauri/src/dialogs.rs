@@ -0,0 +1,80 @@
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+fn path_string(path: FilePath) -> Option<String> {
+    path.into_path()
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Shows a native dialog off the async runtime; the `blocking_*` dialog
+/// calls wait for the user
+async fn run_dialog<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Lets the user choose a private key file, starting in `~/.ssh`. Returns
+/// `None` when the dialog is cancelled.
+#[tauri::command]
+pub async fn pick_private_key(app: AppHandle) -> Result<Option<String>, String> {
+    run_dialog(move || {
+        let mut dialog = app.dialog().file().set_title("Choose private key");
+        if let Some(ssh_dir) = dirs::home_dir()
+            .map(|home| home.join(".ssh"))
+            .filter(|dir| dir.is_dir())
+        {
+            dialog = dialog.set_directory(ssh_dir);
+        }
+        dialog.blocking_pick_file().and_then(path_string)
+    })
+    .await
+}
+
+/// Lets the user choose where to write an export, suggesting `default_name`
+/// and filtering on `extension` (without the dot). Returns `None` when the
+/// dialog is cancelled.
+#[tauri::command]
+pub async fn pick_export_destination(
+    app: AppHandle,
+    default_name: Option<String>,
+    extension: Option<String>,
+) -> Result<Option<String>, String> {
+    run_dialog(move || {
+        let mut dialog = app.dialog().file().set_title("Export to");
+        if let Some(name) = default_name {
+            dialog = dialog.set_file_name(name);
+        }
+        if let Some(extension) = extension.filter(|e| !e.is_empty()) {
+            dialog = dialog.add_filter(extension.to_uppercase(), &[extension.as_str()]);
+        }
+        dialog.blocking_save_file().and_then(path_string)
+    })
+    .await
+}
+
+/// Lets the user choose files, or directories with `directories`, to upload.
+/// Returns an empty list when the dialog is cancelled.
+#[tauri::command]
+pub async fn pick_upload_sources(
+    app: AppHandle,
+    directories: Option<bool>,
+) -> Result<Vec<String>, String> {
+    run_dialog(move || {
+        let dialog = app.dialog().file().set_title("Choose files to upload");
+        let picked = if directories.unwrap_or(false) {
+            dialog.blocking_pick_folders()
+        } else {
+            dialog.blocking_pick_files()
+        };
+        picked
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(path_string)
+            .collect()
+    })
+    .await
+}
@@ -0,0 +1,640 @@
+use crate::ssh::{shell_quote, SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssh2::FileType;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// Minimum pause between two `sync-progress` events of a task
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Files hashed by one remote `sha256sum` run
+const CHECKSUM_BATCH: usize = 100;
+
+/// Time allowed for one remote `sha256sum` run
+const CHECKSUM_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Cancellation flags of running synchronizations by task id
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Options of `sync_directory`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncOptions {
+    /// Compare SHA-256 checksums of files of equal size instead of their
+    /// modification times. Hashing on the server needs `sha256sum`.
+    pub checksum: bool,
+    /// Delete files and directories in the target that the source lacks
+    pub delete_extraneous: bool,
+    /// Only work out the plan; nothing is transferred or deleted
+    pub dry_run: bool,
+}
+
+/// One step of a synchronization plan
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    /// Path relative to the synchronized directories, `/`-separated
+    pub path: String,
+    /// "mkdir", "create", "update", "delete" or "conflict" (a file on one
+    /// side is a directory on the other; left alone)
+    pub action: String,
+    /// "file" or "dir"
+    pub kind: String,
+    /// Bytes to transfer
+    pub size: u64,
+    /// Why an existing file is updated: "size", "mtime" or "checksum"
+    pub reason: Option<String>,
+}
+
+/// Result of `sync_directory`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub plan: Vec<SyncAction>,
+    pub dry_run: bool,
+    pub transferred_files: u64,
+    pub transferred_bytes: u64,
+    pub deleted: u64,
+}
+
+/// Payload of the `sync-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProgress {
+    pub task_id: String,
+    pub session_id: String,
+    /// Plan steps carried out so far
+    pub processed: u64,
+    pub total: u64,
+    pub transferred_bytes: u64,
+    pub total_bytes: u64,
+    /// Path being processed
+    pub current: Option<String>,
+    /// "planning", "running", "success", "error" or "cancelled"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    /// Local directory to the server
+    Upload,
+    Download,
+}
+
+impl Direction {
+    fn parse(name: &str) -> Result<Self, SshError> {
+        match name {
+            "upload" => Ok(Direction::Upload),
+            "download" => Ok(Direction::Download),
+            _ => Err(SshError::OperationFailed(format!(
+                "Unknown sync direction: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// A file or directory found while scanning one side
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    is_dir: bool,
+    size: u64,
+    /// Unix seconds
+    mtime: Option<u64>,
+}
+
+/// Both directories being synchronized
+struct Sides<'a> {
+    handle: &'a SessionHandle,
+    local_dir: PathBuf,
+    remote_dir: String,
+}
+
+impl Sides<'_> {
+    fn local(&self, rel: &str) -> PathBuf {
+        self.local_dir.join(rel)
+    }
+
+    fn remote(&self, rel: &str) -> String {
+        format!("{}/{}", self.remote_dir.trim_end_matches('/'), rel)
+    }
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> SshError {
+    SshError::OperationFailed(format!("Failed to {} {}: {}", action, path.display(), e))
+}
+
+fn sftp_error(action: &str, path: &str, e: ssh2::Error) -> SshError {
+    SshError::OperationFailed(format!("Failed to {} {}: {}", action, path, e))
+}
+
+/// Lists everything below a local directory by relative path. Symlinks
+/// are skipped. A missing directory is empty.
+fn scan_local(root: &Path) -> Result<BTreeMap<String, Entry>, SshError> {
+    let mut entries = BTreeMap::new();
+    if !root.exists() {
+        return Ok(entries);
+    }
+    let mut dirs = vec![String::new()];
+    while let Some(rel_dir) = dirs.pop() {
+        let dir = root.join(&rel_dir);
+        for item in std::fs::read_dir(&dir).map_err(|e| io_error("list", &dir, e))? {
+            let item = item.map_err(|e| io_error("list", &dir, e))?;
+            let file_type = item.file_type().map_err(|e| io_error("stat", &dir, e))?;
+            if file_type.is_symlink() {
+                continue;
+            }
+            let metadata = item
+                .metadata()
+                .map_err(|e| io_error("stat", &item.path(), e))?;
+            let name = item.file_name().to_string_lossy().into_owned();
+            let rel = if rel_dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel_dir, name)
+            };
+            if file_type.is_dir() {
+                dirs.push(rel.clone());
+            }
+            entries.insert(
+                rel,
+                Entry {
+                    is_dir: file_type.is_dir(),
+                    size: if file_type.is_dir() {
+                        0
+                    } else {
+                        metadata.len()
+                    },
+                    mtime: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs()),
+                },
+            );
+        }
+    }
+    Ok(entries)
+}
+
+/// Lists everything below a remote directory by relative path, one SFTP
+/// request per directory. Symlinks are skipped. A missing directory is
+/// empty.
+fn scan_remote(
+    handle: &SessionHandle,
+    root: &str,
+    cancel: &AtomicBool,
+) -> Result<BTreeMap<String, Entry>, SshError> {
+    let mut entries = BTreeMap::new();
+    let exists = handle.with_sftp(|sftp| Ok(sftp.stat(Path::new(root)).is_ok()))?;
+    if !exists {
+        return Ok(entries);
+    }
+    let mut dirs = vec![String::new()];
+    while let Some(rel_dir) = dirs.pop() {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SshError::OperationFailed("Sync cancelled".to_string()));
+        }
+        let dir = if rel_dir.is_empty() {
+            root.to_string()
+        } else {
+            format!("{}/{}", root.trim_end_matches('/'), rel_dir)
+        };
+        let listing = handle.with_sftp(|sftp| {
+            sftp.readdir(Path::new(&dir))
+                .map_err(|e| sftp_error("list", &dir, e))
+        })?;
+        for (path, stat) in listing {
+            if stat.file_type() == FileType::Symlink {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let rel = if rel_dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel_dir, name)
+            };
+            if stat.is_dir() {
+                dirs.push(rel.clone());
+            }
+            entries.insert(
+                rel,
+                Entry {
+                    is_dir: stat.is_dir(),
+                    size: if stat.is_dir() {
+                        0
+                    } else {
+                        stat.size.unwrap_or(0)
+                    },
+                    mtime: stat.mtime,
+                },
+            );
+        }
+    }
+    Ok(entries)
+}
+
+fn local_checksum(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Hashes remote files with `sha256sum`, by relative path. Files whose
+/// hash could not be read are missing from the result.
+fn remote_checksums(
+    handle: &SessionHandle,
+    remote_dir: &str,
+    paths: &[&str],
+    cancel: &AtomicBool,
+) -> Result<HashMap<String, String>, SshError> {
+    let mut sums = HashMap::new();
+    for batch in paths.chunks(CHECKSUM_BATCH) {
+        let names: Vec<String> = batch.iter().map(|p| shell_quote(p)).collect();
+        let command = format!(
+            "cd {} && sha256sum -- {}",
+            shell_quote(remote_dir),
+            names.join(" ")
+        );
+        let output = handle.exec_interruptible(&command, CHECKSUM_TIMEOUT, cancel)?;
+        if output.exit_status == Some(127) {
+            return Err(SshError::OperationFailed(
+                "sha256sum is not installed on the server".to_string(),
+            ));
+        }
+        // Names with special characters are escaped and start with '\'
+        for line in output.stdout.lines().filter(|l| !l.starts_with('\\')) {
+            if let Some((sum, name)) = line.split_once("  ") {
+                sums.insert(name.to_string(), sum.to_string());
+            }
+        }
+    }
+    Ok(sums)
+}
+
+/// Compares source and target and lists the steps that make the target
+/// match. Directories come before their contents; deletions come last,
+/// contents before their directories.
+fn plan(
+    sides: &Sides,
+    source: &BTreeMap<String, Entry>,
+    target: &BTreeMap<String, Entry>,
+    options: &SyncOptions,
+    cancel: &AtomicBool,
+) -> Result<Vec<SyncAction>, SshError> {
+    let action = |path: &str, action: &str, entry: &Entry, reason: Option<&str>| SyncAction {
+        path: path.to_string(),
+        action: action.to_string(),
+        kind: if entry.is_dir { "dir" } else { "file" }.to_string(),
+        size: if action == "delete" { 0 } else { entry.size },
+        reason: reason.map(String::from),
+    };
+
+    let mut steps = Vec::new();
+    let mut same_size = Vec::new();
+    for (path, entry) in source {
+        match target.get(path) {
+            None if entry.is_dir => steps.push(action(path, "mkdir", entry, None)),
+            None => steps.push(action(path, "create", entry, None)),
+            Some(existing) if existing.is_dir != entry.is_dir => {
+                steps.push(action(path, "conflict", entry, None))
+            }
+            Some(_) if entry.is_dir => {}
+            Some(existing) if existing.size != entry.size => {
+                steps.push(action(path, "update", entry, Some("size")))
+            }
+            Some(_) if options.checksum => same_size.push(path.as_str()),
+            Some(existing) if existing.mtime != entry.mtime => {
+                steps.push(action(path, "update", entry, Some("mtime")))
+            }
+            Some(_) => {}
+        }
+    }
+
+    if !same_size.is_empty() {
+        let remote = remote_checksums(sides.handle, &sides.remote_dir, &same_size, cancel)?;
+        for path in same_size {
+            let local = local_checksum(&sides.local(path));
+            if local.is_none() || local.as_ref() != remote.get(path) {
+                steps.push(action(path, "update", &source[path], Some("checksum")));
+            }
+        }
+        // Keep parents before children after appending
+        steps.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    if options.delete_extraneous {
+        // Reverse order puts a directory's contents before it
+        for (path, entry) in target.iter().rev() {
+            if !source.contains_key(path) {
+                steps.push(action(path, "delete", entry, None));
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Creates the remote directory and its missing parents
+pub fn remote_mkdir_all(handle: &SessionHandle, dir: &str) -> Result<(), SshError> {
+    handle.with_sftp(|sftp| {
+        let mut path = String::new();
+        for part in dir.split('/') {
+            if part.is_empty() {
+                if path.is_empty() {
+                    path.push('/');
+                }
+                continue;
+            }
+            if !path.is_empty() && !path.ends_with('/') {
+                path.push('/');
+            }
+            path.push_str(part);
+            if sftp.stat(Path::new(&path)).is_err() {
+                sftp.mkdir(Path::new(&path), 0o755)
+                    .map_err(|e| sftp_error("create directory", &path, e))?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Carries out one step of the plan
+fn execute(
+    sides: &Sides,
+    direction: Direction,
+    step: &SyncAction,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<u64, SshError> {
+    let local = sides.local(&step.path);
+    let remote = sides.remote(&step.path);
+    let is_dir = step.kind == "dir";
+    match (step.action.as_str(), direction) {
+        ("mkdir", Direction::Upload) => remote_mkdir_all(sides.handle, &remote).map(|_| 0),
+        ("mkdir", Direction::Download) => std::fs::create_dir_all(&local)
+            .map(|_| 0)
+            .map_err(|e| io_error("create directory", &local, e)),
+        ("create" | "update", Direction::Upload) => crate::ssh::sftp_upload(
+            sides.handle,
+            &local.to_string_lossy(),
+            &remote,
+            true,
+            cancel,
+            on_progress,
+        ),
+        ("create" | "update", Direction::Download) => crate::ssh::sftp_download(
+            sides.handle,
+            &remote,
+            &local.to_string_lossy(),
+            true,
+            cancel,
+            on_progress,
+        ),
+        ("delete", Direction::Upload) => sides
+            .handle
+            .with_sftp(|sftp| {
+                let result = if is_dir {
+                    sftp.rmdir(Path::new(&remote))
+                } else {
+                    sftp.unlink(Path::new(&remote))
+                };
+                result.map_err(|e| sftp_error("delete", &remote, e))
+            })
+            .map(|_| 0),
+        ("delete", Direction::Download) => {
+            let result = if is_dir {
+                std::fs::remove_dir(&local)
+            } else {
+                std::fs::remove_file(&local)
+            };
+            result.map(|_| 0).map_err(|e| io_error("delete", &local, e))
+        }
+        _ => Ok(0),
+    }
+}
+
+/// Plans and runs a synchronization, reporting progress. Blocking; meant
+/// for a worker thread.
+#[allow(clippy::too_many_arguments)]
+fn run_sync(
+    app_handle: &AppHandle,
+    handle: &SessionHandle,
+    session_id: &str,
+    task_id: &str,
+    local_dir: &str,
+    remote_dir: &str,
+    direction: Direction,
+    options: &SyncOptions,
+    cancel: &AtomicBool,
+) -> Result<SyncReport, SshError> {
+    let sides = Sides {
+        handle,
+        local_dir: PathBuf::from(local_dir),
+        remote_dir: remote_dir.to_string(),
+    };
+    let mut progress = SyncProgress {
+        task_id: task_id.to_string(),
+        session_id: session_id.to_string(),
+        processed: 0,
+        total: 0,
+        transferred_bytes: 0,
+        total_bytes: 0,
+        current: None,
+        status: "planning".to_string(),
+        error: None,
+    };
+    let _ = app_handle.emit("sync-progress", &progress);
+
+    if direction == Direction::Upload && !sides.local_dir.is_dir() {
+        return Err(SshError::OperationFailed(format!(
+            "Local directory not found: {}",
+            local_dir
+        )));
+    }
+    if direction == Direction::Download
+        && handle.with_sftp(|sftp| Ok(sftp.stat(Path::new(remote_dir)).is_err()))?
+    {
+        return Err(SshError::OperationFailed(format!(
+            "Remote directory not found: {}",
+            remote_dir
+        )));
+    }
+    let local = scan_local(&sides.local_dir)?;
+    let remote = scan_remote(handle, remote_dir, cancel)?;
+    let (source, target) = match direction {
+        Direction::Upload => (&local, &remote),
+        Direction::Download => (&remote, &local),
+    };
+    let steps = plan(&sides, source, target, options, cancel)?;
+
+    let mut report = SyncReport {
+        plan: steps,
+        dry_run: options.dry_run,
+        transferred_files: 0,
+        transferred_bytes: 0,
+        deleted: 0,
+    };
+    if options.dry_run {
+        return Ok(report);
+    }
+
+    // The target directory itself may not exist yet
+    match direction {
+        Direction::Upload => remote_mkdir_all(handle, remote_dir)?,
+        Direction::Download => std::fs::create_dir_all(&sides.local_dir)
+            .map_err(|e| io_error("create directory", &sides.local_dir, e))?,
+    }
+
+    progress.status = "running".to_string();
+    progress.total = report.plan.len() as u64;
+    progress.total_bytes = report
+        .plan
+        .iter()
+        .filter(|s| s.action == "create" || s.action == "update")
+        .map(|s| s.size)
+        .sum();
+    let mut last_emit = Instant::now();
+    for step in &report.plan {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SshError::OperationFailed("Sync cancelled".to_string()));
+        }
+        progress.current = Some(step.path.clone());
+        let _ = app_handle.emit("sync-progress", &progress);
+        let done_before = progress.transferred_bytes;
+        let bytes = execute(&sides, direction, step, cancel, |written, _| {
+            progress.transferred_bytes = done_before + written;
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = app_handle.emit("sync-progress", &progress);
+            }
+        })?;
+        progress.transferred_bytes = done_before + bytes;
+        progress.processed += 1;
+        match step.action.as_str() {
+            "create" | "update" => {
+                report.transferred_files += 1;
+                report.transferred_bytes += bytes;
+            }
+            "delete" => report.deleted += 1,
+            _ => {}
+        }
+    }
+    Ok(report)
+}
+
+/// Number of synchronizations in progress
+pub fn running_count() -> usize {
+    RUNNING.lock().map(|running| running.len()).unwrap_or(0)
+}
+
+/// Cancels every synchronization in progress; used when the app quits
+pub fn cancel_all() {
+    if let Ok(running) = RUNNING.lock() {
+        for cancel in running.values() {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Makes `remoteDir` match `localDir` ("upload") or the other way round
+/// ("download"), transferring only new and changed files. Files are
+/// compared by size and modification time, or by checksum with
+/// `options.checksum`; transferred files keep their modification time so
+/// the next run sees them as unchanged. Symlinks are skipped. Progress is
+/// reported through `sync-progress` events. Returns the plan and what was
+/// done; with `options.dryRun` only the plan.
+#[tauri::command]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub async fn sync_directory(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    taskId: String,
+    localDir: String,
+    remoteDir: String,
+    direction: String,
+    options: Option<SyncOptions>,
+) -> Result<SyncReport, SshError> {
+    let direction = Direction::parse(&direction)?;
+    let options = options.unwrap_or_default();
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(taskId.clone(), cancel.clone());
+
+    tokio::task::spawn_blocking(move || {
+        let result = run_sync(
+            &app_handle,
+            &handle,
+            &sessionId,
+            &taskId,
+            &localDir,
+            &remoteDir,
+            direction,
+            &options,
+            &cancel,
+        );
+        if let Ok(mut running) = RUNNING.lock() {
+            running.remove(&taskId);
+        }
+        let (status, error) = match &result {
+            Ok(_) => ("success", None),
+            Err(_) if cancel.load(Ordering::SeqCst) => ("cancelled", None),
+            Err(e) => ("error", Some(e.to_string())),
+        };
+        let (processed, transferred_bytes) = result
+            .as_ref()
+            .map(|r| (r.transferred_files + r.deleted, r.transferred_bytes))
+            .unwrap_or_default();
+        let _ = app_handle.emit(
+            "sync-progress",
+            SyncProgress {
+                task_id: taskId.clone(),
+                session_id: sessionId.clone(),
+                processed,
+                total: result.as_ref().map_or(0, |r| r.plan.len() as u64),
+                transferred_bytes,
+                total_bytes: transferred_bytes,
+                current: None,
+                status: status.to_string(),
+                error,
+            },
+        );
+        result
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Cancels a running `sync_directory`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_sync_directory(taskId: String) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(cancel) = running.get(&taskId) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::ssh::{shell_quote, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long `du` may run before the partial result is returned
+const ANALYZE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Deepest level `analyze_remote_path` reports
+const MAX_DEPTH: u32 = 6;
+
+/// Children kept per node; the rest are summed into `other_size`
+const MAX_CHILDREN: usize = 200;
+
+/// Cancellation flags of running analyses by session id
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A directory in the size tree. Files are not listed, only counted in the
+/// size of their directory, so huge trees stay cheap to report.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageNode {
+    pub name: String,
+    pub path: String,
+    /// Bytes allocated on disk, files included
+    pub size: u64,
+    /// Subdirectories, largest first
+    pub children: Vec<DiskUsageNode>,
+    /// Bytes of the subdirectories left out of `children`
+    pub other_size: u64,
+}
+
+/// Result of `analyze_remote_path`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageReport {
+    pub root: DiskUsageNode,
+    /// False when `du` timed out; sizes then only cover what was scanned
+    pub complete: bool,
+}
+
+/// Tree under construction; `size` is known once `du` reported the entry
+#[derive(Default)]
+struct PendingNode {
+    size: Option<u64>,
+    children: HashMap<String, PendingNode>,
+}
+
+impl PendingNode {
+    fn finish(self, name: String, path: String) -> DiskUsageNode {
+        let mut children: Vec<DiskUsageNode> = self
+            .children
+            .into_iter()
+            .map(|(child, node)| {
+                let child_path = if path.ends_with('/') {
+                    format!("{}{}", path, child)
+                } else {
+                    format!("{}/{}", path, child)
+                };
+                node.finish(child, child_path)
+            })
+            .collect();
+        children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        // Directories still being scanned at a timeout have no total yet
+        let size = self
+            .size
+            .unwrap_or_else(|| children.iter().map(|c| c.size).sum());
+        let other_size = children.iter().skip(MAX_CHILDREN).map(|c| c.size).sum();
+        children.truncate(MAX_CHILDREN);
+        DiskUsageNode {
+            name,
+            path,
+            size,
+            children,
+            other_size,
+        }
+    }
+}
+
+/// Builds the size tree from `du -k` output ("<KiB>\t<path>" per line)
+fn parse_du(output: &str, root: &str) -> DiskUsageNode {
+    let mut tree = PendingNode::default();
+    for line in output.lines() {
+        let Some((kib, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(kib) = kib.trim().parse::<u64>() else {
+            continue;
+        };
+        let Some(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let mut node = &mut tree;
+        for component in relative.split('/').filter(|c| !c.is_empty()) {
+            node = node.children.entry(component.to_string()).or_default();
+        }
+        node.size = Some(kib * 1024);
+    }
+    let name = root.rsplit('/').find(|c| !c.is_empty()).unwrap_or(root);
+    tree.finish(name.to_string(), root.to_string())
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Measures the disk usage of the directories under a remote path with
+/// `du`, `depth` levels deep, staying on one filesystem. Runs on its own exec channel; after two
+/// minutes the partial tree is returned. `cancel_remote_analysis` stops it.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn analyze_remote_path(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    path: String,
+    depth: u32,
+) -> Result<DiskUsageReport, SshError> {
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let root = match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let command = format!(
+        "du -kx -d {} -- {}",
+        depth.clamp(1, MAX_DEPTH),
+        shell_quote(&root)
+    );
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = RUNNING
+            .lock()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        // A new analysis replaces the previous one of the session
+        if let Some(previous) = running.insert(sessionId.clone(), cancel.clone()) {
+            previous.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let flag = cancel.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        handle.exec_interruptible(&command, ANALYZE_TIMEOUT, &flag)
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()));
+
+    if let Ok(mut running) = RUNNING.lock() {
+        if running
+            .get(&sessionId)
+            .is_some_and(|flag| Arc::ptr_eq(flag, &cancel))
+        {
+            running.remove(&sessionId);
+        }
+    }
+
+    let output = result??;
+    if output.stdout.trim().is_empty() {
+        return Err(SshError::OperationFailed(match output.exit_status {
+            Some(127) => "du is not available on the server".to_string(),
+            _ if !output.stderr.trim().is_empty() => output.error_message("du"),
+            _ => format!("Cannot measure {}", root),
+        }));
+    }
+    Ok(DiskUsageReport {
+        root: parse_du(&output.stdout, &root),
+        complete: !output.timed_out,
+    })
+}
+
+/// Stops the session's running disk usage analysis
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_remote_analysis(sessionId: String) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(cancel) = running.get(&sessionId) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
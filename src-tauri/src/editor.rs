@@ -0,0 +1,279 @@
+use crate::ssh::{SessionId, SshError, SshManager};
+use crate::watch::WatchInfo;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Temporary and backup files editors write next to the edited file; they
+/// are not uploaded
+const EDITOR_IGNORE_PATTERNS: &[&str] = &["*.swp", "*.swx", "*~", ".#*", "#*#", "4913"];
+
+/// Time given to the watch to upload the last save after the editor exits,
+/// a little over its debounce window
+const FINAL_UPLOAD_DELAY: Duration = Duration::from_millis(500);
+
+/// Open remote files by edit id
+static EDITS: Lazy<Mutex<HashMap<String, RemoteEdit>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A remote file open in a local editor
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEdit {
+    pub edit_id: String,
+    pub session_id: String,
+    pub remote_path: String,
+    /// Downloaded copy the editor works on
+    pub local_path: String,
+    /// Watch that uploads the copy on save
+    pub watch_id: String,
+    /// Process id of the editor
+    pub pid: u32,
+}
+
+/// Payload of the `remote-edit-closed` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEditClosed {
+    pub edit_id: String,
+    pub session_id: String,
+    pub remote_path: String,
+    /// Exit code of the editor; `None` when it was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+/// Splits a command line into words. Single or double quotes keep spaces
+/// within a word, e.g. `"/Applications/Sublime Text.app/Contents/SharedSupport/bin/subl" -w`;
+/// backslashes are taken as they are, so Windows paths need no escaping.
+fn split_command_line(command_line: &str) -> Result<Vec<String>, SshError> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in command_line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => words.extend(word.take()),
+            None => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(SshError::OperationFailed(format!(
+            "Unclosed quote in the editor command: {}",
+            command_line
+        )));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Finds a program given without extension on the PATH, so shims such as
+/// VS Code's `code.cmd` can be started; `Command` only looks for `.exe`
+#[cfg(windows)]
+fn resolve_program(program: &str) -> std::path::PathBuf {
+    let path = std::path::Path::new(program);
+    if path.extension().is_some() || path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+    let dirs = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| ["exe", "cmd", "bat"].map(|ext| dir.join(program).with_extension(ext)))
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(not(windows))]
+fn resolve_program(program: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(program)
+}
+
+/// Builds the editor command and puts the file where `{file}` appears, or
+/// after the last argument
+fn editor_command(command_line: &str, file: &str) -> Result<Command, SshError> {
+    let mut words = split_command_line(command_line)?.into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| SshError::OperationFailed("No editor command is configured".to_string()))?;
+    let mut args: Vec<String> = words.collect();
+    if args.iter().any(|arg| arg.contains("{file}")) {
+        for arg in &mut args {
+            *arg = arg.replace("{file}", file);
+        }
+    } else {
+        args.push(file.to_string());
+    }
+    let mut command = Command::new(resolve_program(&program));
+    command.args(args);
+    Ok(command)
+}
+
+/// Waits for the editor to exit, then stops uploading and removes the
+/// downloaded copy
+fn wait_for_editor(app_handle: AppHandle, mut child: Child, edit: RemoteEdit, temp_dir: PathBuf) {
+    let exit_code = match child.wait() {
+        Ok(status) => status.code(),
+        Err(e) => {
+            tracing::warn!(
+                "failed to wait for the editor of {}: {}",
+                edit.remote_path,
+                e
+            );
+            None
+        }
+    };
+    std::thread::sleep(FINAL_UPLOAD_DELAY);
+    crate::watch::stop_watch(&edit.watch_id);
+    if let Err(e) = std::fs::remove_dir_all(&temp_dir) {
+        tracing::warn!("failed to remove {}: {}", temp_dir.display(), e);
+    }
+    if let Ok(mut edits) = EDITS.lock() {
+        edits.remove(&edit.edit_id);
+    }
+    let _ = app_handle.emit(
+        "remote-edit-closed",
+        &RemoteEditClosed {
+            edit_id: edit.edit_id,
+            session_id: edit.session_id,
+            remote_path: edit.remote_path,
+            exit_code,
+        },
+    );
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Downloads a remote file to a temporary directory, opens it in a local
+/// editor and uploads it back whenever it is saved, reported through
+/// `watch-upload` events. When the editor exits the copy is removed and
+/// `remote-edit-closed` is sent.
+///
+/// `editorCmd` defaults to the `transfers.editorCommand` setting. Quote a
+/// program path with spaces; the file goes where `{file}` appears in it,
+/// or at the end. The command must keep running while the file is open,
+/// e.g. `code --wait` or `subl -w`; an editor that hands the file to a
+/// running instance and exits ends the edit at once.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn open_remote_in_editor(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    remotePath: String,
+    editorCmd: Option<String>,
+) -> Result<RemoteEdit, SshError> {
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let (remote_dir, name) = remotePath
+        .rsplit_once('/')
+        .filter(|(_, name)| !name.is_empty())
+        .ok_or_else(|| SshError::OperationFailed(format!("Not a file path: {}", remotePath)))?;
+    let remote_dir = if remote_dir.is_empty() {
+        "/"
+    } else {
+        remote_dir
+    }
+    .to_string();
+    let name = name.to_string();
+    let command_line =
+        editorCmd.unwrap_or_else(|| crate::settings::current().transfers.editor_command);
+
+    let edit_id = uuid::Uuid::new_v4().to_string();
+    let temp_dir = std::env::temp_dir().join("nexashell-edit").join(&edit_id);
+    std::fs::create_dir_all(&temp_dir).map_err(|e| {
+        SshError::OperationFailed(format!("Failed to create {}: {}", temp_dir.display(), e))
+    })?;
+    let local = temp_dir.join(&name).to_string_lossy().into_owned();
+    let mut command = match editor_command(&command_line, &local) {
+        Ok(command) => command,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            return Err(e);
+        }
+    };
+
+    let download_handle = handle.clone();
+    let (remote, target) = (remotePath.clone(), local.clone());
+    let downloaded = tokio::task::spawn_blocking(move || {
+        crate::ssh::sftp_download(
+            &download_handle,
+            &remote,
+            &target,
+            true,
+            &AtomicBool::new(false),
+            |_, _| {},
+        )
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?;
+    if let Err(e) = downloaded {
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        return Err(e);
+    }
+
+    // Watching starts before the editor so no early save is missed
+    let watch_id = crate::watch::start_watch(
+        app_handle.clone(),
+        handle,
+        WatchInfo {
+            watch_id: uuid::Uuid::new_v4().to_string(),
+            session_id: sessionId.clone(),
+            local_dir: temp_dir.to_string_lossy().into_owned(),
+            remote_dir,
+            ignore_patterns: EDITOR_IGNORE_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        },
+        temp_dir.clone(),
+    );
+    let child = watch_id.and_then(|watch_id| {
+        let child = command.spawn().map_err(|e| {
+            crate::watch::stop_watch(&watch_id);
+            SshError::OperationFailed(format!("Failed to start {}: {}", command_line, e))
+        })?;
+        Ok((watch_id, child))
+    });
+    let (watch_id, child) = match child {
+        Ok(started) => started,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            return Err(e);
+        }
+    };
+
+    let edit = RemoteEdit {
+        edit_id: edit_id.clone(),
+        session_id: sessionId,
+        remote_path: remotePath,
+        local_path: local,
+        watch_id,
+        pid: child.id(),
+    };
+    EDITS
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(edit_id, edit.clone());
+    let waited = edit.clone();
+    std::thread::spawn(move || wait_for_editor(app_handle, child, waited, temp_dir));
+    Ok(edit)
+}
+
+/// Lists remote files open in a local editor
+#[tauri::command]
+pub fn list_remote_edits() -> Result<Vec<RemoteEdit>, SshError> {
+    let edits = EDITS
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    Ok(edits.values().cloned().collect())
+}
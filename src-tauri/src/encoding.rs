@@ -0,0 +1,132 @@
+use crate::ssh::Utf8Decoder;
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+/// Encodings tried, in order, when an "auto" session first sends output
+/// that is not UTF-8. The first one that decodes it without errors wins;
+/// Windows-1252 (latin-1) accepts anything and is the last resort.
+const AUTO_CANDIDATES: [&Encoding; 4] = [GBK, BIG5, SHIFT_JIS, EUC_KR];
+
+/// Character set of a session's terminal stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEncoding {
+    /// UTF-8 until the output shows otherwise
+    Auto,
+    Fixed(&'static Encoding),
+}
+
+impl Default for SessionEncoding {
+    fn default() -> Self {
+        SessionEncoding::Fixed(UTF_8)
+    }
+}
+
+impl SessionEncoding {
+    /// Parses "auto" or an encoding label such as "utf-8", "gbk", "big5" or
+    /// "latin1"; an empty label means UTF-8
+    pub fn parse(label: &str) -> Result<Self, String> {
+        match label.trim() {
+            "" => Ok(Self::default()),
+            l if l.eq_ignore_ascii_case("auto") => Ok(SessionEncoding::Auto),
+            l => Encoding::for_label(l.as_bytes())
+                .map(SessionEncoding::Fixed)
+                .ok_or_else(|| format!("Unknown encoding: {}", l)),
+        }
+    }
+}
+
+/// Decodes a session's output according to its encoding, keeping
+/// incomplete multi-byte sequences across reads
+pub struct TerminalDecoder {
+    setting: SessionEncoding,
+    /// Encoding in use; `None` while "auto" has only seen ASCII
+    resolved: Option<&'static Encoding>,
+    utf8: Utf8Decoder,
+    /// Decoder for encodings other than UTF-8
+    decoder: Option<encoding_rs::Decoder>,
+}
+
+impl Default for TerminalDecoder {
+    fn default() -> Self {
+        Self::new(SessionEncoding::default())
+    }
+}
+
+impl TerminalDecoder {
+    pub fn new(setting: SessionEncoding) -> Self {
+        let mut decoder = Self {
+            setting,
+            resolved: None,
+            utf8: Utf8Decoder::default(),
+            decoder: None,
+        };
+        if let SessionEncoding::Fixed(encoding) = setting {
+            decoder.resolve(encoding);
+        }
+        decoder
+    }
+
+    /// Switches to another encoding; bytes held back from the previous one
+    /// are dropped
+    pub fn set(&mut self, setting: SessionEncoding) {
+        if setting != self.setting {
+            *self = Self::new(setting);
+        }
+    }
+
+    /// Encoding input is sent in: the detected one, UTF-8 before that
+    pub fn encoding(&self) -> &'static Encoding {
+        self.resolved.unwrap_or(UTF_8)
+    }
+
+    fn resolve(&mut self, encoding: &'static Encoding) {
+        self.resolved = Some(encoding);
+        self.decoder = (encoding != UTF_8).then(|| encoding.new_decoder_without_bom_handling());
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        if self.resolved.is_none() && !bytes.is_ascii() {
+            self.resolve(detect(bytes));
+        }
+        let Some(decoder) = self.decoder.as_mut() else {
+            return self.utf8.decode(bytes);
+        };
+        let capacity = decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len() * 3);
+        let mut out = String::with_capacity(capacity);
+        let _ = decoder.decode_to_string(bytes, &mut out, false);
+        out
+    }
+
+    /// Converts text input to the session's encoding. Bytes that are not
+    /// UTF-8 (raw input) are sent as they are; characters the encoding
+    /// cannot represent become numeric character references.
+    pub fn encode(&self, data: Vec<u8>) -> Vec<u8> {
+        let encoding = self.encoding();
+        if encoding == UTF_8 || data.is_ascii() {
+            return data;
+        }
+        match std::str::from_utf8(&data) {
+            Ok(text) => encoding.encode(text).0.into_owned(),
+            Err(_) => data,
+        }
+    }
+}
+
+/// Guesses the encoding of the first non-ASCII output of an "auto" session
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    // A sequence cut off at the end of the read still counts as UTF-8
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return UTF_8,
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    AUTO_CANDIDATES
+        .into_iter()
+        .find(|encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .is_some()
+        })
+        .unwrap_or(WINDOWS_1252)
+}
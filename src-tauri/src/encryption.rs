@@ -39,10 +39,83 @@ impl EncryptionManager {
         Self::decrypt_with_key(encrypted_base64, &Self::get_machine_id())
     }
 
+    /// Encrypt an arbitrary secret string using the machine-specific ID.
+    pub fn encrypt_secret(value: &str) -> Result<String, String> {
+        Self::seal(value.as_bytes(), &Self::get_machine_id())
+    }
+
+    /// Decrypt a secret string produced by `encrypt_secret`.
+    pub fn decrypt_secret(encrypted_base64: &str) -> Result<String, String> {
+        let plaintext = Self::open(encrypted_base64, &Self::get_machine_id())?;
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+
+    /// Encrypt an arbitrary string with a custom key (e.g. a share bundle).
+    pub fn encrypt_secret_with_key(value: &str, key_str: &str) -> Result<String, String> {
+        Self::seal(value.as_bytes(), key_str)
+    }
+
+    /// Decrypt a string produced by `encrypt_secret_with_key`.
+    pub fn decrypt_secret_with_key(
+        encrypted_base64: &str,
+        key_str: &str,
+    ) -> Result<String, String> {
+        let plaintext = Self::open(encrypted_base64, key_str)?;
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+
     /// Encrypt sensitive data with a custom key (useful for export).
     pub fn encrypt_with_key(data: &SensitiveData, key_str: &str) -> Result<String, String> {
         let json = serde_json::to_string(data).map_err(|e| e.to_string())?;
+        Self::seal(json.as_bytes(), key_str)
+    }
+
+    /// Decrypt sensitive data with a custom key (useful for import).
+    pub fn decrypt_with_key(
+        encrypted_base64: &str,
+        key_str: &str,
+    ) -> Result<SensitiveData, String> {
+        let plaintext = Self::open(encrypted_base64, key_str)?;
+        let data: SensitiveData = serde_json::from_slice(&plaintext).map_err(|e| e.to_string())?;
+        Ok(data)
+    }
 
+    /// Hash a password for later verification, returning base64 of
+    /// Salt(16) + PBKDF2 hash(32).
+    pub fn hash_password(password: &str) -> String {
+        let mut salt = [0u8; 16];
+        thread_rng().fill_bytes(&mut salt);
+        let mut combined = salt.to_vec();
+        combined.extend_from_slice(&Self::derive(password, &salt));
+        general_purpose::STANDARD.encode(combined)
+    }
+
+    /// Check a password against the output of `hash_password`.
+    pub fn verify_password(password: &str, hash_base64: &str) -> bool {
+        let Ok(combined) = general_purpose::STANDARD.decode(hash_base64) else {
+            return false;
+        };
+        if combined.len() != 16 + 32 {
+            return false;
+        }
+        let (salt, expected) = combined.split_at(16);
+        // Compare without an early exit so timing reveals nothing
+        Self::derive(password, salt)
+            .iter()
+            .zip(expected)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+
+    fn derive(password: &str, salt: &[u8]) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, Self::ITERATIONS, &mut key);
+        key
+    }
+
+    /// Encrypt bytes with a key derived from `key_str`, returning base64 of
+    /// Salt(16) + IV(12) + Ciphertext.
+    fn seal(plaintext: &[u8], key_str: &str) -> Result<String, String> {
         // 1. Generate random Salt
         let mut salt = [0u8; 16];
         thread_rng().fill_bytes(&mut salt);
@@ -59,7 +132,7 @@ impl EncryptionManager {
         // 4. Encrypt
         let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
         let ciphertext = cipher
-            .encrypt(nonce, json.as_bytes().as_ref())
+            .encrypt(nonce, plaintext)
             .map_err(|e| format!("Encryption failed: {}", e))?;
 
         // 5. Package: Salt(16) + IV(12) + Ciphertext
@@ -70,11 +143,8 @@ impl EncryptionManager {
         Ok(general_purpose::STANDARD.encode(combined))
     }
 
-    /// Decrypt sensitive data with a custom key (useful for import).
-    pub fn decrypt_with_key(
-        encrypted_base64: &str,
-        key_str: &str,
-    ) -> Result<SensitiveData, String> {
+    /// Decrypt the output of `seal`.
+    fn open(encrypted_base64: &str, key_str: &str) -> Result<Vec<u8>, String> {
         let combined = general_purpose::STANDARD
             .decode(encrypted_base64)
             .map_err(|e| format!("Invalid base64: {}", e))?;
@@ -96,11 +166,8 @@ impl EncryptionManager {
         let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
         let nonce = Nonce::from_slice(iv);
 
-        let plaintext = cipher
+        cipher
             .decrypt(nonce, ciphertext)
-            .map_err(|e| format!("Decryption failed (possibly wrong key): {}", e))?;
-
-        let data: SensitiveData = serde_json::from_slice(&plaintext).map_err(|e| e.to_string())?;
-        Ok(data)
+            .map_err(|e| format!("Decryption failed (possibly wrong key): {}", e))
     }
 }
@@ -0,0 +1,78 @@
+use once_cell::sync::{Lazy, OnceCell};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// The same error is only emitted again after this long, so a failure
+/// repeated by a polling task does not flood the UI. It is still logged.
+const REPEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+/// When each recent error was last emitted, by module, session and message
+type ErrorKey = (String, Option<String>, String);
+static LAST_EMITTED: Lazy<Mutex<HashMap<ErrorKey, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `backend-error` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendError {
+    /// Part of the backend that failed, e.g. "monitoring" or "events"
+    pub module: String,
+    /// Connection the failure belongs to, if any
+    pub session_id: Option<String>,
+    pub message: String,
+    /// Unix timestamp in milliseconds
+    pub time: i64,
+}
+
+/// Lets `report` emit events; called once at startup
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+/// Whether an error was not emitted within `REPEAT_INTERVAL`; records it
+fn due(key: ErrorKey) -> bool {
+    let Ok(mut last) = LAST_EMITTED.lock() else {
+        return true;
+    };
+    let now = Instant::now();
+    last.retain(|_, at| now.duration_since(*at) < REPEAT_INTERVAL);
+    if last.contains_key(&key) {
+        return false;
+    }
+    last.insert(key, now);
+    true
+}
+
+/// Logs a failure of background work that has no caller to return it to
+/// and emits it as `backend-error` so the UI can show it
+pub fn report(module: &str, session_id: Option<&str>, message: impl Display) {
+    let message = message.to_string();
+    tracing::error!(module, session_id, "{}", message);
+    let Some(app) = APP.get() else {
+        return;
+    };
+    if !due((
+        module.to_string(),
+        session_id.map(String::from),
+        message.clone(),
+    )) {
+        return;
+    }
+    let payload = BackendError {
+        module: module.to_string(),
+        session_id: session_id.map(String::from),
+        message,
+        time: chrono::Utc::now().timestamp_millis(),
+    };
+    // Reporting this one as well could only fail the same way
+    if let Err(e) = app.emit("backend-error", &payload) {
+        tracing::warn!("failed to emit backend-error: {}", e);
+    }
+}
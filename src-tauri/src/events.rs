@@ -0,0 +1,214 @@
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// Version of the event contract described by `get_event_schema`. Adding
+/// events or optional fields keeps the version; renaming or removing an
+/// event or field, or changing a field's type, increments it.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Payload of the `ssh-input-{sessionId}` event sent by the frontend, for
+/// SSH sessions and local terminals alike
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TerminalInput {
+    #[serde(default)]
+    pub input: String,
+    /// Base64-encoded raw bytes, sent instead of `input` (SSH only)
+    pub data: Option<String>,
+}
+
+/// Payload of the `ssh-resize-{sessionId}` event sent by the frontend
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TerminalResize {
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// One event of the contract
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSpec {
+    /// Event name; `{sessionId}` is replaced with the session's id
+    pub name: &'static str,
+    /// "backend" for events the backend emits, "frontend" for events the
+    /// frontend emits for the backend to handle
+    pub source: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the payload
+    pub payload: RootSchema,
+}
+
+/// Result of `get_event_schema`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSchema {
+    pub version: u32,
+    pub events: Vec<EventSpec>,
+}
+
+fn backend<T: JsonSchema>(name: &'static str, description: &'static str) -> EventSpec {
+    EventSpec {
+        name,
+        source: "backend",
+        description,
+        payload: schema_for!(T),
+    }
+}
+
+fn frontend<T: JsonSchema>(name: &'static str, description: &'static str) -> EventSpec {
+    EventSpec {
+        source: "frontend",
+        ..backend::<T>(name, description)
+    }
+}
+
+/// Every event exchanged between the backend and the frontend. Events
+/// emitted to a session's window are named with a `{sessionId}` suffix.
+pub fn event_schema() -> EventSchema {
+    use crate::{
+        alerts, archive, backup, capabilities, db_backup, deeplink, dirsync, editor, error_bus,
+        group_connect, group_run, guard, idle, images, multiplexer, multiwindow, network, plugins,
+        quickconnect, scheduler, settings, shell_integration, ssh, sudo, sync, system, transport,
+        tray, triggers, tunnel, watch, workflow,
+    };
+
+    let events = vec![
+        // Terminal sessions
+        frontend::<TerminalInput>("ssh-input-{sessionId}", "Keystrokes or pasted text"),
+        frontend::<TerminalResize>("ssh-resize-{sessionId}", "Terminal size changed"),
+        backend::<transport::OutputChunk>(
+            "ssh-output-{sessionId}",
+            "Output of an SSH session or local terminal",
+        ),
+        backend::<ssh::OutputTruncated>(
+            "ssh-output-truncated-{sessionId}",
+            "Output was dropped to keep the app responsive",
+        ),
+        backend::<ssh::PasteProgress>("ssh-paste-progress-{sessionId}", "Progress of a paste"),
+        backend::<ssh::ClipboardRequest>(
+            "ssh-clipboard-{sessionId}",
+            "The server asked to set the clipboard (OSC 52)",
+        ),
+        backend::<images::InlineImage>("ssh-image-{sessionId}", "Inline image in the output"),
+        backend::<shell_integration::CwdChanged>(
+            "ssh-cwd-{sessionId}",
+            "Working directory reported by the shell",
+        ),
+        backend::<shell_integration::PromptStarted>(
+            "ssh-prompt-{sessionId}",
+            "A shell prompt was printed",
+        ),
+        backend::<shell_integration::CommandFinished>(
+            "ssh-command-finished-{sessionId}",
+            "A command finished",
+        ),
+        backend::<shell_integration::CommandFinished>(
+            "ssh-notify-done-{sessionId}",
+            "A command the user asked to be notified about finished",
+        ),
+        backend::<guard::GuardedCommand>(
+            "ssh-command-guard-{sessionId}",
+            "A command matching a guard pattern waits for confirmation",
+        ),
+        backend::<sudo::SudoAutofillEvent>(
+            "ssh-sudo-autofill-{sessionId}",
+            "Outcome of answering a sudo prompt",
+        ),
+        backend::<triggers::TriggerFired>("ssh-trigger-{sessionId}", "An output trigger fired"),
+        backend::<idle::IdleEvent>("ssh-idle-{sessionId}", "Idle timeout state changed"),
+        backend::<ssh::ServerStatus>("ssh-status-{sessionId}", "Periodic server metrics"),
+        backend::<alerts::AlertEvent>(
+            "ssh-alert-{sessionId}",
+            "A metric alert was triggered or resolved",
+        ),
+        backend::<multiplexer::ResumeAvailable>(
+            "resume-available",
+            "tmux or screen sessions can be resumed after connecting",
+        ),
+        backend::<network::WakeStatus>("wol-status-{sessionId}", "Wake-on-LAN progress"),
+        backend::<network::WakeConnect>("wol-connect", "A woken host is ready to connect"),
+        backend::<capabilities::CapabilitiesProbed>(
+            "session-capabilities",
+            "The operating system and tools of a connected server were probed",
+        ),
+        backend::<network::SessionHealth>("session-health", "Result of a reachability check"),
+        backend::<multiwindow::SessionMoved>(
+            "session-moved",
+            "A session was moved to another window",
+        ),
+        // Files
+        backend::<ssh::UploadProgress>("upload-progress", "Progress of an SFTP upload"),
+        backend::<ssh::UploadProgress>("download-progress", "Progress of an SFTP download"),
+        backend::<archive::ArchiveProgress>("archive-progress", "Progress of an archive task"),
+        backend::<dirsync::SyncProgress>("sync-progress", "Progress of a directory sync"),
+        backend::<watch::WatchUploadEvent>(
+            "watch-upload",
+            "A watched file is being or was uploaded",
+        ),
+        backend::<editor::RemoteEditClosed>(
+            "remote-edit-closed",
+            "The editor of a remote file opened with open_remote_in_editor exited",
+        ),
+        backend::<system::HashProgress>("hash-progress", "Progress of hashing a local file"),
+        backend::<tunnel::TunnelInfo>("tunnel-opened", "A local tunnel was opened"),
+        backend::<tunnel::TunnelInfo>("tunnel-closed", "A local tunnel was closed"),
+        backend::<group_connect::GroupConnectProgress>(
+            "group-connect-progress",
+            "A session opened by connect_group is connecting, connected or failed",
+        ),
+        backend::<group_connect::GroupConnectSummary>(
+            "group-connect-done",
+            "All sessions of a connect_group have finished connecting",
+        ),
+        // Automation
+        backend::<group_run::GroupRunOutput>("group-run-output", "Output of one host"),
+        backend::<group_run::GroupRunHostResult>("group-run-host", "A host has finished"),
+        backend::<group_run::GroupRunSummary>("group-run-done", "A group run has finished"),
+        backend::<workflow::WorkflowProgress>("workflow-progress", "A workflow step changed"),
+        backend::<workflow::WorkflowPrompt>("workflow-prompt", "A workflow step waits for input"),
+        backend::<workflow::WorkflowSummary>("workflow-done", "A workflow run has finished"),
+        backend::<scheduler::ScheduledTaskResult>("scheduled-task-run", "A scheduled task has run"),
+        backend::<backup::BackupRunResult>("backup-snapshot", "A backup task has run"),
+        // App
+        backend::<settings::SettingsChanged>("settings-changed", "A setting was changed"),
+        backend::<sync::SyncReport>("sync-completed", "The library was synchronized"),
+        backend::<db_backup::DbBackup>(
+            "db-restored",
+            "The database was restored; the payload is the backup taken before",
+        ),
+        backend::<String>(
+            "config-rolled-back",
+            "Configuration was rolled back to a commit",
+        ),
+        backend::<deeplink::DeepLinkOpen>("deep-link-open", "A deep link was opened"),
+        backend::<deeplink::DeepLinkError>("deep-link-error", "A deep link could not be used"),
+        backend::<tray::TraySessionEvent>(
+            "tray-open-session",
+            "A session was chosen in the tray menu",
+        ),
+        backend::<tray::TraySessionEvent>(
+            "tray-session-closed",
+            "A session was disconnected from the tray menu",
+        ),
+        backend::<()>("quick-connect-shown", "The quick connect window was shown"),
+        backend::<quickconnect::QuickConnectOpen>(
+            "quick-connect-open",
+            "A session was picked in quick connect; sent to the main window",
+        ),
+        backend::<plugins::PluginEvent>("plugin-event", "Event emitted by a plugin"),
+        backend::<error_bus::BackendError>("backend-error", "Background work failed"),
+    ];
+    EventSchema {
+        version: EVENT_SCHEMA_VERSION,
+        events,
+    }
+}
+
+/// Describes every event the backend emits or listens for, with a JSON
+/// Schema of its payload and the contract version, for the frontend and
+/// plugin authors.
+#[command]
+pub fn get_event_schema() -> EventSchema {
+    event_schema()
+}
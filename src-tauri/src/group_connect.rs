@@ -0,0 +1,279 @@
+use crate::ssh::{ConnectOptions, SessionId, SshError, SshManager};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sessions connected at once when no parallelism is given
+const DEFAULT_PARALLELISM: usize = 4;
+
+/// Upper bound for the requested parallelism
+const MAX_PARALLELISM: usize = 16;
+
+/// Terminal size used until the frontend sends the real one
+const DEFAULT_COLS: u32 = 80;
+const DEFAULT_ROWS: u32 = 24;
+
+/// Options of `connect_group`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GroupConnectOptions {
+    /// Sessions connected at once
+    pub parallelism: Option<usize>,
+    pub cols: Option<u32>,
+    pub rows: Option<u32>,
+    /// See `ConnectOptions::shell_integration`
+    pub shell_integration: bool,
+    /// Master password for protected sessions of the group
+    pub master_password: Option<String>,
+}
+
+/// A session of the group and the connection opened for it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConnectTarget {
+    /// Id of the connection, as passed to `connect_ssh`
+    pub session_id: String,
+    pub saved_session_id: String,
+    pub server_name: String,
+    /// Whether the saved session already had an open connection, which is
+    /// kept instead of connecting again
+    pub already_open: bool,
+}
+
+/// Result of `connect_group`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConnect {
+    pub connect_id: String,
+    pub sessions: Vec<GroupConnectTarget>,
+}
+
+/// Payload of the `group-connect-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConnectProgress {
+    pub connect_id: String,
+    pub session_id: String,
+    pub saved_session_id: String,
+    /// "connecting", "connected", "error" or "cancelled"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// Payload of the `group-connect-done` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConnectSummary {
+    pub connect_id: String,
+    pub group_id: String,
+    pub total: usize,
+    pub connected: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+/// Connects one saved session with its stored credentials and reports the
+/// outcome; returns the final status
+async fn connect_target(
+    app_handle: &AppHandle,
+    connect_id: &str,
+    saved: crate::db::Session,
+    target: &GroupConnectTarget,
+    window: &str,
+    options: &GroupConnectOptions,
+) -> String {
+    let progress = |status: &str, error: Option<String>| {
+        let _ = app_handle.emit(
+            "group-connect-progress",
+            &GroupConnectProgress {
+                connect_id: connect_id.to_string(),
+                session_id: target.session_id.clone(),
+                saved_session_id: target.saved_session_id.clone(),
+                status: status.to_string(),
+                error,
+            },
+        );
+    };
+    progress("connecting", None);
+
+    let saved_id = saved.id.clone();
+    let result = async {
+        let port = u16::try_from(saved.port)
+            .map_err(|_| SshError::OperationFailed(format!("Invalid port: {}", saved.port)))?;
+        // Key sessions read their passphrase from the store themselves
+        let password =
+            tokio::task::spawn_blocking(move || crate::db::load_session_credentials(&saved_id))
+                .await
+                .map_err(|e| SshError::TaskError(e.to_string()))?
+                .map_err(SshError::OperationFailed)?
+                .and_then(|credentials| credentials.password)
+                .unwrap_or_default();
+        app_handle
+            .state::<SshManager>()
+            .connect_ssh(
+                Some(app_handle.clone()),
+                SessionId::from(target.session_id.clone()),
+                saved.addr.clone(),
+                port,
+                saved.username.clone(),
+                password,
+                options.cols.unwrap_or(DEFAULT_COLS),
+                options.rows.unwrap_or(DEFAULT_ROWS),
+                ConnectOptions {
+                    shell_integration: options.shell_integration,
+                    saved_session_id: Some(saved.id.clone()),
+                    window: Some(window.to_string()),
+                    master_password: options.master_password.clone(),
+                    ..ConnectOptions::default()
+                },
+            )
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            let id = saved.id;
+            let _ =
+                tokio::task::spawn_blocking(move || crate::db::update_session_timestamp(id)).await;
+            progress("connected", None);
+            "connected"
+        }
+        Err(SshError::Cancelled) => {
+            progress("cancelled", None);
+            "cancelled"
+        }
+        Err(e) => {
+            progress("error", Some(e.to_string()));
+            "error"
+        }
+    }
+    .to_string()
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Opens a terminal session for every saved session of a group, at most
+/// `parallelism` at a time, authenticating with the stored credentials.
+/// Saved sessions that already have an open connection keep it. When
+/// biometric unlock is enabled, one confirmation covers the whole group.
+///
+/// Returns at once with the connection id assigned to each session, so
+/// tabs can be created before they connect. Each connection reports
+/// `group-connect-progress` events, and `group-connect-done` follows when
+/// all have finished. A single connection can be aborted with
+/// `cancel_connect`.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn connect_group(
+    app_handle: AppHandle,
+    window: tauri::WebviewWindow,
+    state: tauri::State<'_, SshManager>,
+    groupId: String,
+    options: Option<GroupConnectOptions>,
+) -> Result<GroupConnect, SshError> {
+    let options = options.unwrap_or_default();
+    let sessions = crate::db::get_sessions(
+        Some(groupId.clone()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .map_err(SshError::OperationFailed)?;
+    let open: HashMap<String, String> = state
+        .active_sessions()
+        .into_iter()
+        .filter(|s| s.connected)
+        .filter_map(|s| Some((s.saved_session_id?, s.session_id)))
+        .collect();
+
+    let mut targets = Vec::new();
+    let mut pending = Vec::new();
+    for saved in sessions.into_iter().filter(|s| !s.is_template) {
+        let target = GroupConnectTarget {
+            session_id: open
+                .get(&saved.id)
+                .cloned()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            saved_session_id: saved.id.clone(),
+            server_name: saved.server_name.clone(),
+            already_open: open.contains_key(&saved.id),
+        };
+        if !target.already_open {
+            pending.push((saved, target.clone()));
+        }
+        targets.push(target);
+    }
+    if targets.is_empty() {
+        return Err(SshError::OperationFailed(
+            "The group has no sessions".to_string(),
+        ));
+    }
+
+    if !pending.is_empty() {
+        tokio::task::spawn_blocking(|| crate::biometric::confirm("connect to a group of sessions"))
+            .await
+            .map_err(|e| SshError::TaskError(e.to_string()))?
+            .map_err(SshError::AuthenticationFailed)?;
+    }
+
+    let connect_id = uuid::Uuid::new_v4().to_string();
+    let permits = Arc::new(tokio::sync::Semaphore::new(
+        options
+            .parallelism
+            .unwrap_or(DEFAULT_PARALLELISM)
+            .clamp(1, MAX_PARALLELISM),
+    ));
+    let options = Arc::new(options);
+    let window = window.label().to_string();
+    let id = connect_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let total = pending.len();
+        let mut tasks = tokio::task::JoinSet::new();
+        for (saved, target) in pending {
+            let (app_handle, id, window, options, permits) = (
+                app_handle.clone(),
+                id.clone(),
+                window.clone(),
+                options.clone(),
+                permits.clone(),
+            );
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.ok()?;
+                Some(connect_target(&app_handle, &id, saved, &target, &window, &options).await)
+            });
+        }
+
+        let mut statuses = Vec::with_capacity(total);
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(Some(status)) = joined {
+                statuses.push(status);
+            }
+        }
+        let count = |status: &str| statuses.iter().filter(|s| *s == status).count();
+        let _ = app_handle.emit(
+            "group-connect-done",
+            &GroupConnectSummary {
+                connect_id: id,
+                group_id: groupId,
+                total,
+                connected: count("connected"),
+                failed: count("error"),
+                cancelled: count("cancelled"),
+            },
+        );
+    });
+    Ok(GroupConnect {
+        connect_id,
+        sessions: targets,
+    })
+}
@@ -0,0 +1,294 @@
+use crate::ssh::{SessionHandle, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+/// Hosts worked on at once when no parallelism is given
+const DEFAULT_PARALLELISM: usize = 5;
+
+/// Upper bound for the requested parallelism
+const MAX_PARALLELISM: usize = 32;
+
+/// Cancellation flags of running group commands by run id
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `group-run-output` event: stdout of one host as it arrives
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRunOutput {
+    pub run_id: String,
+    pub session_id: String,
+    pub data: String,
+}
+
+/// Payload of the `group-run-host` event, sent when a host has finished
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRunHostResult {
+    pub run_id: String,
+    pub session_id: String,
+    pub server_name: String,
+    /// "success", "failed" (non-zero exit), "error" (connect or channel
+    /// failure) or "cancelled"
+    pub status: String,
+    pub exit_status: Option<i32>,
+    pub stderr: String,
+    pub error: Option<String>,
+    /// Whether an open connection of the session was used
+    pub reused: bool,
+    pub duration_ms: u64,
+}
+
+/// Payload of the `group-run-done` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRunSummary {
+    pub run_id: String,
+    pub group_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+/// Splits off the end of `pending` that may be the start of a multi-byte
+/// character, returning the decodable text before it
+fn take_text(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let rest = pending.split_off(valid);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = rest;
+    text
+}
+
+/// Runs the command on one host, over its open connection when there is
+/// one and a fresh connection otherwise. Blocking.
+fn run_host(
+    app_handle: &AppHandle,
+    run_id: &str,
+    saved: &crate::db::Session,
+    open: Option<SessionHandle>,
+    command: &str,
+    cancel: &AtomicBool,
+) -> GroupRunHostResult {
+    let started = Instant::now();
+    let reused = open.is_some();
+    let outcome = (|| -> Result<crate::ssh::ExecOutput, SshError> {
+        let (handle, fresh) = match open {
+            Some(handle) => (handle, false),
+            None => (
+                SessionHandle::detached(crate::ssh::connect_saved_session(saved)?),
+                true,
+            ),
+        };
+        let send = |data: String| {
+            if !data.is_empty() {
+                let _ = app_handle.emit(
+                    "group-run-output",
+                    &GroupRunOutput {
+                        run_id: run_id.to_string(),
+                        session_id: saved.id.clone(),
+                        data,
+                    },
+                );
+            }
+        };
+        let mut pending = Vec::new();
+        let result = handle.exec_streaming(command, None, cancel, |chunk| {
+            pending.extend_from_slice(chunk);
+            send(take_text(&mut pending));
+        });
+        send(String::from_utf8_lossy(&pending).into_owned());
+        if fresh {
+            handle.blocking(|sess| {
+                let _ = sess.disconnect(None, "command complete", None);
+            });
+        }
+        result
+    })();
+
+    let mut result = GroupRunHostResult {
+        run_id: run_id.to_string(),
+        session_id: saved.id.clone(),
+        server_name: saved.server_name.clone(),
+        status: String::new(),
+        exit_status: None,
+        stderr: String::new(),
+        error: None,
+        reused,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    match outcome {
+        Ok(output) => {
+            result.status = if output.exit_status == Some(0) {
+                "success"
+            } else {
+                "failed"
+            }
+            .to_string();
+            result.exit_status = output.exit_status;
+            result.stderr = output.stderr;
+        }
+        Err(_) if cancel.load(Ordering::SeqCst) => result.status = "cancelled".to_string(),
+        Err(e) => {
+            result.status = "error".to_string();
+            result.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Runs a command on every session of a group via exec channels, at most
+/// `parallelism` hosts at a time. Open connections of a session are reused;
+/// other sessions are connected with their stored credentials for the run.
+///
+/// Sessions that need a new connection and are protected ask for
+/// `masterPassword` or a biometric check once before the run starts.
+///
+/// Returns the run id at once. Output streams as `group-run-output`
+/// events, each host's exit status and stderr follow as `group-run-host`,
+/// and `group-run-done` reports the counts at the end.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn run_on_group(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    groupId: String,
+    command: String,
+    parallelism: Option<usize>,
+    masterPassword: Option<String>,
+) -> Result<String, SshError> {
+    if command.trim().is_empty() {
+        return Err(SshError::OperationFailed("No command given".to_string()));
+    }
+    let sessions = crate::db::get_sessions(
+        Some(groupId.clone()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .map_err(SshError::OperationFailed)?;
+    let hosts: VecDeque<_> = sessions
+        .into_iter()
+        .filter(|s| !s.is_template)
+        .map(|s| {
+            let open = state.saved_session_handle(&s.id);
+            (s, open)
+        })
+        .collect();
+    if hosts.is_empty() {
+        return Err(SshError::OperationFailed(
+            "The group has no sessions".to_string(),
+        ));
+    }
+    // Open connections were confirmed when they were opened
+    let fresh: Vec<String> = hosts
+        .iter()
+        .filter(|(_, open)| open.is_none())
+        .map(|(saved, _)| saved.id.clone())
+        .collect();
+    tokio::task::spawn_blocking(move || {
+        crate::biometric::confirm_protected_sessions(&fresh, masterPassword.as_deref())
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+    .map_err(SshError::AuthenticationFailed)?;
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let cancel = Arc::new(AtomicBool::new(false));
+    RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(run_id.clone(), cancel.clone());
+
+    let workers = parallelism
+        .unwrap_or(DEFAULT_PARALLELISM)
+        .clamp(1, MAX_PARALLELISM)
+        .min(hosts.len());
+    let total = hosts.len();
+    let queue = Mutex::new(hosts);
+    let id = run_id.clone();
+    std::thread::spawn(move || {
+        let results = Mutex::new(Vec::with_capacity(total));
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let Some((saved, open)) = queue.lock().ok().and_then(|mut q| q.pop_front())
+                    else {
+                        break;
+                    };
+                    let result = if cancel.load(Ordering::SeqCst) {
+                        GroupRunHostResult {
+                            run_id: id.clone(),
+                            session_id: saved.id.clone(),
+                            server_name: saved.server_name.clone(),
+                            status: "cancelled".to_string(),
+                            exit_status: None,
+                            stderr: String::new(),
+                            error: None,
+                            reused: false,
+                            duration_ms: 0,
+                        }
+                    } else {
+                        run_host(&app_handle, &id, &saved, open, &command, &cancel)
+                    };
+                    let _ = app_handle.emit("group-run-host", &result);
+                    if let Ok(mut results) = results.lock() {
+                        results.push(result.status);
+                    }
+                });
+            }
+        });
+
+        if let Ok(mut running) = RUNNING.lock() {
+            running.remove(&id);
+        }
+        let statuses = results.into_inner().unwrap_or_default();
+        let count = |status: &str| statuses.iter().filter(|s| *s == status).count();
+        let _ = app_handle.emit(
+            "group-run-done",
+            &GroupRunSummary {
+                run_id: id.clone(),
+                group_id: groupId,
+                total,
+                succeeded: count("success"),
+                failed: count("failed") + count("error"),
+                cancelled: count("cancelled"),
+            },
+        );
+    });
+    Ok(run_id)
+}
+
+/// Cancels a running group command. Commands already running are closed;
+/// hosts not started yet are skipped.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_group_run(runId: String) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(cancel) = running.get(&runId) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use crate::ssh::{InputCommand, BRACKETED_PASTE_END, BRACKETED_PASTE_START};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Longest line kept for matching; longer lines are not checked
+const MAX_LINE_LEN: usize = 4096;
+
+/// Ctrl+C, sent when a held command is rejected so the shell drops the
+/// line that is still in its buffer
+const CANCEL_LINE: u8 = 0x03;
+
+/// Payload of the `ssh-command-guard-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardedCommand {
+    pub session_id: String,
+    /// Id passed back to `confirm_guarded_command`
+    pub guard_id: String,
+    /// The line that matched
+    pub command: String,
+    /// The pattern it matched
+    pub pattern: String,
+}
+
+struct Pending {
+    id: String,
+    /// The Enter that submits the command and all input after it
+    input: Vec<InputCommand>,
+}
+
+/// Holds back input that would run a dangerous command until the user
+/// confirms it.
+///
+/// Typed input is tracked line by line. When Enter submits a line matching
+/// one of the patterns, the Enter and everything after it are held until
+/// `confirm` is called. A paste is held as a whole when one of its lines
+/// matches. Lines changed with cursor keys, completion or history recall
+/// cannot be reconstructed from the input and are not checked.
+pub struct CommandGuard {
+    session_id: String,
+    patterns: Vec<Regex>,
+    /// Bytes typed on the current line
+    line: Vec<u8>,
+    /// Set when `line` no longer matches what the shell has
+    line_unknown: bool,
+    pending: Option<Pending>,
+    /// Input let through by `confirm`, picked up by the I/O task
+    released: Vec<InputCommand>,
+}
+
+impl CommandGuard {
+    /// Builds a guard from regular expressions; invalid ones are skipped
+    pub fn new(session_id: String, patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|p| {
+                Regex::new(p)
+                    .map_err(|e| tracing::warn!("invalid pattern {:?}: {}", p, e))
+                    .ok()
+            })
+            .collect();
+        Self {
+            session_id,
+            patterns,
+            line: Vec::new(),
+            line_unknown: false,
+            pending: None,
+            released: Vec::new(),
+        }
+    }
+
+    /// Passes input through the guard. Returns the input to forward now
+    /// and, when a command was just held, the event to emit.
+    pub fn filter(&mut self, input: InputCommand) -> (Vec<InputCommand>, Option<GuardedCommand>) {
+        if let Some(pending) = &mut self.pending {
+            pending.input.push(input);
+            return (Vec::new(), None);
+        }
+        self.check(input, false)
+    }
+
+    /// Answers a held command: `allow` forwards it with the input that
+    /// followed, otherwise that input is dropped and the line cancelled.
+    /// Input after a confirmed command is checked again and may be held in
+    /// turn, which is returned as a new event.
+    pub fn confirm(
+        &mut self,
+        guard_id: &str,
+        allow: bool,
+    ) -> Result<Option<GuardedCommand>, String> {
+        let pending = match self.pending.take() {
+            Some(pending) if pending.id == guard_id => pending,
+            other => {
+                self.pending = other;
+                return Err(format!("No command awaiting confirmation: {}", guard_id));
+            }
+        };
+        if !allow {
+            self.clear_line();
+            self.released.push(InputCommand::Data(vec![CANCEL_LINE]));
+            return Ok(None);
+        }
+        let mut inputs = pending.input.into_iter();
+        if let Some(first) = inputs.next() {
+            let (forward, _) = self.check(first, true);
+            self.released.extend(forward);
+        }
+        for input in inputs {
+            let (forward, held) = self.filter(input);
+            self.released.extend(forward);
+            if held.is_some() {
+                return Ok(held);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Takes the input released by `confirm`
+    pub fn take_released(&mut self) -> Vec<InputCommand> {
+        std::mem::take(&mut self.released)
+    }
+
+    fn check(
+        &mut self,
+        input: InputCommand,
+        allowed: bool,
+    ) -> (Vec<InputCommand>, Option<GuardedCommand>) {
+        let data = match &input {
+            InputCommand::Data(data) => data,
+            InputCommand::Paste { data, .. } => {
+                let body = data.strip_prefix(BRACKETED_PASTE_START).unwrap_or(data);
+                let body = body.strip_suffix(BRACKETED_PASTE_END).unwrap_or(body);
+                // A held paste leaves the line as it was before it
+                let saved = (self.line.clone(), self.line_unknown);
+                let matched = if allowed {
+                    body.iter().for_each(|&b| {
+                        self.track(b);
+                    });
+                    None
+                } else {
+                    body.iter().find_map(|&b| self.track(b))
+                };
+                return match matched {
+                    Some((command, pattern)) => {
+                        (self.line, self.line_unknown) = saved;
+                        (Vec::new(), Some(self.hold(input, command, pattern)))
+                    }
+                    None => (vec![input], None),
+                };
+            }
+        };
+        for (i, &b) in data.iter().enumerate() {
+            if let Some((command, pattern)) = self.track(b).filter(|_| !allowed) {
+                let before = data[..i].to_vec();
+                let held = InputCommand::Data(data[i..].to_vec());
+                let forward = if before.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![InputCommand::Data(before)]
+                };
+                return (forward, Some(self.hold(held, command, pattern)));
+            }
+        }
+        (vec![input], None)
+    }
+
+    fn hold(&mut self, input: InputCommand, command: String, pattern: String) -> GuardedCommand {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.pending = Some(Pending {
+            id: id.clone(),
+            input: vec![input],
+        });
+        GuardedCommand {
+            session_id: self.session_id.clone(),
+            guard_id: id,
+            command,
+            pattern,
+        }
+    }
+
+    /// Updates the current line with one input byte. On Enter, returns
+    /// the submitted line and the pattern it matched, if any.
+    fn track(&mut self, b: u8) -> Option<(String, String)> {
+        match b {
+            b'\r' | b'\n' => {
+                let line = String::from_utf8_lossy(&self.line).into_owned();
+                let checked = !self.line_unknown;
+                self.clear_line();
+                if !checked {
+                    return None;
+                }
+                let pattern = self.patterns.iter().find(|p| p.is_match(&line))?;
+                Some((line, pattern.as_str().to_string()))
+            }
+            // Backspace and Delete
+            0x08 | 0x7f => {
+                // Drop a whole UTF-8 character
+                while let Some(last) = self.line.pop() {
+                    if last & 0xc0 != 0x80 {
+                        break;
+                    }
+                }
+                None
+            }
+            // Ctrl+C and Ctrl+U start the line over
+            0x03 | 0x15 => {
+                self.clear_line();
+                None
+            }
+            // Escape sequences (cursor keys, history) and other control
+            // keys (completion, Ctrl+A...) edit the line in ways that are
+            // not visible here
+            0x00..=0x1f => {
+                self.line_unknown = true;
+                None
+            }
+            _ => {
+                if self.line.len() < MAX_LINE_LEN {
+                    self.line.push(b);
+                } else {
+                    self.line_unknown = true;
+                }
+                None
+            }
+        }
+    }
+
+    fn clear_line(&mut self) {
+        self.line.clear();
+        self.line_unknown = false;
+    }
+}
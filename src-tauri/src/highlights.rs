@@ -0,0 +1,232 @@
+use crate::db::HighlightRule;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Spans reported per chunk at most
+const MAX_SPANS: usize = 512;
+
+/// Longest unterminated line kept for counting, as for triggers
+const MAX_LINE_LEN: usize = 4096;
+
+/// A highlighted range of an output chunk
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightSpan {
+    /// Start offset in UTF-16 code units of the chunk's `output`
+    pub start: usize,
+    /// End offset (exclusive), in UTF-16 code units
+    pub end: usize,
+    pub tag: String,
+    pub rule_id: String,
+}
+
+/// Lines matched by a counting rule since the session started or the
+/// counters were reset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchCounter {
+    pub rule_id: String,
+    pub name: String,
+    pub tag: String,
+    pub count: u64,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    id: String,
+    name: String,
+    tag: String,
+    regex: Regex,
+    count: bool,
+}
+
+/// Visible text of a chunk with escape sequences removed, and where each
+/// run of it starts in the original
+struct VisibleText {
+    text: String,
+    /// (byte offset in `text`, UTF-16 offset in the original)
+    runs: Vec<(usize, usize)>,
+}
+
+impl VisibleText {
+    fn new(s: &str) -> Self {
+        let mut text = String::with_capacity(s.len());
+        let mut runs = Vec::new();
+        let mut utf16 = 0;
+        let mut in_run = false;
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                if !in_run {
+                    runs.push((text.len(), utf16));
+                    in_run = true;
+                }
+                text.push(c);
+                utf16 += c.len_utf16();
+                continue;
+            }
+            in_run = false;
+            utf16 += 1;
+            match chars.next() {
+                Some('[') => {
+                    utf16 += 1;
+                    for c in chars.by_ref() {
+                        utf16 += c.len_utf16();
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    utf16 += 1;
+                    while let Some(c) = chars.next() {
+                        utf16 += c.len_utf16();
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            utf16 += 1;
+                            break;
+                        }
+                    }
+                }
+                Some(c) => utf16 += c.len_utf16(),
+                None => {}
+            }
+        }
+        Self { text, runs }
+    }
+
+    /// Maps a byte offset of the visible text to a UTF-16 offset of the
+    /// original. An end offset stays in the run it closes.
+    fn original(&self, pos: usize, is_end: bool) -> usize {
+        let idx = self
+            .runs
+            .partition_point(|&(start, _)| if is_end { start < pos } else { start <= pos });
+        let (start, utf16) = self.runs[idx.saturating_sub(1)];
+        utf16 + self.text[start..pos].encode_utf16().count()
+    }
+}
+
+/// Evaluates the session's highlight rules against output.
+///
+/// Every chunk is matched on its visible text and gets the matching ranges
+/// as spans. Counting rules additionally count the complete lines they
+/// match, once per rule and line.
+#[derive(Debug, Default)]
+pub struct HighlightEngine {
+    rules: Vec<CompiledRule>,
+    counts: HashMap<String, u64>,
+    line: String,
+}
+
+impl HighlightEngine {
+    /// Builds an engine from stored rules. Disabled rules and rules whose
+    /// pattern no longer compiles are skipped.
+    pub fn new(rules: Vec<HighlightRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| {
+                Some(CompiledRule {
+                    regex: Regex::new(&r.pattern).ok()?,
+                    id: r.id,
+                    name: r.name,
+                    tag: r.tag,
+                    count: r.count,
+                })
+            })
+            .collect();
+        Self {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the rules, keeping the counts of rules that still exist
+    pub fn replace_rules(&mut self, rules: Vec<HighlightRule>) {
+        let mut counts = std::mem::take(&mut self.counts);
+        *self = Self::new(rules);
+        counts.retain(|id, _| self.rules.iter().any(|r| &r.id == id));
+        self.counts = counts;
+    }
+
+    /// Feeds decoded output. Returns its highlight spans unless `spans` is
+    /// false, in which case only the counters are updated.
+    pub fn feed(&mut self, text: &str, spans: bool) -> Vec<HighlightSpan> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let visible = VisibleText::new(text);
+        self.count_lines(&visible.text);
+        if !spans {
+            return Vec::new();
+        }
+
+        let mut result = Vec::new();
+        for rule in &self.rules {
+            for m in rule.regex.find_iter(&visible.text) {
+                if result.len() >= MAX_SPANS {
+                    break;
+                }
+                if m.is_empty() {
+                    continue;
+                }
+                result.push(HighlightSpan {
+                    start: visible.original(m.start(), false),
+                    end: visible.original(m.end(), true),
+                    tag: rule.tag.clone(),
+                    rule_id: rule.id.clone(),
+                });
+            }
+        }
+        result.sort_by_key(|s| s.start);
+        result
+    }
+
+    fn count_lines(&mut self, text: &str) {
+        if !self.rules.iter().any(|r| r.count) {
+            return;
+        }
+        let mut rest = text;
+        while let Some(pos) = rest.find('\n') {
+            self.push(&rest[..pos]);
+            let line = std::mem::take(&mut self.line);
+            for rule in self.rules.iter().filter(|r| r.count) {
+                if rule.regex.is_match(&line) {
+                    *self.counts.entry(rule.id.clone()).or_insert(0) += 1;
+                }
+            }
+            rest = &rest[pos + 1..];
+        }
+        self.push(rest);
+    }
+
+    fn push(&mut self, s: &str) {
+        let s = s.trim_end_matches('\r');
+        if self.line.len() + s.len() <= MAX_LINE_LEN {
+            self.line.push_str(s);
+        }
+    }
+
+    /// Counters of the counting rules, in rule order
+    pub fn counters(&self) -> Vec<MatchCounter> {
+        self.rules
+            .iter()
+            .filter(|r| r.count)
+            .map(|r| MatchCounter {
+                rule_id: r.id.clone(),
+                name: r.name.clone(),
+                tag: r.tag.clone(),
+                count: self.counts.get(&r.id).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.counts.clear();
+    }
+}
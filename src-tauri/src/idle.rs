@@ -0,0 +1,124 @@
+use crate::db::SessionEnvironment;
+use crate::settings::SecuritySettings;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What happens to a session left without input for its idle timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Input is refused until the session is unlocked
+    Lock,
+    Disconnect,
+}
+
+impl IdleAction {
+    /// Unknown values lock, which keeps the session recoverable
+    fn parse(s: &str) -> Self {
+        match s {
+            "disconnect" => IdleAction::Disconnect,
+            _ => IdleAction::Lock,
+        }
+    }
+}
+
+/// Effective idle timeout of a session
+#[derive(Debug, Clone, Copy)]
+pub struct IdlePolicy {
+    pub timeout: Duration,
+    /// How long before the timeout the warning is emitted
+    pub warning: Duration,
+    pub action: IdleAction,
+}
+
+/// Payload of the `ssh-idle-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleEvent {
+    pub session_id: String,
+    /// "warning", "active" (input arrived after a warning), "locked",
+    /// "unlocked" or "disconnected"
+    pub state: String,
+    /// Seconds left before the timeout; 0 once it has passed
+    pub remaining_secs: u64,
+}
+
+/// Input activity and lock state of one session
+pub struct IdleGuard {
+    /// Overrides from the saved session; the rest comes from the settings
+    timeout_minutes: Option<u32>,
+    action: String,
+    last_input: Mutex<Instant>,
+    locked: AtomicBool,
+}
+
+impl IdleGuard {
+    pub fn new(environment: &SessionEnvironment) -> Self {
+        Self {
+            timeout_minutes: environment.idle_timeout_minutes,
+            action: environment.idle_action.clone(),
+            last_input: Mutex::new(Instant::now()),
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    /// Policy under the current settings; `None` when the timeout is off
+    pub fn policy(&self, settings: &SecuritySettings) -> Option<IdlePolicy> {
+        let minutes = self
+            .timeout_minutes
+            .unwrap_or(settings.idle_timeout_minutes);
+        if minutes == 0 {
+            return None;
+        }
+        let action = if self.action.is_empty() {
+            &settings.idle_action
+        } else {
+            &self.action
+        };
+        Some(IdlePolicy {
+            timeout: Duration::from_secs(u64::from(minutes) * 60),
+            warning: Duration::from_secs(u64::from(settings.idle_warning_seconds)),
+            action: IdleAction::parse(action),
+        })
+    }
+
+    /// Records user input; returns false when the session is locked and
+    /// the input must be dropped
+    pub fn on_input(&self) -> bool {
+        if self.is_locked() {
+            return false;
+        }
+        if let Ok(mut last) = self.last_input.lock() {
+            *last = Instant::now();
+        }
+        true
+    }
+
+    /// Time since the last user input
+    pub fn idle_for(&self) -> Duration {
+        self.last_input
+            .lock()
+            .map(|last| last.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    pub fn lock(&self) {
+        self.locked.store(true, Ordering::SeqCst);
+    }
+
+    /// Unlocks the session and restarts the idle clock; returns false if
+    /// it was not locked
+    pub fn unlock(&self) -> bool {
+        let was_locked = self.locked.swap(false, Ordering::SeqCst);
+        if let Ok(mut last) = self.last_input.lock() {
+            *last = Instant::now();
+        }
+        was_locked
+    }
+}
@@ -0,0 +1,445 @@
+use base64::{engine::general_purpose, Engine as _};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Largest encoded image kept; bigger ones are skipped
+const MAX_IMAGE_PAYLOAD: usize = 8 * 1024 * 1024;
+
+/// Largest decoded Sixel image, in pixels per side
+const MAX_SIXEL_SIZE: usize = 4096;
+
+/// Prefix of an iTerm2 file transfer (`ESC ] 1337 ; File=args : data BEL`)
+const ITERM_PREFIX: &str = "1337;File=";
+
+/// Payload of the `ssh-image-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineImage {
+    pub session_id: String,
+    /// Sequence number of the output chunk containing the image; it goes
+    /// at the cursor position reached after that chunk
+    pub seq: u64,
+    /// "iterm2" or "sixel"
+    pub protocol: String,
+    /// "image/png", "image/jpeg", "image/gif", "image/webp" or "image/bmp"
+    pub mime: String,
+    /// Base64-encoded image file. Sixel graphics are converted to PNG.
+    pub data: String,
+    pub name: Option<String>,
+    /// Size in pixels, when the format makes it cheap to read
+    pub pixel_width: Option<u32>,
+    pub pixel_height: Option<u32>,
+    /// Requested width as sent by iTerm2 tools: "N" cells, "Npx", "N%" or
+    /// "auto"
+    pub width: Option<String>,
+    pub height: Option<String>,
+    pub preserve_aspect_ratio: bool,
+}
+
+/// An image sequence cut out of the output, not yet decoded
+#[derive(Debug)]
+pub enum ImageSequence {
+    /// Arguments and base64 file content of an OSC 1337 transfer
+    Iterm { args: String, data: String },
+    /// Sixel data following the DCS introducer
+    Sixel(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    /// Inside an OSC, deciding whether it is an iTerm2 file
+    OscPrefix,
+    Iterm,
+    /// Inside a DCS, reading parameters up to the final character
+    DcsParams,
+    Sixel,
+    /// `ESC` inside an image payload, expecting `\`
+    PayloadEscape,
+}
+
+/// Incremental scanner cutting iTerm2 and Sixel images out of terminal
+/// output. Like `OscScanner` it keeps state across chunks and leaves the
+/// output untouched.
+#[derive(Debug, Default)]
+pub struct ImageScanner {
+    state: ScanState,
+    /// State the payload escape returns to
+    payload: ScanState,
+    buf: String,
+    overflow: bool,
+}
+
+impl ImageScanner {
+    /// Scans `text` and returns every image sequence completed within it
+    pub fn feed(&mut self, text: &str) -> Vec<ImageSequence> {
+        let mut found = Vec::new();
+        if self.state == ScanState::Ground && !text.contains('\x1b') {
+            return found;
+        }
+
+        for c in text.chars() {
+            match self.state {
+                ScanState::Ground => {
+                    if c == '\x1b' {
+                        self.state = ScanState::Escape;
+                    }
+                }
+                ScanState::Escape => self.start(c),
+                ScanState::OscPrefix => {
+                    self.buf.push(c);
+                    if self.buf == ITERM_PREFIX {
+                        self.buf.clear();
+                        self.state = ScanState::Iterm;
+                    } else if !ITERM_PREFIX.starts_with(&self.buf) {
+                        self.state = ScanState::Ground;
+                    }
+                }
+                ScanState::DcsParams => match c {
+                    '0'..='9' | ';' => {}
+                    'q' => self.state = ScanState::Sixel,
+                    _ => self.state = ScanState::Ground,
+                },
+                ScanState::Iterm | ScanState::Sixel => match c {
+                    '\x07' if self.state == ScanState::Iterm => self.finish(&mut found),
+                    '\x1b' => {
+                        self.payload = self.state;
+                        self.state = ScanState::PayloadEscape;
+                    }
+                    _ => self.push(c),
+                },
+                ScanState::PayloadEscape => {
+                    self.state = self.payload;
+                    if c == '\\' {
+                        self.finish(&mut found);
+                    } else {
+                        // Unterminated; whatever follows is a new sequence
+                        self.start(c);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Handles the character after an `ESC`
+    fn start(&mut self, c: char) {
+        self.buf.clear();
+        self.overflow = false;
+        self.state = match c {
+            ']' => ScanState::OscPrefix,
+            'P' => ScanState::DcsParams,
+            '\x1b' => ScanState::Escape,
+            _ => ScanState::Ground,
+        };
+    }
+
+    fn push(&mut self, c: char) {
+        if self.buf.len() + c.len_utf8() > MAX_IMAGE_PAYLOAD {
+            self.overflow = true;
+            self.buf.clear();
+        } else if !self.overflow {
+            self.buf.push(c);
+        }
+    }
+
+    fn finish(&mut self, found: &mut Vec<ImageSequence>) {
+        let kind = self.state;
+        self.state = ScanState::Ground;
+        if std::mem::take(&mut self.overflow) {
+            return;
+        }
+        let raw = std::mem::take(&mut self.buf);
+        match kind {
+            ScanState::Iterm => {
+                if let Some((args, data)) = raw.split_once(':') {
+                    found.push(ImageSequence::Iterm {
+                        args: args.to_string(),
+                        data: data.to_string(),
+                    });
+                }
+            }
+            ScanState::Sixel => found.push(ImageSequence::Sixel(raw)),
+            _ => {}
+        }
+    }
+}
+
+/// Decodes a scanned sequence into an event payload. Returns `None` for
+/// file downloads (`inline=0`), unknown formats and broken data.
+pub fn decode(sequence: ImageSequence, session_id: &str, seq: u64) -> Option<InlineImage> {
+    match sequence {
+        ImageSequence::Iterm { args, data } => decode_iterm(&args, &data, session_id, seq),
+        ImageSequence::Sixel(data) => {
+            let (png, width, height) = decode_sixel(&data)?;
+            Some(InlineImage {
+                session_id: session_id.to_string(),
+                seq,
+                protocol: "sixel".to_string(),
+                mime: "image/png".to_string(),
+                data: general_purpose::STANDARD.encode(png),
+                name: None,
+                pixel_width: Some(width),
+                pixel_height: Some(height),
+                width: None,
+                height: None,
+                preserve_aspect_ratio: true,
+            })
+        }
+    }
+}
+
+fn decode_iterm(args: &str, data: &str, session_id: &str, seq: u64) -> Option<InlineImage> {
+    let mut image = InlineImage {
+        session_id: session_id.to_string(),
+        seq,
+        protocol: "iterm2".to_string(),
+        mime: String::new(),
+        data: String::new(),
+        name: None,
+        pixel_width: None,
+        pixel_height: None,
+        width: None,
+        height: None,
+        preserve_aspect_ratio: true,
+    };
+    let mut inline = false;
+    for arg in args.split(';') {
+        let Some((key, value)) = arg.split_once('=') else {
+            continue;
+        };
+        match key {
+            "inline" => inline = value == "1",
+            "name" => {
+                image.name = general_purpose::STANDARD
+                    .decode(value)
+                    .ok()
+                    .map(|n| String::from_utf8_lossy(&n).into_owned());
+            }
+            "width" => image.width = Some(value.to_string()),
+            "height" => image.height = Some(value.to_string()),
+            "preserveAspectRatio" => image.preserve_aspect_ratio = value != "0",
+            _ => {}
+        }
+    }
+    if !inline {
+        return None;
+    }
+
+    // Tools wrap long base64 lines
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = general_purpose::STANDARD.decode(&data).ok()?;
+    let (mime, size) = sniff(&bytes)?;
+    image.mime = mime.to_string();
+    if let Some((width, height)) = size {
+        image.pixel_width = Some(width);
+        image.pixel_height = Some(height);
+    }
+    // Already base64; pass the file on as it came
+    image.data = data;
+    Some(image)
+}
+
+/// Image type the webview can display, and the size when the header has it
+fn sniff(bytes: &[u8]) -> Option<(&'static str, Option<(u32, u32)>)> {
+    let be32 = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let le16 = |at: usize| {
+        bytes
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+    };
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(("image/png", be32(16).zip(be32(20))))
+    } else if bytes.starts_with(b"GIF8") {
+        Some(("image/gif", le16(6).zip(le16(8))))
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(("image/jpeg", None))
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some(("image/webp", None))
+    } else if bytes.starts_with(b"BM") {
+        Some(("image/bmp", None))
+    } else {
+        None
+    }
+}
+
+/// The VT340 default palette, in RGB
+const SIXEL_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [51, 51, 204],
+    [204, 36, 36],
+    [51, 204, 51],
+    [204, 51, 204],
+    [51, 204, 204],
+    [204, 204, 51],
+    [120, 120, 120],
+    [69, 69, 69],
+    [87, 87, 153],
+    [153, 69, 69],
+    [87, 153, 87],
+    [153, 87, 153],
+    [87, 153, 153],
+    [153, 153, 87],
+    [204, 204, 204],
+];
+
+/// Converts a DEC HLS color (hue 0 = blue) to RGB
+fn hls_to_rgb(h: u32, l: u32, s: u32) -> [u8; 3] {
+    let h = ((h + 240) % 360) as f64 / 360.0;
+    let l = l.min(100) as f64 / 100.0;
+    let s = s.min(100) as f64 / 100.0;
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v];
+    }
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+/// Reads the number starting at `i`, returning it and the index after it
+fn number(bytes: &[u8], mut i: usize) -> (u32, usize) {
+    let mut n: u32 = 0;
+    while let Some(d) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+        n = n.saturating_mul(10).saturating_add((d - b'0') as u32);
+        i += 1;
+    }
+    (n, i)
+}
+
+/// Reads `;`-separated numbers starting at `i`
+fn numbers(bytes: &[u8], mut i: usize) -> (Vec<u32>, usize) {
+    let mut values = Vec::new();
+    loop {
+        let (n, next) = number(bytes, i);
+        values.push(n);
+        i = next;
+        if bytes.get(i) != Some(&b';') {
+            return (values, i);
+        }
+        i += 1;
+    }
+}
+
+/// Renders Sixel data into a PNG. Pixels never drawn stay transparent.
+fn decode_sixel(data: &str) -> Option<(Vec<u8>, u32, u32)> {
+    let bytes = data.as_bytes();
+    let mut palette: Vec<[u8; 3]> = SIXEL_PALETTE.to_vec();
+    palette.resize(256, [0, 0, 0]);
+    let mut color = 0usize;
+    let mut rows: Vec<Vec<[u8; 4]>> = Vec::new();
+    let (mut x, mut band) = (0usize, 0usize);
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        i += 1;
+        let mut repeat = 1;
+        let sixel = match b {
+            b'"' => {
+                // Raster attributes only announce the size
+                i = numbers(bytes, i).1;
+                continue;
+            }
+            b'#' => {
+                let (values, next) = numbers(bytes, i);
+                i = next;
+                color = values[0] as usize % palette.len();
+                if let [_, space, a, b, c] = values[..] {
+                    palette[color] = match space {
+                        1 => hls_to_rgb(a, b, c),
+                        _ => [a, b, c].map(|v| (v.min(100) * 255 / 100) as u8),
+                    };
+                }
+                continue;
+            }
+            b'$' => {
+                x = 0;
+                continue;
+            }
+            b'-' => {
+                x = 0;
+                band += 6;
+                continue;
+            }
+            b'!' => {
+                let (n, next) = number(bytes, i);
+                repeat = n.max(1) as usize;
+                i = next;
+                match bytes.get(i) {
+                    Some(&c @ b'?'..=b'~') => {
+                        i += 1;
+                        c - b'?'
+                    }
+                    _ => continue,
+                }
+            }
+            b'?'..=b'~' => b - b'?',
+            _ => continue,
+        };
+
+        if x + repeat > MAX_SIXEL_SIZE || band + 6 > MAX_SIXEL_SIZE {
+            return None;
+        }
+        let [r, g, b] = palette[color];
+        for bit in 0..6 {
+            if sixel & (1 << bit) == 0 {
+                continue;
+            }
+            let y = band + bit;
+            if rows.len() <= y {
+                rows.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut rows[y];
+            if row.len() < x + repeat {
+                row.resize(x + repeat, [0, 0, 0, 0]);
+            }
+            row[x..x + repeat].fill([r, g, b, 255]);
+        }
+        x += repeat;
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let height = rows.len();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for mut row in rows {
+        row.resize(width, [0, 0, 0, 0]);
+        pixels.extend(row.into_iter().flatten());
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .ok()?;
+    Some((png, width as u32, height as u32))
+}
@@ -0,0 +1,37 @@
+use crate::encryption::SensitiveData;
+
+/// Service name under which session credentials are stored in the OS
+/// keychain (macOS Keychain, Windows Credential Manager, Secret Service)
+const SERVICE: &str = "NexaShell";
+
+fn entry(session_id: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, session_id).map_err(|e| e.to_string())
+}
+
+/// Reads a session's credentials from the keychain; `None` when no entry
+/// exists
+pub fn read(session_id: &str) -> Result<Option<SensitiveData>, String> {
+    match entry(session_id)?.get_password() {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Keychain entry is unreadable: {}", e)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Keychain entry is unreadable: {}", e)),
+    }
+}
+
+/// Stores a session's credentials in the keychain, replacing any entry
+pub fn write(session_id: &str, data: &SensitiveData) -> Result<(), String> {
+    let json = serde_json::to_string(data).map_err(|e| e.to_string())?;
+    entry(session_id)?
+        .set_password(&json)
+        .map_err(|e| format!("Failed to write keychain entry: {}", e))
+}
+
+/// Removes a session's keychain entry; a missing entry is not an error
+pub fn delete(session_id: &str) -> Result<(), String> {
+    match entry(session_id)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete keychain entry: {}", e)),
+    }
+}
@@ -1,30 +1,107 @@
+mod alerts;
+mod archive;
+mod aws;
+mod backup;
+mod biometric;
+mod capabilities;
+mod certificate;
+mod cloud;
+mod config_history;
 mod db;
+mod db_backup;
+mod deeplink;
+mod diagnostics;
+mod dialogs;
+mod dirsync;
+mod disk_usage;
+mod editor;
+mod encoding;
 mod encryption;
+mod error_bus;
+mod events;
+mod group_connect;
+mod group_run;
+mod guard;
+mod highlights;
+mod idle;
+mod images;
+mod keychain;
+mod lines;
+mod links;
+mod logging;
+mod metrics;
+mod multiplexer;
+mod multiwindow;
+mod network;
+mod notifications;
+mod osc;
+mod output_index;
+mod plugins;
+mod ports;
+mod quickconnect;
+mod scheduler;
+mod settings;
+mod sftp;
+mod share;
+mod shell_integration;
+mod shutdown;
 mod ssh;
+mod sudo;
+mod suspend;
+mod sync;
 mod system;
 mod terminal;
+mod transfer;
+mod transport;
+mod tray;
+mod triggers;
+mod tunnel;
+mod watch;
+mod workflow;
 
 use ssh::SshManager;
 use tauri::Manager;
 use terminal::TerminalManager;
+use transfer::TransferManager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+    // Must be the first plugin; a second launch forwards its deep links to
+    // the running instance
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    let builder = builder
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            deeplink::on_second_instance(app);
+        }))
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build());
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(SshManager::default())
         .manage(TerminalManager::default())
+        .manage(TransferManager::default())
         .setup(|app| {
             // Initialize database before app is fully started. This ensures
             // schema and indexes exist even if the DB file was absent.
-            match db::init_db() {
-                Ok(v) => {
-                    // log to stdout for visibility during startup
-                    println!("db init: {}", v);
-                }
-                Err(e) => {
-                    eprintln!("db init error: {}", e);
-                }
+            let db_init = db::init_db();
+            // After the database so the log level setting can be read
+            logging::init();
+            match db_init {
+                Ok(v) => tracing::info!("db init: {}", v),
+                Err(e) => tracing::error!("db init error: {}", e),
+            }
+            error_bus::init(app.handle());
+            scheduler::start_scheduler(app.handle().clone());
+            db_backup::start_scheduler();
+            plugins::start_plugins(app.handle().clone());
+            deeplink::init(app.handle());
+            quickconnect::init(app.handle());
+            if let Err(e) = tray::init(app.handle()) {
+                tracing::error!("tray init error: {}", e);
             }
             #[cfg(target_os = "macos")]
             {
@@ -79,16 +156,110 @@ pub fn run() {
             system::close_window,
             system::read_file_preview,
             system::get_file_size,
+            system::hash_local_file,
+            system::write_clipboard,
+            system::list_local_dir,
+            system::create_local_dir,
+            system::rename_local,
+            system::delete_local_to_trash,
+            system::open_in_file_manager,
+            dialogs::pick_private_key,
+            dialogs::pick_export_destination,
+            dialogs::pick_upload_sources,
             ssh::connect_ssh,
+            ssh::cancel_connect,
+            ssh::get_session_info,
+            ssh::get_connection_trace,
+            certificate::inspect_certificate,
+            certificate::validate_private_key,
             ssh::disconnect_ssh,
             ssh::send_ssh_input,
+            ssh::paste_text,
+            ssh::send_ssh_bytes,
+            ssh::set_raw_mode,
+            ssh::set_session_readonly,
+            ssh::set_sudo_autofill,
+            ssh::set_session_encoding,
+            ssh::unlock_ssh_session,
+            ssh::confirm_guarded_command,
+            ssh::reload_triggers,
+            ssh::reload_highlight_rules,
+            ssh::reload_alert_rules,
+            ssh::get_match_counters,
+            ssh::reset_match_counters,
+            multiplexer::list_multiplexer_sessions,
+            multiplexer::attach_multiplexer,
+            group_connect::connect_group,
+            group_run::run_on_group,
+            group_run::cancel_group_run,
+            db::add_workflow,
+            db::list_workflows,
+            db::edit_workflow,
+            db::delete_workflow,
+            workflow::run_workflow,
+            workflow::answer_workflow_prompt,
+            workflow::cancel_workflow,
+            db::add_scheduled_task,
+            db::list_scheduled_tasks,
+            db::edit_scheduled_task,
+            db::set_scheduled_task_enabled,
+            db::delete_scheduled_task,
+            db::list_scheduled_runs,
+            scheduler::run_scheduled_task,
+            plugins::list_plugins,
+            plugins::reload_plugins,
+            plugins::get_plugins_dir,
+            plugins::invoke_plugin_command,
+            plugins::list_plugin_session_actions,
+            plugins::run_plugin_session_action,
+            deeplink::take_pending_deep_links,
+            deeplink::open_deep_link,
+            quickconnect::show_quick_connect,
+            quickconnect::hide_quick_connect,
+            quickconnect::quick_connect_open,
+            multiwindow::open_terminal_window,
+            multiwindow::move_session_to_window,
+            multiwindow::get_session_window,
+            ssh::notify_when_done,
             ssh::get_ssh_output,
             ssh::get_buffered_ssh_output,
+            ssh::get_session_banner,
+            capabilities::get_session_capabilities,
             ssh::upload_file_sftp,
+            ssh::download_file_sftp,
+            sftp::sftp_list_dir,
+            sftp::sftp_readlink,
+            sftp::sftp_symlink,
+            sftp::sftp_chmod,
+            sftp::sftp_chown,
+            sftp::paste_clipboard_as_remote_file,
+            disk_usage::analyze_remote_path,
+            disk_usage::cancel_remote_analysis,
+            archive::compress_remote,
+            archive::extract_remote,
+            archive::cancel_remote_archive,
+            dirsync::sync_directory,
+            dirsync::cancel_sync_directory,
+            watch::watch_and_upload,
+            watch::stop_watch_and_upload,
+            watch::list_watch_uploads,
+            editor::open_remote_in_editor,
+            editor::list_remote_edits,
+            transfer::list_transfers,
+            transfer::reorder_transfer,
+            transfer::set_transfer_priority,
+            transfer::cancel_transfer,
+            transfer::set_transfer_concurrency,
             ssh::probe_remote_path,
+            ssh::get_remote_cwd,
             ssh::set_ssh_status_refresh_rate,
+            ssh::suspend_session,
+            ssh::resume_session,
+            ssh::get_all_session_statuses,
+            ssh::get_session_stats,
             terminal::connect_local,
             terminal::disconnect_local,
+            output_index::search_output,
             db::init_db,
             db::add_session,
             db::save_session,
@@ -106,7 +277,39 @@ pub fn run() {
             db::link_session_tag,
             db::unlink_session_tag,
             db::list_tags_for_session,
+            db::add_assignment_rule,
+            db::list_assignment_rules,
+            db::edit_assignment_rule,
+            db::delete_assignment_rule,
             db::get_sessions,
+            db::count_sessions,
+            db::get_sessions_detailed,
+            db::save_session_secret,
+            db::get_session_secret,
+            db::list_session_secrets,
+            db::delete_session_secret,
+            db::verify_credentials,
+            db::migrate_credentials,
+            biometric::get_biometric_options,
+            biometric::set_biometric_options,
+            biometric::set_session_protected,
+            biometric::set_master_password,
+            db::get_session_protected,
+            settings::get_settings,
+            settings::set_setting,
+            db_backup::backup_db_now,
+            db_backup::list_db_backups,
+            db_backup::restore_db,
+            sync::get_sync_status,
+            sync::set_sync_secrets,
+            sync::sync_now,
+            config_history::config_history,
+            config_history::config_rollback,
+            share::share_sessions,
+            share::import_shared_bundle,
+            cloud::list_cloud_providers,
+            cloud::import_cloud_hosts,
+            cloud::refresh_cloud_hosts,
             db::edit_group,
             db::delete_group,
             db::edit_tag,
@@ -116,13 +319,73 @@ pub fn run() {
             db::toggle_favorite,
             db::export_sessions,
             db::import_sessions,
+            db::add_trigger,
+            db::list_triggers,
+            db::edit_trigger,
+            db::delete_trigger,
+            db::add_highlight_rule,
+            db::list_highlight_rules,
+            db::edit_highlight_rule,
+            db::delete_highlight_rule,
+            db::add_alert_rule,
+            db::list_alert_rules,
+            db::edit_alert_rule,
+            db::delete_alert_rule,
+            db::list_alerts,
+            db::acknowledge_alerts,
+            db::clear_alerts,
+            db::get_metrics_history,
+            db::set_open_tabs,
+            db::get_restorable_sessions,
+            network::wake_host,
+            network::probe_host,
+            network::check_all_sessions,
+            ports::scan_ports,
+            ports::list_remote_listeners,
+            tunnel::open_tunnel_to_service,
+            tunnel::close_tunnel,
+            tunnel::list_tunnels,
+            shutdown::get_quit_check,
+            logging::get_log_path,
+            logging::set_log_level,
+            logging::get_recent_errors,
+            events::get_event_schema,
+            db::get_knock_sequence,
+            db::set_knock_sequence,
+            db::get_advanced_options,
+            db::set_advanced_options,
+            backup::list_backup_snapshots,
+            backup::read_backup_snapshot,
+            backup::diff_backup_snapshots,
+            db::suggest_sessions,
+            db::get_usage_stats,
+            db::list_connection_history,
+            db::record_clipboard,
+            db::list_clipboard_history,
+            db::clear_clipboard_history,
+            db::list_recent_remote_paths,
+            db::get_clipboard_access,
+            db::set_clipboard_access,
+            db::get_session_environment,
+            db::set_session_environment,
+            db::set_session_appearance,
+            db::set_group_appearance,
+            db::set_session_template,
+            db::list_session_templates,
+            db::duplicate_session,
+            db::bulk_edit_sessions,
+            db::bulk_delete_sessions,
+            db::reorder_sessions,
+            db::find_duplicate_sessions,
+            db::merge_sessions,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
-            if let tauri::RunEvent::ExitRequested { .. } = event {
-                let manager = app_handle.state::<SshManager>();
-                manager.disconnect_all();
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if shutdown::on_exit_requested(app_handle) {
+                    api.prevent_exit();
+                }
             }
         });
 }
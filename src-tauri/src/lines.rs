@@ -0,0 +1,151 @@
+/// Longest logical line kept. Output past it is dropped until the next
+/// line break, so a stream without newlines cannot grow the buffer.
+const MAX_LINE_CHARS: usize = 4096;
+
+/// Longest CSI parameter string kept; longer ones are not valid anyway
+const MAX_CSI_PARAMS: usize = 32;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    /// After ESC
+    Escape,
+    /// After ESC and an intermediate byte, e.g. `ESC ( B`; the next
+    /// character ends the sequence
+    EscapeIntermediate,
+    /// Inside `ESC [`, collecting parameters up to the final byte
+    Csi,
+    /// Inside an OSC, DCS, APC, PM or SOS string, up to BEL or ST
+    String,
+    /// ESC inside a string: ST when followed by `\`
+    StringEscape,
+}
+
+/// Rebuilds the logical lines of a terminal stream as they appear on
+/// screen: without escape sequences, with carriage returns and backspaces
+/// overwriting what they move back over, and with erase-in-line applied.
+/// Sequences and lines split across chunks are carried over to the next
+/// `feed`.
+///
+/// Triggers and the output search index work on these lines; the chunks
+/// sent for rendering are left untouched.
+#[derive(Debug, Default)]
+pub struct LineAssembler {
+    state: State,
+    line: Vec<char>,
+    /// Cursor column within `line`
+    col: usize,
+    params: String,
+}
+
+impl LineAssembler {
+    /// Feeds decoded output and returns the lines it completed, without
+    /// trailing whitespace
+    pub fn feed(&mut self, text: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        for c in text.chars() {
+            self.step(c, &mut lines);
+        }
+        lines
+    }
+
+    /// The current unterminated line, e.g. a prompt waiting for input
+    pub fn partial(&self) -> String {
+        let line: String = self.line.iter().collect();
+        line.trim_end().to_string()
+    }
+
+    fn step(&mut self, c: char, lines: &mut Vec<String>) {
+        match self.state {
+            State::Ground => match c {
+                '\x1b' => self.state = State::Escape,
+                '\n' => {
+                    lines.push(self.partial());
+                    self.line.clear();
+                    self.col = 0;
+                }
+                '\r' => self.col = 0,
+                '\x08' => self.col = self.col.saturating_sub(1),
+                '\t' => self.put('\t'),
+                c if c.is_control() => {}
+                c => self.put(c),
+            },
+            State::Escape => {
+                self.state = match c {
+                    '[' => {
+                        self.params.clear();
+                        State::Csi
+                    }
+                    ']' | 'P' | '_' | '^' | 'X' => State::String,
+                    ' '..='/' => State::EscapeIntermediate,
+                    _ => State::Ground,
+                }
+            }
+            State::EscapeIntermediate => self.state = State::Ground,
+            State::Csi => {
+                if ('\x40'..='\x7e').contains(&c) {
+                    self.state = State::Ground;
+                    self.csi(c);
+                } else if self.params.len() < MAX_CSI_PARAMS {
+                    self.params.push(c);
+                }
+            }
+            State::String => match c {
+                '\x07' => self.state = State::Ground,
+                '\x1b' => self.state = State::StringEscape,
+                _ => {}
+            },
+            State::StringEscape => {
+                // Any other ESC ends the string and starts a new sequence
+                self.state = State::Escape;
+                if c == '\\' {
+                    self.state = State::Ground;
+                } else {
+                    self.step(c, lines);
+                }
+            }
+        }
+    }
+
+    /// Writes a character at the cursor, padding with spaces when the
+    /// cursor was moved past the end of the line
+    fn put(&mut self, c: char) {
+        if self.col >= MAX_LINE_CHARS {
+            return;
+        }
+        if self.col < self.line.len() {
+            self.line[self.col] = c;
+        } else {
+            self.line.resize(self.col, ' ');
+            self.line.push(c);
+        }
+        self.col += 1;
+    }
+
+    /// Applies the CSI sequences that move within or erase the line;
+    /// anything else only affects rendering
+    fn csi(&mut self, fin: char) {
+        let n = self
+            .params
+            .split(';')
+            .next()
+            .and_then(|p| p.parse::<usize>().ok())
+            .unwrap_or(0);
+        match fin {
+            // Erase in line: to the end, to the start, or all of it
+            'K' => match n {
+                0 => self.line.truncate(self.col),
+                1 => {
+                    let end = (self.col + 1).min(self.line.len());
+                    self.line[..end].fill(' ');
+                }
+                _ => self.line.clear(),
+            },
+            'C' => self.col = (self.col + n.max(1)).min(MAX_LINE_CHARS),
+            'D' => self.col = self.col.saturating_sub(n.max(1)),
+            'G' => self.col = n.saturating_sub(1).min(MAX_LINE_CHARS),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Spans reported per chunk at most; the rest of a huge chunk stays plain
+const MAX_SPANS: usize = 256;
+
+/// URLs with a scheme the frontend can open
+static URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\b(?:https?|ftp|file)://[^\s<>"'`\x00-\x1f\x7f]+"#).expect("valid url regex")
+});
+
+/// Absolute or home-relative paths, optionally followed by `:line[:col]`
+/// as printed by compilers. The leading group stands in for a lookbehind.
+static PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?:^|[\s"'(\[=])(~?/[\w.@%+~-]+(?:/[\w.@%+~-]*)*(?::\d+(?::\d+)?)?)"#)
+        .expect("valid path regex")
+});
+
+/// A clickable range of an output chunk
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkSpan {
+    /// Start offset in UTF-16 code units of the chunk's `output`, as used
+    /// by JavaScript strings
+    pub start: usize,
+    /// End offset (exclusive), in UTF-16 code units
+    pub end: usize,
+    /// "url" or "path"
+    pub kind: String,
+    pub text: String,
+}
+
+/// Drops punctuation that ends a sentence rather than the URL, keeping a
+/// closing parenthesis that has a partner inside the URL
+fn trim_url(url: &str) -> &str {
+    let mut url = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+    while let Some(rest) = url.strip_suffix([')', ']', '}']) {
+        let open = match &url[rest.len()..] {
+            ")" => '(',
+            "]" => '[',
+            _ => '{',
+        };
+        if rest.contains(open) {
+            break;
+        }
+        url = rest.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+    }
+    url
+}
+
+/// Finds URLs and file paths in decoded output. Offsets refer to the text
+/// as given, escape sequences included. A link split across two chunks is
+/// not detected.
+pub fn detect(text: &str) -> Vec<LinkSpan> {
+    let mut ranges: Vec<(usize, usize, &'static str)> = URL
+        .find_iter(text)
+        .map(|m| (m.start(), m.start() + trim_url(m.as_str()).len(), "url"))
+        .filter(|(start, end, _)| end > start)
+        .take(MAX_SPANS)
+        .collect();
+
+    let urls = ranges.len();
+    for caps in PATH.captures_iter(text) {
+        if ranges.len() >= MAX_SPANS {
+            break;
+        }
+        let Some(m) = caps.get(1) else {
+            continue;
+        };
+        // Path parts of URLs are already covered
+        let inside_url = ranges[..urls]
+            .iter()
+            .any(|&(start, end, _)| m.start() < end && start < m.end());
+        if !inside_url {
+            let end = m.start() + m.as_str().trim_end_matches(['.', ',', ':']).len();
+            ranges.push((m.start(), end, "path"));
+        }
+    }
+    ranges.sort_by_key(|&(start, _, _)| start);
+
+    // Convert byte offsets in one pass
+    let mut spans = Vec::with_capacity(ranges.len());
+    let mut byte_pos = 0;
+    let mut utf16_pos = 0;
+    let mut to_utf16 = |byte: usize| {
+        utf16_pos += text[byte_pos..byte].encode_utf16().count();
+        byte_pos = byte;
+        utf16_pos
+    };
+    for (start, end, kind) in ranges {
+        let start16 = to_utf16(start);
+        let end16 = to_utf16(end);
+        spans.push(LinkSpan {
+            start: start16,
+            end: end16,
+            kind: kind.to_string(),
+            text: text[start..end].to_string(),
+        });
+    }
+    spans
+}
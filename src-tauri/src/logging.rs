@@ -0,0 +1,235 @@
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{command, AppHandle};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Layer, Registry};
+
+/// Log files kept; one is started per day
+const MAX_LOG_FILES: usize = 7;
+
+/// Warnings and errors kept for `get_recent_errors`
+const MAX_RECENT_ERRORS: usize = 200;
+
+/// Values following these words are masked in every log line, as a last
+/// line of defence; code must not log credentials in the first place
+static SECRET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(password|passphrase|passwd|secret|token|api[_-]?key|private[_-]?key)(["']?\s*[=:]\s*)("[^"]*"|'[^']*'|\S+)"#)
+        .expect("valid secret regex")
+});
+
+/// Changes the level at runtime
+static LEVEL: OnceCell<reload::Handle<LevelFilter, Registry>> = OnceCell::new();
+
+/// Flushes buffered lines to the log file when dropped
+static FILE_GUARD: Lazy<Mutex<Option<WorkerGuard>>> = Lazy::new(|| Mutex::new(None));
+
+static RECENT_ERRORS: Lazy<Mutex<VecDeque<LogEntry>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_RECENT_ERRORS)));
+
+/// A warning or error logged since the app started
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// Unix timestamp in milliseconds
+    pub time: i64,
+    /// "ERROR" or "WARN"
+    pub level: String,
+    /// Module that logged it, e.g. `nexashell_lib::ssh`
+    pub target: String,
+    pub message: String,
+}
+
+/// Masks credentials that slipped into a log line
+fn redact(text: &str) -> std::borrow::Cow<'_, str> {
+    SECRET.replace_all(text, "$1$2[redacted]")
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unknown log level: {}", level))
+}
+
+/// Directory the log files are written to
+fn log_dir() -> Result<PathBuf, String> {
+    Ok(crate::db::app_data_dir()?.join("logs"))
+}
+
+/// Writer that masks credentials before passing each line on
+struct Redacting<W>(W);
+
+impl<W: Write> Write for Redacting<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The formatter hands over one whole line per write
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct RedactingMakeWriter<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+/// Collects the message and fields of an event into one line
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            self.0.push_str(&format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Keeps the latest warnings and errors in memory
+struct RecentErrors;
+
+impl<S: Subscriber> Layer<S> for RecentErrors {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::WARN {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let entry = LogEntry {
+            time: chrono::Utc::now().timestamp_millis(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: redact(&visitor.0).into_owned(),
+        };
+        if let Ok(mut recent) = RECENT_ERRORS.lock() {
+            if recent.len() == MAX_RECENT_ERRORS {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+}
+
+/// Sets up logging to stderr and to daily log files in the app data
+/// directory, at the level of the `logging.level` setting. Without a
+/// usable log directory only stderr is written.
+pub fn init() {
+    let settings = crate::settings::current().logging;
+    let level = parse_level(&settings.level).unwrap_or(LevelFilter::INFO);
+    let (level_layer, handle) = reload::Layer::new(level);
+
+    let file_writer = log_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("nexashell")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(|e| e.to_string())
+    });
+    let file_layer = match file_writer {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            if let Ok(mut slot) = FILE_GUARD.lock() {
+                *slot = Some(guard);
+            }
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(RedactingMakeWriter(writer)),
+            )
+        }
+        Err(e) => {
+            eprintln!("Failed to open log directory: {}", e);
+            None
+        }
+    };
+
+    let result = tracing_subscriber::registry()
+        .with(level_layer)
+        .with(tracing_subscriber::fmt::layer().with_writer(RedactingMakeWriter(std::io::stderr)))
+        .with(file_layer)
+        .with(RecentErrors)
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = LEVEL.set(handle);
+        }
+        Err(e) => eprintln!("Failed to set up logging: {}", e),
+    }
+}
+
+/// Applies a new `logging.level` setting
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = parse_level(level)?;
+    if let Some(handle) = LEVEL.get() {
+        handle.reload(filter).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Writes out buffered log lines; later lines are no longer written to
+/// the file. Called when the app quits.
+pub fn flush() {
+    if let Ok(mut guard) = FILE_GUARD.lock() {
+        guard.take();
+    }
+}
+
+/// Returns the directory holding the log files.
+#[command]
+pub fn get_log_path() -> Result<String, String> {
+    Ok(log_dir()?.to_string_lossy().into_owned())
+}
+
+/// Changes the log level ("error", "warn", "info", "debug" or "trace")
+/// and stores it as the `logging.level` setting.
+#[command]
+pub fn set_log_level(app: AppHandle, level: String) -> Result<(), String> {
+    parse_level(&level)?;
+    crate::settings::set_setting(app, "logging.level".to_string(), level.into())?;
+    Ok(())
+}
+
+/// Returns the latest warnings and errors logged since the app started,
+/// newest first, for attaching to support requests.
+#[command]
+pub fn get_recent_errors(limit: Option<usize>) -> Vec<LogEntry> {
+    let Ok(recent) = RECENT_ERRORS.lock() else {
+        return Vec::new();
+    };
+    recent
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(MAX_RECENT_ERRORS))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,127 @@
+use crate::ssh::ServerStatus;
+use serde::Serialize;
+
+/// Length of one stored sample; readings within it are averaged
+pub const SAMPLE_INTERVAL_MS: i64 = 15_000;
+
+/// Samples kept per session, i.e. 24 hours of history
+pub const MAX_SAMPLES: i64 = 24 * 60 * 60 * 1000 / SAMPLE_INTERVAL_MS;
+
+/// Server status value recorded in the history and watched by alert rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cpu,
+    Memory,
+    Swap,
+    Disk,
+    /// One-minute load average
+    Load,
+    /// Bytes per second
+    NetUp,
+    NetDown,
+    /// Milliseconds
+    Latency,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 8] = [
+        Metric::Cpu,
+        Metric::Memory,
+        Metric::Swap,
+        Metric::Disk,
+        Metric::Load,
+        Metric::NetUp,
+        Metric::NetDown,
+        Metric::Latency,
+    ];
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "cpu" => Ok(Metric::Cpu),
+            "memory" => Ok(Metric::Memory),
+            "swap" => Ok(Metric::Swap),
+            "disk" => Ok(Metric::Disk),
+            "load" => Ok(Metric::Load),
+            "netUp" => Ok(Metric::NetUp),
+            "netDown" => Ok(Metric::NetDown),
+            "latency" => Ok(Metric::Latency),
+            other => Err(format!("Unknown metric: {}", other)),
+        }
+    }
+
+    /// Column of the `metrics_history` table holding this metric
+    pub fn column(self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::Memory => "memory",
+            Metric::Swap => "swap",
+            Metric::Disk => "disk",
+            Metric::Load => "load",
+            Metric::NetUp => "net_up",
+            Metric::NetDown => "net_down",
+            Metric::Latency => "latency",
+        }
+    }
+
+    pub fn value(self, status: &ServerStatus) -> f64 {
+        match self {
+            Metric::Cpu => status.cpu_usage,
+            Metric::Memory => status.mem_usage,
+            Metric::Swap => status.swap_usage,
+            Metric::Disk => status.disk_usage,
+            Metric::Load => status.load_avg[0],
+            Metric::NetUp => status.net_up,
+            Metric::NetDown => status.net_down,
+            Metric::Latency => status.latency as f64,
+        }
+    }
+}
+
+/// One point of a metric's history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricPoint {
+    /// Unix timestamp (ms) of the start of the sample
+    pub time: i64,
+    /// Average of the readings taken during the sample
+    pub value: f64,
+}
+
+/// Averaged readings of one sample interval, in `Metric::ALL` order
+#[derive(Debug, Clone)]
+pub struct MetricsSample {
+    /// Unix timestamp (ms) of the start of the interval
+    pub taken_at: i64,
+    pub values: [f64; Metric::ALL.len()],
+}
+
+/// Downsamples status readings into one sample per `SAMPLE_INTERVAL_MS`
+#[derive(Debug, Default)]
+pub struct MetricsRecorder {
+    /// Start of the interval being collected
+    bucket: i64,
+    sums: [f64; Metric::ALL.len()],
+    count: u32,
+}
+
+impl MetricsRecorder {
+    /// Adds a reading taken at `now` (Unix ms); returns the previous
+    /// interval's sample once a reading falls into a new interval
+    pub fn add(&mut self, status: &ServerStatus, now: i64) -> Option<MetricsSample> {
+        let bucket = now - now.rem_euclid(SAMPLE_INTERVAL_MS);
+        let finished = (bucket != self.bucket && self.count > 0).then(|| MetricsSample {
+            taken_at: self.bucket,
+            values: self.sums.map(|sum| sum / self.count as f64),
+        });
+        if bucket != self.bucket {
+            self.bucket = bucket;
+            self.sums = [0.0; Metric::ALL.len()];
+            self.count = 0;
+        }
+        for (sum, metric) in self.sums.iter_mut().zip(Metric::ALL) {
+            *sum += metric.value(status);
+        }
+        self.count += 1;
+        finished
+    }
+}
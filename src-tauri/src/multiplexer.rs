@@ -0,0 +1,186 @@
+use crate::ssh::{shell_quote, SessionId, SshError, SshManager};
+use schemars::JsonSchema;
+use serde::Serialize;
+use ssh2::Session;
+use std::io::Read;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// Time allowed for listing sessions on a connected server
+const LIST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for listing sessions on connect, which waits for it
+const CONNECT_LIST_TIMEOUT_MS: u32 = 3000;
+
+/// Lists tmux sessions, then screen sessions after a marker line
+const LIST_COMMAND: &str = "tmux list-sessions -F '#{session_attached} #{session_windows} #{session_activity} #{session_name}' 2>/dev/null; echo '--screen--'; screen -ls 2>/dev/null";
+
+/// A tmux or screen session found on the server
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplexerSession {
+    /// "tmux" or "screen"
+    pub multiplexer: String,
+    /// Session name; for screen the full `pid.name`
+    pub name: String,
+    /// Whether a client is attached elsewhere
+    pub attached: bool,
+    /// Number of windows (tmux only)
+    pub windows: Option<u32>,
+    /// Unix seconds of the last activity (tmux only)
+    pub last_activity: Option<u64>,
+}
+
+/// Payload of the `resume-available` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeAvailable {
+    pub session_id: String,
+    pub sessions: Vec<MultiplexerSession>,
+}
+
+/// What a connecting session does about existing multiplexer sessions
+#[derive(Debug, Clone)]
+pub enum ResumeAction {
+    None,
+    /// Type this command to attach
+    Attach(String),
+    /// Let the user pick one of these sessions
+    Offer(Vec<MultiplexerSession>),
+}
+
+/// Parses a tmux line; the name goes last as it may contain spaces
+fn parse_tmux(line: &str) -> Option<MultiplexerSession> {
+    let mut fields = line.splitn(4, ' ');
+    let attached = fields.next()?.parse::<u32>().ok()? > 0;
+    let windows = fields.next()?.parse().ok();
+    let last_activity = fields.next()?.parse().ok();
+    let name = fields.next().filter(|n| !n.is_empty())?;
+    Some(MultiplexerSession {
+        multiplexer: "tmux".to_string(),
+        name: name.to_string(),
+        attached,
+        windows,
+        last_activity,
+    })
+}
+
+/// Parses a `screen -ls` line such as `\t1234.work\t(Detached)`
+fn parse_screen(line: &str) -> Option<MultiplexerSession> {
+    if !line.starts_with('\t') {
+        return None;
+    }
+    let mut fields = line.split('\t').filter(|f| !f.is_empty());
+    let name = fields.next()?;
+    let (pid, _) = name.split_once('.')?;
+    if pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let state = fields.next_back().unwrap_or_default();
+    Some(MultiplexerSession {
+        multiplexer: "screen".to_string(),
+        name: name.to_string(),
+        attached: state.contains("Attached"),
+        windows: None,
+        last_activity: None,
+    })
+}
+
+/// Lists the tmux and screen sessions on the server via a short-lived exec
+/// channel. Missing tools simply contribute nothing, and a server that does
+/// not answer within `CONNECT_LIST_TIMEOUT_MS` delays the connection no further.
+///
+/// Must be called while the session is in blocking mode.
+pub fn list_sessions(sess: &Session) -> Vec<MultiplexerSession> {
+    let list = || -> Result<String, ssh2::Error> {
+        let mut channel = sess.channel_session()?;
+        channel.exec(LIST_COMMAND)?;
+        let mut output = String::new();
+        let _ = channel.read_to_string(&mut output);
+        let _ = channel.wait_close();
+        Ok(output)
+    };
+    let previous = sess.timeout();
+    sess.set_timeout(CONNECT_LIST_TIMEOUT_MS);
+    let output = list();
+    sess.set_timeout(previous);
+    output.map(|output| parse_list(&output)).unwrap_or_default()
+}
+
+/// Parses the output of `LIST_COMMAND`
+pub fn parse_list(output: &str) -> Vec<MultiplexerSession> {
+    let (tmux, screen) = output.split_once("--screen--").unwrap_or((output, ""));
+    tmux.lines()
+        .filter_map(parse_tmux)
+        .chain(screen.lines().filter_map(parse_screen))
+        .collect()
+}
+
+/// Command typed into the shell to attach to a session
+pub fn attach_command(multiplexer: &str, name: &str) -> Option<String> {
+    match multiplexer {
+        "tmux" => Some(format!("tmux attach-session -t {}\n", shell_quote(name))),
+        "screen" => Some(format!("screen -r {}\n", shell_quote(name))),
+        _ => None,
+    }
+}
+
+/// Decides what to do on connect. `mode` is the saved session's resume
+/// setting: "attach" resumes the most recently used detached session,
+/// "ask" offers every session, anything else does nothing.
+pub fn resume_action(sess: &Session, mode: &str) -> ResumeAction {
+    if mode != "attach" && mode != "ask" {
+        return ResumeAction::None;
+    }
+    let sessions = list_sessions(sess);
+    if sessions.is_empty() {
+        return ResumeAction::None;
+    }
+    if mode == "ask" {
+        return ResumeAction::Offer(sessions);
+    }
+    // screen reports no activity time, so detached tmux sessions come first
+    sessions
+        .iter()
+        .filter(|s| !s.attached)
+        .max_by_key(|s| s.last_activity.unwrap_or(0))
+        .and_then(|s| attach_command(&s.multiplexer, &s.name))
+        .map(ResumeAction::Attach)
+        .unwrap_or(ResumeAction::Offer(sessions))
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Lists the tmux and screen sessions on a connected server
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn list_multiplexer_sessions(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<Vec<MultiplexerSession>, SshError> {
+    let handle = state.session_handle(&SessionId::from(sessionId))?;
+    let output = tokio::task::spawn_blocking(move || {
+        handle.exec_interruptible(LIST_COMMAND, LIST_TIMEOUT, &AtomicBool::new(false))
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))??;
+    Ok(parse_list(&output.stdout))
+}
+
+/// Attaches the terminal to a tmux or screen session, e.g. one offered by
+/// `resume-available`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn attach_multiplexer(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    multiplexer: String,
+    name: String,
+) -> Result<(), SshError> {
+    let command = attach_command(&multiplexer, &name).ok_or_else(|| {
+        SshError::OperationFailed(format!("Unknown multiplexer: {}", multiplexer))
+    })?;
+    state.send_ssh_input(&SessionId::from(sessionId), command)
+}
@@ -0,0 +1,230 @@
+use crate::transport::{self, SessionId};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+/// Window sessions are routed to unless they are moved
+pub const MAIN_WINDOW: &str = "main";
+
+/// Prefix of the labels of additional terminal windows
+const TERMINAL_WINDOW_PREFIX: &str = "terminal-";
+
+/// Output kept per session for replay in the window it is moved to
+const REPLAY_BYTES: usize = 512 * 1024;
+
+struct RouteState<C> {
+    window: String,
+    replay: VecDeque<(C, usize)>,
+    replay_bytes: usize,
+}
+
+/// The window a live session's events are sent to, with its most recent
+/// output so another window can take the session over mid-stream
+pub struct SessionRoute<C>(Arc<Mutex<RouteState<C>>>);
+
+impl<C> Clone for SessionRoute<C> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<C: Clone + Serialize> SessionRoute<C> {
+    pub fn new(window: &str) -> Self {
+        Self(Arc::new(Mutex::new(RouteState {
+            window: window.to_string(),
+            replay: VecDeque::new(),
+            replay_bytes: 0,
+        })))
+    }
+
+    /// Label of the window the session's events go to
+    pub fn window(&self) -> String {
+        self.0
+            .lock()
+            .map(|s| s.window.clone())
+            .unwrap_or_else(|_| MAIN_WINDOW.to_string())
+    }
+
+    /// Emits an event of the session to its window
+    pub fn emit<S: Serialize + Clone>(&self, app: &AppHandle, event: &str, payload: S) {
+        if let Err(e) = app.emit_to(self.window().as_str(), event, payload) {
+            crate::error_bus::report("events", None, format!("failed to emit {}: {}", event, e));
+        }
+    }
+
+    /// Emits an output chunk to the session's window and keeps it for
+    /// replay. Both happen under the lock, so a chunk emitted during a move
+    /// is either replayed or delivered to the new window, never lost.
+    pub fn emit_output(&self, app: Option<&AppHandle>, event: &str, chunk: &C, size: usize) {
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        if let Some(app) = app {
+            if let Err(e) = app.emit_to(state.window.as_str(), event, chunk) {
+                crate::error_bus::report(
+                    "events",
+                    None,
+                    format!("failed to emit {}: {}", event, e),
+                );
+            }
+        }
+        state.replay.push_back((chunk.clone(), size));
+        state.replay_bytes += size;
+        while state.replay_bytes > REPLAY_BYTES {
+            match state.replay.pop_front() {
+                Some((_, size)) => state.replay_bytes -= size,
+                None => break,
+            }
+        }
+    }
+
+    /// Sends the session's events to `window` from now on and returns the
+    /// kept output, oldest first
+    pub fn move_to(&self, window: &str) -> Vec<C> {
+        let Ok(mut state) = self.0.lock() else {
+            return Vec::new();
+        };
+        state.window = window.to_string();
+        state.replay.iter().map(|(c, _)| c.clone()).collect()
+    }
+}
+
+/// Payload of the `session-moved` event: the session's tab belongs to
+/// `window` now and should be closed elsewhere
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMoved {
+    pub session_id: String,
+    pub window: String,
+}
+
+/// Result of `move_session_to_window`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHandoff {
+    pub session_id: String,
+    /// "ssh" or "local"
+    pub kind: String,
+    /// Recent output chunks to write before live output, oldest first.
+    /// Chunks carry sequence numbers, so ones that also arrived live can be
+    /// skipped.
+    pub replay: Vec<Value>,
+}
+
+/// Routes a session to `window`, returning its kind and kept output
+fn move_session(app: &AppHandle, session_id: &str, window: &str) -> Result<SessionHandoff, String> {
+    let Some((manager, route)) =
+        transport::find_session(app, &SessionId::from(session_id.to_string()))
+    else {
+        return Err(format!("Session not found: {}", session_id));
+    };
+    let kind = manager.kind();
+    let replay = match serde_json::to_value(route.move_to(window)).map_err(|e| e.to_string())? {
+        Value::Array(chunks) => chunks,
+        _ => Vec::new(),
+    };
+    let _ = app.emit(
+        "session-moved",
+        &SessionMoved {
+            session_id: session_id.to_string(),
+            window: window.to_string(),
+        },
+    );
+    Ok(SessionHandoff {
+        session_id: session_id.to_string(),
+        kind: kind.to_string(),
+        replay,
+    })
+}
+
+/// Routes the sessions of a closed terminal window back to the main window
+fn return_sessions(app: &AppHandle, window: &str) {
+    let orphaned: Vec<String> = transport::managers(app)
+        .into_iter()
+        .flat_map(|manager| manager.session_routes())
+        .filter(|(_, route)| route.window() == window)
+        .map(|(id, _)| id)
+        .collect();
+    for session_id in orphaned {
+        let _ = move_session(app, &session_id, MAIN_WINDOW);
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Opens an additional terminal window and returns its label. With a
+/// session id the window is told which session to take over (through the
+/// `session` query parameter); it should call `move_session_to_window` once
+/// its listeners are registered.
+#[command]
+pub async fn open_terminal_window(
+    app: AppHandle,
+    session_id: Option<String>,
+) -> Result<String, String> {
+    let label = format!(
+        "{}{}",
+        TERMINAL_WINDOW_PREFIX,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let url = match &session_id {
+        // Session ids are generated by the frontend; anything that would
+        // need escaping is not one
+        Some(id)
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            return Err(format!("Invalid session id: {}", id));
+        }
+        Some(id) => format!("index.html?window=terminal&session={}", id),
+        None => "index.html?window=terminal".to_string(),
+    };
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title("NexaShell")
+        .inner_size(1024.0, 680.0)
+        .resizable(true)
+        .center()
+        .focused(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let handle = app.clone();
+    let closed = label.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            return_sessions(&handle, &closed);
+        }
+    });
+    Ok(label)
+}
+
+/// Moves a live SSH or local session to another window: its events are
+/// sent there from now on and the recent output is returned for replay.
+/// Input and resize events are listened for app-wide, so the new window
+/// can write to the session right away. Emits `session-moved`, on which
+/// the previous window closes the tab and drops its listeners; windows that
+/// listen for any target would otherwise keep receiving the events.
+#[command]
+pub fn move_session_to_window(
+    app: AppHandle,
+    session_id: String,
+    window: String,
+) -> Result<SessionHandoff, String> {
+    if app.get_webview_window(&window).is_none() {
+        return Err(format!("Window not found: {}", window));
+    }
+    move_session(&app, &session_id, &window)
+}
+
+/// Label of the window a session's events are sent to.
+#[command]
+pub fn get_session_window(app: AppHandle, session_id: String) -> Result<String, String> {
+    transport::find_session(&app, &SessionId::from(session_id.clone()))
+        .map(|(_, route)| route.window())
+        .ok_or_else(|| format!("Session not found: {}", session_id))
+}
@@ -0,0 +1,555 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+// ============================================================================
+// Wake-on-LAN
+// ============================================================================
+
+/// UDP port conventionally used for magic packets
+const WOL_PORT: u16 = 9;
+
+/// Interval between reachability checks while waiting for a woken host
+const WAKE_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Default time to wait for a woken host to accept SSH connections
+const WAKE_DEFAULT_TIMEOUT_MS: u64 = 120_000;
+
+/// Parses a MAC address written as `aa:bb:cc:dd:ee:ff`, `aa-bb-...` or
+/// `aabbccddeeff`
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], String> {
+    let hex: String = mac
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address: {}", mac));
+    }
+    let mut out = [0u8; 6];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Ok(out)
+}
+
+/// Returns the canonical `aa:bb:cc:dd:ee:ff` form of a MAC address
+pub fn normalize_mac(mac: &str) -> Result<String, String> {
+    let bytes = parse_mac(mac)?;
+    Ok(bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+/// Builds a magic packet: 6 x 0xFF followed by the MAC repeated 16 times
+fn magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+/// Broadcasts a Wake-on-LAN magic packet on the local network
+pub fn send_magic_packet(mac: [u8; 6]) -> Result<(), String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| e.to_string())?;
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    socket
+        .send_to(&magic_packet(mac), (Ipv4Addr::BROADCAST, WOL_PORT))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Returns true when a TCP connection to `addr:port` succeeds within `timeout`
+async fn tcp_reachable(addr: &str, port: u16, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, tokio::net::TcpStream::connect((addr, port))).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Payload of the `wol-status-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WakeStatus {
+    pub session_id: String,
+    /// "sent", "online" or "timeout"
+    pub status: String,
+}
+
+/// Payload of the `wol-connect` event: the woken host accepts connections
+/// and the frontend should open a connected tab for the session
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WakeConnect {
+    pub session_id: String,
+    pub server_name: String,
+}
+
+/// Result of `wake_host`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WakeResult {
+    pub mac_address: String,
+    /// Whether the SSH port became reachable; `None` when not waited for
+    pub online: Option<bool>,
+}
+
+/// Sends a Wake-on-LAN packet to a saved session's host.
+///
+/// With `wait_online`, polls the session's SSH port until it accepts
+/// connections (or `timeout_ms` elapses). Progress is reported through
+/// `wol-status-{sessionId}`. With `connect`, which implies `wait_online`,
+/// `wol-connect` is emitted once the port opens so the session is
+/// connected as soon as the host is up.
+#[command]
+pub async fn wake_host(
+    app: AppHandle,
+    session_id: String,
+    wait_online: Option<bool>,
+    timeout_ms: Option<u64>,
+    connect: Option<bool>,
+) -> Result<WakeResult, String> {
+    let connect = connect.unwrap_or(false);
+    let sid = session_id.clone();
+    let session = tokio::task::spawn_blocking(move || crate::db::load_session(&sid))
+        .await
+        .map_err(|e| e.to_string())??;
+    let mac_address = session
+        .mac_address
+        .ok_or_else(|| "No MAC address configured for this session".to_string())?;
+    let mac = parse_mac(&mac_address)?;
+
+    let emit_status = |status: &str| {
+        let payload = WakeStatus {
+            session_id: session_id.clone(),
+            status: status.to_string(),
+        };
+        let _ = app.emit(&format!("wol-status-{}", session_id), &payload);
+    };
+
+    // Magic packets are unacknowledged; send a few in case one is lost
+    for _ in 0..3 {
+        send_magic_packet(mac)?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    emit_status("sent");
+
+    if !wait_online.unwrap_or(false) && !connect {
+        return Ok(WakeResult {
+            mac_address,
+            online: None,
+        });
+    }
+
+    let port = u16::try_from(session.port).map_err(|e| e.to_string())?;
+    let deadline = tokio::time::Instant::now()
+        + Duration::from_millis(timeout_ms.unwrap_or(WAKE_DEFAULT_TIMEOUT_MS));
+    loop {
+        if tcp_reachable(&session.addr, port, Duration::from_secs(2)).await {
+            emit_status("online");
+            if connect {
+                let payload = WakeConnect {
+                    session_id: session_id.clone(),
+                    server_name: session.server_name.clone(),
+                };
+                let _ = app.emit("wol-connect", &payload);
+            }
+            return Ok(WakeResult {
+                mac_address,
+                online: Some(true),
+            });
+        }
+        if tokio::time::Instant::now() + WAKE_POLL_INTERVAL > deadline {
+            emit_status("timeout");
+            return Ok(WakeResult {
+                mac_address,
+                online: Some(false),
+            });
+        }
+        tokio::time::sleep(WAKE_POLL_INTERVAL).await;
+    }
+}
+
+// ============================================================================
+// Reachability Probe
+// ============================================================================
+
+/// Default timeout for `probe_host`
+const PROBE_DEFAULT_TIMEOUT_MS: u64 = 3000;
+
+/// Result of `probe_host`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub addr: String,
+    pub port: u16,
+    /// Addresses returned by DNS resolution
+    pub resolved_ips: Vec<String>,
+    /// Whether a TCP connection to the port succeeded
+    pub reachable: bool,
+    /// Address the TCP connection succeeded on
+    pub connected_ip: Option<String>,
+    /// Time to resolve the host name
+    pub dns_ms: Option<f64>,
+    /// Time to establish the TCP connection
+    pub latency_ms: Option<f64>,
+    /// ICMP round-trip time, when requested and permitted
+    pub ping_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Pings a host once with the system `ping` utility (which has the raw
+/// socket privileges an unprivileged app lacks). Returns the round-trip time.
+async fn icmp_ping(ip: std::net::IpAddr, timeout: Duration) -> Option<f64> {
+    // macOS pings IPv6 addresses with a separate `ping6`, which has no
+    // `-6` and no timeout option; the timeout below bounds it instead
+    let macos_ipv6 = cfg!(target_os = "macos") && ip.is_ipv6();
+    let mut cmd = tokio::process::Command::new(if macos_ipv6 { "ping6" } else { "ping" });
+    if cfg!(target_os = "windows") {
+        cmd.args(["-n", "1", "-w", &timeout.as_millis().to_string()]);
+    } else if macos_ipv6 {
+        cmd.args(["-c", "1"]);
+    } else if cfg!(target_os = "macos") {
+        cmd.args(["-c", "1", "-t", &timeout.as_secs().max(1).to_string()]);
+    } else {
+        cmd.args(["-c", "1", "-W", &timeout.as_secs().max(1).to_string()]);
+        if ip.is_ipv6() {
+            cmd.arg("-6");
+        }
+    }
+    cmd.arg(ip.to_string()).kill_on_drop(true);
+    let output = tokio::time::timeout(timeout + Duration::from_secs(1), cmd.output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Both "time=12.3 ms" and Windows' "time<1ms" forms
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let start = stdout.find("time=").or_else(|| stdout.find("time<"))? + 5;
+    let value: String = stdout[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    value.parse().ok()
+}
+
+/// Resolves `addr` and attempts a TCP connection to `port`; `timeout`
+/// bounds each of the resolution, connect and ping steps
+pub async fn probe(addr: String, port: u16, timeout: Duration, icmp: bool) -> ProbeResult {
+    let mut result = ProbeResult {
+        addr: addr.clone(),
+        port,
+        resolved_ips: Vec::new(),
+        reachable: false,
+        connected_ip: None,
+        dns_ms: None,
+        latency_ms: None,
+        ping_ms: None,
+        error: None,
+    };
+
+    let started = std::time::Instant::now();
+    let resolved =
+        match tokio::time::timeout(timeout, tokio::net::lookup_host((addr.as_str(), port))).await {
+            Ok(Ok(addrs)) => addrs.collect::<Vec<_>>(),
+            Ok(Err(e)) => {
+                result.error = Some(format!("DNS resolution failed: {}", e));
+                return result;
+            }
+            Err(_) => {
+                result.error = Some("DNS resolution timed out".to_string());
+                return result;
+            }
+        };
+    result.dns_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+    result.resolved_ips = resolved.iter().map(|a| a.ip().to_string()).collect();
+    result.resolved_ips.dedup();
+
+    for socket_addr in &resolved {
+        let started = std::time::Instant::now();
+        match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(socket_addr)).await {
+            Ok(Ok(_)) => {
+                result.reachable = true;
+                result.connected_ip = Some(socket_addr.ip().to_string());
+                result.latency_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+                result.error = None;
+                break;
+            }
+            Ok(Err(e)) => result.error = Some(e.to_string()),
+            Err(_) => result.error = Some("Connection timed out".to_string()),
+        }
+    }
+
+    if icmp {
+        if let Some(first) = resolved.first() {
+            result.ping_ms = icmp_ping(first.ip(), timeout).await;
+        }
+    }
+
+    result
+}
+
+/// Probes a host without starting an SSH handshake, so the session list can
+/// show online/offline badges.
+#[command]
+pub async fn probe_host(
+    addr: String,
+    port: u16,
+    timeout_ms: Option<u64>,
+    icmp: Option<bool>,
+) -> Result<ProbeResult, String> {
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(PROBE_DEFAULT_TIMEOUT_MS));
+    Ok(probe(addr, port, timeout, icmp.unwrap_or(false)).await)
+}
+
+// ============================================================================
+// Bulk Health Check
+// ============================================================================
+
+/// Maximum number of hosts probed at the same time by `check_all_sessions`
+const HEALTH_CHECK_PARALLELISM: usize = 16;
+
+/// Payload of the `session-health` event and element of the
+/// `check_all_sessions` result
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHealth {
+    pub session_id: String,
+    /// "online" or "offline"
+    pub status: String,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub checked_at: u128,
+}
+
+/// Probes every saved session's host:port concurrently.
+///
+/// Each result is emitted as a `session-health` event as soon as it is known
+/// and stored on the session (`health_status`, `health_checked_at`).
+#[command]
+pub async fn check_all_sessions(
+    app: AppHandle,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<SessionHealth>, String> {
+    let sessions = tokio::task::spawn_blocking(crate::db::list_sessions)
+        .await
+        .map_err(|e| e.to_string())??;
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(PROBE_DEFAULT_TIMEOUT_MS));
+    let permits = Arc::new(tokio::sync::Semaphore::new(HEALTH_CHECK_PARALLELISM));
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for session in sessions {
+        let permits = permits.clone();
+        let app = app.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            let result = match u16::try_from(session.port) {
+                Ok(port) => probe(session.addr, port, timeout, false).await,
+                Err(_) => ProbeResult {
+                    addr: session.addr,
+                    port: 0,
+                    resolved_ips: Vec::new(),
+                    reachable: false,
+                    connected_ip: None,
+                    dns_ms: None,
+                    latency_ms: None,
+                    ping_ms: None,
+                    error: Some(format!("Invalid port: {}", session.port)),
+                },
+            };
+            let health = SessionHealth {
+                session_id: session.id,
+                status: if result.reachable {
+                    "online"
+                } else {
+                    "offline"
+                }
+                .to_string(),
+                latency_ms: result.latency_ms,
+                error: result.error,
+                checked_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis(),
+            };
+
+            let (id, status) = (health.session_id.clone(), health.status.clone());
+            let _ =
+                tokio::task::spawn_blocking(move || crate::db::update_session_health(&id, &status))
+                    .await;
+            let _ = app.emit("session-health", &health);
+            Some(health)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(Some(health)) = joined {
+            results.push(health);
+        }
+    }
+    Ok(results)
+}
+
+// ============================================================================
+// Port Knocking
+// ============================================================================
+
+/// How long a TCP knock waits for the SYN to be answered
+const KNOCK_TCP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Transport used for a knock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnockProtocol {
+    Tcp,
+    Udp,
+}
+
+/// One step of a port knocking sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnockStep {
+    pub port: u16,
+    pub protocol: KnockProtocol,
+    /// Pause after this knock, before the next one (or the connection)
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Performs a knock sequence against `host`. Blocking.
+///
+/// Knocks are fire-and-forget: a refused or timed-out TCP knock is expected,
+/// since the firewall only watches for the packet.
+pub fn knock(host: &str, steps: &[KnockStep]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Ok(());
+    }
+    let ip = (host, 0)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {} for knocking: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No addresses found for {}", host))?
+        .ip();
+
+    for step in steps {
+        let target = SocketAddr::new(ip, step.port);
+        match step.protocol {
+            KnockProtocol::Tcp => {
+                let _ = TcpStream::connect_timeout(&target, KNOCK_TCP_TIMEOUT);
+            }
+            KnockProtocol::Udp => {
+                let bind: SocketAddr = if ip.is_ipv6() {
+                    (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                };
+                let socket = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+                socket.send_to(&[0], target).map_err(|e| e.to_string())?;
+            }
+        }
+        if step.delay_ms > 0 {
+            std::thread::sleep(Duration::from_millis(step.delay_ms));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Happy Eyeballs
+// ============================================================================
+
+/// Delay before starting the connection attempt to the next address
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// How often a connection attempt checks whether it was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Orders addresses so IPv6 and IPv4 alternate, starting with the family
+/// of the first resolved address (RFC 8305 section 4)
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let prefer_v6 = addrs.first().is_some_and(|a| a.is_ipv6());
+    let (mut first, mut second): (VecDeque<_>, VecDeque<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == prefer_v6);
+    let mut ordered = Vec::with_capacity(first.len() + second.len());
+    while !first.is_empty() || !second.is_empty() {
+        ordered.extend(first.pop_front());
+        ordered.extend(second.pop_front());
+    }
+    ordered
+}
+
+/// Resolves `host:port` to every address it maps to. Blocking.
+pub fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve address: {}", e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err("No addresses found".to_string());
+    }
+    Ok(addrs)
+}
+
+/// Connects to the first reachable of `addrs` using staggered attempts
+/// (happy eyeballs); the first connection to succeed wins. Blocking; gives
+/// up shortly after `cancel` is set, leaving pending attempts to time out
+/// on their own threads.
+///
+/// Returns the stream and the address it connected to. On failure the
+/// error lists the reason for each address.
+pub fn connect_happy_eyeballs(
+    addrs: Vec<SocketAddr>,
+    timeout: Duration,
+    cancel: &AtomicBool,
+) -> Result<(TcpStream, SocketAddr), String> {
+    if addrs.is_empty() {
+        return Err("No addresses found".to_string());
+    }
+    let addrs = interleave_families(addrs);
+
+    let done = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = std::sync::mpsc::channel();
+    for (i, addr) in addrs.iter().copied().enumerate() {
+        let done = done.clone();
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(CONNECTION_ATTEMPT_DELAY * i as u32);
+            if done.load(Ordering::SeqCst) {
+                return;
+            }
+            let _ = tx.send((addr, TcpStream::connect_timeout(&addr, timeout)));
+        });
+    }
+    drop(tx);
+
+    let mut failures = Vec::new();
+    // Every attempt reports once, unless a winner was found first
+    loop {
+        if cancel.load(Ordering::SeqCst) {
+            done.store(true, Ordering::SeqCst);
+            return Err("Cancelled".to_string());
+        }
+        match rx.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok((addr, Ok(stream))) => {
+                done.store(true, Ordering::SeqCst);
+                return Ok((stream, addr));
+            }
+            Ok((addr, Err(e))) => failures.push(format!("{} - {}", addr, e)),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Err(failures.join("; "))
+}
@@ -0,0 +1,135 @@
+use crate::settings::NotificationSettings;
+use crate::ssh::{ServerStatus, SshManager};
+use crate::triggers::TriggerFired;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// CPU usage (percent) above which a server counts as overloaded
+pub const CPU_THRESHOLD: f64 = 90.0;
+
+/// Disk usage (percent) above which a server counts as running out of space
+pub const DISK_THRESHOLD: f64 = 95.0;
+
+/// Kinds of notifications, each with its own switch in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Disconnects,
+    Transfers,
+    Thresholds,
+    Triggers,
+}
+
+impl Category {
+    fn enabled(self, settings: &NotificationSettings) -> bool {
+        settings.enabled
+            && match self {
+                Category::Disconnects => settings.disconnects,
+                Category::Transfers => settings.transfers,
+                Category::Thresholds => settings.thresholds,
+                Category::Triggers => settings.triggers,
+            }
+    }
+}
+
+fn app_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|w| w.is_focused().unwrap_or(false))
+}
+
+/// Shows a native notification if its category is enabled
+pub fn notify(app: &AppHandle, category: Category, title: String, body: String) {
+    let settings = crate::settings::current().notifications;
+    if !category.enabled(&settings) || (settings.only_when_unfocused && app_focused(app)) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// `user@host` of an SSH session, or its id when it is gone
+fn session_label(app: &AppHandle, session_id: &str) -> String {
+    app.state::<SshManager>()
+        .active_sessions()
+        .into_iter()
+        .find(|s| s.session_id == session_id)
+        .map(|s| s.label)
+        .unwrap_or_else(|| session_id.to_string())
+}
+
+/// A connection dropped without being closed from the app or by the server
+pub fn session_disconnected(app: &AppHandle, session_id: &str) {
+    notify(
+        app,
+        Category::Disconnects,
+        "Disconnected".to_string(),
+        format!(
+            "{}: the connection was lost",
+            session_label(app, session_id)
+        ),
+    );
+}
+
+/// A transfer finished; `error` is set when it failed
+pub fn transfer_finished(app: &AppHandle, direction: &str, path: &str, error: Option<&str>) {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let (title, body) = match (direction, error) {
+        ("upload", None) => ("Upload complete", name.to_string()),
+        (_, None) => ("Download complete", name.to_string()),
+        ("upload", Some(e)) => ("Upload failed", format!("{}: {}", name, e)),
+        (_, Some(e)) => ("Download failed", format!("{}: {}", name, e)),
+    };
+    notify(app, Category::Transfers, title.to_string(), body);
+}
+
+/// A trigger with the "notify" action matched
+pub fn trigger_fired(app: &AppHandle, event: &TriggerFired) {
+    let body = if event.message.is_empty() {
+        event.matched.clone()
+    } else {
+        event.message.clone()
+    };
+    notify(app, Category::Triggers, event.name.clone(), body);
+}
+
+/// Notifies once when a server goes over the CPU or disk threshold; the
+/// notification is re-armed when usage drops back below it
+#[derive(Debug, Default)]
+pub struct ThresholdWatch {
+    cpu: bool,
+    disk: bool,
+}
+
+impl ThresholdWatch {
+    pub fn check(&mut self, app: &AppHandle, session_id: &str, status: &ServerStatus) {
+        let cpu = status.cpu_usage > CPU_THRESHOLD;
+        let disk = status.disk_usage > DISK_THRESHOLD;
+        if cpu && !self.cpu {
+            notify(
+                app,
+                Category::Thresholds,
+                "High CPU usage".to_string(),
+                format!(
+                    "{}: CPU at {:.0}%",
+                    session_label(app, session_id),
+                    status.cpu_usage
+                ),
+            );
+        }
+        if disk && !self.disk {
+            notify(
+                app,
+                Category::Thresholds,
+                "Disk almost full".to_string(),
+                format!(
+                    "{}: disk at {:.0}%",
+                    session_label(app, session_id),
+                    status.disk_usage
+                ),
+            );
+        }
+        self.cpu = cpu;
+        self.disk = disk;
+    }
+}
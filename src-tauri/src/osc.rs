@@ -0,0 +1,137 @@
+use base64::{engine::general_purpose, Engine as _};
+
+/// Maximum payload length kept for a single OSC sequence. Longer sequences
+/// are discarded rather than buffered without bound.
+const MAX_OSC_PAYLOAD: usize = 64 * 1024;
+
+/// A complete Operating System Command sequence (`ESC ] code ; payload ST`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscSequence {
+    /// Numeric (or textual) command code before the first `;`
+    pub code: String,
+    /// Everything after the first `;`, without the terminator
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Incremental scanner that extracts OSC sequences from terminal output.
+///
+/// Sequences may be split across chunks; the scanner keeps its state between
+/// calls to `feed`. Both BEL and `ESC \` terminators are accepted. The
+/// output itself is not modified.
+#[derive(Debug, Default)]
+pub struct OscScanner {
+    state: ScanState,
+    buf: String,
+    overflow: bool,
+}
+
+impl OscScanner {
+    /// Scans `text` and returns every sequence completed within it
+    pub fn feed(&mut self, text: &str) -> Vec<OscSequence> {
+        let mut found = Vec::new();
+        if self.state == ScanState::Ground && !text.contains('\x1b') {
+            return found;
+        }
+
+        for c in text.chars() {
+            match self.state {
+                ScanState::Ground => {
+                    if c == '\x1b' {
+                        self.state = ScanState::Escape;
+                    }
+                }
+                ScanState::Escape => {
+                    if c == ']' {
+                        self.buf.clear();
+                        self.overflow = false;
+                        self.state = ScanState::Osc;
+                    } else if c != '\x1b' {
+                        self.state = ScanState::Ground;
+                    }
+                }
+                ScanState::Osc => match c {
+                    '\x07' => self.finish(&mut found),
+                    '\x1b' => self.state = ScanState::OscEscape,
+                    _ => self.push(c),
+                },
+                ScanState::OscEscape => {
+                    if c == '\\' {
+                        self.finish(&mut found);
+                    } else if c == ']' {
+                        // Unterminated sequence followed by a new one
+                        self.buf.clear();
+                        self.overflow = false;
+                        self.state = ScanState::Osc;
+                    } else {
+                        self.state = ScanState::Ground;
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn push(&mut self, c: char) {
+        if self.buf.len() + c.len_utf8() > MAX_OSC_PAYLOAD {
+            self.overflow = true;
+            self.buf.clear();
+        } else if !self.overflow {
+            self.buf.push(c);
+        }
+    }
+
+    fn finish(&mut self, found: &mut Vec<OscSequence>) {
+        self.state = ScanState::Ground;
+        if self.overflow {
+            self.overflow = false;
+            return;
+        }
+        let raw = std::mem::take(&mut self.buf);
+        let (code, payload) = match raw.split_once(';') {
+            Some((code, payload)) => (code.to_string(), payload.to_string()),
+            None => (raw, String::new()),
+        };
+        found.push(OscSequence { code, payload });
+    }
+}
+
+/// Decodes `%XX` escapes in a URI component, leaving invalid escapes as-is
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Text a remote program puts on the clipboard with OSC 52
+/// (`ESC ] 52 ; <selection> ; <base64> ST`). Clipboard queries (`?`) are
+/// never answered and empty writes are ignored, so both yield `None`.
+pub fn parse_clipboard(seq: &OscSequence) -> Option<String> {
+    if seq.code != "52" {
+        return None;
+    }
+    let (_selection, data) = seq.payload.split_once(';')?;
+    let bytes = general_purpose::STANDARD.decode(data.trim()).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned()).filter(|text| !text.is_empty())
+}
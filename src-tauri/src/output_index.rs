@@ -0,0 +1,263 @@
+use crate::lines::LineAssembler;
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use tauri::command;
+
+/// Lines kept per session; older lines are dropped from the index
+const MAX_LINES_PER_SESSION: u64 = 200_000;
+
+/// Lines over the limit before the oldest are deleted, so pruning runs in
+/// batches rather than on every line
+const PRUNE_SLACK: u64 = 10_000;
+
+/// Matches returned when no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+/// Batches of lines queued for the indexing thread at most; output is left
+/// out of the index rather than slowing the terminal when it falls behind
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Output of every session, in memory only: terminal output may contain
+/// secrets and is not worth keeping after the session is closed
+static INDEX: OnceCell<Mutex<Connection>> = OnceCell::new();
+
+fn index() -> Result<&'static Mutex<Connection>, String> {
+    INDEX.get_or_try_init(|| {
+        let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE output_lines (
+                id INTEGER PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                line TEXT NOT NULL
+            );
+            CREATE INDEX idx_output_lines_session ON output_lines(session_id, id);
+            CREATE VIRTUAL TABLE output_fts USING fts5(
+                line, content='output_lines', content_rowid='id'
+            );
+            CREATE TRIGGER output_lines_ai AFTER INSERT ON output_lines BEGIN
+                INSERT INTO output_fts(rowid, line) VALUES (new.id, new.line);
+            END;
+            CREATE TRIGGER output_lines_ad AFTER DELETE ON output_lines BEGIN
+                INSERT INTO output_fts(output_fts, rowid, line)
+                VALUES ('delete', old.id, old.line);
+            END;",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Mutex::new(conn))
+    })
+}
+
+/// Work for the indexing thread, done in the order it was queued
+enum IndexJob {
+    Insert {
+        session_id: String,
+        seq: u64,
+        lines: Vec<String>,
+    },
+    Remove(String),
+}
+
+static QUEUE: OnceCell<mpsc::SyncSender<IndexJob>> = OnceCell::new();
+
+/// Queue of the thread that writes to the index, started on first use
+fn queue() -> &'static mpsc::SyncSender<IndexJob> {
+    QUEUE.get_or_init(|| {
+        let (sender, jobs) = mpsc::sync_channel(QUEUE_CAPACITY);
+        std::thread::spawn(move || {
+            // Lines indexed per session since it was opened
+            let mut counts: HashMap<String, u64> = HashMap::new();
+            for job in jobs {
+                match job {
+                    IndexJob::Insert {
+                        session_id,
+                        seq,
+                        lines,
+                    } => {
+                        let count = counts.entry(session_id.clone()).or_default();
+                        if let Err(e) = insert(&session_id, seq, &lines, count) {
+                            crate::error_bus::report("output-index", Some(&session_id), e);
+                        }
+                    }
+                    IndexJob::Remove(session_id) => {
+                        counts.remove(&session_id);
+                        delete_session(&session_id);
+                    }
+                }
+            }
+        });
+        sender
+    })
+}
+
+fn insert(session_id: &str, seq: u64, lines: &[String], count: &mut u64) -> Result<(), String> {
+    let mut conn = index()?.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare_cached("INSERT INTO output_lines (session_id, seq, line) VALUES (?1, ?2, ?3)")
+            .map_err(|e| e.to_string())?;
+        for line in lines {
+            stmt.execute(params![session_id, seq as i64, line])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    *count += lines.len() as u64;
+    if *count > MAX_LINES_PER_SESSION + PRUNE_SLACK {
+        let excess = *count - MAX_LINES_PER_SESSION;
+        tx.execute(
+            "DELETE FROM output_lines WHERE id IN (
+                SELECT id FROM output_lines WHERE session_id = ?1 ORDER BY id LIMIT ?2
+            )",
+            params![session_id, excess as i64],
+        )
+        .map_err(|e| e.to_string())?;
+        *count = MAX_LINES_PER_SESSION;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn delete_session(session_id: &str) {
+    let Some(index) = INDEX.get() else {
+        return;
+    };
+    if let Ok(conn) = index.lock() {
+        if let Err(e) = conn.execute(
+            "DELETE FROM output_lines WHERE session_id = ?1",
+            params![session_id],
+        ) {
+            tracing::warn!("failed to clear the output index of {}: {}", session_id, e);
+        }
+    }
+}
+
+/// A line of output matching a search
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputMatch {
+    /// Sequence number of the output chunk the line ended in
+    pub seq: u64,
+    /// The line without escape sequences
+    pub line: String,
+    /// The line with matches wrapped in `[` and `]`, shortened around them
+    pub snippet: String,
+}
+
+/// Adds the lines of one session's text output to the search index. Used
+/// by SSH sessions and local terminals alike. Lines are written by a
+/// dedicated thread, so feeding never waits on the index.
+pub struct OutputIndexer {
+    session_id: String,
+    stdout: LineAssembler,
+    /// Kept apart so interleaved streams do not merge partial lines
+    stderr: LineAssembler,
+}
+
+impl OutputIndexer {
+    /// An indexer for a session, or `None` when indexing is turned off in
+    /// the settings
+    pub fn for_session(session_id: &str) -> Option<Self> {
+        if !crate::settings::current().terminal.index_output {
+            return None;
+        }
+        // A reopened session id starts with an empty index
+        remove_session(session_id);
+        Some(Self {
+            session_id: session_id.to_string(),
+            stdout: LineAssembler::default(),
+            stderr: LineAssembler::default(),
+        })
+    }
+
+    /// Indexes the complete lines of a chunk of decoded terminal output
+    pub fn feed(&mut self, seq: u64, text: &str) {
+        let lines = self.stdout.feed(text);
+        self.queue_lines(seq, lines);
+    }
+
+    /// Indexes the complete lines of a chunk of decoded stderr output
+    pub fn feed_stderr(&mut self, seq: u64, text: &str) {
+        let lines = self.stderr.feed(text);
+        self.queue_lines(seq, lines);
+    }
+
+    fn queue_lines(&self, seq: u64, lines: Vec<String>) {
+        let lines: Vec<String> = lines.into_iter().filter(|l| !l.is_empty()).collect();
+        if lines.is_empty() {
+            return;
+        }
+        let _ = queue().try_send(IndexJob::Insert {
+            session_id: self.session_id.clone(),
+            seq,
+            lines,
+        });
+    }
+}
+
+/// Drops the indexed output of a session; called when it is closed. Queued
+/// behind the session's pending lines so none are indexed afterwards.
+pub fn remove_session(session_id: &str) {
+    if QUEUE.get().is_none() {
+        return;
+    }
+    let _ = queue().send(IndexJob::Remove(session_id.to_string()));
+}
+
+/// Turns free text into an FTS5 query matching lines that contain all of
+/// its words, so quotes and operators in the text are searched for as is
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Searches the indexed output of a session, newest lines first
+pub fn search(session_id: &str, query: &str, limit: usize) -> Result<Vec<OutputMatch>, String> {
+    let query = fts_query(query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conn = index()?.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT l.seq, l.line, snippet(output_fts, 0, '[', ']', '…', 16)
+             FROM output_fts
+             JOIN output_lines l ON l.id = output_fts.rowid
+             WHERE output_fts MATCH ?1 AND l.session_id = ?2
+             ORDER BY l.id DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![query, session_id, limit as i64], |row| {
+            Ok(OutputMatch {
+                seq: row.get::<_, i64>(0)? as u64,
+                line: row.get(1)?,
+                snippet: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Searches the output of an SSH session or local terminal for lines
+/// containing every word of `query`, newest first. Only output received
+/// while `terminal.indexOutput` was enabled is searched.
+#[command]
+pub fn search_output(
+    session_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<OutputMatch>, String> {
+    search(&session_id, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+}
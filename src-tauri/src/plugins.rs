@@ -0,0 +1,676 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+/// Version of the plugin protocol. Plugins declare the version they were
+/// written against and are not started when it differs.
+pub const API_VERSION: u32 = 1;
+
+/// Manifest file expected in each plugin directory
+const MANIFEST_FILE: &str = "plugin.json";
+
+/// Messages queued for a plugin's stdin at most; output observations are
+/// dropped rather than blocking the terminal when a plugin falls behind
+const INPUT_QUEUE: usize = 256;
+
+/// How long a command or session action may take to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest stderr line logged for a plugin; the rest of the line is dropped
+const MAX_STDERR_LINE: u64 = 4096;
+
+/// `plugin.json` of a plugin.
+///
+/// Plugins are external programs started with `command` and `args` in the
+/// plugin directory. They talk to the host with one JSON object per line
+/// on stdin and stdout, so they can be written in any language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub api_version: u32,
+    /// Program to run; relative paths are resolved in the plugin directory
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Commands callable through `invoke_plugin_command`
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Receive the decoded output of every terminal session, once the user
+    /// allows it in the plugin settings
+    #[serde(default)]
+    pub observe_output: bool,
+    #[serde(default)]
+    pub session_actions: Vec<PluginActionManifest>,
+}
+
+/// An entry the plugin adds to the session context menu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginActionManifest {
+    pub id: String,
+    pub label: String,
+}
+
+/// Messages sent to a plugin
+#[derive(Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum HostMessage<'a> {
+    /// First message after start
+    Init {
+        api_version: u32,
+        app_version: &'a str,
+    },
+    /// Call of one of the manifest's commands; answered with a `result`
+    Invoke {
+        id: u64,
+        command: &'a str,
+        args: &'a Value,
+    },
+    /// A session action was chosen; answered with a `result`
+    Action {
+        id: u64,
+        action: &'a str,
+        session: &'a PluginSession,
+    },
+    /// Decoded terminal output, for plugins observing output
+    Output {
+        session_id: &'a str,
+        saved_session_id: Option<&'a str>,
+        data: &'a str,
+    },
+}
+
+/// Messages read from a plugin
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PluginMessage {
+    /// Answer to an `invoke` or `action`; `error` marks a failure
+    Result {
+        id: u64,
+        #[serde(default)]
+        value: Value,
+        error: Option<String>,
+    },
+    /// Forwarded to the frontend as a `plugin-event` event
+    Emit {
+        event: String,
+        #[serde(default)]
+        payload: Value,
+    },
+    Log {
+        message: String,
+    },
+}
+
+/// The saved session a session action runs on. Credentials are never
+/// passed to plugins.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSession {
+    pub id: String,
+    pub server_name: String,
+    pub addr: String,
+    pub port: i64,
+    pub username: String,
+}
+
+/// Payload of the `plugin-event` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginEvent {
+    pub plugin_id: String,
+    pub event: String,
+    pub payload: Value,
+}
+
+/// A plugin found in the plugins directory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    /// Manifest id, or the directory name when the manifest is unreadable
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub path: String,
+    /// Whether the user enabled the plugin; disabled plugins are not started
+    pub enabled: bool,
+    /// Whether the plugin asks to receive terminal output
+    pub observe_output: bool,
+    /// Whether the user allowed it to
+    pub output_allowed: bool,
+    pub running: bool,
+    /// Why an enabled plugin is not running
+    pub error: Option<String>,
+    pub commands: Vec<String>,
+    pub session_actions: Vec<PluginActionManifest>,
+}
+
+/// A session action contributed by a running plugin
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSessionAction {
+    pub plugin_id: String,
+    pub plugin_name: String,
+    pub id: String,
+    pub label: String,
+}
+
+type Reply = mpsc::Sender<Result<Value, String>>;
+
+/// A started plugin process
+struct PluginProcess {
+    manifest: PluginManifest,
+    /// Whether terminal output is passed to the plugin
+    observe: bool,
+    input: mpsc::SyncSender<String>,
+    pending: Mutex<HashMap<u64, Reply>>,
+    next_id: AtomicU64,
+    exited: AtomicBool,
+    child: Mutex<Child>,
+}
+
+impl PluginProcess {
+    fn send(&self, message: &HostMessage) -> Result<(), String> {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        self.input
+            .send(line)
+            .map_err(|_| "Plugin is not running".to_string())
+    }
+
+    /// Sends a request built for a fresh id and waits for its `result`
+    fn request(&self, build: impl FnOnce(u64) -> Result<String, String>) -> Result<Value, String> {
+        if self.exited.load(Ordering::SeqCst) {
+            return Err("Plugin is not running".to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(id, tx);
+        let sent = build(id).and_then(|line| {
+            self.input
+                .send(line)
+                .map_err(|_| "Plugin is not running".to_string())
+        });
+        let result = sent.and_then(|_| match rx.recv_timeout(REQUEST_TIMEOUT) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Err("Plugin did not answer in time".to_string())
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err("Plugin exited".to_string()),
+        });
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+        result
+    }
+
+    fn stop(&self) {
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+struct LoadedPlugin {
+    info: PluginInfo,
+    process: Option<Arc<PluginProcess>>,
+}
+
+/// Plugins found by the last (re)load
+static PLUGINS: Lazy<RwLock<Vec<LoadedPlugin>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Whether any running plugin observes output; checked before building
+/// output messages so terminals pay nothing without such plugins
+static OBSERVING: AtomicBool = AtomicBool::new(false);
+
+fn plugins_dir() -> Result<PathBuf, String> {
+    let dir = crate::db::app_data_dir()?.join("plugins");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let text = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE, e))?;
+    let manifest: PluginManifest =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    if manifest.id.trim().is_empty() {
+        return Err("Plugin id must not be empty".to_string());
+    }
+    if manifest.api_version != API_VERSION {
+        return Err(format!(
+            "Plugin targets API version {}, this version of NexaShell provides {}",
+            manifest.api_version, API_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+fn spawn_plugin(
+    app: &AppHandle,
+    dir: &Path,
+    manifest: &PluginManifest,
+    observe: bool,
+) -> Result<Arc<PluginProcess>, String> {
+    let program = dir.join(&manifest.command);
+    let program = if program.is_file() {
+        program
+    } else {
+        PathBuf::from(&manifest.command)
+    };
+    let mut child = Command::new(&program)
+        .args(&manifest.args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", manifest.command, e))?;
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (input, queued) = mpsc::sync_channel::<String>(INPUT_QUEUE);
+    let process = Arc::new(PluginProcess {
+        manifest: manifest.clone(),
+        observe,
+        input,
+        pending: Mutex::new(HashMap::new()),
+        next_id: AtomicU64::new(1),
+        exited: AtomicBool::new(false),
+        child: Mutex::new(child),
+    });
+
+    if let Some(mut stdin) = stdin {
+        std::thread::spawn(move || {
+            for line in queued {
+                if writeln!(stdin, "{}", line)
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+    if let Some(stderr) = stderr {
+        let id = manifest.id.clone();
+        std::thread::spawn(move || log_stderr(&id, stderr));
+    }
+    if let Some(stdout) = stdout {
+        let process = Arc::clone(&process);
+        let app = app.clone();
+        std::thread::spawn(move || read_messages(&app, &process, stdout));
+    }
+
+    process.send(&HostMessage::Init {
+        api_version: API_VERSION,
+        app_version: env!("CARGO_PKG_VERSION"),
+    })?;
+    Ok(process)
+}
+
+/// Logs a plugin's stderr line by line until it exits, cutting long lines
+/// so a chatty plugin cannot grow memory without bound
+fn log_stderr(id: &str, stderr: impl Read) {
+    let mut reader = BufReader::new(stderr);
+    let mut line = Vec::new();
+    let mut truncated = false;
+    loop {
+        line.clear();
+        match (&mut reader)
+            .take(MAX_STDERR_LINE)
+            .read_until(b'\n', &mut line)
+        {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let complete = line.ends_with(b"\n");
+        // The remainder of a cut line is skipped
+        if !truncated {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end();
+            if !text.is_empty() {
+                let cut = if complete { "" } else { "..." };
+                tracing::warn!(plugin = %id, "{}{}", text, cut);
+            }
+        }
+        truncated = !complete;
+    }
+}
+
+/// Handles a plugin's stdout until it exits
+fn read_messages(app: &AppHandle, process: &PluginProcess, stdout: impl Read) {
+    let id = &process.manifest.id;
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<PluginMessage>(&line) {
+            Ok(PluginMessage::Result {
+                id: request,
+                value,
+                error,
+            }) => {
+                let reply = process
+                    .pending
+                    .lock()
+                    .ok()
+                    .and_then(|mut p| p.remove(&request));
+                if let Some(reply) = reply {
+                    let _ = reply.send(match error {
+                        Some(error) => Err(error),
+                        None => Ok(value),
+                    });
+                }
+            }
+            Ok(PluginMessage::Emit { event, payload }) => {
+                let _ = app.emit(
+                    "plugin-event",
+                    &PluginEvent {
+                        plugin_id: id.clone(),
+                        event,
+                        payload,
+                    },
+                );
+            }
+            Ok(PluginMessage::Log { message }) => tracing::info!(plugin = %id, "{}", message),
+            Err(e) => tracing::warn!(plugin = %id, "invalid message: {}", e),
+        }
+    }
+    process.exited.store(true, Ordering::SeqCst);
+    if let Ok(mut pending) = process.pending.lock() {
+        pending.clear();
+    }
+    tracing::info!(plugin = %id, "exited");
+}
+
+/// Stops running plugins, then scans the plugins directory and starts the
+/// enabled plugins found there
+fn load_plugins(app: &AppHandle, settings: &crate::settings::PluginSettings) -> Result<(), String> {
+    let dir = plugins_dir()?;
+    let mut loaded: Vec<LoadedPlugin> = Vec::new();
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let dir_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut info = PluginInfo {
+            id: dir_name.clone(),
+            name: dir_name,
+            version: String::new(),
+            description: String::new(),
+            path: path.to_string_lossy().into_owned(),
+            enabled: false,
+            observe_output: false,
+            output_allowed: false,
+            running: false,
+            error: None,
+            commands: Vec::new(),
+            session_actions: Vec::new(),
+        };
+        let started = read_manifest(&path).and_then(|manifest| {
+            info.id = manifest.id.clone();
+            info.name = manifest.name.clone();
+            info.version = manifest.version.clone();
+            info.description = manifest.description.clone();
+            info.commands = manifest.commands.clone();
+            info.session_actions = manifest.session_actions.clone();
+            info.enabled = settings.enabled.contains(&manifest.id);
+            info.observe_output = manifest.observe_output;
+            info.output_allowed = settings.output_access.contains(&manifest.id);
+            if loaded.iter().any(|p| p.info.id == manifest.id) {
+                return Err(format!("Duplicate plugin id: {}", manifest.id));
+            }
+            if !info.enabled {
+                return Ok(None);
+            }
+            let observe = info.observe_output && info.output_allowed;
+            spawn_plugin(app, &path, &manifest, observe).map(Some)
+        });
+        match started {
+            Ok(process) => loaded.push(LoadedPlugin { info, process }),
+            Err(e) => {
+                info.error = Some(e);
+                loaded.push(LoadedPlugin {
+                    info,
+                    process: None,
+                });
+            }
+        }
+    }
+
+    let observing = loaded
+        .iter()
+        .filter_map(|p| p.process.as_ref())
+        .any(|p| p.observe);
+    let previous = {
+        let mut plugins = PLUGINS.write().map_err(|e| e.to_string())?;
+        std::mem::replace(&mut *plugins, loaded)
+    };
+    OBSERVING.store(observing, Ordering::SeqCst);
+    for plugin in previous {
+        if let Some(process) = plugin.process {
+            process.stop();
+        }
+    }
+    Ok(())
+}
+
+fn running_plugin(plugin_id: &str) -> Result<Arc<PluginProcess>, String> {
+    let plugins = PLUGINS.read().map_err(|e| e.to_string())?;
+    plugins
+        .iter()
+        .find(|p| p.info.id == plugin_id)
+        .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?
+        .process
+        .clone()
+        .filter(|p| !p.exited.load(Ordering::SeqCst))
+        .ok_or_else(|| format!("Plugin is not running: {}", plugin_id))
+}
+
+/// Starts the enabled plugins found in the plugins directory
+pub fn start_plugins(app: AppHandle) {
+    apply_settings(&app, &crate::settings::current().plugins);
+}
+
+/// Restarts the plugins after the plugin settings changed
+pub fn apply_settings(app: &AppHandle, settings: &crate::settings::PluginSettings) {
+    let app = app.clone();
+    let settings = settings.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = load_plugins(&app, &settings) {
+            crate::error_bus::report("plugins", None, format!("failed to load plugins: {}", e));
+        }
+    });
+}
+
+/// Passes decoded terminal output to plugins observing output. Never
+/// blocks: output is dropped for plugins whose input queue is full.
+pub fn observe_output(session_id: &str, saved_session_id: Option<&str>, data: &str) {
+    if !OBSERVING.load(Ordering::Relaxed) || data.is_empty() {
+        return;
+    }
+    let Ok(plugins) = PLUGINS.read() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(&HostMessage::Output {
+        session_id,
+        saved_session_id,
+        data,
+    }) else {
+        return;
+    };
+    for process in plugins.iter().filter_map(|p| p.process.as_ref()) {
+        if process.observe && !process.exited.load(Ordering::Relaxed) {
+            let _ = process.input.try_send(line.clone());
+        }
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Lists the plugins in the plugins directory, with the reason for each one
+/// that is not running.
+#[command]
+pub fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+    let plugins = PLUGINS.read().map_err(|e| e.to_string())?;
+    Ok(plugins
+        .iter()
+        .map(|p| {
+            let mut info = p.info.clone();
+            info.running = p
+                .process
+                .as_ref()
+                .is_some_and(|p| !p.exited.load(Ordering::SeqCst));
+            if info.enabled && !info.running && info.error.is_none() {
+                info.error = Some("Plugin exited".to_string());
+            }
+            info
+        })
+        .collect())
+}
+
+/// Restarts all enabled plugins, picking up added, removed and changed ones.
+#[command]
+pub async fn reload_plugins(app: AppHandle) -> Result<Vec<PluginInfo>, String> {
+    let settings = crate::settings::current().plugins;
+    tokio::task::spawn_blocking(move || load_plugins(&app, &settings))
+        .await
+        .map_err(|e| e.to_string())??;
+    list_plugins()
+}
+
+/// Path of the plugins directory, for "open plugins folder".
+#[command]
+pub fn get_plugins_dir() -> Result<String, String> {
+    Ok(plugins_dir()?.to_string_lossy().into_owned())
+}
+
+/// Calls a command declared in a plugin's manifest and returns the value
+/// the plugin answers with.
+#[command]
+pub async fn invoke_plugin_command(
+    plugin_id: String,
+    command: String,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    let process = running_plugin(&plugin_id)?;
+    if !process.manifest.commands.contains(&command) {
+        return Err(format!(
+            "Plugin {} has no command named {}",
+            plugin_id, command
+        ));
+    }
+    let args = args.unwrap_or(Value::Null);
+    tokio::task::spawn_blocking(move || {
+        process.request(|id| {
+            serde_json::to_string(&HostMessage::Invoke {
+                id,
+                command: &command,
+                args: &args,
+            })
+            .map_err(|e| e.to_string())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Lists the session actions contributed by running plugins.
+#[command]
+pub fn list_plugin_session_actions() -> Result<Vec<PluginSessionAction>, String> {
+    let plugins = PLUGINS.read().map_err(|e| e.to_string())?;
+    Ok(plugins
+        .iter()
+        .filter(|p| {
+            p.process
+                .as_ref()
+                .is_some_and(|p| !p.exited.load(Ordering::SeqCst))
+        })
+        .flat_map(|p| {
+            p.info.session_actions.iter().map(|a| PluginSessionAction {
+                plugin_id: p.info.id.clone(),
+                plugin_name: p.info.name.clone(),
+                id: a.id.clone(),
+                label: a.label.clone(),
+            })
+        })
+        .collect())
+}
+
+/// Runs a plugin's session action on a saved session and returns the value
+/// the plugin answers with.
+#[command]
+pub async fn run_plugin_session_action(
+    plugin_id: String,
+    action_id: String,
+    session_id: String,
+) -> Result<Value, String> {
+    let process = running_plugin(&plugin_id)?;
+    if !process
+        .manifest
+        .session_actions
+        .iter()
+        .any(|a| a.id == action_id)
+    {
+        return Err(format!(
+            "Plugin {} has no session action named {}",
+            plugin_id, action_id
+        ));
+    }
+    tokio::task::spawn_blocking(move || {
+        let saved = crate::db::load_session(&session_id)?;
+        let session = PluginSession {
+            id: saved.id,
+            server_name: saved.server_name,
+            addr: saved.addr,
+            port: saved.port,
+            username: saved.username,
+        };
+        process.request(|id| {
+            serde_json::to_string(&HostMessage::Action {
+                id,
+                action: &action_id,
+                session: &session,
+            })
+            .map_err(|e| e.to_string())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,323 @@
+use crate::ssh::{shell_quote, SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+/// Most ports checked by one `scan_ports` call
+const MAX_SCAN_PORTS: usize = 256;
+
+/// Ports checked at the same time from this machine
+const SCAN_PARALLELISM: usize = 32;
+
+/// Default time allowed for a connection to be accepted
+const SCAN_DEFAULT_TIMEOUT_MS: u64 = 2000;
+
+/// How long an open port is given to send a greeting
+const BANNER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest banner kept
+const MAX_BANNER_BYTES: usize = 256;
+
+/// Lists listening TCP and UDP sockets with their processes, using
+/// netstat where ss is missing; for servers not probed yet
+const LISTENERS_COMMAND: &str =
+    "if command -v ss >/dev/null; then ss -tulpn; else netstat -tulpn 2>/dev/null; fi";
+
+/// Time allowed for `LISTENERS_COMMAND`
+const LISTENERS_TIMEOUT: Duration = Duration::from_secs(15);
+
+const NO_LISTENER_TOOL: &str = "Neither ss nor netstat is installed on the server";
+
+/// Command listing the listeners of a session, picked from the tools its
+/// capability probe found
+fn listeners_command(session_id: &str) -> Result<&'static str, SshError> {
+    let Some(capabilities) = crate::capabilities::cached(session_id) else {
+        return Ok(LISTENERS_COMMAND);
+    };
+    if capabilities.has_tool("ss") {
+        Ok("ss -tulpn")
+    } else if capabilities.has_tool("netstat") {
+        Ok("netstat -tulpn 2>/dev/null")
+    } else {
+        Err(SshError::OperationFailed(NO_LISTENER_TOOL.to_string()))
+    }
+}
+
+/// First process of an ss `users:(("name",pid=123,fd=4),...)` column
+static SS_PROCESS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\("([^"]*)",pid=(\d+)"#).expect("valid ss process regex"));
+
+/// Outcome of checking one port
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortCheck {
+    pub port: u16,
+    /// "open", "closed" (refused) or "filtered" (no answer within the
+    /// timeout)
+    pub state: String,
+    /// First line(s) the service sent on its own, as SSH, SMTP or FTP
+    /// servers do
+    pub banner: Option<String>,
+}
+
+/// Keeps the printable part of a banner; `None` when nothing is left
+fn clean_banner(bytes: &[u8]) -> Option<String> {
+    let text: String = String::from_utf8_lossy(bytes)
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Checks a port from this machine
+async fn check_local(addr: std::net::SocketAddr, timeout: Duration) -> PortCheck {
+    let port = addr.port();
+    let mut stream = match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr)).await
+    {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(_)) => return port_check(port, "closed", None),
+        Err(_) => return port_check(port, "filtered", None),
+    };
+    let mut buffer = [0u8; MAX_BANNER_BYTES];
+    let banner = match tokio::time::timeout(BANNER_TIMEOUT, stream.read(&mut buffer)).await {
+        Ok(Ok(n)) => clean_banner(&buffer[..n]),
+        _ => None,
+    };
+    port_check(port, "open", banner)
+}
+
+fn port_check(port: u16, state: &str, banner: Option<String>) -> PortCheck {
+    PortCheck {
+        port,
+        state: state.to_string(),
+        banner,
+    }
+}
+
+/// Checks ports from the server with bash's `/dev/tcp`, all at once.
+/// Prints `<port> open <hex banner>`, `<port> closed` or
+/// `<port> filtered` per port. Blocking.
+fn check_remote(
+    handle: &SessionHandle,
+    host: &str,
+    ports: &[u16],
+    timeout: Duration,
+) -> Result<Vec<PortCheck>, SshError> {
+    let limit = (timeout + BANNER_TIMEOUT).as_secs_f64();
+    let ports_list: Vec<String> = ports.iter().map(u16::to_string).collect();
+    let command = format!(
+        "command -v bash >/dev/null && command -v timeout >/dev/null || exit 127; \
+         h={host}; for p in {ports}; do (\
+         b=$(timeout {limit:.1} bash -c 'exec 3<>\"/dev/tcp/$0/$1\" || exit 1; printf ok; \
+         timeout {banner} head -c {max} <&3 | od -An -tx1 | tr -d \" \\n\"' \"$h\" \"$p\" 2>/dev/null); \
+         rc=$?; case \"$b\" in ok*) echo \"$p open ${{b#ok}}\";; \
+         *) if [ $rc = 124 ]; then echo \"$p filtered\"; else echo \"$p closed\"; fi;; esac) & \
+         done; wait",
+        host = shell_quote(host),
+        ports = ports_list.join(" "),
+        limit = limit,
+        banner = BANNER_TIMEOUT.as_secs(),
+        max = MAX_BANNER_BYTES,
+    );
+    let output = handle.exec_interruptible(
+        &command,
+        timeout + BANNER_TIMEOUT + Duration::from_secs(10),
+        &AtomicBool::new(false),
+    )?;
+    if output.exit_status == Some(127) {
+        return Err(SshError::OperationFailed(
+            "Checking ports from the server needs bash and timeout".to_string(),
+        ));
+    }
+
+    let mut checks: Vec<PortCheck> = output
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let port = fields.next()?.parse().ok()?;
+            let state = fields.next()?;
+            let banner = fields.next().filter(|h| h.is_ascii()).and_then(|hex| {
+                let bytes: Vec<u8> = (0..hex.len() / 2)
+                    .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+                    .collect();
+                clean_banner(&bytes)
+            });
+            Some(port_check(port, state, banner))
+        })
+        .collect();
+    // Ports without a line did not finish in time
+    for &port in ports {
+        if !checks.iter().any(|c| c.port == port) {
+            checks.push(port_check(port, "filtered", None));
+        }
+    }
+    Ok(checks)
+}
+
+/// A socket listening on the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Listener {
+    /// "tcp" or "udp"
+    pub proto: String,
+    /// Bound address; `0.0.0.0`, `::` or `*` for all interfaces
+    pub address: String,
+    pub port: u16,
+    /// Only known for processes of the logged-in user, or all as root
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// Splits `addr:port`, `[v6]:port` or `v6:port` at the last colon;
+/// ss's `%interface` suffix is dropped
+fn split_address(local: &str) -> Option<(String, u16)> {
+    let (address, port) = local.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let address = address.split('%').next().unwrap_or(address);
+    Some((address.to_string(), port))
+}
+
+/// Parses `ss -tulpn` or `netstat -tulpn` output
+fn parse_listeners(output: &str) -> Vec<Listener> {
+    let mut listeners: Vec<Listener> = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let proto = fields.first()?;
+            let proto = if proto.starts_with("tcp") {
+                "tcp"
+            } else if proto.starts_with("udp") {
+                "udp"
+            } else {
+                return None;
+            };
+            // ss: Netid State Recv-Q Send-Q Local Peer [Process]
+            // netstat: Proto Recv-Q Send-Q Local Foreign [State] [PID/Program]
+            let is_ss = fields.get(1)?.parse::<u64>().is_err();
+            let (address, port) = split_address(fields.get(if is_ss { 4 } else { 3 })?)?;
+            let (pid, process) = if is_ss {
+                let process = fields.get(6..).map(|f| f.join(" ")).unwrap_or_default();
+                SS_PROCESS
+                    .captures(&process)
+                    .map(|c| (c[2].parse().ok(), Some(c[1].to_string())))
+                    .unwrap_or((None, None))
+            } else {
+                fields
+                    .last()
+                    .and_then(|f| f.split_once('/'))
+                    .map(|(pid, name)| (pid.parse().ok(), Some(name.to_string())))
+                    .unwrap_or((None, None))
+            };
+            Some(Listener {
+                proto: proto.to_string(),
+                address,
+                port,
+                pid,
+                process,
+            })
+        })
+        .collect();
+    listeners.sort_by(|a, b| (a.port, &a.proto, &a.address).cmp(&(b.port, &b.proto, &b.address)));
+    listeners.dedup();
+    listeners
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Checks whether TCP ports accept connections and reads the greeting of
+/// open ones. With `sessionId` the check runs on that server (which needs
+/// bash), against `host` or its own loopback address; otherwise it runs
+/// from this machine against `host`. Results are sorted by port.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn scan_ports(
+    state: tauri::State<'_, SshManager>,
+    sessionId: Option<String>,
+    host: Option<String>,
+    ports: Vec<u16>,
+    timeoutMs: Option<u64>,
+) -> Result<Vec<PortCheck>, SshError> {
+    let mut ports = ports;
+    ports.sort_unstable();
+    ports.dedup();
+    if ports.is_empty() {
+        return Err(SshError::OperationFailed("No ports given".to_string()));
+    }
+    if ports.len() > MAX_SCAN_PORTS {
+        return Err(SshError::OperationFailed(format!(
+            "At most {} ports can be checked at once",
+            MAX_SCAN_PORTS
+        )));
+    }
+    let timeout = Duration::from_millis(timeoutMs.unwrap_or(SCAN_DEFAULT_TIMEOUT_MS));
+    let host = host.map(|h| h.trim().to_string()).filter(|h| !h.is_empty());
+
+    let mut checks = if let Some(session_id) = sessionId {
+        let handle = state.session_handle(&SessionId::from(session_id))?;
+        let host = host.unwrap_or_else(|| "127.0.0.1".to_string());
+        tokio::task::spawn_blocking(move || check_remote(&handle, &host, &ports, timeout))
+            .await
+            .map_err(|e| SshError::TaskError(e.to_string()))??
+    } else {
+        let host = host.ok_or_else(|| SshError::OperationFailed("No host given".to_string()))?;
+        let addr = tokio::net::lookup_host((host.as_str(), 0))
+            .await
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| SshError::OperationFailed(format!("Failed to resolve {}", host)))?;
+        let permits = Arc::new(tokio::sync::Semaphore::new(SCAN_PARALLELISM));
+        let mut tasks = tokio::task::JoinSet::new();
+        for port in ports {
+            let permits = permits.clone();
+            let mut addr = addr;
+            addr.set_port(port);
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.ok()?;
+                Some(check_local(addr, timeout).await)
+            });
+        }
+        let mut checks = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(Some(check)) = joined {
+                checks.push(check);
+            }
+        }
+        checks
+    };
+    checks.sort_by_key(|c| c.port);
+    Ok(checks)
+}
+
+/// Lists the TCP and UDP sockets listening on a connected server, sorted
+/// by port. Uses `ss`, or `netstat` where it is missing, as found by the
+/// capability probe.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn list_remote_listeners(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<Vec<Listener>, SshError> {
+    let command = listeners_command(&sessionId)?;
+    let handle = state.session_handle(&SessionId::from(sessionId))?;
+    let output = tokio::task::spawn_blocking(move || {
+        handle.exec_interruptible(command, LISTENERS_TIMEOUT, &AtomicBool::new(false))
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))??;
+    if output.exit_status == Some(127) {
+        return Err(SshError::OperationFailed(NO_LISTENER_TOOL.to_string()));
+    }
+    if output.stdout.trim().is_empty() {
+        return Err(SshError::OperationFailed(output.error_message("ss")));
+    }
+    Ok(parse_listeners(&output.stdout))
+}
@@ -0,0 +1,153 @@
+use crate::settings::QuickConnectSettings;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// Label of the quick-connect window
+pub const WINDOW_LABEL: &str = "quick-connect";
+
+/// Accelerator currently registered for the quick-connect window
+static REGISTERED: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Payload of the `quick-connect-open` event sent to the main window
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickConnectOpen {
+    pub session_id: String,
+}
+
+/// Shows the quick-connect window, creating it on first use. It hides
+/// again when it loses focus, so it can be summoned over other apps.
+fn show_window(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())?;
+        let _ = app.emit_to(WINDOW_LABEL, "quick-connect-shown", ());
+        return Ok(());
+    }
+    let window = WebviewWindowBuilder::new(
+        app,
+        WINDOW_LABEL,
+        WebviewUrl::App("index.html?window=quick-connect".into()),
+    )
+    .title("Quick Connect")
+    .inner_size(560.0, 380.0)
+    .resizable(false)
+    .decorations(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .center()
+    .focused(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = handle.hide();
+        }
+    });
+    Ok(())
+}
+
+fn toggle_window(app: &AppHandle) {
+    let visible = app
+        .get_webview_window(WINDOW_LABEL)
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false);
+    let result = if visible {
+        hide_window(app)
+    } else {
+        show_window(app)
+    };
+    if let Err(e) = result {
+        tracing::warn!("{}", e);
+    }
+}
+
+fn hide_window(app: &AppHandle) -> Result<(), String> {
+    match app.get_webview_window(WINDOW_LABEL) {
+        Some(window) => window.hide().map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Registers the configured hotkey in place of the previous one. When the
+/// new hotkey cannot be registered (invalid, or taken by another app) the
+/// previous one stays active.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn apply_settings(app: &AppHandle, settings: &QuickConnectSettings) -> Result<(), String> {
+    let mut registered = REGISTERED.lock().map_err(|e| e.to_string())?;
+    let wanted = Some(settings.hotkey.trim())
+        .filter(|h| settings.enabled && !h.is_empty())
+        .map(str::to_string);
+    if *registered == wanted {
+        return Ok(());
+    }
+    let shortcuts = app.global_shortcut();
+    if let Some(hotkey) = &wanted {
+        shortcuts
+            .on_shortcut(hotkey.as_str(), |app, _shortcut, event| {
+                if event.state() == ShortcutState::Pressed {
+                    toggle_window(app);
+                }
+            })
+            .map_err(|e| format!("Failed to register {}: {}", hotkey, e))?;
+    }
+    if let Some(previous) = registered.take() {
+        let _ = shortcuts.unregister(previous.as_str());
+    }
+    *registered = wanted;
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+pub fn apply_settings(_app: &AppHandle, _settings: &QuickConnectSettings) -> Result<(), String> {
+    Ok(())
+}
+
+/// Registers the quick-connect hotkey from the settings
+pub fn init(app: &AppHandle) {
+    if let Err(e) = apply_settings(app, &crate::settings::current().quick_connect) {
+        tracing::warn!("{}", e);
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Shows the quick-connect window. Async so the window is not created on
+/// the main thread, which deadlocks on Windows.
+#[command]
+pub async fn show_quick_connect(app: AppHandle) -> Result<(), String> {
+    show_window(&app)
+}
+
+/// Hides the quick-connect window, e.g. on Escape.
+#[command]
+pub fn hide_quick_connect(app: AppHandle) -> Result<(), String> {
+    hide_window(&app)
+}
+
+/// Opens a session picked in the quick-connect window: hides it, brings up
+/// the main window and asks it to open a connected tab with a
+/// `quick-connect-open` event.
+#[command]
+pub fn quick_connect_open(app: AppHandle, session_id: String) -> Result<(), String> {
+    hide_window(&app)?;
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    let _ = window.show();
+    let _ = window.set_focus();
+    app.emit_to(
+        "main",
+        "quick-connect-open",
+        &QuickConnectOpen { session_id },
+    )
+    .map_err(|e| e.to_string())
+}
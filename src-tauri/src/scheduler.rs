@@ -0,0 +1,423 @@
+use crate::db::ScheduledTask;
+use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDateTime, TimeZone, Timelike};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+/// How often the scheduler looks for due tasks; minutes passed between two
+/// ticks are all checked, so a late tick does not skip a run
+const SCHEDULER_TICK: Duration = Duration::from_secs(20);
+
+/// Minutes caught up at most after a stall (e.g. the machine slept)
+const MAX_CATCH_UP_MINUTES: i64 = 5;
+
+/// Output stored per run at most
+const MAX_RUN_OUTPUT: usize = 64 * 1024;
+
+/// Tasks currently running, so a slow run is never started twice at once
+static RUNNING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week (0 or 7 is Sunday). Fields take `*`, numbers, ranges (`1-5`),
+/// lists (`1,15`) and steps (`*/10`, `8-18/2`). `@hourly`, `@daily`,
+/// `@weekly`, `@monthly` and `@yearly` are accepted as shorthands.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day-of-month and day-of-week fields were `*`; when both
+    /// are restricted a day matching either runs, as in cron
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("Invalid step: {}", part))?,
+            ),
+            None => (part, 1),
+        };
+        let number = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("{} is out of range {}-{}", s, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // A single value with a step runs from that value on
+                None if part.contains('/') => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("Invalid range: {}", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        let day = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        day && self.months & (1 << time.month()) != 0
+    }
+
+    /// Whether the schedule fires in the minute of `time` (local time)
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        self.matches_day(time)
+            && self.hours & (1 << time.hour()) != 0
+            && self.minutes & (1 << time.minute()) != 0
+    }
+
+    /// First minute after `time` the schedule fires in, within a year
+    pub fn next_after(&self, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next = time.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let limit = next + ChronoDuration::days(366);
+        while next < limit {
+            if !self.matches_day(&next) {
+                next = next.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << next.hour()) == 0 {
+                next = next.with_minute(0)? + ChronoDuration::hours(1);
+            } else if self.minutes & (1 << next.minute()) == 0 {
+                next += ChronoDuration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+/// Unix milliseconds of the next run of a schedule, for listings
+pub fn next_run_millis(expression: &str) -> Option<i64> {
+    let schedule = CronSchedule::parse(expression).ok()?;
+    let next = schedule.next_after(&Local::now().naive_local())?;
+    Local
+        .from_local_datetime(&next)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+}
+
+/// Payload of the `scheduled-task-run` event, sent when a run has finished
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTaskResult {
+    pub task_id: String,
+    /// "ok", or a summary of the failures
+    pub status: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+fn now_millis() -> i64 {
+    Local::now().timestamp_millis()
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_RUN_OUTPUT {
+        let mut cut = MAX_RUN_OUTPUT;
+        while !output.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        output.truncate(cut);
+    }
+    output
+}
+
+/// Runs a task on each of its sessions over fresh connections and records
+/// a history entry per session. The output of a "backup" task is stored as
+/// a snapshot instead. Blocking.
+fn run_task(app: &AppHandle, task: &ScheduledTask) -> ScheduledTaskResult {
+    let started_at = now_millis();
+    let workflow = match task.kind.as_str() {
+        "workflow" => Some(crate::db::load_workflow(&task.workflow_id)),
+        _ => None,
+    };
+
+    // (session id, status, output, error) per session
+    let outcomes: Vec<(String, bool, String, Option<String>)> = match workflow {
+        Some(Err(e)) => task
+            .session_ids
+            .iter()
+            .map(|id| (id.clone(), false, String::new(), Some(e.clone())))
+            .collect(),
+        Some(Ok(workflow)) => {
+            let mut results = Vec::new();
+            let mut targets = Vec::new();
+            for id in &task.session_ids {
+                let connected = crate::db::load_session(id).and_then(|saved| {
+                    crate::ssh::connect_unattended(&saved).map_err(|e| e.to_string())
+                });
+                match connected {
+                    Ok(sess) => {
+                        targets.push((id.clone(), crate::ssh::SessionHandle::detached(sess)))
+                    }
+                    Err(e) => results.push((id.clone(), false, String::new(), Some(e))),
+                }
+            }
+            match crate::workflow::run_blocking(app, &workflow, &targets) {
+                Ok(summary) => {
+                    for (id, _) in &targets {
+                        let ok = summary.succeeded.contains(id);
+                        let error = match summary.errors.get(id) {
+                            Some(error) => Some(error.clone()),
+                            None if !ok => Some("Cancelled".to_string()),
+                            None => None,
+                        };
+                        results.push((id.clone(), ok, String::new(), error));
+                    }
+                }
+                Err(e) => {
+                    for (id, _) in &targets {
+                        results.push((id.clone(), false, String::new(), Some(e.clone())));
+                    }
+                }
+            }
+            for (_, handle) in &targets {
+                handle.blocking(|sess| {
+                    let _ = sess.disconnect(None, "scheduled task complete", None);
+                });
+            }
+            results
+        }
+        None => std::thread::scope(|scope| {
+            let runs: Vec<_> = task
+                .session_ids
+                .iter()
+                .map(|id| {
+                    scope.spawn(move || {
+                        let result = crate::db::load_session(id).and_then(|saved| {
+                            let handle = crate::ssh::SessionHandle::detached(
+                                crate::ssh::connect_unattended(&saved)
+                                    .map_err(|e| e.to_string())?,
+                            );
+                            let output = handle
+                                .exec_capture(&task.command)
+                                .map_err(|e| e.to_string());
+                            handle.blocking(|sess| {
+                                let _ = sess.disconnect(None, "scheduled task complete", None);
+                            });
+                            output
+                        });
+                        if task.kind == "backup" {
+                            return match crate::backup::record_run(
+                                app, &task.id, id, started_at, result,
+                            ) {
+                                Ok(Some(snapshot_id)) => (
+                                    id.clone(),
+                                    true,
+                                    format!("Stored snapshot {}", snapshot_id),
+                                    None,
+                                ),
+                                Ok(None) => (
+                                    id.clone(),
+                                    true,
+                                    "Output unchanged since the last snapshot".to_string(),
+                                    None,
+                                ),
+                                Err(e) => (id.clone(), false, String::new(), Some(e)),
+                            };
+                        }
+                        match result {
+                            Ok(output) if output.exit_status == Some(0) => {
+                                (id.clone(), true, truncate_output(output.stdout), None)
+                            }
+                            Ok(output) => {
+                                let error = output.error_message("Command");
+                                (
+                                    id.clone(),
+                                    false,
+                                    truncate_output(output.stdout),
+                                    Some(error),
+                                )
+                            }
+                            Err(e) => (id.clone(), false, String::new(), Some(e)),
+                        }
+                    })
+                })
+                .collect();
+            runs.into_iter().filter_map(|run| run.join().ok()).collect()
+        }),
+    };
+
+    let finished_at = now_millis();
+    let failed = outcomes.iter().filter(|(_, ok, _, _)| !ok).count();
+    let succeeded = outcomes.len() - failed;
+    for (session_id, ok, output, error) in &outcomes {
+        let status = if *ok { "ok" } else { "error" };
+        if let Err(e) = crate::db::record_scheduled_run(
+            &task.id,
+            session_id,
+            started_at,
+            finished_at,
+            status,
+            output,
+            error.as_deref(),
+        ) {
+            crate::error_bus::report(
+                "scheduler",
+                None,
+                format!("failed to record run of {}: {}", task.id, e),
+            );
+        }
+    }
+    let status = match (failed, &outcomes[..]) {
+        (0, _) => "ok".to_string(),
+        (1, [(_, _, _, Some(error))]) => error.clone(),
+        _ => format!("{} of {} sessions failed", failed, outcomes.len()),
+    };
+    if let Err(e) = crate::db::mark_scheduled_task_run(&task.id, started_at, &status) {
+        crate::error_bus::report(
+            "scheduler",
+            None,
+            format!("failed to record run of {}: {}", task.id, e),
+        );
+    }
+    ScheduledTaskResult {
+        task_id: task.id.clone(),
+        status,
+        succeeded,
+        failed,
+        started_at,
+        finished_at,
+    }
+}
+
+/// Runs a task off the async runtime, skipping it when already running
+async fn run_task_guarded(
+    app: &AppHandle,
+    task: ScheduledTask,
+) -> Result<ScheduledTaskResult, String> {
+    {
+        let mut running = RUNNING.lock().map_err(|e| e.to_string())?;
+        if !running.insert(task.id.clone()) {
+            return Err("Scheduled task is already running".to_string());
+        }
+    }
+    let task_id = task.id.clone();
+    let app_for_run = app.clone();
+    let joined = tokio::task::spawn_blocking(move || run_task(&app_for_run, &task)).await;
+    if let Ok(mut running) = RUNNING.lock() {
+        running.remove(&task_id);
+    }
+    let result = joined.map_err(|e| e.to_string())?;
+    let _ = app.emit("scheduled-task-run", &result);
+    Ok(result)
+}
+
+/// Starts the background scheduler that runs scheduled tasks whose cron
+/// expression matches the current local minute
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let minute = |t: NaiveDateTime| t.with_second(0).and_then(|t| t.with_nanosecond(0));
+        let mut checked = minute(Local::now().naive_local());
+        loop {
+            tokio::time::sleep(SCHEDULER_TICK).await;
+            let Some(now) = minute(Local::now().naive_local()) else {
+                continue;
+            };
+            let from = match checked {
+                // The clock went back; start over rather than run twice
+                Some(last) if last > now => now,
+                Some(last) => last.max(now - ChronoDuration::minutes(MAX_CATCH_UP_MINUTES)),
+                None => now - ChronoDuration::minutes(1),
+            };
+            if from == now {
+                checked = Some(now);
+                continue;
+            }
+            checked = Some(now);
+            let tasks = match tokio::task::spawn_blocking(crate::db::list_scheduled_tasks).await {
+                Ok(Ok(tasks)) => tasks,
+                _ => continue,
+            };
+            for task in tasks.into_iter().filter(|t| t.enabled) {
+                let Ok(schedule) = CronSchedule::parse(&task.schedule) else {
+                    continue;
+                };
+                // Minutes in (from, now]
+                let due = (1..=(now - from).num_minutes())
+                    .any(|m| schedule.matches(&(from + ChronoDuration::minutes(m))));
+                if due {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = run_task_guarded(&app, task).await;
+                    });
+                }
+            }
+        }
+    });
+}
+
+/// Runs a scheduled task immediately.
+#[command]
+pub async fn run_scheduled_task(
+    app: AppHandle,
+    task_id: String,
+) -> Result<ScheduledTaskResult, String> {
+    let task = tokio::task::spawn_blocking(move || crate::db::load_scheduled_task(&task_id))
+        .await
+        .map_err(|e| e.to_string())??;
+    run_task_guarded(&app, task).await
+}
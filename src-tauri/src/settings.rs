@@ -0,0 +1,431 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::RwLock;
+use tauri::{command, AppHandle, Emitter};
+
+/// Settings loaded from the database, cached until the next change
+static CACHE: Lazy<RwLock<Option<Settings>>> = Lazy::new(|| RwLock::new(None));
+
+/// Application settings. Stored as one row per dotted key (e.g.
+/// `terminal.defaultCols`); missing keys fall back to these defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub terminal: TerminalSettings,
+    pub monitoring: MonitoringSettings,
+    pub logging: LoggingSettings,
+    pub database: DatabaseSettings,
+    pub sync: SyncSettings,
+    pub versioning: VersioningSettings,
+    pub transfers: TransferSettings,
+    pub clipboard: ClipboardSettings,
+    pub quick_connect: QuickConnectSettings,
+    pub notifications: NotificationSettings,
+    pub security: SecuritySettings,
+    pub restore: RestoreSettings,
+    pub plugins: PluginSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalSettings {
+    /// Size used when a terminal is opened before the frontend knows its size
+    pub default_cols: u16,
+    pub default_rows: u16,
+    /// TERM requested for SSH sessions that do not set their own
+    pub term: String,
+    /// Shell for local terminals; defaults to `$SHELL` (PowerShell on Windows)
+    pub local_shell: Option<String>,
+    /// Attach URL and file path spans to output chunks
+    pub detect_links: bool,
+    /// Show iTerm2 (OSC 1337) and Sixel images printed by remote programs
+    pub inline_images: bool,
+    /// Keep a full-text index of session output for `search_output`, so
+    /// the frontend does not have to hold all of it
+    pub index_output: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonitoringSettings {
+    /// Initial interval of the remote status monitor
+    pub refresh_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoggingSettings {
+    /// "error", "warn", "info", "debug" or "trace"
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DatabaseSettings {
+    /// Hours between automatic backups; 0 disables them
+    pub backup_interval_hours: i64,
+    /// Number of backups kept
+    pub backup_retention: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    /// "folder" (Dropbox, iCloud Drive, Syncthing...) or "webdav"
+    pub target: String,
+    pub folder_path: Option<String>,
+    /// WebDAV collection URL the library file is stored in
+    pub webdav_url: Option<String>,
+    pub webdav_username: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VersioningSettings {
+    /// Commit the session library to a local git repository on every edit
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferSettings {
+    /// Carry file mode and modification time over on SFTP transfers
+    pub preserve_attributes: bool,
+    /// Local editor `open_remote_in_editor` starts, e.g. `code --wait`;
+    /// quote a program path that contains spaces
+    pub editor_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardSettings {
+    /// Remember text copied from terminals per saved session. Off by
+    /// default: copied text may hold secrets and is stored unencrypted.
+    pub history_enabled: bool,
+    /// Entries kept per session
+    pub history_limit: usize,
+    /// Larger copies are not remembered
+    pub max_entry_bytes: usize,
+    /// Whether remote programs may set the clipboard through OSC 52:
+    /// "deny", "ask" or "allow". Saved sessions can override it.
+    pub osc52: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuickConnectSettings {
+    /// Register the system-wide hotkey
+    pub enabled: bool,
+    /// Accelerator that toggles the quick-connect window, e.g.
+    /// `CommandOrControl+Shift+Space`
+    pub hotkey: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    /// Master switch for native notifications
+    pub enabled: bool,
+    /// Connections that drop without being closed from the app
+    pub disconnects: bool,
+    /// Finished and failed transfers
+    pub transfers: bool,
+    /// CPU above 90% or disk above 95% on a monitored server
+    pub thresholds: bool,
+    /// Triggers with the "notify" action
+    pub triggers: bool,
+    /// Stay quiet while an app window has focus
+    pub only_when_unfocused: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SecuritySettings {
+    /// Minutes without user input before an SSH session is locked or
+    /// disconnected; 0 disables the timeout. Saved sessions can override it.
+    pub idle_timeout_minutes: u32,
+    /// "lock" (input is refused until unlocked) or "disconnect"
+    pub idle_action: String,
+    /// Seconds before the timeout at which a warning is emitted
+    pub idle_warning_seconds: u32,
+    /// Hold commands matching `dangerous_commands` on guarded sessions
+    /// until they are confirmed
+    pub command_guard: bool,
+    /// Saved sessions with any of these tags are guarded
+    pub guarded_tags: Vec<String>,
+    /// Regular expressions matched against each submitted command line
+    pub dangerous_commands: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RestoreSettings {
+    /// Offer the tabs open when the app last quit or crashed on startup
+    pub reopen_sessions: bool,
+    /// Reconnect them without asking
+    pub auto_reconnect: bool,
+}
+
+/// Plugins are started only once the user enables them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PluginSettings {
+    /// Ids of the plugins to start
+    pub enabled: Vec<String>,
+    /// Ids of the plugins allowed to receive terminal output, when their
+    /// manifest asks for it
+    pub output_access: Vec<String>,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            default_cols: 80,
+            default_rows: 24,
+            term: "xterm-256color".to_string(),
+            local_shell: None,
+            detect_links: true,
+            inline_images: true,
+            index_output: false,
+        }
+    }
+}
+
+impl Default for MonitoringSettings {
+    fn default() -> Self {
+        Self {
+            refresh_interval_ms: 3000,
+        }
+    }
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            backup_interval_hours: 24,
+            backup_retention: 7,
+        }
+    }
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: "folder".to_string(),
+            folder_path: None,
+            webdav_url: None,
+            webdav_username: None,
+        }
+    }
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            history_enabled: false,
+            history_limit: 50,
+            max_entry_bytes: 16 * 1024,
+            osc52: "ask".to_string(),
+        }
+    }
+}
+
+impl Default for QuickConnectSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hotkey: "CommandOrControl+Shift+Space".to_string(),
+        }
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            disconnects: true,
+            transfers: true,
+            thresholds: true,
+            triggers: true,
+            only_when_unfocused: true,
+        }
+    }
+}
+
+impl Default for SecuritySettings {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: 0,
+            idle_action: "lock".to_string(),
+            idle_warning_seconds: 60,
+            command_guard: false,
+            guarded_tags: vec!["production".to_string()],
+            dangerous_commands: [
+                r"\brm\s+(-\S+\s+)*-\S*[rR]\S*\s+(-\S+\s+)*/\*?(\s|$)",
+                r"\bmkfs(\.\w+)?\b",
+                r"\bdd\b.*\bof=/dev/",
+                r"(?i)\bdrop\s+(database|schema)\b",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+impl Default for RestoreSettings {
+    fn default() -> Self {
+        Self {
+            reopen_sessions: true,
+            auto_reconnect: false,
+        }
+    }
+}
+
+/// Payload of the `settings-changed` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub key: String,
+    pub value: Value,
+    /// All settings after the change, as returned by `get_settings`
+    #[schemars(with = "Value")]
+    pub settings: Settings,
+}
+
+/// Sets `key` (dotted path) in `tree`. Only keys that exist in the defaults
+/// can be set.
+fn set_path(tree: &mut Value, key: &str, value: Value) -> Result<(), String> {
+    let mut node = tree;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let map = node
+            .as_object_mut()
+            .ok_or_else(|| format!("Unknown setting: {}", key))?;
+        let child = map
+            .get_mut(part)
+            .ok_or_else(|| format!("Unknown setting: {}", key))?;
+        if parts.peek().is_none() {
+            if child.is_object() {
+                return Err(format!("Setting {} is a section, not a value", key));
+            }
+            *child = value;
+            return Ok(());
+        }
+        node = child;
+    }
+    Err(format!("Unknown setting: {}", key))
+}
+
+/// Builds settings from the defaults and the stored rows
+fn build(rows: Vec<(String, String)>) -> Settings {
+    let mut tree = serde_json::to_value(Settings::default()).unwrap_or(Value::Null);
+    for (key, json) in rows {
+        // Stale or malformed rows are ignored rather than breaking startup
+        if let Ok(value) = serde_json::from_str(&json) {
+            let mut candidate = tree.clone();
+            if set_path(&mut candidate, &key, value).is_ok()
+                && serde_json::from_value::<Settings>(candidate.clone()).is_ok()
+            {
+                tree = candidate;
+            }
+        }
+    }
+    serde_json::from_value(tree).unwrap_or_default()
+}
+
+/// Current settings (backend use)
+pub fn current() -> Settings {
+    if let Some(settings) = CACHE.read().ok().and_then(|c| c.clone()) {
+        return settings;
+    }
+    let settings = build(crate::db::load_settings().unwrap_or_default());
+    if let Ok(mut cache) = CACHE.write() {
+        *cache = Some(settings.clone());
+    }
+    settings
+}
+
+/// Drops the cached settings so they are reloaded, e.g. after a restore
+pub fn invalidate() {
+    if let Ok(mut cache) = CACHE.write() {
+        *cache = None;
+    }
+}
+
+/// Applies settings that take effect outside the settings cache. Runs
+/// before the change is stored so a rejected value is not kept.
+fn apply(app: &AppHandle, key: &str, settings: &Settings) -> Result<(), String> {
+    if key.starts_with("quickConnect.") {
+        crate::quickconnect::apply_settings(app, &settings.quick_connect)?;
+    }
+    if key.starts_with("logging.") {
+        crate::logging::set_level(&settings.logging.level)?;
+    }
+    if key.starts_with("plugins.") {
+        crate::plugins::apply_settings(app, &settings.plugins);
+    }
+    Ok(())
+}
+
+/// Returns all settings with defaults filled in.
+#[command]
+pub fn get_settings() -> Settings {
+    current()
+}
+
+/// Changes one setting, e.g. `set_setting("terminal.defaultCols", 120)`,
+/// and emits `settings-changed`. A `null` value restores the default.
+#[command]
+pub fn set_setting(app: AppHandle, key: String, value: Value) -> Result<Settings, String> {
+    let mut tree = serde_json::to_value(current()).map_err(|e| e.to_string())?;
+    let settings = if value.is_null() {
+        let default = serde_json::to_value(Settings::default()).map_err(|e| e.to_string())?;
+        let default_value = key
+            .split('.')
+            .try_fold(&default, |node, part| node.get(part))
+            .cloned()
+            .ok_or_else(|| format!("Unknown setting: {}", key))?;
+        set_path(&mut tree, &key, default_value)?;
+        let settings = serde_json::from_value::<Settings>(tree).map_err(|e| e.to_string())?;
+        apply(&app, &key, &settings)?;
+        crate::db::delete_setting(&key)?;
+        settings
+    } else {
+        set_path(&mut tree, &key, value.clone())?;
+        let settings = serde_json::from_value::<Settings>(tree)
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+        apply(&app, &key, &settings)?;
+        crate::db::save_setting(&key, &value.to_string())?;
+        settings
+    };
+
+    if let Ok(mut cache) = CACHE.write() {
+        *cache = Some(settings.clone());
+    }
+    if key.starts_with("versioning.") {
+        // Record the starting point as soon as versioning is turned on
+        crate::config_history::schedule_commit();
+    }
+    let _ = app.emit(
+        "settings-changed",
+        &SettingsChanged {
+            key,
+            value,
+            settings: settings.clone(),
+        },
+    );
+    Ok(settings)
+}
@@ -0,0 +1,551 @@
+use crate::ssh::{SessionId, SshError, SshManager};
+use crate::transfer::{TransferJob, TransferManager};
+use serde::Serialize;
+use ssh2::{FileStat, FileType, OpenFlags, OpenType, Sftp};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// One entry of a remote directory listing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEntry {
+    pub name: String,
+    pub path: String,
+    /// "file", "dir", "symlink", "socket", "fifo", "char", "block" or
+    /// "other"; symlinks are not followed
+    pub kind: String,
+    pub size: u64,
+    /// Permission bits, without the file type
+    pub mode: u32,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Names of `uid` and `gid`; `None` when the server does not list them
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// Unix seconds
+    pub modified: Option<u64>,
+    /// Symlink target as stored in the link
+    pub link_target: Option<String>,
+    /// Kind of what a symlink points to; `None` for dangling links
+    pub target_kind: Option<String>,
+    /// Absolute path a symlink resolves to, for navigating into linked
+    /// directories
+    pub resolved_path: Option<String>,
+}
+
+/// Outcome of `paste_clipboard_as_remote_file`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardPaste {
+    /// "text" when the clipboard text was written to a remote file,
+    /// "files" when copied local files were queued for upload
+    pub kind: String,
+    /// Remote paths written or being uploaded to
+    pub paths: Vec<String>,
+    /// Transfer ids of the queued uploads, reported through
+    /// `upload-progress` events
+    pub task_ids: Vec<String>,
+}
+
+/// User and group names of a server by id, read from its /etc/passwd and
+/// /etc/group. Accounts from directory services (LDAP, NIS) are not in
+/// these files and stay unnamed.
+#[derive(Debug, Default)]
+pub struct OwnerNames {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// Reads both files; one that cannot be read leaves its names empty
+    pub fn read(sftp: &Sftp) -> Self {
+        Self {
+            users: read_id_names(sftp, "/etc/passwd"),
+            groups: read_id_names(sftp, "/etc/group"),
+        }
+    }
+
+    pub fn user(&self, uid: u32) -> Option<&str> {
+        self.users.get(&uid).map(String::as_str)
+    }
+
+    pub fn group(&self, gid: u32) -> Option<&str> {
+        self.groups.get(&gid).map(String::as_str)
+    }
+
+    /// Id of a user given by name or number
+    fn uid_of(&self, user: &str) -> Result<u32, SshError> {
+        find_id(&self.users, user)
+            .ok_or_else(|| SshError::OperationFailed(format!("Unknown user: {}", user)))
+    }
+
+    /// Id of a group given by name or number
+    fn gid_of(&self, group: &str) -> Result<u32, SshError> {
+        find_id(&self.groups, group)
+            .ok_or_else(|| SshError::OperationFailed(format!("Unknown group: {}", group)))
+    }
+}
+
+fn find_id(names: &HashMap<u32, String>, name: &str) -> Option<u32> {
+    name.parse().ok().or_else(|| {
+        names
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(id, _)| *id)
+    })
+}
+
+/// Parses `name:password:id:...` lines, the layout shared by passwd and
+/// group files. The first name listed for an id wins.
+fn read_id_names(sftp: &Sftp, path: &str) -> HashMap<u32, String> {
+    let mut text = String::new();
+    let read = sftp
+        .open(Path::new(path))
+        .map_err(std::io::Error::from)
+        .and_then(|mut file| file.read_to_string(&mut text));
+    let mut names = HashMap::new();
+    if read.is_err() {
+        return names;
+    }
+    for line in text.lines() {
+        let mut fields = line.split(':');
+        let (Some(name), Some(id)) = (fields.next(), fields.nth(1)) else {
+            continue;
+        };
+        if let Ok(id) = id.parse() {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+fn kind_name(stat: &FileStat) -> &'static str {
+    match stat.file_type() {
+        FileType::RegularFile => "file",
+        FileType::Directory => "dir",
+        FileType::Symlink => "symlink",
+        FileType::Socket => "socket",
+        FileType::NamedPipe => "fifo",
+        FileType::CharDevice => "char",
+        FileType::BlockDevice => "block",
+        FileType::Other(_) => "other",
+    }
+}
+
+fn sftp_error(action: &str, path: &str, e: ssh2::Error) -> SshError {
+    SshError::OperationFailed(format!("Failed to {} {}: {}", action, path, e))
+}
+
+/// Builds a listing entry from `lstat` attributes, resolving symlinks
+fn remote_entry(sftp: &Sftp, names: &OwnerNames, path: &Path, stat: &FileStat) -> RemoteEntry {
+    let mut entry = RemoteEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+        path: path.to_string_lossy().into_owned(),
+        kind: kind_name(stat).to_string(),
+        size: stat.size.unwrap_or(0),
+        mode: stat.perm.unwrap_or(0) & 0o7777,
+        uid: stat.uid,
+        gid: stat.gid,
+        owner: stat.uid.and_then(|uid| names.user(uid)).map(String::from),
+        group: stat.gid.and_then(|gid| names.group(gid)).map(String::from),
+        modified: stat.mtime,
+        link_target: None,
+        target_kind: None,
+        resolved_path: None,
+    };
+    if stat.file_type() == FileType::Symlink {
+        entry.link_target = sftp
+            .readlink(path)
+            .ok()
+            .map(|t| t.to_string_lossy().into_owned());
+        // A dangling link fails to stat; it is still listed
+        if let Ok(target) = sftp.stat(path) {
+            entry.target_kind = Some(kind_name(&target).to_string());
+            entry.resolved_path = sftp
+                .realpath(path)
+                .ok()
+                .map(|p| p.to_string_lossy().into_owned());
+        }
+    }
+    entry
+}
+
+/// Runs an SFTP operation on the session's cached subsystem off the async
+/// runtime
+async fn run<T: Send + 'static>(
+    state: &SshManager,
+    session_id: String,
+    f: impl FnOnce(&Sftp) -> Result<T, SshError> + Send + 'static,
+) -> Result<T, SshError> {
+    let handle = state.session_handle(&SessionId::from(session_id))?;
+    tokio::task::spawn_blocking(move || handle.with_sftp(f))
+        .await
+        .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Applies `f` to `path` and, with `recursive`, to everything below it.
+/// Symlinks below `path` are neither followed nor changed, since changing
+/// their attributes would change their targets. A directory is read before
+/// `f` runs on it so removing its read permission does not stop the walk.
+/// Returns the number of entries `f` ran on.
+fn apply_tree(
+    sftp: &Sftp,
+    path: &str,
+    recursive: bool,
+    mut f: impl FnMut(&Path, &FileStat) -> Result<(), SshError>,
+) -> Result<u64, SshError> {
+    let stat = sftp
+        .stat(Path::new(path))
+        .map_err(|e| sftp_error("stat", path, e))?;
+    let mut pending = vec![(Path::new(path).to_path_buf(), stat)];
+    let mut count = 0;
+    while let Some((path, stat)) = pending.pop() {
+        if recursive && stat.is_dir() {
+            let entries = sftp
+                .readdir(&path)
+                .map_err(|e| sftp_error("list", &path.to_string_lossy(), e))?;
+            pending.extend(
+                entries
+                    .into_iter()
+                    .filter(|(_, stat)| stat.file_type() != FileType::Symlink),
+            );
+        }
+        f(&path, &stat)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Local file a clipboard line refers to: a `file://` URI, as file
+/// managers put copied files on the clipboard, or an absolute path
+fn clipboard_local_path(line: &str) -> Option<PathBuf> {
+    let path = match line.strip_prefix("file://") {
+        // The host part, usually empty or "localhost", is skipped
+        Some(rest) => {
+            let path = crate::osc::percent_decode(&rest[rest.find('/')?..]);
+            // file:///C:/Users/... on Windows
+            if cfg!(windows) {
+                path.trim_start_matches('/').to_string()
+            } else {
+                path
+            }
+        }
+        None => line.to_string(),
+    };
+    let path = PathBuf::from(path);
+    (path.is_absolute() && path.exists()).then_some(path)
+}
+
+/// Adds a path to the saved session's recently used remote paths
+pub fn remember_path(saved_session_id: &str, path: &str, kind: &str) {
+    if let Err(e) = crate::db::record_recent_remote_path(saved_session_id, path, kind) {
+        tracing::warn!("failed to remember remote path {}: {}", path, e);
+    }
+}
+
+fn empty_stat() -> FileStat {
+    FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm: None,
+        atime: None,
+        mtime: None,
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Lists a remote directory sorted by name. Symlinks are reported as such,
+/// with their target and the kind of file they point to.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn sftp_list_dir(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    path: String,
+) -> Result<Vec<RemoteEntry>, SshError> {
+    let session_id = SessionId::from(sessionId);
+    let handle = state.session_handle(&session_id)?;
+    let saved_id = state.saved_session_id(&session_id);
+    tokio::task::spawn_blocking(move || {
+        let entries = handle.with_sftp(|sftp| {
+            let names = handle.owner_names(sftp);
+            let mut entries: Vec<RemoteEntry> = sftp
+                .readdir(Path::new(&path))
+                .map_err(|e| sftp_error("list", &path, e))?
+                .iter()
+                .map(|(entry_path, stat)| remote_entry(sftp, &names, entry_path, stat))
+                .collect();
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(entries)
+        })?;
+        if let Some(saved_id) = saved_id {
+            remember_path(&saved_id, &path, "dir");
+        }
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Returns the target stored in a remote symlink
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn sftp_readlink(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    path: String,
+) -> Result<String, SshError> {
+    run(&state, sessionId, move |sftp| {
+        sftp.readlink(Path::new(&path))
+            .map(|t| t.to_string_lossy().into_owned())
+            .map_err(|e| sftp_error("read link", &path, e))
+    })
+    .await
+}
+
+/// Creates a remote symlink at `linkPath` pointing to `target`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn sftp_symlink(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    target: String,
+    linkPath: String,
+) -> Result<(), SshError> {
+    run(&state, sessionId, move |sftp| {
+        // libssh2 sends the arguments in the order OpenSSH expects, which
+        // is the reverse of the SFTP draft
+        sftp.symlink(Path::new(&target), Path::new(&linkPath))
+            .map_err(|e| sftp_error("create link", &linkPath, e))
+    })
+    .await
+}
+
+/// Sets the permission bits of a remote path (e.g. `0o644`); with
+/// `recursive` also of everything below a directory. Returns the number of
+/// entries changed.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn sftp_chmod(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    path: String,
+    mode: u32,
+    recursive: bool,
+) -> Result<u64, SshError> {
+    if mode > 0o7777 {
+        return Err(SshError::OperationFailed(format!(
+            "Invalid mode: {:o}",
+            mode
+        )));
+    }
+    run(&state, sessionId, move |sftp| {
+        apply_tree(sftp, &path, recursive, |entry, _| {
+            let stat = FileStat {
+                perm: Some(mode),
+                ..empty_stat()
+            };
+            sftp.setstat(entry, stat)
+                .map_err(|e| sftp_error("change mode of", &entry.to_string_lossy(), e))
+        })
+    })
+    .await
+}
+
+/// Changes the owner and/or group of a remote path, given by name or
+/// numeric id; with `recursive` also of everything below a directory.
+/// Changing the owner usually needs the server to be logged in as root.
+/// Returns the number of entries changed.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn sftp_chown(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    path: String,
+    owner: Option<String>,
+    group: Option<String>,
+    recursive: bool,
+) -> Result<u64, SshError> {
+    if owner.is_none() && group.is_none() {
+        return Err(SshError::OperationFailed(
+            "An owner or a group is required".to_string(),
+        ));
+    }
+    let handle = state.session_handle(&SessionId::from(sessionId))?;
+    tokio::task::spawn_blocking(move || {
+        handle.with_sftp(|sftp| {
+            let names = handle.owner_names(sftp);
+            let uid = owner.as_deref().map(|o| names.uid_of(o)).transpose()?;
+            let gid = group.as_deref().map(|g| names.gid_of(g)).transpose()?;
+            apply_tree(sftp, &path, recursive, |entry, current| {
+                let entry_path = entry.to_string_lossy();
+                // Owner and group are always sent together; the one not
+                // being changed keeps its current value
+                let (Some(uid), Some(gid)) = (uid.or(current.uid), gid.or(current.gid)) else {
+                    return Err(SshError::OperationFailed(format!(
+                        "Unknown owner of {}",
+                        entry_path
+                    )));
+                };
+                let stat = FileStat {
+                    uid: Some(uid),
+                    gid: Some(gid),
+                    ..empty_stat()
+                };
+                sftp.setstat(entry, stat)
+                    .map_err(|e| sftp_error("change owner of", &entry_path, e))
+            })
+        })
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))?
+}
+
+/// Pastes the local clipboard onto the server. When the clipboard holds
+/// copied files (file URIs or absolute paths, one per line) they are
+/// queued for upload into the remote directory `remotePath`; any other
+/// text is written to the remote file `remotePath`. Existing remote files
+/// are only replaced when `overwrite` is set; otherwise the paste fails
+/// with `FileExists` before anything is written.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn paste_clipboard_as_remote_file(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SshManager>,
+    transfers: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    remotePath: String,
+    overwrite: Option<bool>,
+) -> Result<ClipboardPaste, SshError> {
+    let overwrite = overwrite.unwrap_or(false);
+    let text = app_handle
+        .clipboard()
+        .read_text()
+        .map_err(|e| SshError::OperationFailed(format!("Failed to read the clipboard: {}", e)))?;
+    if text.is_empty() {
+        return Err(SshError::OperationFailed(
+            "The clipboard is empty".to_string(),
+        ));
+    }
+    let files: Option<Vec<PathBuf>> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(clipboard_local_path)
+        .collect::<Option<Vec<_>>>()
+        .filter(|files| !files.is_empty());
+    let session_id = SessionId::from(sessionId);
+    let handle = state.session_handle(&session_id)?;
+
+    let Some(files) = files else {
+        let path = remotePath.clone();
+        tokio::task::spawn_blocking(move || {
+            handle.with_sftp(|sftp| {
+                if !overwrite && sftp.stat(Path::new(&path)).is_ok() {
+                    return Err(SshError::FileExists(path.clone()));
+                }
+                let mut file = sftp
+                    .open_mode(
+                        Path::new(&path),
+                        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+                        0o644,
+                        OpenType::File,
+                    )
+                    .map_err(|e| sftp_error("open", &path, e))?;
+                file.write_all(text.as_bytes()).map_err(|e| {
+                    SshError::OperationFailed(format!("Failed to write {}: {}", path, e))
+                })
+            })
+        })
+        .await
+        .map_err(|e| SshError::TaskError(e.to_string()))??;
+        return Ok(ClipboardPaste {
+            kind: "text".to_string(),
+            paths: vec![remotePath],
+            task_ids: Vec::new(),
+        });
+    };
+
+    if let Some(dir) = files.iter().find(|f| f.is_dir()) {
+        return Err(SshError::OperationFailed(format!(
+            "{} is a folder; only files can be pasted",
+            dir.display()
+        )));
+    }
+    let targets: Vec<(PathBuf, String)> = files
+        .into_iter()
+        .filter_map(|file| {
+            let remote = format!(
+                "{}/{}",
+                remotePath.trim_end_matches('/'),
+                file.file_name()?.to_string_lossy()
+            );
+            Some((file, remote))
+        })
+        .collect();
+    let dest = remotePath.clone();
+    let remotes: Vec<String> = targets.iter().map(|(_, remote)| remote.clone()).collect();
+    let dest_handle = handle.clone();
+    tokio::task::spawn_blocking(move || {
+        dest_handle.with_sftp(|sftp| {
+            let is_dir = sftp
+                .stat(Path::new(&dest))
+                .map(|stat| stat.is_dir())
+                .map_err(|e| sftp_error("stat", &dest, e))?;
+            if !is_dir {
+                return Err(SshError::OperationFailed(format!(
+                    "{} is not a directory",
+                    dest
+                )));
+            }
+            // Checked for all files up front, so a conflict does not leave
+            // half of the paste uploaded
+            if overwrite {
+                return Ok(());
+            }
+            match remotes
+                .into_iter()
+                .find(|remote| sftp.stat(Path::new(remote)).is_ok())
+            {
+                Some(existing) => Err(SshError::FileExists(existing)),
+                None => Ok(()),
+            }
+        })
+    })
+    .await
+    .map_err(|e| SshError::TaskError(e.to_string()))??;
+
+    let preserve = crate::settings::current().transfers.preserve_attributes;
+    let mut paste = ClipboardPaste {
+        kind: "files".to_string(),
+        paths: Vec::new(),
+        task_ids: Vec::new(),
+    };
+    for (file, remote) in targets {
+        let task_id = uuid::Uuid::new_v4().to_string();
+        transfers.enqueue(
+            app_handle.clone(),
+            session_id.as_ref(),
+            handle.clone(),
+            task_id.clone(),
+            TransferJob::Upload {
+                local_path: file.to_string_lossy().into_owned(),
+                remote_path: remote.clone(),
+                preserve,
+            },
+            0,
+        )?;
+        paste.paths.push(remote);
+        paste.task_ids.push(task_id);
+    }
+    Ok(paste)
+}
@@ -0,0 +1,326 @@
+use crate::db::{ExportSession, Group, Tag};
+use crate::encryption::EncryptionManager;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::command;
+
+/// Identifies share bundle files
+const BUNDLE_FORMAT: &str = "nexashell-share";
+
+/// Format version of share bundles
+const BUNDLE_VERSION: u32 = 1;
+
+/// File written by `share_sessions`. Everything but the header is encrypted
+/// with the share passphrase.
+#[derive(Serialize, Deserialize)]
+struct BundleFile {
+    format: String,
+    version: u32,
+    payload: String,
+}
+
+/// Decrypted bundle content. Session credentials are additionally encrypted
+/// with the passphrase, as in exports.
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    sessions: Vec<ExportSession>,
+    groups: Vec<Group>,
+    tags: Vec<Tag>,
+}
+
+/// How one bundle record would be merged
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareItem {
+    pub id: String,
+    pub name: String,
+    /// "new", "update", "unchanged" or "duplicate" (same host and user as
+    /// an existing session; skipped)
+    pub status: String,
+    /// Local record the item matches, when not new
+    pub existing_id: Option<String>,
+    pub has_credentials: bool,
+}
+
+/// Merge preview (or result) of a shared bundle
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharePreview {
+    pub sessions: Vec<ShareItem>,
+    pub groups: Vec<ShareItem>,
+    pub tags: Vec<ShareItem>,
+    /// Whether the bundle was written to the database
+    pub applied: bool,
+}
+
+fn read_bundle(path: &str, passphrase: &str) -> Result<Bundle, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: BundleFile =
+        serde_json::from_str(&json).map_err(|_| "Not a NexaShell share bundle".to_string())?;
+    if file.format != BUNDLE_FORMAT {
+        return Err("Not a NexaShell share bundle".to_string());
+    }
+    if file.version > BUNDLE_VERSION {
+        return Err("Bundle was created by a newer NexaShell".to_string());
+    }
+    let payload = EncryptionManager::decrypt_secret_with_key(&file.payload, passphrase)
+        .map_err(|_| "Wrong passphrase or damaged bundle".to_string())?;
+    serde_json::from_str(&payload).map_err(|e| format!("Bundle is unreadable: {}", e))
+}
+
+/// Host identity used to spot a shared session that already exists under
+/// another id
+fn host_key(session: &ExportSession) -> (String, i64, String) {
+    (
+        session.metadata.addr.to_lowercase(),
+        session.metadata.port,
+        session.metadata.username.clone(),
+    )
+}
+
+/// Fields compared to tell "update" from "unchanged"
+fn session_fields(session: &ExportSession) -> impl PartialEq {
+    let m = &session.metadata;
+    let mut group_ids = session.group_ids.clone();
+    let mut tag_ids = session.tag_ids.clone();
+    group_ids.sort();
+    tag_ids.sort();
+    (
+        (
+            m.addr.clone(),
+            m.port,
+            m.server_name.clone(),
+            m.username.clone(),
+        ),
+        (m.auth_type.clone(), m.private_key_path.clone()),
+        (m.mac_address.clone(), m.certificate_path.clone()),
+        (group_ids, tag_ids),
+    )
+}
+
+/// Matches bundle groups/tags to local ones by id, then by name, and
+/// returns the preview items and the id each bundle record maps to
+fn plan_named<T>(
+    records: &[T],
+    local: &[T],
+    fields: fn(&T) -> (&str, &str),
+    same: fn(&T, &T) -> bool,
+) -> (Vec<ShareItem>, HashMap<String, String>) {
+    let mut items = Vec::new();
+    let mut ids = HashMap::new();
+    for record in records {
+        let (id, name) = fields(record);
+        let existing = local.iter().find(|l| fields(l).0 == id).or_else(|| {
+            local
+                .iter()
+                .find(|l| fields(l).1.eq_ignore_ascii_case(name))
+        });
+        let (status, existing_id) = match existing {
+            Some(l) if same(l, record) => ("unchanged", Some(fields(l).0.to_string())),
+            // A name match keeps the local record as it is
+            Some(l) if fields(l).0 != id => ("unchanged", Some(fields(l).0.to_string())),
+            Some(l) => ("update", Some(fields(l).0.to_string())),
+            None => ("new", None),
+        };
+        ids.insert(
+            id.to_string(),
+            existing_id.clone().unwrap_or_else(|| id.to_string()),
+        );
+        items.push(ShareItem {
+            id: id.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            existing_id,
+            has_credentials: false,
+        });
+    }
+    (items, ids)
+}
+
+/// Exports the given sessions, with the groups and tags they use, as an
+/// encrypted bundle file at `path`. Credentials are included only when
+/// `include_credentials` is set, after biometric confirmation when it is
+/// enabled and re-authentication when any of the sessions is protected.
+#[command]
+pub async fn share_sessions(
+    ids: Vec<String>,
+    passphrase: String,
+    path: String,
+    include_credentials: Option<bool>,
+    master_password: Option<String>,
+) -> Result<usize, String> {
+    if passphrase.is_empty() {
+        return Err("A passphrase is required".to_string());
+    }
+    let include_credentials = include_credentials.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        if include_credentials {
+            crate::biometric::confirm_protected_sessions(&ids, master_password.as_deref())?;
+            crate::biometric::confirm("share saved credentials")?;
+        }
+        write_bundle(&ids, &passphrase, &path, include_credentials)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn write_bundle(
+    ids: &[String],
+    passphrase: &str,
+    path: &str,
+    include_credentials: bool,
+) -> Result<usize, String> {
+    let library = crate::db::load_library(include_credentials.then_some(passphrase))?;
+
+    let wanted: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let sessions: Vec<ExportSession> = library
+        .sessions
+        .into_iter()
+        .filter(|s| wanted.contains(s.metadata.id.as_str()))
+        .collect();
+    if sessions.is_empty() {
+        return Err("No sessions selected".to_string());
+    }
+    let group_ids: HashSet<&str> = sessions
+        .iter()
+        .flat_map(|s| s.group_ids.iter().map(String::as_str))
+        .collect();
+    let tag_ids: HashSet<&str> = sessions
+        .iter()
+        .flat_map(|s| s.tag_ids.iter().map(String::as_str))
+        .collect();
+    let groups = library
+        .groups
+        .into_iter()
+        .filter(|g| group_ids.contains(g.id.as_str()))
+        .collect();
+    let tags = library
+        .tags
+        .into_iter()
+        .filter(|t| tag_ids.contains(t.id.as_str()))
+        .collect();
+
+    let count = sessions.len();
+    let payload = serde_json::to_string(&Bundle {
+        sessions,
+        groups,
+        tags,
+    })
+    .map_err(|e| e.to_string())?;
+    let file = BundleFile {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        payload: EncryptionManager::encrypt_secret_with_key(&payload, passphrase)?,
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Previews or imports a bundle written by `share_sessions`. With
+/// `dry_run` nothing is written and the preview shows what an import would
+/// do. `session_ids` limits the import to some of the bundle's sessions.
+/// Groups and tags are matched by name so a team's "Production" group is
+/// not duplicated.
+#[command]
+pub fn import_shared_bundle(
+    path: String,
+    passphrase: String,
+    dry_run: Option<bool>,
+    session_ids: Option<Vec<String>>,
+) -> Result<SharePreview, String> {
+    let bundle = read_bundle(&path, &passphrase)?;
+    let local = crate::db::load_library(None)?;
+
+    let (groups, group_map) = plan_named(
+        &bundle.groups,
+        &local.groups,
+        |g| (g.id.as_str(), g.name.as_str()),
+        |a, b| a.name == b.name && a.sort == b.sort,
+    );
+    let (tags, tag_map) = plan_named(
+        &bundle.tags,
+        &local.tags,
+        |t| (t.id.as_str(), t.name.as_str()),
+        |a, b| a.name == b.name && a.color == b.color && a.sort == b.sort,
+    );
+
+    let selected: Option<HashSet<String>> = session_ids.map(|ids| ids.into_iter().collect());
+    let local_by_id: HashMap<&str, &ExportSession> = local
+        .sessions
+        .iter()
+        .map(|s| (s.metadata.id.as_str(), s))
+        .collect();
+    let local_by_host: HashMap<(String, i64, String), &ExportSession> =
+        local.sessions.iter().map(|s| (host_key(s), s)).collect();
+
+    let mut sessions = Vec::new();
+    let mut to_import = Vec::new();
+    for mut session in bundle.sessions {
+        if selected
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&session.metadata.id))
+        {
+            continue;
+        }
+        session.group_ids = session
+            .group_ids
+            .iter()
+            .filter_map(|id| group_map.get(id).cloned())
+            .collect();
+        session.tag_ids = session
+            .tag_ids
+            .iter()
+            .filter_map(|id| tag_map.get(id).cloned())
+            .collect();
+
+        let (status, existing_id) = match local_by_id.get(session.metadata.id.as_str()) {
+            Some(existing) if session_fields(existing) == session_fields(&session) => {
+                ("unchanged", Some(existing.metadata.id.clone()))
+            }
+            Some(existing) => ("update", Some(existing.metadata.id.clone())),
+            None => match local_by_host.get(&host_key(&session)) {
+                Some(existing) => ("duplicate", Some(existing.metadata.id.clone())),
+                None => ("new", None),
+            },
+        };
+        let has_credentials = session.encrypted_credentials.is_some();
+        sessions.push(ShareItem {
+            id: session.metadata.id.clone(),
+            name: session.metadata.server_name.clone(),
+            status: status.to_string(),
+            existing_id,
+            has_credentials,
+        });
+        if status == "new" || status == "update" || (status == "unchanged" && has_credentials) {
+            to_import.push(session);
+        }
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        let new_groups: Vec<Group> = bundle
+            .groups
+            .into_iter()
+            .filter(|g| {
+                groups
+                    .iter()
+                    .any(|i| i.id == g.id && i.status != "unchanged")
+            })
+            .collect();
+        let new_tags: Vec<Tag> = bundle
+            .tags
+            .into_iter()
+            .filter(|t| tags.iter().any(|i| i.id == t.id && i.status != "unchanged"))
+            .collect();
+        crate::db::import_shared_records(&new_groups, &new_tags, &to_import, &passphrase)?;
+        crate::config_history::schedule_commit();
+    }
+
+    Ok(SharePreview {
+        sessions,
+        groups,
+        tags,
+        applied: !dry_run,
+    })
+}
@@ -0,0 +1,262 @@
+use crate::osc::{percent_decode, OscSequence};
+use schemars::JsonSchema;
+use serde::Serialize;
+use ssh2::Session;
+use std::io::Read;
+use std::sync::RwLock;
+
+// ============================================================================
+// Shell Detection
+// ============================================================================
+
+/// Login shell flavour of the remote user, used to pick injection snippets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Unknown,
+}
+
+impl ShellKind {
+    /// Maps a shell path such as `/usr/bin/zsh` to its kind
+    pub fn from_path(path: &str) -> Self {
+        match path.trim().rsplit('/').next().unwrap_or_default() {
+            "bash" => ShellKind::Bash,
+            "zsh" => ShellKind::Zsh,
+            "fish" => ShellKind::Fish,
+            _ => ShellKind::Unknown,
+        }
+    }
+}
+
+/// Detects the remote login shell via a short-lived exec channel.
+///
+/// Must be called while the session is in blocking mode.
+pub fn detect_shell(sess: &Session) -> ShellKind {
+    let detect = || -> Result<String, ssh2::Error> {
+        let mut channel = sess.channel_session()?;
+        channel.exec("echo $SHELL")?;
+        let mut output = String::new();
+        let _ = channel.read_to_string(&mut output);
+        let _ = channel.wait_close();
+        Ok(output)
+    };
+    detect()
+        .map(|out| ShellKind::from_path(&out))
+        .unwrap_or(ShellKind::Unknown)
+}
+
+// ============================================================================
+// Injection Snippets
+// ============================================================================
+
+/// bash: OSC 133 D (exit status) + OSC 7 + OSC 133 A before every prompt and
+/// OSC 133 B at the end of PS1. bash has no preexec hook, so command start
+/// is inferred from the Enter key in user input.
+///
+/// The OSC 7 path is a URL, which `parse_cwd` percent-decodes; the hooks
+/// encode `%` so directory names containing one come back unchanged.
+const BASH_HOOK: &str = " __nx_prompt() { local s=$?; printf '\\033]133;D;%s\\007\\033]7;file://%s%s\\007\\033]133;A\\007' \"$s\" \"${HOSTNAME:-}\" \"${PWD//\\%/%25}\"; }; PROMPT_COMMAND=\"__nx_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"; PS1=\"$PS1\\[\\033]133;B\\007\\]\"\n";
+
+/// zsh: same markers via precmd/preexec, with OSC 133 C on command start
+const ZSH_HOOK: &str = " __nx_precmd() { local s=$?; printf '\\033]133;D;%s\\033\\\\\\033]7;file://%s%s\\033\\\\\\033]133;A\\033\\\\' \"$s\" \"${HOST:-}\" \"${PWD//\\%/%25}\"; }; __nx_preexec() { printf '\\033]133;C\\033\\\\'; }; precmd_functions+=(__nx_precmd); preexec_functions+=(__nx_preexec); PS1=\"$PS1%{$(printf '\\033]133;B\\033\\\\')%}\"\n";
+
+/// fish: markers through fish_prompt/fish_preexec/fish_postexec events
+const FISH_HOOK: &str = " function __nx_prompt --on-event fish_prompt; printf '\\033]7;file://%s%s\\007\\033]133;A\\007' (hostname) (string replace -a '%' '%25' -- $PWD); end; function __nx_preexec --on-event fish_preexec; printf '\\033]133;C\\007'; end; function __nx_postexec --on-event fish_postexec; printf '\\033]133;D;%s\\007' $status; end\n";
+
+/// Returns the line typed into the shell to enable shell integration
+/// (working-directory reports and OSC 133 command markers). The leading
+/// space keeps it out of history where `HISTCONTROL=ignorespace` (or the
+/// zsh/fish equivalent) is set.
+pub fn integration_hook(kind: ShellKind) -> Option<&'static str> {
+    match kind {
+        ShellKind::Bash => Some(BASH_HOOK),
+        ShellKind::Zsh => Some(ZSH_HOOK),
+        ShellKind::Fish => Some(FISH_HOOK),
+        ShellKind::Unknown => None,
+    }
+}
+
+// ============================================================================
+// Command Tracking
+// ============================================================================
+
+/// Semantic prompt marks (OSC 133)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptMark {
+    /// `A`: prompt is about to be drawn
+    PromptStart,
+    /// `B`: prompt drawn, user is typing a command
+    CommandInput,
+    /// `C`: command started executing
+    CommandExecuted,
+    /// `D[;status]`: command finished
+    CommandFinished(Option<i32>),
+}
+
+/// Parses an OSC 133 sequence into a prompt mark
+pub fn parse_prompt_mark(seq: &OscSequence) -> Option<PromptMark> {
+    if seq.code != "133" {
+        return None;
+    }
+    let mut parts = seq.payload.split(';');
+    match parts.next()? {
+        "A" => Some(PromptMark::PromptStart),
+        "B" => Some(PromptMark::CommandInput),
+        "C" => Some(PromptMark::CommandExecuted),
+        "D" => Some(PromptMark::CommandFinished(
+            parts.next().and_then(|s| s.trim().parse().ok()),
+        )),
+        _ => None,
+    }
+}
+
+/// Payload of the `ssh-command-finished-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandFinished {
+    pub session_id: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Output sequence number around which the command started
+    pub start_seq: u64,
+    /// Output sequence number of the chunk that reported completion
+    pub end_seq: u64,
+    pub cwd: Option<String>,
+}
+
+/// Payload of the `ssh-prompt-{sessionId}` event, used for
+/// jump-to-previous-command navigation
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptStarted {
+    pub session_id: String,
+    pub seq: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum CommandPhase {
+    #[default]
+    Idle,
+    Input,
+    Running,
+}
+
+/// Reconstructs command boundaries from prompt marks and user input
+#[derive(Debug, Default)]
+pub struct CommandTracker {
+    phase: CommandPhase,
+    started: Option<(std::time::Instant, u64)>,
+}
+
+/// A command boundary derived by the tracker
+#[derive(Debug, Clone, Copy)]
+pub enum CommandEvent {
+    Prompt,
+    Finished {
+        exit_code: Option<i32>,
+        duration: std::time::Duration,
+        start_seq: u64,
+    },
+}
+
+impl CommandTracker {
+    /// Applies a prompt mark observed in the output chunk `seq`
+    pub fn on_mark(&mut self, mark: PromptMark, seq: u64) -> Option<CommandEvent> {
+        match mark {
+            PromptMark::PromptStart => {
+                self.phase = CommandPhase::Idle;
+                Some(CommandEvent::Prompt)
+            }
+            PromptMark::CommandInput => {
+                self.phase = CommandPhase::Input;
+                None
+            }
+            PromptMark::CommandExecuted => {
+                self.start(seq);
+                None
+            }
+            PromptMark::CommandFinished(exit_code) => {
+                let was_running = self.phase == CommandPhase::Running;
+                self.phase = CommandPhase::Idle;
+                let (started_at, start_seq) = self.started.take()?;
+                was_running.then(|| CommandEvent::Finished {
+                    exit_code,
+                    duration: started_at.elapsed(),
+                    start_seq,
+                })
+            }
+        }
+    }
+
+    /// Infers command start from Enter typed at the prompt (bash fallback)
+    pub fn on_input(&mut self, data: &[u8], next_seq: u64) {
+        if self.phase == CommandPhase::Input && data.contains(&b'\r') {
+            self.start(next_seq);
+        }
+    }
+
+    fn start(&mut self, seq: u64) {
+        if self.phase != CommandPhase::Running {
+            self.phase = CommandPhase::Running;
+            self.started = Some((std::time::Instant::now(), seq));
+        }
+    }
+}
+
+// ============================================================================
+// Working Directory Tracking
+// ============================================================================
+
+/// Extracts a working directory from OSC 7 (`file://host/path`) or iTerm2's
+/// OSC 1337 `CurrentDir=` sequences
+pub fn parse_cwd(seq: &OscSequence) -> Option<String> {
+    match seq.code.as_str() {
+        "7" => {
+            let rest = seq.payload.strip_prefix("file://")?;
+            // Skip the host part; the path starts at the next '/'
+            let path = &rest[rest.find('/')?..];
+            Some(percent_decode(path))
+        }
+        "1337" => seq
+            .payload
+            .strip_prefix("CurrentDir=")
+            .map(|p| p.to_string()),
+        _ => None,
+    }
+    .filter(|p| !p.is_empty())
+}
+
+/// Interactive shell state reconstructed from the output stream
+#[derive(Debug, Default)]
+pub struct ShellState {
+    cwd: RwLock<Option<String>>,
+}
+
+impl ShellState {
+    /// Last directory reported by the shell, if any
+    pub fn cwd(&self) -> Option<String> {
+        self.cwd.read().ok().and_then(|c| c.clone())
+    }
+
+    /// Stores a new directory; returns true when it changed
+    pub fn set_cwd(&self, cwd: String) -> bool {
+        match self.cwd.write() {
+            Ok(mut current) if current.as_deref() != Some(cwd.as_str()) => {
+                *current = Some(cwd);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Payload of the `ssh-cwd-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CwdChanged {
+    pub session_id: String,
+    pub cwd: String,
+}
@@ -0,0 +1,141 @@
+use crate::ssh::{SshError, SshManager};
+use crate::transfer::{TransferInfo, TransferManager};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// How long quitting waits for running transfers to finish
+const TRANSFER_GRACE: Duration = Duration::from_secs(10);
+
+/// How long cancelled transfers and syncs get to close their files
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// Pause between checks while waiting for work to finish
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set once the first exit request has started the shutdown
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// An open connection with work that quitting would interrupt
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusySession {
+    pub session_id: String,
+    /// `user@host`
+    pub label: String,
+    /// What is running: "transfer", "watch" or "tunnel"
+    pub activities: Vec<String>,
+}
+
+/// Result of `get_quit_check`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuitCheck {
+    /// Running and queued SFTP transfers
+    pub transfers: Vec<TransferInfo>,
+    /// Directory synchronizations in progress
+    pub syncs: usize,
+    pub busy_sessions: Vec<BusySession>,
+    /// Open SSH connections, busy or not
+    pub connected_sessions: usize,
+    /// Whether quitting would interrupt anything
+    pub has_active_work: bool,
+}
+
+/// Waits until `done` holds or `timeout` passes; returns whether it held
+fn wait_until(timeout: Duration, done: impl Fn() -> bool) -> bool {
+    let started = Instant::now();
+    while !done() {
+        if started.elapsed() >= timeout {
+            return false;
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+    true
+}
+
+/// Lets running work end before the connections are closed. Queued
+/// transfers are dropped; running ones get `TRANSFER_GRACE` to finish and
+/// are then cancelled so they close their files. Blocking.
+fn finish_work(app_handle: &AppHandle) {
+    let transfers = app_handle.state::<TransferManager>();
+    transfers.cancel_all(false);
+    if !wait_until(TRANSFER_GRACE, || transfers.running_count() == 0) {
+        transfers.cancel_all(true);
+    }
+    crate::dirsync::cancel_all();
+    wait_until(CANCEL_GRACE, || {
+        transfers.running_count() == 0 && crate::dirsync::running_count() == 0
+    });
+    for manager in crate::transport::managers(app_handle) {
+        manager.disconnect_all();
+    }
+    crate::logging::flush();
+}
+
+/// Handles an exit request. The first one is held back while running
+/// work finishes on a worker thread, which then exits the app again;
+/// returns true when the exit must be prevented for now.
+pub fn on_exit_requested(app_handle: &AppHandle) -> bool {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        finish_work(&app_handle);
+        app_handle.exit(0);
+    });
+    true
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Reports the work quitting would interrupt, so the frontend can ask for
+/// confirmation first
+#[tauri::command]
+pub fn get_quit_check(
+    ssh: tauri::State<'_, SshManager>,
+    transfers: tauri::State<'_, TransferManager>,
+) -> Result<QuitCheck, SshError> {
+    let transfers = transfers.list_all()?;
+    let mut activities: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut add = |session_id: &str, activity: &str| {
+        let list = activities.entry(session_id.to_string()).or_default();
+        if !list.iter().any(|a| a == activity) {
+            list.push(activity.to_string());
+        }
+    };
+    for transfer in &transfers {
+        add(&transfer.session_id, "transfer");
+    }
+    for watch in crate::watch::running_watches(None) {
+        add(&watch.session_id, "watch");
+    }
+    for tunnel in crate::tunnel::open_tunnels(None) {
+        add(&tunnel.session_id, "tunnel");
+    }
+
+    let sessions = ssh.active_sessions();
+    let busy_sessions: Vec<BusySession> = sessions
+        .iter()
+        .filter_map(|s| {
+            Some(BusySession {
+                session_id: s.session_id.clone(),
+                label: s.label.clone(),
+                activities: activities.remove(&s.session_id)?,
+            })
+        })
+        .collect();
+    let syncs = crate::dirsync::running_count();
+    Ok(QuitCheck {
+        has_active_work: !transfers.is_empty() || syncs > 0 || !busy_sessions.is_empty(),
+        transfers,
+        syncs,
+        busy_sessions,
+        connected_sessions: sessions.iter().filter(|s| s.connected).count(),
+    })
+}
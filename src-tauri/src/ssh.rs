@@ -1,15 +1,46 @@
-use serde::Serialize;
-use ssh2::{Session, OpenFlags, OpenType};
-use std::collections::HashMap;
-use std::io::{Read, Write, Seek, SeekFrom};
-use std::net::TcpStream;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use crate::alerts::AlertEngine;
+use crate::diagnostics::ConnectionTrace;
+use crate::encoding::{SessionEncoding, TerminalDecoder};
+use crate::events::{TerminalInput, TerminalResize};
+use crate::guard::CommandGuard;
+use crate::highlights::{HighlightEngine, MatchCounter};
+use crate::idle::{IdleAction, IdleEvent, IdleGuard};
+use crate::images::ImageScanner;
+use crate::lines::LineAssembler;
+use crate::links;
+use crate::metrics::MetricsRecorder;
+use crate::multiplexer::{ResumeAction, ResumeAvailable};
+use crate::multiwindow::{SessionRoute, MAIN_WINDOW};
+use crate::osc::{self, OscScanner};
+use crate::output_index::OutputIndexer;
+use crate::sftp::OwnerNames;
+use crate::shell_integration::{
+    self, CommandEvent, CommandFinished, CommandTracker, CwdChanged, PromptStarted, ShellKind,
+    ShellState,
+};
+use crate::sudo::{SudoAction, SudoAutofillEvent, SudoPromptDetector};
+use crate::suspend::Suspension;
+use crate::transfer::{TransferJob, TransferManager};
+use crate::transport::{self, ConnectionManager, SessionListeners};
+use crate::triggers::{TriggerAction, TriggerEngine};
+use base64::{engine::general_purpose, Engine as _};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ssh2::{OpenFlags, OpenType, Session, Sftp};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
-use tauri::{Emitter, Listener};
+use tauri::Manager;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_notification::NotificationExt;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
+pub use crate::transport::{OutputChunk, OutputStream, SessionId};
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -37,11 +68,27 @@ pub enum SshError {
     #[error("Session not found: {0}")]
     SessionNotFound(String),
 
+    #[error("Session is already connected: {0}")]
+    AlreadyConnected(String),
+
+    #[error("Remote file already exists: {0}")]
+    FileExists(String),
+
+    #[error("Connection attempt cancelled")]
+    Cancelled,
+
     #[error("State lock poisoned: {0}")]
     LockPoisoned(String),
 
     #[error("Task join error: {0}")]
     TaskError(String),
+
+    /// A connection failure with the diagnostics trace of the attempt
+    #[error("{error}")]
+    Traced {
+        error: Box<SshError>,
+        trace: ConnectionTrace,
+    },
 }
 
 // ============================================================================
@@ -64,126 +111,1955 @@ const INITIAL_BUFFERING_TIMEOUT_MS: u64 = 2000; // 2 seconds to capture all init
 const NORMAL_BATCH_SIZE_THRESHOLD: usize = 1024;
 const NORMAL_BATCH_TIME_MS: u64 = 20;
 
+/// Flood batching: once the output rate exceeds `FLOOD_BYTES_PER_SEC`,
+/// output is coalesced into fewer, larger chunks
+const FLOOD_BYTES_PER_SEC: usize = 2 * 1024 * 1024;
+const FLOOD_BATCH_SIZE_THRESHOLD: usize = 256 * 1024;
+const FLOOD_BATCH_TIME_MS: u64 = 100;
+
+/// Maximum bytes held in a single pending batch. When a flood produces more
+/// than this between two emits, the middle of the batch is dropped and an
+/// `ssh-output-truncated-{sessionId}` marker event is emitted instead.
+const MAX_PENDING_BATCH_BYTES: usize = 1024 * 1024;
+
+/// Capacity (in chunks) of the polling queue drained by `get_ssh_output`
+const OUTPUT_QUEUE_CAPACITY: usize = 1024;
+
+/// Budget of undrained bytes in the polling queue; chunks beyond it are
+/// only delivered through events
+const MAX_QUEUED_OUTPUT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Maximum bytes written to the channel per I/O loop iteration, so large
+/// pastes interleave with output reads instead of stalling them
+const INPUT_SLICE_SIZE: usize = 16 * 1024;
+
+/// Minimum interval between paste progress events
+const PASTE_PROGRESS_INTERVAL_MS: u64 = 100;
+
+/// Bracketed paste mode delimiters
+pub const BRACKETED_PASTE_START: &[u8] = b"\x1b[200~";
+pub const BRACKETED_PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Removes bracketed paste delimiters from pasted text, so a paste cannot
+/// end early and have the rest run as typed input. Repeated until none are
+/// left, as removing one can join the pieces of another.
+fn strip_paste_delimiters(text: &str) -> String {
+    let mut text = text.to_string();
+    while text.contains("\x1b[200~") || text.contains("\x1b[201~") {
+        text = text.replace("\x1b[200~", "").replace("\x1b[201~", "");
+    }
+    text
+}
+
 // ============================================================================
 // Data Structures
 // ============================================================================
 
-/// Newtype pattern for type-safe session identifiers
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
-pub struct SessionId(String);
+/// SSH connection configuration
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshSession {
+    pub ip: String,
+    pub port: u16,
+    pub username: String,
+    /// Resolved address the TCP connection was established to
+    pub connected_addr: String,
+}
+
+/// Optional connect-time behaviour supplied by the frontend
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConnectOptions {
+    /// Inject shell hooks that report the working directory (OSC 7) and
+    /// command boundaries (OSC 133)
+    pub shell_integration: bool,
+    /// Saved session this connection was opened from; selects its triggers
+    pub saved_session_id: Option<String>,
+    /// Transport settings; defaults to the saved session's stored settings
+    pub advanced: Option<SshAdvancedOptions>,
+    /// Return the connection trace with connect errors and record the
+    /// authentication methods offered by the server
+    pub diagnostics: bool,
+    /// Window the session's events are sent to; the window that connected
+    #[serde(skip)]
+    pub window: Option<String>,
+    /// Master password for a protected saved session; without it a
+    /// biometric check is requested
+    pub master_password: Option<String>,
+    /// When the session id is still connected, disconnect it and connect
+    /// again instead of failing with `AlreadyConnected`
+    pub takeover: bool,
+}
+
+/// Default TCP connect timeout
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+
+/// Per-session transport settings applied before the handshake
+///
+/// Algorithm preferences are comma-separated lists in order of preference,
+/// as accepted by libssh2 (e.g. `curve25519-sha256,ecdh-sha2-nistp256`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SshAdvancedOptions {
+    pub connect_timeout_secs: Option<u64>,
+    pub kex: Option<String>,
+    pub host_key: Option<String>,
+    pub ciphers: Option<String>,
+    pub macs: Option<String>,
+    pub compression: Option<bool>,
+    /// Reach an EC2 instance through Session Manager and/or authenticate
+    /// with EC2 Instance Connect
+    pub aws: Option<crate::aws::AwsConnectOptions>,
+}
+
+impl SshAdvancedOptions {
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.connect_timeout_secs
+                .filter(|s| *s > 0)
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Applies algorithm preferences and compression to a session that has
+    /// not completed its handshake yet
+    fn apply(&self, sess: &Session) -> Result<(), SshError> {
+        let prefs = [
+            (ssh2::MethodType::Kex, &self.kex, "key exchange"),
+            (ssh2::MethodType::HostKey, &self.host_key, "host key"),
+            (ssh2::MethodType::CryptCs, &self.ciphers, "cipher"),
+            (ssh2::MethodType::CryptSc, &self.ciphers, "cipher"),
+            (ssh2::MethodType::MacCs, &self.macs, "MAC"),
+            (ssh2::MethodType::MacSc, &self.macs, "MAC"),
+        ];
+        for (method, value, label) in prefs {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                sess.method_pref(method, value).map_err(|e| {
+                    SshError::OperationFailed(format!("Invalid {} preference: {}", label, e))
+                })?;
+            }
+        }
+        if let Some(compress) = self.compression {
+            sess.set_compress(compress);
+        }
+        Ok(())
+    }
+}
+
+/// Represents the progress of an SFTP file transfer. Downloads are
+/// reported through `download-progress`, with `uploaded_bytes` counting the
+/// bytes received.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgress {
+    pub task_id: String,
+    pub session_id: String,
+    pub progress: f64,
+    pub uploaded_bytes: u64,
+    pub total_bytes: u64,
+    /// "queued", "uploading", "downloading", "success", "error" or
+    /// "cancelled"
+    pub status: String,
+    pub message: String,
+    pub speed: f64,
+    pub error: Option<String>,
+    /// 1-based position in the session's transfer queue while queued
+    pub queue_position: Option<usize>,
+}
+
+/// Payload of the `ssh-clipboard-{sessionId}` event, sent when a remote
+/// program sets the clipboard through OSC 52
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardRequest {
+    pub session_id: String,
+    /// "copied", "ask" (confirm, then call `write_clipboard`), "blocked" or
+    /// "error"
+    pub status: String,
+    /// The text, only included for "ask"
+    pub text: Option<String>,
+    /// Length of the text in characters
+    pub length: usize,
+}
+
+/// Server performance metrics
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub cpu_usage: f64,
+    pub mem_usage: f64,
+    pub mem_total: u64,
+    pub mem_used: u64,
+    pub mem_avail: u64,
+    pub swap_usage: f64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+    pub disk_usage: f64,
+    pub disk_total: u64,
+    pub disk_used: u64,
+    pub disk_avail: u64,
+    pub net_up: f64,
+    pub net_down: f64,
+    /// Network round trip (ms) from the latest latency probe; 0 before the
+    /// first one
+    pub latency: u32,
+    /// Time (ms) the status command took to run, including round trips
+    pub command_ms: u32,
+    pub load_avg: [f64; 3],
+    pub uptime: String,
+}
+
+/// Input queued for the I/O task
+#[derive(Debug)]
+pub enum InputCommand {
+    /// Keystrokes or other small writes
+    Data(Vec<u8>),
+    /// A paste written in window-sized slices with progress events
+    Paste { paste_id: String, data: Vec<u8> },
+}
+
+/// Progress of a flow-controlled paste
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteProgress {
+    pub paste_id: String,
+    pub written_bytes: u64,
+    pub total_bytes: u64,
+    pub done: bool,
+}
+
+/// Result of a `paste_text` request
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteOutcome {
+    /// Identifier used in `ssh-paste-progress-{sessionId}` events
+    pub paste_id: Option<String>,
+    pub total_bytes: u64,
+    /// True when the text exceeds the confirmation threshold and was not sent
+    pub requires_confirmation: bool,
+}
+
+/// Marker emitted when output was dropped to keep the app responsive
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputTruncated {
+    /// Sequence number of the chunk that follows the gap
+    pub seq: u64,
+    pub dropped_bytes: u64,
+    pub ts: u128,
+}
+
+/// Formats a command duration for notifications, e.g. `2m 05s`
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", d.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
 
-impl From<String> for SessionId {
-    fn from(s: String) -> Self {
-        SessionId(s)
+/// Refuses input to a session locked after inactivity and otherwise
+/// records it as activity
+fn check_unlocked(channel_info: &SshChannelInfo) -> Result<(), SshError> {
+    if channel_info.idle.on_input() {
+        Ok(())
+    } else {
+        Err(SshError::OperationFailed(
+            "Session is locked after inactivity".to_string(),
+        ))
     }
 }
 
-impl AsRef<str> for SessionId {
-    fn as_ref(&self) -> &str {
-        &self.0
+/// Dangerous command patterns when the command guard is on and the saved
+/// session carries one of the guarded tags
+fn command_guard_for(saved_session_id: &str) -> Option<Vec<String>> {
+    let security = crate::settings::current().security;
+    if !security.command_guard {
+        return None;
     }
+    let tags = crate::db::list_tags_for_session(saved_session_id.to_string()).ok()?;
+    tags.iter()
+        .any(|tag| {
+            security
+                .guarded_tags
+                .iter()
+                .any(|guarded| guarded.eq_ignore_ascii_case(&tag.name))
+        })
+        .then_some(security.dangerous_commands)
 }
 
-/// SSH connection configuration
-#[derive(Debug, Clone)]
-pub struct SshSession {
-    #[allow(dead_code)]
-    pub ip: String,
-    #[allow(dead_code)]
-    pub port: u16,
+fn now_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// Incremental UTF-8 decoder that keeps incomplete multi-byte sequences
+/// across reads instead of turning them into replacement characters.
+#[derive(Default)]
+pub struct Utf8Decoder {
+    partial: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// Decodes `bytes`, holding back a trailing incomplete sequence for the
+    /// next call. Genuinely invalid bytes are replaced with U+FFFD.
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.partial);
+        input.extend_from_slice(bytes);
+
+        let mut out = String::with_capacity(input.len());
+        let mut rest = &input[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    out.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            // Incomplete sequence at the end of the input
+                            self.partial = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// An open connection as listed by `SshManager::active_sessions`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveSession {
+    pub session_id: String,
+    /// `user@host`
+    pub label: String,
+    pub saved_session_id: Option<String>,
+    /// False once the connection has dropped but the tab is still open
+    pub connected: bool,
+    /// Input is dropped while output keeps streaming
+    pub read_only: bool,
+    /// Monitoring is paused and output read less often, see
+    /// `suspend_session`
+    pub suspended: bool,
+    pub status: Option<ServerStatus>,
+}
+
+/// What the server showed when the session was opened
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBanner {
+    /// Identification string sent before authentication, e.g.
+    /// `SSH-2.0-OpenSSH_9.6`
+    pub server_banner: Option<String>,
+    /// Lines printed before the first prompt, without escape sequences;
+    /// `None` when there were none
+    pub motd: Option<String>,
+}
+
+/// Escape sequence shell integration sends when a prompt starts
+const PROMPT_START_MARK: &str = "\x1b]133;A";
+
+/// Extracts the message of the day from a session's initial output: the
+/// complete lines before the first prompt. Without shell integration the
+/// prompt is only known as the last, unterminated line.
+fn motd_from_output(chunks: &[OutputChunk]) -> Option<String> {
+    let mut assembler = LineAssembler::default();
+    let mut lines = Vec::new();
+    for chunk in chunks.iter().filter(|c| c.stream == OutputStream::Stdout) {
+        match chunk.output.find(PROMPT_START_MARK) {
+            Some(end) => {
+                lines.extend(assembler.feed(&chunk.output[..end]));
+                break;
+            }
+            None => lines.extend(assembler.feed(&chunk.output)),
+        }
+    }
+    let start = lines.iter().position(|l| !l.is_empty())?;
+    let end = lines.iter().rposition(|l| !l.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+/// Contains state and communication handles for an active SSH channel
+pub struct SshChannelInfo {
+    /// Bounded receiver for SSH output chunks (polling fallback). A std
+    /// mutex, as it is only held to drain it and commands may run on the
+    /// async runtime.
+    pub receiver: Arc<Mutex<mpsc::Receiver<OutputChunk>>>,
+
+    /// Bytes currently waiting in `receiver`
+    pub queued_bytes: Arc<AtomicUsize>,
+
+    /// Raw bytes mode flag (binary-safe output)
+    pub raw_mode: Arc<AtomicBool>,
+
+    /// Read-only flag: user input is dropped, output still streams
+    pub read_only: Arc<AtomicBool>,
+
+    /// Shell state tracked from the output stream (working directory)
+    pub shell_state: Arc<ShellState>,
+
+    /// Saved session id used for sudo password autofill (None = disabled)
+    pub sudo_autofill: Arc<RwLock<Option<String>>>,
+
+    /// Character set output is decoded from and input encoded to
+    pub encoding: Arc<RwLock<SessionEncoding>>,
+
+    /// Output triggers evaluated by the I/O task
+    pub triggers: Arc<std::sync::Mutex<TriggerEngine>>,
+
+    /// Highlight rules and match counters evaluated by the I/O task
+    pub highlights: Arc<std::sync::Mutex<HighlightEngine>>,
+
+    /// Alert rules evaluated by the monitoring task
+    pub alerts: Arc<std::sync::Mutex<AlertEngine>>,
+
+    /// Last user input and idle lock, watched by the idle task
+    pub idle: Arc<IdleGuard>,
+
+    /// Holds dangerous commands until confirmed; `None` when not guarded
+    pub guard: Option<Arc<std::sync::Mutex<CommandGuard>>>,
+
+    /// Notify once when the running command finishes while unfocused
+    pub notify_when_done: Arc<AtomicBool>,
+
+    /// Pauses monitoring and slows the read loop while the tab is hidden
+    pub suspension: Arc<Suspension>,
+
+    /// Handle to the background tokio task processing the SSH data
+    pub handle: Option<tokio::task::JoinHandle<()>>,
+
+    /// Handle to the background monitoring task
+    pub status_handle: Option<tokio::task::JoinHandle<()>>,
+
+    /// Sender to transmit user input to the SSH channel
+    pub input_sender: mpsc::UnboundedSender<InputCommand>,
+
+    /// Atomic flag to signal the background task to terminate
+    pub stop_flag: Arc<AtomicBool>,
+
+    /// Monotonically increasing sequence number for output chunks
     #[allow(dead_code)]
-    pub username: String,
+    pub next_seq: Arc<AtomicU64>,
+
+    /// Cached initial output (welcome banner) for late-joining clients;
+    /// never held across an await
+    pub initial_outputs: Arc<Mutex<Vec<OutputChunk>>>,
+
+    /// Refresh interval for monitoring task (in milliseconds)
+    pub refresh_interval: Arc<AtomicU64>,
+
+    /// Latest reading of the monitoring task; `None` before the first one
+    /// and after a failed one
+    pub last_status: Arc<RwLock<Option<ServerStatus>>>,
+
+    /// Session handle for opening new channels
+    pub sess_arc: Arc<tokio::sync::Mutex<Session>>,
+
+    /// Identification string the server sent before authentication
+    pub server_banner: Option<String>,
+
+    /// Session handle with the cached SFTP subsystem
+    pub sftp_handle: SessionHandle,
+
+    /// Saved session this connection was opened from
+    pub saved_session_id: Option<String>,
+
+    /// Window the session's events are sent to, with output for replay
+    pub route: SessionRoute<OutputChunk>,
+
+    /// Input and resize listeners, removed when the session is
+    pub listeners: SessionListeners,
+}
+
+impl SshChannelInfo {
+    /// Banner and MOTD the server showed when the session was opened
+    fn banner(&self) -> SessionBanner {
+        SessionBanner {
+            server_banner: self.server_banner.clone(),
+            motd: self
+                .initial_outputs
+                .lock()
+                .ok()
+                .and_then(|chunks| motd_from_output(&chunks)),
+        }
+    }
+}
+
+// ============================================================================
+// Output Pipeline
+// ============================================================================
+
+/// Delivers batched output to the frontend while bounding memory use.
+///
+/// Output is emitted as `ssh-output-{sessionId}` events to the session's
+/// window and mirrored into a bounded polling queue. When a command floods the terminal, batches are
+/// coalesced and, past `MAX_PENDING_BATCH_BYTES`, their middle is dropped;
+/// the gap is reported with an `ssh-output-truncated-{sessionId}` event.
+struct OutputPipeline {
+    app_handle: Option<tauri::AppHandle>,
+    session_id: SessionId,
+    route: SessionRoute<OutputChunk>,
+    sender: mpsc::Sender<OutputChunk>,
+    queued_bytes: Arc<AtomicUsize>,
+    /// When set, chunks carry raw bytes instead of decoded text
+    raw_mode: Arc<AtomicBool>,
+    /// Character set of the session, changed by `set_session_encoding`
+    encoding: Arc<RwLock<SessionEncoding>>,
+    decoder: TerminalDecoder,
+    stderr_decoder: TerminalDecoder,
+    osc: OscScanner,
+    shell_state: Arc<ShellState>,
+    commands: CommandTracker,
+    /// Saved session whose stored password answers sudo prompts, when
+    /// autofill is enabled
+    sudo_autofill: Arc<RwLock<Option<String>>>,
+    sudo: SudoPromptDetector,
+    triggers: Arc<std::sync::Mutex<TriggerEngine>>,
+    highlights: Arc<std::sync::Mutex<HighlightEngine>>,
+    notify_when_done: Arc<AtomicBool>,
+    input_sender: mpsc::UnboundedSender<InputCommand>,
+    /// Saved session this connection was opened from
+    saved_session_id: Option<String>,
+    /// Queue of OSC 52 clipboard writes, started by the first one
+    clipboard: Option<mpsc::UnboundedSender<String>>,
+    /// Attach link spans to text chunks
+    detect_links: bool,
+    /// Cuts inline images out of the output; `None` when they are disabled
+    images: Option<ImageScanner>,
+    /// Adds text output to the search index; `None` when it is disabled
+    index: Option<OutputIndexer>,
+    /// Sequence number of the chunk being emitted
+    current_seq: u64,
+    /// Bytes dropped since the last emitted chunk
+    dropped_bytes: u64,
+    /// Bytes read in the current one-second rate window
+    window_bytes: usize,
+    window_start: std::time::Instant,
+    flooding: bool,
+    stats: Arc<SessionStats>,
+    /// Sent when the server sends ENQ; empty to stay silent
+    answerback: String,
+    guard: Option<Arc<std::sync::Mutex<CommandGuard>>>,
+}
+
+impl OutputPipeline {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        app_handle: Option<tauri::AppHandle>,
+        session_id: SessionId,
+        route: SessionRoute<OutputChunk>,
+        sender: mpsc::Sender<OutputChunk>,
+        queued_bytes: Arc<AtomicUsize>,
+        raw_mode: Arc<AtomicBool>,
+        shell_state: Arc<ShellState>,
+        sudo_autofill: Arc<RwLock<Option<String>>>,
+        username: String,
+        triggers: Arc<std::sync::Mutex<TriggerEngine>>,
+        highlights: Arc<std::sync::Mutex<HighlightEngine>>,
+        notify_when_done: Arc<AtomicBool>,
+        input_sender: mpsc::UnboundedSender<InputCommand>,
+        saved_session_id: Option<String>,
+        stats: Arc<SessionStats>,
+        encoding: Arc<RwLock<SessionEncoding>>,
+        answerback: String,
+        guard: Option<Arc<std::sync::Mutex<CommandGuard>>>,
+    ) -> Self {
+        let terminal = crate::settings::current().terminal;
+        let initial_encoding = encoding.read().map(|e| *e).unwrap_or_default();
+        let index = OutputIndexer::for_session(&session_id.0);
+        Self {
+            app_handle,
+            session_id,
+            route,
+            sender,
+            queued_bytes,
+            raw_mode,
+            decoder: TerminalDecoder::new(initial_encoding),
+            stderr_decoder: TerminalDecoder::new(initial_encoding),
+            encoding,
+            osc: OscScanner::default(),
+            shell_state,
+            commands: CommandTracker::default(),
+            sudo_autofill,
+            sudo: SudoPromptDetector::new(username),
+            triggers,
+            highlights,
+            notify_when_done,
+            input_sender,
+            saved_session_id,
+            clipboard: None,
+            detect_links: terminal.detect_links,
+            images: terminal.inline_images.then(ImageScanner::default),
+            index,
+            current_seq: 0,
+            dropped_bytes: 0,
+            window_bytes: 0,
+            window_start: std::time::Instant::now(),
+            flooding: false,
+            stats,
+            answerback,
+            guard,
+        }
+    }
+
+    /// Tracks the incoming byte rate to detect output floods
+    fn record_rate(&mut self, n: usize) {
+        self.stats
+            .terminal_in
+            .fetch_add(n as u64, Ordering::Relaxed);
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            self.flooding = self.window_bytes > FLOOD_BYTES_PER_SEC;
+            self.window_bytes = 0;
+            self.window_start = std::time::Instant::now();
+        }
+        self.window_bytes += n;
+        if self.window_bytes > FLOOD_BYTES_PER_SEC {
+            self.flooding = true;
+        }
+    }
+
+    fn is_flooding(&self) -> bool {
+        self.flooding
+    }
+
+    /// Drops the middle of an oversized batch, keeping its head and tail.
+    /// Both cuts are moved off UTF-8 continuation bytes so no character is
+    /// split.
+    fn cap_pending(&mut self, pending: &mut Vec<u8>) {
+        if pending.len() <= MAX_PENDING_BATCH_BYTES {
+            return;
+        }
+        let is_continuation = |b: u8| b & 0xC0 == 0x80;
+        let mut keep = MAX_PENDING_BATCH_BYTES / 4;
+        while keep > 0 && is_continuation(pending[keep]) {
+            keep -= 1;
+        }
+        let mut tail_start = pending.len() - MAX_PENDING_BATCH_BYTES / 4;
+        while tail_start < pending.len() && is_continuation(pending[tail_start]) {
+            tail_start += 1;
+        }
+        self.dropped_bytes += (tail_start - keep) as u64;
+        pending.drain(keep..tail_start);
+    }
+
+    /// Tracks shell state reported through OSC sequences
+    fn scan_osc(&mut self, text: &str) {
+        for seq in self.osc.feed(text) {
+            if let Some(mark) = shell_integration::parse_prompt_mark(&seq) {
+                if let Some(event) = self.commands.on_mark(mark, self.current_seq) {
+                    self.emit_command_event(event);
+                }
+            } else if let Some(text) = osc::parse_clipboard(&seq) {
+                self.handle_clipboard(text);
+            } else if let Some(cwd) = shell_integration::parse_cwd(&seq) {
+                if self.shell_state.set_cwd(cwd.clone()) {
+                    if let Some(h) = &self.app_handle {
+                        let payload = CwdChanged {
+                            session_id: self.session_id.0.clone(),
+                            cwd,
+                        };
+                        self.route
+                            .emit(h, &format!("ssh-cwd-{}", self.session_id.0), &payload);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes iTerm2 and Sixel images off the I/O task and emits them as
+    /// `ssh-image-{sessionId}` events
+    fn scan_images(&mut self, text: &str) {
+        let Some(scanner) = &mut self.images else {
+            return;
+        };
+        for sequence in scanner.feed(text) {
+            let Some(h) = self.app_handle.clone() else {
+                continue;
+            };
+            let sid = self.session_id.0.clone();
+            let seq = self.current_seq;
+            let route = self.route.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Some(image) = crate::images::decode(sequence, &sid, seq) {
+                    route.emit(&h, &format!("ssh-image-{}", sid), &image);
+                }
+            });
+        }
+    }
+
+    fn emit_command_event(&self, event: CommandEvent) {
+        let Some(h) = &self.app_handle else {
+            return;
+        };
+        let sid = &self.session_id.0;
+        match event {
+            CommandEvent::Prompt => {
+                let payload = PromptStarted {
+                    session_id: sid.clone(),
+                    seq: self.current_seq,
+                };
+                self.route.emit(h, &format!("ssh-prompt-{}", sid), &payload);
+            }
+            CommandEvent::Finished {
+                exit_code,
+                duration,
+                start_seq,
+            } => {
+                let payload = CommandFinished {
+                    session_id: sid.clone(),
+                    exit_code,
+                    duration_ms: duration.as_millis() as u64,
+                    start_seq,
+                    end_seq: self.current_seq,
+                    cwd: self.shell_state.cwd(),
+                };
+                self.route.emit(h, &format!("ssh-command-finished-{}", sid), &payload);
+
+                if self.notify_when_done.swap(false, Ordering::SeqCst) {
+                    let focused = h
+                        .webview_windows()
+                        .values()
+                        .any(|w| w.is_focused().unwrap_or(false));
+                    if !focused {
+                        let title = match exit_code {
+                            Some(0) | None => "Command finished".to_string(),
+                            Some(code) => format!("Command failed (exit {})", code),
+                        };
+                        let _ = h
+                            .notification()
+                            .builder()
+                            .title(title)
+                            .body(format!("Completed in {}", format_duration(duration)))
+                            .show();
+                        self.route.emit(h, &format!("ssh-notify-done-{}", sid), &payload);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles a clipboard write requested through OSC 52 according to the
+    /// saved session's clipboard access, or the global setting. Writes are
+    /// handled one at a time, in the order they arrived.
+    fn handle_clipboard(&mut self, text: String) {
+        let Some(h) = self.app_handle.clone() else {
+            return;
+        };
+        let queue = self.clipboard.get_or_insert_with(|| {
+            let (queue, requests) = mpsc::unbounded_channel();
+            tokio::spawn(Self::write_clipboard(
+                h,
+                self.session_id.0.clone(),
+                self.saved_session_id.clone(),
+                self.route.clone(),
+                requests,
+            ));
+            queue
+        });
+        let _ = queue.send(text);
+    }
+
+    /// Handles the session's queued OSC 52 clipboard writes until the
+    /// pipeline is dropped
+    async fn write_clipboard(
+        h: tauri::AppHandle,
+        sid: String,
+        saved_id: Option<String>,
+        route: SessionRoute<OutputChunk>,
+        mut requests: mpsc::UnboundedReceiver<String>,
+    ) {
+        while let Some(text) = requests.recv().await {
+            let saved_id = saved_id.clone();
+            let access = tokio::task::spawn_blocking(move || {
+                saved_id
+                    .and_then(|id| crate::db::load_clipboard_access(&id).ok().flatten())
+                    .unwrap_or_else(|| crate::settings::current().clipboard.osc52)
+            })
+            .await
+            .unwrap_or_default();
+
+            let length = text.chars().count();
+            let (status, text) = match access.as_str() {
+                "allow" => match h.clipboard().write_text(text) {
+                    Ok(()) => ("copied", None),
+                    Err(e) => {
+                        tracing::warn!("OSC 52 clipboard write failed: {}", e);
+                        ("error", None)
+                    }
+                },
+                "ask" => ("ask", Some(text)),
+                _ => ("blocked", None),
+            };
+            let payload = ClipboardRequest {
+                session_id: sid.clone(),
+                status: status.to_string(),
+                text,
+                length,
+            };
+            route.emit(&h, &format!("ssh-clipboard-{}", sid), &payload);
+        }
+    }
+
+    /// Answers sudo prompts with the stored password when autofill is on.
+    ///
+    /// The password is loaded off the I/O task and never logged or emitted.
+    fn check_sudo_prompt(&mut self, text: &str) {
+        let saved_id = match self.sudo_autofill.read() {
+            Ok(guard) => guard.clone(),
+            Err(_) => None,
+        };
+        let Some(saved_id) = saved_id else {
+            return;
+        };
+
+        match self.sudo.feed(text) {
+            Some(SudoAction::Fill) => {
+                let input_tx = self.input_sender.clone();
+                let app_handle = self.app_handle.clone();
+                let sid = self.session_id.0.clone();
+                let route = self.route.clone();
+                tokio::spawn(async move {
+                    let password = tokio::task::spawn_blocking(move || {
+                        crate::db::load_session_credentials(&saved_id)
+                            .ok()
+                            .flatten()
+                            .and_then(|c| c.password)
+                    })
+                    .await
+                    .ok()
+                    .flatten();
+
+                    let status = match password {
+                        Some(password) => {
+                            let mut data = password.into_bytes();
+                            data.push(b'\n');
+                            let _ = input_tx.send(InputCommand::Data(data));
+                            "filled"
+                        }
+                        None => "unavailable",
+                    };
+                    if let Some(h) = app_handle {
+                        let payload = SudoAutofillEvent {
+                            session_id: sid.clone(),
+                            status: status.to_string(),
+                        };
+                        route.emit(&h, &format!("ssh-sudo-autofill-{}", sid), &payload);
+                    }
+                });
+            }
+            Some(SudoAction::Rejected) => {
+                // Never retry a rejected password: turn autofill off
+                if let Ok(mut guard) = self.sudo_autofill.write() {
+                    *guard = None;
+                }
+                if let Some(h) = &self.app_handle {
+                    let payload = SudoAutofillEvent {
+                        session_id: self.session_id.0.clone(),
+                        status: "rejected".to_string(),
+                    };
+                    self.route.emit(
+                        h,
+                        &format!("ssh-sudo-autofill-{}", self.session_id.0),
+                        &payload,
+                    );
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Runs output triggers and performs their actions
+    fn check_triggers(&mut self, text: &str) {
+        let matches = match self.triggers.lock() {
+            Ok(mut engine) => engine.feed(&self.session_id.0, text),
+            Err(_) => return,
+        };
+        for m in matches {
+            match m.action {
+                TriggerAction::Send(value) => {
+                    let _ = self
+                        .input_sender
+                        .send(InputCommand::Data(value.into_bytes()));
+                }
+                TriggerAction::Run(value) => {
+                    let mut data = value.into_bytes();
+                    data.push(b'\n');
+                    let _ = self.input_sender.send(InputCommand::Data(data));
+                }
+                TriggerAction::Notify(_) => {
+                    if let Some(h) = &self.app_handle {
+                        crate::notifications::trigger_fired(h, &m.event);
+                    }
+                }
+            }
+            if let Some(h) = &self.app_handle {
+                self.route
+                    .emit(h, &format!("ssh-trigger-{}", self.session_id.0), &m.event);
+            }
+        }
+    }
+
+    /// Observes user input for command tracking
+    fn on_input(&mut self, data: &[u8]) {
+        self.commands.on_input(data, self.current_seq + 1);
+    }
+
+    /// Replies to ENQ (0x05) with the session's answerback string
+    fn answer_enquiry(&self, bytes: &[u8]) {
+        if !self.answerback.is_empty() && bytes.contains(&0x05) {
+            let _ = self
+                .input_sender
+                .send(InputCommand::Data(self.answerback.as_bytes().to_vec()));
+        }
+    }
+
+    /// Picks up an encoding changed at runtime
+    fn sync_encoding(&mut self) {
+        if let Ok(setting) = self.encoding.read() {
+            self.decoder.set(*setting);
+            self.stderr_decoder.set(*setting);
+        }
+    }
+
+    /// Passes input through the command guard; returns what may be written
+    /// now and emits `ssh-command-guard-{sessionId}` for a held command
+    fn guard_input(&self, input: InputCommand) -> Vec<InputCommand> {
+        let Some(guard) = &self.guard else {
+            return vec![input];
+        };
+        let Ok(mut guard) = guard.lock() else {
+            return vec![input];
+        };
+        let (forward, held) = guard.filter(input);
+        if let (Some(held), Some(h)) = (held, &self.app_handle) {
+            self.route.emit(
+                h,
+                &format!("ssh-command-guard-{}", self.session_id.0),
+                &held,
+            );
+        }
+        forward
+    }
+
+    /// Input released by confirming or rejecting a held command
+    fn released_input(&self) -> Vec<InputCommand> {
+        self.guard
+            .as_ref()
+            .and_then(|guard| guard.lock().ok())
+            .map(|mut guard| guard.take_released())
+            .unwrap_or_default()
+    }
+
+    /// Converts text input to the session's encoding
+    fn encode_input(&self, input: InputCommand) -> InputCommand {
+        match input {
+            InputCommand::Data(data) => InputCommand::Data(self.decoder.encode(data)),
+            InputCommand::Paste { paste_id, data } => InputCommand::Paste {
+                paste_id,
+                data: self.decoder.encode(data),
+            },
+        }
+    }
+
+    /// Reports a connection that dropped on its own; a clean EOF, such as
+    /// the remote shell exiting, is not reported
+    fn on_disconnected(&self) {
+        if let Some(h) = &self.app_handle {
+            crate::notifications::session_disconnected(h, &self.session_id.0);
+        }
+    }
+
+    fn emit_paste_progress(&self, progress: PasteProgress) {
+        if let Some(h) = &self.app_handle {
+            self.route.emit(
+                h,
+                &format!("ssh-paste-progress-{}", self.session_id.0),
+                &progress,
+            );
+        }
+    }
+
+    /// Emits a chunk to the frontend and the polling queue
+    fn emit(&mut self, seq: u64, bytes: Vec<u8>) -> OutputChunk {
+        self.current_seq = seq;
+        self.sync_encoding();
+        self.answer_enquiry(&bytes);
+        let chunk = if self.raw_mode.load(Ordering::SeqCst) {
+            let text = String::from_utf8_lossy(&bytes);
+            self.scan_osc(&text);
+            self.scan_images(&text);
+            OutputChunk::raw(seq, &bytes)
+        } else {
+            let text = self.decoder.decode(&bytes);
+            self.scan_osc(&text);
+            self.scan_images(&text);
+            self.check_sudo_prompt(&text);
+            self.check_triggers(&text);
+            if let Some(index) = &mut self.index {
+                index.feed(seq, &text);
+            }
+            crate::plugins::observe_output(
+                &self.session_id.0,
+                self.saved_session_id.as_deref(),
+                &text,
+            );
+            let mut chunk = OutputChunk::new(seq, text);
+            // Floods are left plain; nobody clicks what scrolls by that fast
+            if self.detect_links && !self.flooding {
+                let links = links::detect(&chunk.output);
+                if !links.is_empty() {
+                    chunk.links = Some(links);
+                }
+            }
+            if let Ok(mut engine) = self.highlights.lock() {
+                let spans = engine.feed(&chunk.output, !self.flooding);
+                if !spans.is_empty() {
+                    chunk.highlights = Some(spans);
+                }
+            }
+            chunk
+        };
+        self.deliver(chunk)
+    }
+
+    /// Emits a batch read from the channel's stderr stream. Only the
+    /// terminal stream is scanned for triggers, prompts and escape
+    /// sequences.
+    fn emit_stderr(&mut self, seq: u64, bytes: Vec<u8>) -> OutputChunk {
+        self.current_seq = seq;
+        self.sync_encoding();
+        let mut chunk = if self.raw_mode.load(Ordering::SeqCst) {
+            OutputChunk::raw(seq, &bytes)
+        } else {
+            let text = self.stderr_decoder.decode(&bytes);
+            if let Some(index) = &mut self.index {
+                index.feed_stderr(seq, &text);
+            }
+            OutputChunk::new(seq, text)
+        };
+        chunk.stream = OutputStream::Stderr;
+        self.deliver(chunk)
+    }
+
+    /// Sends a chunk to the session's window and the polling queue
+    fn deliver(&mut self, chunk: OutputChunk) -> OutputChunk {
+        let seq = chunk.seq;
+        if self.dropped_bytes > 0 {
+            if let Some(h) = &self.app_handle {
+                let marker = OutputTruncated {
+                    seq,
+                    dropped_bytes: self.dropped_bytes,
+                    ts: chunk.ts,
+                };
+                self.route.emit(
+                    h,
+                    &format!("ssh-output-truncated-{}", self.session_id.0),
+                    &marker,
+                );
+            }
+            self.dropped_bytes = 0;
+        }
+
+        let len = chunk.size();
+        self.route.emit_output(
+            self.app_handle.as_ref(),
+            &format!("ssh-output-{}", self.session_id.0),
+            &chunk,
+            len,
+        );
+
+        // The polling queue is best effort: when nobody drains it, stop
+        // queueing instead of growing without bound
+        if self.queued_bytes.load(Ordering::SeqCst) + len <= MAX_QUEUED_OUTPUT_BYTES
+            && self.sender.try_send(chunk.clone()).is_ok()
+        {
+            self.queued_bytes.fetch_add(len, Ordering::SeqCst);
+        }
+
+        chunk
+    }
+}
+
+/// A pending write, optionally belonging to a tracked paste
+struct PendingWrite {
+    data: Vec<u8>,
+    offset: usize,
+    paste_id: Option<String>,
+    last_progress: std::time::Instant,
 }
 
-/// Represents a chunk of output data from an SSH session
+/// Input waiting to be written to the non-blocking shell channel.
 ///
-/// Each chunk has a monotonically increasing sequence number
-/// to enable reliable client-side buffering and deduplication.
+/// Writes are limited to `INPUT_SLICE_SIZE` per iteration and to the remote
+/// write window, and partial writes resume where they stopped instead of
+/// being dropped.
+#[derive(Default)]
+struct InputQueue {
+    pending: VecDeque<PendingWrite>,
+}
+
+impl InputQueue {
+    fn push(&mut self, input: InputCommand) {
+        let (data, paste_id) = match input {
+            InputCommand::Data(data) => (data, None),
+            InputCommand::Paste { paste_id, data } => (data, Some(paste_id)),
+        };
+        self.pending.push_back(PendingWrite {
+            data,
+            offset: 0,
+            paste_id,
+            last_progress: std::time::Instant::now(),
+        });
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Writes the next slice of queued input; returns an error only when the
+    /// channel is unusable
+    fn write_to(
+        &mut self,
+        ch: &mut ssh2::Channel,
+        pipeline: &OutputPipeline,
+    ) -> std::io::Result<()> {
+        let mut budget = INPUT_SLICE_SIZE.min(ch.write_window().remaining as usize);
+        if budget == 0 {
+            return Ok(());
+        }
+
+        while budget > 0 {
+            let Some(current) = self.pending.front_mut() else {
+                break;
+            };
+            let end = (current.offset + budget).min(current.data.len());
+            match ch.write(&current.data[current.offset..end]) {
+                Ok(n) => {
+                    current.offset += n;
+                    budget -= n.min(budget);
+                    pipeline
+                        .stats
+                        .terminal_out
+                        .fetch_add(n as u64, Ordering::Relaxed);
+                    if n == 0 {
+                        break;
+                    }
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+
+            let finished = current.offset >= current.data.len();
+            if let Some(paste_id) = &current.paste_id {
+                if finished
+                    || current.last_progress.elapsed()
+                        > Duration::from_millis(PASTE_PROGRESS_INTERVAL_MS)
+                {
+                    pipeline.emit_paste_progress(PasteProgress {
+                        paste_id: paste_id.clone(),
+                        written_bytes: current.offset as u64,
+                        total_bytes: current.data.len() as u64,
+                        done: finished,
+                    });
+                    current.last_progress = std::time::Instant::now();
+                }
+            }
+            if finished {
+                self.pending.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        match ch.flush() {
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(()),
+            other => other,
+        }
+    }
+}
+
+// ============================================================================
+// Connection Establishment
+// ============================================================================
+
+/// Resolves the host, opens the TCP connection and completes the SSH
+/// handshake. Blocking; the returned session is not yet authenticated.
+///
+/// All resolved addresses are tried (happy eyeballs); the address that
+/// connected is returned alongside the session. Setting `cancel` aborts
+/// the TCP connect with `Cancelled`.
+fn open_transport(
+    host: &str,
+    port: u16,
+    advanced: &SshAdvancedOptions,
+    cancel: &AtomicBool,
+    trace: &mut ConnectionTrace,
+) -> Result<(Session, SocketAddr), SshError> {
+    let connection_failed = |reason| SshError::ConnectionFailed {
+        host: format!("{}:{}", host, port),
+        port,
+        reason,
+    };
+    let mut sess = Session::new()
+        .map_err(|e| SshError::OperationFailed(format!("Failed to create session: {}", e)))?;
+
+    let session_manager = advanced.aws.as_ref().filter(|aws| aws.session_manager);
+    let (connected_addr, cli_stderr) = match session_manager {
+        Some(aws) => {
+            let tunnel = trace
+                .step(
+                    "tunnel",
+                    crate::aws::open_session_manager(aws, port),
+                    |_| format!("Session Manager session to {} started", aws.instance_id),
+                )
+                .map_err(connection_failed)?;
+            sess.set_tcp_stream(tunnel.stream);
+            // A stalled CLI would otherwise hang the handshake forever
+            sess.set_timeout(advanced.connect_timeout().as_millis() as u32);
+            (tunnel.local_addr, Some(tunnel.stderr))
+        }
+        None => {
+            let addrs = trace
+                .step("resolve", crate::network::resolve(host, port), |addrs| {
+                    let list: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+                    format!("{} resolved to {}", host, list.join(", "))
+                })
+                .map_err(connection_failed)?;
+            let connected = trace.step(
+                "tcp",
+                crate::network::connect_happy_eyeballs(addrs, advanced.connect_timeout(), cancel),
+                |(_, addr)| format!("Connected to {}", addr),
+            );
+            if cancel.load(Ordering::SeqCst) {
+                return Err(SshError::Cancelled);
+            }
+            let (tcp, connected_addr) = connected.map_err(connection_failed)?;
+            sess.set_tcp_stream(tcp);
+            (connected_addr, None)
+        }
+    };
+
+    advanced.apply(&sess)?;
+    trace
+        .step("handshake", sess.handshake(), |_| negotiated_summary(&sess))
+        .map_err(|e| {
+            // The CLI's complaint (expired credentials, missing plugin) says
+            // more than libssh2's
+            let reason = cli_stderr
+                .as_ref()
+                .and_then(|stderr| stderr.last_line())
+                .unwrap_or_else(|| e.to_string());
+            SshError::OperationFailed(format!("Handshake failed: {}", reason))
+        })?;
+    sess.set_timeout(0);
+    Ok((sess, connected_addr))
+}
+
+/// Describes the algorithms negotiated during the handshake and the host
+/// key fingerprint
+fn negotiated_summary(sess: &Session) -> String {
+    use ssh2::MethodType;
+    let method = |m| sess.methods(m).unwrap_or("?");
+    let mut summary = format!(
+        "kex={} hostkey={} cipher={} mac={} compression={}",
+        method(MethodType::Kex),
+        method(MethodType::HostKey),
+        method(MethodType::CryptCs),
+        method(MethodType::MacCs),
+        method(MethodType::CompCs),
+    );
+    if let Some(hash) = sess.host_key_hash(ssh2::HashType::Sha256) {
+        summary.push_str(&format!(
+            " fingerprint=SHA256:{}",
+            general_purpose::STANDARD_NO_PAD.encode(hash)
+        ));
+    }
+    if let Some(banner) = sess.banner() {
+        summary.push_str(&format!(" server={}", banner));
+    }
+    summary
+}
+
+/// Performs the saved session's port knocking sequence, if one is configured
+fn knock_saved_session(host: &str, port: u16, saved_id: &str) -> Result<(), SshError> {
+    let steps = crate::db::load_knock_sequence(saved_id).unwrap_or_default();
+    crate::network::knock(host, &steps).map_err(|reason| SshError::ConnectionFailed {
+        host: host.to_string(),
+        port,
+        reason: format!("Port knocking failed: {}", reason),
+    })
+}
+
+/// Authenticates with a private key, offering its OpenSSH certificate when
+/// one is configured or found next to the key. The certificate's validity
+/// window and principals are checked locally first so an expired
+/// certificate fails with a clear reason instead of a generic auth error.
+/// A certificate found next to the key that fails these checks is skipped
+/// and the plain key offered instead, as OpenSSH does.
+fn authenticate_with_key(
+    sess: &Session,
+    username: &str,
+    key_path: &str,
+    certificate_path: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<String, SshError> {
+    let explicit = certificate_path.is_some_and(|p| !p.trim().is_empty());
+    let mut certificate = crate::certificate::certificate_for_key(key_path, certificate_path);
+    let mut accepted = None;
+    if let Some(cert) = &certificate {
+        match crate::certificate::validate_certificate(cert, username) {
+            Ok(info) => accepted = Some(info),
+            Err(reason) if !explicit => {
+                tracing::warn!("skipping {}: {}", cert.display(), reason);
+                certificate = None;
+            }
+            Err(reason) => return Err(SshError::AuthenticationFailed(reason)),
+        }
+    }
+    let description = match accepted {
+        Some(info) => format!("certificate {} accepted for {}", info.key_id, username),
+        None => format!("key accepted for {}", username),
+    };
+    sess.userauth_pubkey_file(
+        username,
+        certificate.as_deref(),
+        std::path::Path::new(key_path),
+        passphrase,
+    )
+    .map_err(|e| SshError::AuthenticationFailed(e.to_string()))?;
+    Ok(description)
+}
+
+/// Authenticates with a one-time key pushed through EC2 Instance Connect.
+/// The key is deleted once libssh2 has used it.
+fn authenticate_instance_connect(
+    sess: &Session,
+    username: &str,
+    aws: &crate::aws::AwsConnectOptions,
+) -> Result<String, SshError> {
+    let key =
+        crate::aws::push_ephemeral_key(aws, username).map_err(SshError::AuthenticationFailed)?;
+    sess.userauth_pubkey_file(username, None, &key.private_key_path(), None)
+        .map_err(|e| SshError::AuthenticationFailed(e.to_string()))?;
+    Ok(format!(
+        "Instance Connect key accepted for {} on {}",
+        username, aws.instance_id
+    ))
+}
+
+/// Connects to a saved session for a job that runs without the user, such
+/// as a scheduled task or config backup. Protected sessions are refused,
+/// as nobody is there to re-authenticate. Blocking.
+pub fn connect_unattended(saved: &crate::db::Session) -> Result<Session, SshError> {
+    if crate::db::load_session_protected(&saved.id).map_err(SshError::OperationFailed)? {
+        return Err(SshError::AuthenticationFailed(format!(
+            "{} is protected and cannot be connected without re-authentication",
+            saved.server_name
+        )));
+    }
+    connect_saved_session(saved)
+}
+
+/// Connects to a saved session and authenticates with its stored
+/// credentials. Blocking; used by background jobs that run without a
+/// terminal tab. Callers confirm protected sessions first.
+pub fn connect_saved_session(saved: &crate::db::Session) -> Result<Session, SshError> {
+    let port = u16::try_from(saved.port)
+        .map_err(|_| SshError::OperationFailed(format!("Invalid port: {}", saved.port)))?;
+    let credentials = crate::db::load_session_credentials(&saved.id)
+        .map_err(SshError::OperationFailed)?
+        .unwrap_or(crate::encryption::SensitiveData {
+            password: None,
+            key_passphrase: None,
+        });
+
+    knock_saved_session(&saved.addr, port, &saved.id)?;
+    let advanced = crate::db::load_advanced_options(&saved.id).unwrap_or_default();
+    let (sess, _) = open_transport(
+        &saved.addr,
+        port,
+        &advanced,
+        &AtomicBool::new(false),
+        &mut ConnectionTrace::new(),
+    )?;
+    let instance_connect = advanced.aws.as_ref().filter(|aws| aws.instance_connect);
+    match (
+        instance_connect,
+        saved.auth_type.as_str(),
+        &saved.private_key_path,
+    ) {
+        (Some(aws), _, _) => {
+            authenticate_instance_connect(&sess, &saved.username, aws)?;
+        }
+        (None, "key", Some(key_path)) => {
+            authenticate_with_key(
+                &sess,
+                &saved.username,
+                key_path,
+                saved.certificate_path.as_deref(),
+                credentials.key_passphrase.as_deref(),
+            )?;
+        }
+        _ => {
+            let password = credentials
+                .password
+                .ok_or_else(|| SshError::AuthenticationFailed("No stored password".to_string()))?;
+            sess.userauth_password(&saved.username, &password)
+                .map_err(|_| SshError::AuthenticationFailed("Invalid credentials".to_string()))?
+        }
+    }
+
+    if !sess.authenticated() {
+        return Err(SshError::AuthenticationFailed(
+            "Authentication failed".to_string(),
+        ));
+    }
+    Ok(sess)
+}
+
+/// Quotes a string for a POSIX shell command line
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Pause between polls of a long-running exec channel
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// stderr kept from an exec channel; the rest is read and dropped
+const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+/// Pause between two network round-trip measurements of a session
+const LATENCY_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pause between two checks of a session's idle timeout
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pause between reads of a suspended session's channel when no data came
+const SUSPENDED_READ_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest wait of the paused monitoring task before it checks whether
+/// the session has been closed
+const SUSPENDED_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of a command run on an exec channel
+#[derive(Debug, Clone)]
+pub struct ExecOutput {
+    /// Empty for `SessionHandle::exec_streaming`, which hands stdout out
+    /// as it arrives
+    pub stdout: String,
+    /// The first `MAX_STDERR_BYTES` of stderr
+    pub stderr: String,
+    /// `None` when the command was stopped by the timeout
+    pub exit_status: Option<i32>,
+    pub timed_out: bool,
+}
+
+impl ExecOutput {
+    /// Message for a failed command: the last line of stderr, or the exit
+    /// status when the command printed no error
+    pub fn error_message(&self, tool: &str) -> String {
+        self.stderr
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                format!(
+                    "{} exited with status {}",
+                    tool,
+                    self.exit_status.unwrap_or(-1)
+                )
+            })
+    }
+}
+
+/// Appends to `buf` up to `limit` bytes in total
+fn push_limited(buf: &mut Vec<u8>, data: &[u8], limit: usize) {
+    let room = limit.saturating_sub(buf.len());
+    buf.extend_from_slice(&data[..data.len().min(room)]);
+}
+
+/// Bytes written per session-lock acquisition during SFTP uploads. libssh2
+/// pipelines the SFTP write packets of one call, so larger chunks mean fewer
+/// round trips; 1 MiB keeps each lock hold short enough that the terminal
+/// does not stutter on slow links.
+const SFTP_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Cumulative traffic of a session in bytes, counted as payload read from
+/// and written to its channels
+#[derive(Debug)]
+pub struct SessionStats {
+    /// Unix timestamp (ms) of when counting started
+    since: i64,
+    terminal_in: AtomicU64,
+    terminal_out: AtomicU64,
+    sftp_in: AtomicU64,
+    sftp_out: AtomicU64,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            since: now_millis() as i64,
+            terminal_in: AtomicU64::new(0),
+            terminal_out: AtomicU64::new(0),
+            sftp_in: AtomicU64::new(0),
+            sftp_out: AtomicU64::new(0),
+        }
+    }
+}
+
+impl SessionStats {
+    pub fn snapshot(&self) -> SessionTraffic {
+        let terminal_in = self.terminal_in.load(Ordering::Relaxed);
+        let terminal_out = self.terminal_out.load(Ordering::Relaxed);
+        let sftp_in = self.sftp_in.load(Ordering::Relaxed);
+        let sftp_out = self.sftp_out.load(Ordering::Relaxed);
+        SessionTraffic {
+            since: self.since,
+            terminal_in,
+            terminal_out,
+            sftp_in,
+            sftp_out,
+            total_in: terminal_in + sftp_in,
+            total_out: terminal_out + sftp_out,
+        }
+    }
+}
+
+/// Traffic totals returned by `get_session_stats`
 #[derive(Debug, Clone, Serialize)]
-pub struct OutputChunk {
-    pub seq: u64,
-    pub output: String,
-    pub ts: u128,
+#[serde(rename_all = "camelCase")]
+pub struct SessionTraffic {
+    /// Unix timestamp (ms) of when the session connected
+    pub since: i64,
+    /// Shell output received
+    pub terminal_in: u64,
+    /// Keystrokes and pastes sent
+    pub terminal_out: u64,
+    /// Downloaded file content
+    pub sftp_in: u64,
+    /// Uploaded file content
+    pub sftp_out: u64,
+    pub total_in: u64,
+    pub total_out: u64,
+}
+
+/// Session handle shared with SFTP transfers and file operations
+#[derive(Clone)]
+pub struct SessionHandle {
+    pub sess: Arc<tokio::sync::Mutex<Session>>,
+    /// Traffic counters of the connection, shared with its I/O task
+    pub stats: Arc<SessionStats>,
+    /// SFTP subsystem, opened on first use and kept for the session's
+    /// lifetime instead of being re-negotiated per operation
+    sftp: Arc<std::sync::Mutex<Option<Sftp>>>,
+    /// User and group names of the server, read on first use
+    owner_names: Arc<std::sync::Mutex<Option<Arc<OwnerNames>>>>,
+}
+
+impl SessionHandle {
+    fn new(sess: Arc<tokio::sync::Mutex<Session>>) -> Self {
+        Self {
+            sess,
+            stats: Arc::default(),
+            sftp: Arc::new(std::sync::Mutex::new(None)),
+            owner_names: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    /// Wraps a standalone session, such as one from
+    /// `connect_saved_session`, so the exec helpers can run on it
+    pub fn detached(sess: Session) -> Self {
+        sess.set_blocking(false);
+        Self::new(Arc::new(tokio::sync::Mutex::new(sess)))
+    }
+
+    /// Runs `f` with the session lock held and the session in blocking
+    /// mode, restoring non-blocking mode for the I/O task afterwards.
+    /// Blocking; meant for a worker thread.
+    pub fn blocking<T>(&self, f: impl FnOnce(&Session) -> T) -> T {
+        let sess = self.sess.blocking_lock();
+        sess.set_blocking(true);
+        let result = f(&sess);
+        sess.set_blocking(false);
+        result
+    }
+
+    /// Runs `f` against the session's SFTP subsystem, opening it on first
+    /// use. Blocking.
+    pub fn with_sftp<T>(
+        &self,
+        f: impl FnOnce(&Sftp) -> Result<T, SshError>,
+    ) -> Result<T, SshError> {
+        self.blocking(|sess| {
+            let mut cached = self
+                .sftp
+                .lock()
+                .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+            if cached.is_none() {
+                *cached = Some(sess.sftp().map_err(|e| {
+                    SshError::OperationFailed(format!("Failed to start SFTP: {}", e))
+                })?);
+            }
+            f(cached.as_ref().expect("SFTP handle was just opened"))
+        })
+    }
+
+    /// User and group names of the server, read through `sftp` the first
+    /// time they are needed
+    pub fn owner_names(&self, sftp: &Sftp) -> Arc<OwnerNames> {
+        let Ok(mut cached) = self.owner_names.lock() else {
+            return Arc::new(OwnerNames::read(sftp));
+        };
+        cached
+            .get_or_insert_with(|| Arc::new(OwnerNames::read(sftp)))
+            .clone()
+    }
+
+    /// Runs a command on a separate exec channel. The session lock is only
+    /// held while polling for output, so a slow command does not stall the
+    /// terminal. When `timeout` expires the channel is closed and the output
+    /// read so far is returned; setting `cancel` closes it with an error.
+    /// Blocking; meant for a worker thread.
+    pub fn exec_interruptible(
+        &self,
+        command: &str,
+        timeout: Duration,
+        cancel: &AtomicBool,
+    ) -> Result<ExecOutput, SshError> {
+        self.exec_collect(command, Some(timeout), cancel)
+    }
+
+    /// Runs a command on a separate exec channel until it exits, without a
+    /// timeout; for jobs that run unattended. Blocking.
+    pub fn exec_capture(&self, command: &str) -> Result<ExecOutput, SshError> {
+        self.exec_collect(command, None, &AtomicBool::new(false))
+    }
+
+    fn exec_collect(
+        &self,
+        command: &str,
+        timeout: Option<Duration>,
+        cancel: &AtomicBool,
+    ) -> Result<ExecOutput, SshError> {
+        let mut output = Vec::new();
+        let mut result = self.exec_streaming(command, timeout, cancel, |chunk| {
+            output.extend_from_slice(chunk)
+        })?;
+        result.stdout = String::from_utf8_lossy(&output).into_owned();
+        Ok(result)
+    }
+
+    /// Like `exec_interruptible`, but hands stdout to `on_output` as it
+    /// arrives instead of collecting it.
+    pub fn exec_streaming(
+        &self,
+        command: &str,
+        timeout: Option<Duration>,
+        cancel: &AtomicBool,
+        mut on_output: impl FnMut(&[u8]),
+    ) -> Result<ExecOutput, SshError> {
+        let mut channel = self.blocking(|sess| {
+            let mut channel = sess
+                .channel_session()
+                .map_err(|e| SshError::ChannelError(format!("Create channel failed: {}", e)))?;
+            channel
+                .exec(command)
+                .map_err(|e| SshError::ChannelError(format!("Exec failed: {}", e)))?;
+            Ok::<_, SshError>(channel)
+        })?;
+
+        let started = std::time::Instant::now();
+        let mut buffer = [0u8; 8192];
+        let mut errors = Vec::new();
+        let result = loop {
+            if cancel.load(Ordering::SeqCst) {
+                break Err(SshError::OperationFailed("Command cancelled".to_string()));
+            }
+            if timeout.is_some_and(|t| started.elapsed() >= t) {
+                break Ok(true);
+            }
+            // The session is in non-blocking mode whenever the lock is free
+            let polled = {
+                let _sess = self.sess.blocking_lock();
+                // Drain stderr first so it cannot fill the channel window
+                loop {
+                    match channel.stderr().read(&mut buffer) {
+                        Ok(n) if n > 0 => push_limited(&mut errors, &buffer[..n], MAX_STDERR_BYTES),
+                        _ => break,
+                    }
+                }
+                loop {
+                    match channel.read(&mut buffer) {
+                        Ok(0) if channel.eof() => break Ok(true),
+                        Ok(0) => break Ok(false),
+                        Ok(n) => on_output(&buffer[..n]),
+                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            break Ok(false)
+                        }
+                        Err(e) => break Err(SshError::ChannelError(format!("Read failed: {}", e))),
+                    }
+                }
+            };
+            match polled {
+                Ok(true) => break Ok(false),
+                Ok(false) => std::thread::sleep(EXEC_POLL_INTERVAL),
+                Err(e) => break Err(e),
+            }
+        };
+
+        let exit_status = self.blocking(|_| match &result {
+            Ok(false) => {
+                let mut rest = Vec::new();
+                let _ = channel.stderr().read_to_end(&mut rest);
+                push_limited(&mut errors, &rest, MAX_STDERR_BYTES);
+                let _ = channel.wait_close();
+                channel.exit_status().ok()
+            }
+            _ => {
+                let _ = channel.close();
+                None
+            }
+        });
+        result.map(|timed_out| ExecOutput {
+            stdout: String::new(),
+            stderr: String::from_utf8_lossy(&errors).into_owned(),
+            exit_status,
+            timed_out,
+        })
+    }
 }
 
-/// Represents the progress of an SFTP file upload
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UploadProgress {
-    pub task_id: String,
-    pub session_id: String,
-    pub progress: f64,
-    pub uploaded_bytes: u64,
-    pub total_bytes: u64,
-    pub status: String,
-    pub message: String,
-    pub speed: f64,
-    pub error: Option<String>,
+/// Seconds since the Unix epoch of a local file time
+fn unix_secs(time: std::io::Result<std::time::SystemTime>) -> Option<u64> {
+    time.ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
 }
 
-/// Server performance metrics
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ServerStatus {
-    pub cpu_usage: f64,
-    pub mem_usage: f64,
-    pub mem_total: u64,
-    pub mem_used: u64,
-    pub mem_avail: u64,
-    pub swap_usage: f64,
-    pub swap_total: u64,
-    pub swap_used: u64,
-    pub disk_usage: f64,
-    pub disk_total: u64,
-    pub disk_used: u64,
-    pub disk_avail: u64,
-    pub net_up: f64,
-    pub net_down: f64,
-    pub latency: u32,
-    pub load_avg: [f64; 3],
-    pub uptime: String,
+/// Mode and times of a local file in the form `setstat` expects. The mode
+/// is only carried over from Unix hosts.
+fn local_file_stat(metadata: &std::fs::Metadata) -> ssh2::FileStat {
+    #[cfg(unix)]
+    let perm = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o7777)
+    };
+    #[cfg(not(unix))]
+    let perm = None;
+    let mtime = unix_secs(metadata.modified());
+    ssh2::FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm,
+        // libssh2 sends both times together
+        atime: unix_secs(metadata.accessed()).or(mtime),
+        mtime,
+    }
 }
 
-impl OutputChunk {
-    /// Creates a new output chunk with current timestamp
-    fn new(seq: u64, output: String) -> Self {
-        let ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis();
-        Self { seq, output, ts }
+/// Applies the mode and times of a remote file to a local file
+fn apply_remote_stat(file: &std::fs::File, stat: &ssh2::FileStat) -> std::io::Result<()> {
+    if let Some(mtime) = stat.mtime {
+        let to_time = |secs: u64| std::time::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut times = std::fs::FileTimes::new().set_modified(to_time(mtime));
+        if let Some(atime) = stat.atime {
+            times = times.set_accessed(to_time(atime));
+        }
+        file.set_times(times)?;
+    }
+    #[cfg(unix)]
+    if let Some(perm) = stat.perm {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(perm & 0o7777))?;
     }
+    Ok(())
 }
 
-/// Contains state and communication handles for an active SSH channel
-pub struct SshChannelInfo {
-    /// Asynchronous receiver for SSH output chunks
-    pub receiver: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<OutputChunk>>>,
+/// Uploads a local file via SFTP. Blocking; meant for a worker thread.
+///
+/// The remote file is opened once and written sequentially through the
+/// session's cached SFTP handle. The session lock is released between
+/// chunks so the terminal stays responsive. `on_progress` receives the
+/// bytes written and the total size after each chunk; setting `cancel`
+/// stops the upload. With `preserve` the remote file gets the local
+/// file's mode and modification time.
+pub fn sftp_upload(
+    handle: &SessionHandle,
+    local_path: &str,
+    remote_path: &str,
+    preserve: bool,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<u64, SshError> {
+    let mut local_file = std::fs::File::open(local_path).map_err(|e| {
+        SshError::OperationFailed(format!("Failed to open local file {}: {}", local_path, e))
+    })?;
+    let metadata = local_file.metadata().ok();
+    let total_bytes = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+
+    let mut remote_file = handle.with_sftp(|sftp| {
+        sftp.open_mode(
+            std::path::Path::new(remote_path),
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            0o644,
+            OpenType::File,
+        )
+        .map_err(|e| {
+            SshError::OperationFailed(format!("Failed to open remote file {}: {}", remote_path, e))
+        })
+    })?;
 
-    /// Handle to the background tokio task processing the SSH data
-    pub handle: Option<tokio::task::JoinHandle<()>>,
+    let mut buffer = vec![0u8; SFTP_CHUNK_SIZE];
+    let mut total_written: u64 = 0;
+    let result = (|| {
+        loop {
+            if cancel.load(Ordering::SeqCst) {
+                return Err(SshError::OperationFailed("Transfer cancelled".to_string()));
+            }
 
-    /// Handle to the background monitoring task
-    pub status_handle: Option<tokio::task::JoinHandle<()>>,
+            let n = local_file
+                .read(&mut buffer)
+                .map_err(|e| SshError::OperationFailed(format!("Read local file failed: {}", e)))?;
+            if n == 0 {
+                break;
+            }
 
-    /// Sender to transmit user input to the SSH channel
-    pub input_sender: mpsc::UnboundedSender<String>,
+            // The lock is released after each chunk; tokio's mutex is fair,
+            // so the I/O task gets its turn without an explicit pause
+            handle
+                .blocking(|_| remote_file.write_all(&buffer[..n]))
+                .map_err(|e| {
+                    SshError::OperationFailed(format!("Failed to write to remote file: {}", e))
+                })?;
 
-    /// Atomic flag to signal the background task to terminate
-    pub stop_flag: Arc<AtomicBool>,
+            total_written += n as u64;
+            handle.stats.sftp_out.fetch_add(n as u64, Ordering::Relaxed);
+            on_progress(total_written, total_bytes);
+        }
 
-    /// Monotonically increasing sequence number for output chunks
-    #[allow(dead_code)]
-    pub next_seq: Arc<AtomicU64>,
+        handle
+            .blocking(|_| match remote_file.fsync() {
+                // fsync is an OpenSSH extension; other servers flush on close
+                Err(e) if e.code() != ssh2::ErrorCode::SFTP(8) => Err(e),
+                _ => Ok(()),
+            })
+            .map_err(|e| {
+                SshError::OperationFailed(format!("Failed to finish remote file: {}", e))
+            })?;
+
+        if let Some(metadata) = metadata.as_ref().filter(|_| preserve) {
+            handle
+                .blocking(|_| remote_file.setstat(local_file_stat(metadata)))
+                .map_err(|e| {
+                    SshError::OperationFailed(format!(
+                        "Failed to set attributes of {}: {}",
+                        remote_path, e
+                    ))
+                })?;
+        }
+        Ok(())
+    })();
 
-    /// Cached initial output (welcome banner) for late-joining clients
-    pub initial_outputs: Arc<tokio::sync::Mutex<Vec<OutputChunk>>>,
+    // Close the remote handle in blocking mode so the close request is sent
+    handle.blocking(|_| drop(remote_file));
+    result.map(|_| total_written)
+}
 
-    /// Refresh interval for monitoring task (in milliseconds)
-    pub refresh_interval: Arc<AtomicU64>,
+/// Downloads a remote file via SFTP. Blocking; meant for a worker thread.
+///
+/// Works like `sftp_upload` in the other direction. With `preserve` the
+/// local file gets the remote file's mode and modification time.
+pub fn sftp_download(
+    handle: &SessionHandle,
+    remote_path: &str,
+    local_path: &str,
+    preserve: bool,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<u64, SshError> {
+    let (mut remote_file, stat) = handle.with_sftp(|sftp| {
+        let mut file = sftp.open(std::path::Path::new(remote_path)).map_err(|e| {
+            SshError::OperationFailed(format!("Failed to open remote file {}: {}", remote_path, e))
+        })?;
+        let stat = file.stat().map_err(|e| {
+            SshError::OperationFailed(format!("Failed to stat remote file {}: {}", remote_path, e))
+        })?;
+        Ok((file, stat))
+    })?;
+    let total_bytes = stat.size.unwrap_or(0);
+
+    let mut buffer = vec![0u8; SFTP_CHUNK_SIZE];
+    let mut total_read: u64 = 0;
+    let result = (|| {
+        let mut local_file = std::fs::File::create(local_path).map_err(|e| {
+            SshError::OperationFailed(format!("Failed to create local file {}: {}", local_path, e))
+        })?;
+        loop {
+            if cancel.load(Ordering::SeqCst) {
+                return Err(SshError::OperationFailed("Transfer cancelled".to_string()));
+            }
 
-    /// Session handle for opening new channels
-    pub sess_arc: Arc<tokio::sync::Mutex<Session>>,
+            // Fill the buffer under one lock acquisition; SFTP reads return
+            // at most one packet at a time
+            let n = handle
+                .blocking(|_| -> std::io::Result<usize> {
+                    let mut filled = 0;
+                    while filled < buffer.len() {
+                        match remote_file.read(&mut buffer[filled..])? {
+                            0 => break,
+                            n => filled += n,
+                        }
+                    }
+                    Ok(filled)
+                })
+                .map_err(|e| {
+                    SshError::OperationFailed(format!("Failed to read remote file: {}", e))
+                })?;
+            if n == 0 {
+                break;
+            }
+
+            local_file.write_all(&buffer[..n]).map_err(|e| {
+                SshError::OperationFailed(format!("Write local file failed: {}", e))
+            })?;
+
+            total_read += n as u64;
+            handle.stats.sftp_in.fetch_add(n as u64, Ordering::Relaxed);
+            on_progress(total_read, total_bytes);
+        }
+
+        if preserve {
+            apply_remote_stat(&local_file, &stat).map_err(|e| {
+                SshError::OperationFailed(format!(
+                    "Failed to set attributes of {}: {}",
+                    local_path, e
+                ))
+            })?;
+        }
+        Ok(())
+    })();
+
+    handle.blocking(|_| drop(remote_file));
+    result.map(|_| total_read)
 }
 
 /// Global manager for coordinating SSH sessions and channels
@@ -195,6 +2071,20 @@ pub struct SshChannelInfo {
 pub struct SshManager {
     sessions: Arc<RwLock<HashMap<SessionId, SshSession>>>,
     channels: Arc<RwLock<HashMap<SessionId, SshChannelInfo>>>,
+    /// Trace of the connection of each open session; failed attempts
+    /// return theirs with the error when diagnostics are requested
+    traces: Arc<RwLock<HashMap<SessionId, ConnectionTrace>>>,
+    /// Connect attempts in progress, for `cancel_connect`
+    connecting: Arc<RwLock<HashMap<SessionId, Arc<PendingConnect>>>>,
+}
+
+/// A connect attempt in progress
+#[derive(Default)]
+struct PendingConnect {
+    /// Checked by the blocking connect between steps
+    cancelled: AtomicBool,
+    /// Wakes the waiting `connect_ssh` so it returns right away
+    notify: tokio::sync::Notify,
 }
 
 impl SshManager {
@@ -215,6 +2105,7 @@ impl SshManager {
     /// * `password` - SSH password
     /// * `cols` - Terminal columns
     /// * `rows` - Terminal rows
+    /// * `options` - Optional connect-time behaviour
     ///
     /// # Returns
     /// `Ok(())` on success, `Err(SshError)` with detailed error context on failure
@@ -229,96 +2120,350 @@ impl SshManager {
         password: String,
         cols: u32,
         rows: u32,
+        options: ConnectOptions,
     ) -> Result<(), SshError> {
+        // An id still in the registry is either live, which needs an
+        // explicit takeover, or dropped and waiting to be reconnected.
+        // Either way the old entry is torn down first, so its listeners
+        // and tasks do not linger next to the new ones.
+        let existing = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+            .get(&session_id)
+            .map(|info| !info.stop_flag.load(Ordering::SeqCst));
+        match existing {
+            Some(true) if !options.takeover => {
+                return Err(SshError::AlreadyConnected(session_id.0.clone()));
+            }
+            Some(_) => self.disconnect_ssh(&session_id)?,
+            None => {}
+        }
+
         let sessions_arc = Arc::clone(&self.sessions);
         let channels_arc = Arc::clone(&self.channels);
 
-        let addr = format!("{}:{}", ip, port);
+        let host_for_spawn = ip.clone();
         let username_for_spawn = username.clone();
         let password_for_spawn = password.clone();
 
+        let terminal_settings = crate::settings::current().terminal;
+
+        // Protected sessions ask again even when their credentials are cached
+        if let Some(saved_id) = options.saved_session_id.clone() {
+            let master_password = options.master_password.clone();
+            tokio::task::spawn_blocking(move || {
+                crate::biometric::confirm_protected_session(&saved_id, master_password.as_deref())
+            })
+            .await
+            .map_err(|e| SshError::TaskError(e.to_string()))?
+            .map_err(SshError::AuthenticationFailed)?;
+        }
+
         // 1. Establish connection and authenticate (blocking part in separate thread)
-        let connection_res = tokio::task::spawn_blocking(move || {
-            use std::net::ToSocketAddrs;
-            let socket_addr = addr
-                .to_socket_addrs()
-                .map_err(|e| SshError::ConnectionFailed {
-                    host: addr.clone(),
-                    port,
-                    reason: format!("Failed to resolve address: {}", e),
-                })?
-                .next()
-                .ok_or_else(|| SshError::ConnectionFailed {
-                    host: addr.clone(),
+        let diagnostics = options.diagnostics;
+        let saved_session_id = options.saved_session_id.clone();
+        let route = SessionRoute::new(options.window.as_deref().unwrap_or(MAIN_WINDOW));
+        let pending = Arc::new(PendingConnect::default());
+        if let Ok(mut connecting) = self.connecting.write() {
+            connecting.insert(session_id.clone(), pending.clone());
+        }
+        let cancel = pending.clone();
+        let connect_task = tokio::task::spawn_blocking(move || {
+            let mut trace = ConnectionTrace::new();
+            let result = (|| {
+                if let Some(saved_id) = &options.saved_session_id {
+                    trace.step(
+                        "knock",
+                        knock_saved_session(&host_for_spawn, port, saved_id),
+                        |_| "Knock sequence sent".to_string(),
+                    )?;
+                }
+                let advanced = match (&options.advanced, &options.saved_session_id) {
+                    (Some(advanced), _) => advanced.clone(),
+                    (None, Some(saved_id)) => {
+                        crate::db::load_advanced_options(saved_id).unwrap_or_default()
+                    }
+                    (None, None) => SshAdvancedOptions::default(),
+                };
+                let (sess, connected_addr) = open_transport(
+                    &host_for_spawn,
                     port,
-                    reason: "No addresses found".to_string(),
-                })?;
-
-            let tcp =
-                TcpStream::connect_timeout(&socket_addr, Duration::from_secs(30)).map_err(|e| {
-                    SshError::ConnectionFailed {
-                        host: addr.clone(),
-                        port,
-                        reason: e.to_string(),
+                    &advanced,
+                    &cancel.cancelled,
+                    &mut trace,
+                )?;
+
+                if options.diagnostics {
+                    let offered = sess.auth_methods(&username_for_spawn);
+                    trace
+                        .step("auth", offered, |m| format!("Server offers: {}", m))
+                        .ok();
+                }
+                // Key-based saved sessions authenticate with their key (and
+                // certificate); the password argument then carries the
+                // passphrase when none is stored
+                let saved_key = options
+                    .saved_session_id
+                    .as_deref()
+                    .and_then(|id| crate::db::load_session(id).ok())
+                    .filter(|saved| saved.auth_type == "key")
+                    .and_then(|saved| {
+                        let key_path = saved.private_key_path?;
+                        Some((key_path, saved.certificate_path, saved.id))
+                    });
+                if let Some(aws) = advanced.aws.as_ref().filter(|aws| aws.instance_connect) {
+                    let result = authenticate_instance_connect(&sess, &username_for_spawn, aws);
+                    trace.step("auth", result, |d| d.clone())?;
+                } else if let Some((key_path, certificate_path, saved_id)) = saved_key {
+                    let stored = crate::db::load_session_credentials(&saved_id)
+                        .ok()
+                        .flatten()
+                        .and_then(|c| c.key_passphrase);
+                    if stored.is_some() {
+                        crate::biometric::confirm("use a saved key passphrase")
+                            .map_err(SshError::AuthenticationFailed)?;
                     }
-                })?;
-
-            let mut sess = Session::new().map_err(|e| {
-                SshError::OperationFailed(format!("Failed to create session: {}", e))
-            })?;
-            sess.set_tcp_stream(tcp);
-            sess.handshake()
-                .map_err(|e| SshError::OperationFailed(format!("Handshake failed: {}", e)))?;
+                    let passphrase = stored
+                        .or_else(|| Some(password_for_spawn.clone()).filter(|p| !p.is_empty()));
+                    let result = authenticate_with_key(
+                        &sess,
+                        &username_for_spawn,
+                        &key_path,
+                        certificate_path.as_deref(),
+                        passphrase.as_deref(),
+                    );
+                    trace.step("auth", result, |d| d.clone())?;
+                } else {
+                    trace
+                        .step(
+                            "auth",
+                            sess.userauth_password(&username_for_spawn, &password_for_spawn),
+                            |_| format!("password accepted for {}", username_for_spawn),
+                        )
+                        .map_err(|_| {
+                            SshError::AuthenticationFailed("Invalid credentials".to_string())
+                        })?;
+                }
 
-            sess.userauth_password(&username_for_spawn, &password_for_spawn)
-                .map_err(|_| SshError::AuthenticationFailed("Invalid credentials".to_string()))?;
+                if !sess.authenticated() {
+                    return Err(SshError::AuthenticationFailed(
+                        "Authentication failed".to_string(),
+                    ));
+                }
+                if cancel.cancelled.load(Ordering::SeqCst) {
+                    return Err(SshError::Cancelled);
+                }
 
-            if !sess.authenticated() {
-                return Err(SshError::AuthenticationFailed(
-                    "Authentication failed".to_string(),
-                ));
-            }
+                // A broken trigger table must not prevent connecting
+                let triggers =
+                    crate::db::load_triggers_for_session(options.saved_session_id.as_deref())
+                        .unwrap_or_default();
+                let highlight_rules = crate::db::load_highlight_rules_for_session(
+                    options.saved_session_id.as_deref(),
+                )
+                .unwrap_or_default();
+
+                let shell_kind = if options.shell_integration {
+                    shell_integration::detect_shell(&sess)
+                } else {
+                    ShellKind::Unknown
+                };
 
-            let mut channel = sess
-                .channel_session()
-                .map_err(|e| SshError::ChannelError(format!("Create channel failed: {}", e)))?;
+                let environment = options
+                    .saved_session_id
+                    .as_deref()
+                    .and_then(|id| crate::db::load_session_environment(id).ok())
+                    .unwrap_or_default();
+
+                // A window that is not laid out yet reports a zero size
+                let cols = match (cols, environment.cols) {
+                    (0, 0) => u32::from(terminal_settings.default_cols),
+                    (0, session_cols) => u32::from(session_cols),
+                    (cols, _) => cols,
+                };
+                let rows = match (rows, environment.rows) {
+                    (0, 0) => u32::from(terminal_settings.default_rows),
+                    (0, session_rows) => u32::from(session_rows),
+                    (rows, _) => rows,
+                };
+                let term = [environment.term.as_str(), terminal_settings.term.as_str()]
+                    .into_iter()
+                    .find(|term| !term.is_empty())
+                    .unwrap_or("xterm-256color");
 
-            channel
-                .request_pty("xterm-256color", None, Some((cols, rows, 0, 0)))
-                .map_err(|e| SshError::ChannelError(format!("Failed to request PTY: {}", e)))?;
+                let mut channel = sess
+                    .channel_session()
+                    .map_err(|e| SshError::ChannelError(format!("Create channel failed: {}", e)))?;
 
-            channel
-                .shell()
-                .map_err(|e| SshError::ChannelError(format!("Failed to start shell: {}", e)))?;
+                channel
+                    .request_pty(term, None, Some((cols, rows, 0, 0)))
+                    .map_err(|e| SshError::ChannelError(format!("Failed to request PTY: {}", e)))?;
+                for (name, value) in &environment.env_vars {
+                    // Rejected variables (not in the server's AcceptEnv) are
+                    // only noted in the trace
+                    trace
+                        .step("env", channel.setenv(name, value), |_| {
+                            format!("{} set", name)
+                        })
+                        .ok();
+                }
 
-            // Set non-blocking mode for async I/O
-            sess.set_blocking(false);
+                trace
+                    .step("channel", channel.shell(), |_| "Shell started".to_string())
+                    .map_err(|e| SshError::ChannelError(format!("Failed to start shell: {}", e)))?;
 
-            Ok((sess, channel))
-        })
-        .await;
+                for command in &environment.startup_commands {
+                    channel
+                        .write_all(format!("{}\n", command).as_bytes())
+                        .map_err(|e| {
+                            SshError::ChannelError(format!("Failed to run startup command: {}", e))
+                        })?;
+                }
 
-        let (sess, channel) = match connection_res {
-            Ok(Ok(val)) => val,
-            Ok(Err(e)) => return Err(e),
-            Err(e) => return Err(SshError::TaskError(e.to_string())),
+                let resume = crate::multiplexer::resume_action(&sess, &environment.resume);
+
+                // Set non-blocking mode for async I/O
+                sess.set_blocking(false);
+
+                Ok((
+                    sess,
+                    channel,
+                    shell_kind,
+                    triggers,
+                    highlight_rules,
+                    resume,
+                    connected_addr,
+                    environment,
+                ))
+            })();
+            if let (Err(e), Some(saved_id)) = (&result, &options.saved_session_id) {
+                if !matches!(e, SshError::Cancelled) {
+                    if let Err(e) = crate::db::record_connect_failure(saved_id, &e.to_string()) {
+                        tracing::warn!("{}", e);
+                    }
+                }
+            }
+            (result, trace)
+        });
+        // The blocking attempt cannot be interrupted mid-handshake; on
+        // cancel it is left to finish and its session is dropped
+        let connection_res = tokio::select! {
+            joined = connect_task => Some(joined),
+            _ = pending.notify.notified() => None,
         };
+        if let Ok(mut connecting) = self.connecting.write() {
+            // A newer attempt for the same id may have replaced this one
+            if connecting
+                .get(&session_id)
+                .is_some_and(|p| Arc::ptr_eq(p, &pending))
+            {
+                connecting.remove(&session_id);
+            }
+        }
+        let connection_res = connection_res.ok_or(SshError::Cancelled)?;
+
+        let (result, trace) = connection_res.map_err(|e| SshError::TaskError(e.to_string()))?;
+        let (
+            sess,
+            channel,
+            shell_kind,
+            triggers,
+            highlight_rules,
+            resume,
+            connected_addr,
+            environment,
+        ) = match result {
+            Ok(val) => val,
+            Err(e) if diagnostics => {
+                return Err(SshError::Traced {
+                    error: Box::new(e),
+                    trace,
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        if let Ok(mut traces) = self.traces.write() {
+            traces.insert(session_id.clone(), trace);
+        }
+
+        let server_banner = sess.banner().map(String::from);
 
         // 2. Setup communication channels
-        let (output_sender, output_receiver) = mpsc::unbounded_channel::<OutputChunk>();
-        let (input_sender, input_receiver) = mpsc::unbounded_channel::<String>();
+        let (output_sender, output_receiver) = mpsc::channel::<OutputChunk>(OUTPUT_QUEUE_CAPACITY);
+        let queued_bytes = Arc::new(AtomicUsize::new(0));
+        let raw_mode = Arc::new(AtomicBool::new(false));
+        let read_only = Arc::new(AtomicBool::new(false));
+        let shell_state = Arc::new(ShellState::default());
+        let sudo_autofill = Arc::new(RwLock::new(None));
+        // A stored encoding that no longer parses falls back to UTF-8
+        let encoding = Arc::new(RwLock::new(
+            SessionEncoding::parse(&environment.encoding).unwrap_or_default(),
+        ));
+        let triggers = Arc::new(std::sync::Mutex::new(TriggerEngine::new(triggers)));
+        let highlights = Arc::new(std::sync::Mutex::new(HighlightEngine::new(highlight_rules)));
+        let alert_rules = crate::db::load_alert_rules_for_session(saved_session_id.as_deref())
+            .unwrap_or_default();
+        let alerts = Arc::new(std::sync::Mutex::new(AlertEngine::new(alert_rules)));
+        let idle = Arc::new(IdleGuard::new(&environment));
+        let guard = saved_session_id
+            .as_deref()
+            .and_then(command_guard_for)
+            .map(|patterns| {
+                Arc::new(std::sync::Mutex::new(CommandGuard::new(
+                    session_id.0.clone(),
+                    &patterns,
+                )))
+            });
+        let notify_when_done = Arc::new(AtomicBool::new(false));
+        let suspension = Arc::new(Suspension::default());
+        let (input_sender, input_receiver) = mpsc::unbounded_channel::<InputCommand>();
         let stop_flag = Arc::new(AtomicBool::new(false));
         let next_seq = Arc::new(AtomicU64::new(1));
-        let initial_outputs = Arc::new(tokio::sync::Mutex::new(Vec::new()));
-        let refresh_interval = Arc::new(AtomicU64::new(3000)); // Default to idle: 3s
+        let initial_outputs = Arc::new(Mutex::new(Vec::new()));
+        let refresh_interval = Arc::new(AtomicU64::new(
+            crate::settings::current().monitoring.refresh_interval_ms,
+        ));
+        let last_status = Arc::new(RwLock::new(None));
+
+        // Shell integration hooks are typed into the shell before any user input
+        if let Some(hook) = shell_integration::integration_hook(shell_kind) {
+            let _ = input_sender.send(InputCommand::Data(hook.as_bytes().to_vec()));
+        }
+        // Attaching comes last so the hook runs in the outer shell
+        if let ResumeAction::Attach(command) = &resume {
+            let _ = input_sender.send(InputCommand::Data(command.as_bytes().to_vec()));
+        }
 
         let channel_arc = Arc::new(tokio::sync::Mutex::new(channel));
         let sess_arc = Arc::new(tokio::sync::Mutex::new(sess));
+        let sftp_handle = SessionHandle::new(sess_arc.clone());
 
         // 3. Register event listeners for user input and resize
+        let mut listeners = SessionListeners::default();
         if let Some(h) = &app_handle {
-            Self::register_input_listener(h, &session_id, &input_sender, &stop_flag);
-            Self::register_resize_listener(h, &session_id, &channel_arc, &stop_flag);
+            listeners = SessionListeners::new(h);
+            listeners.push(Self::register_input_listener(
+                h,
+                &session_id,
+                &input_sender,
+                &read_only,
+                &idle,
+                &stop_flag,
+            ));
+            listeners.push(Self::register_resize_listener(
+                h,
+                &session_id,
+                &channel_arc,
+                &stop_flag,
+            ));
+            Self::spawn_idle_watch(
+                h.clone(),
+                session_id.clone(),
+                route.clone(),
+                idle.clone(),
+                stop_flag.clone(),
+            );
         }
 
         // 4. Spawn I/O task
@@ -326,21 +2471,61 @@ impl SshManager {
             channel_arc,
             sess_arc.clone(),
             stop_flag.clone(),
+            suspension.clone(),
             next_seq.clone(),
             initial_outputs.clone(),
             input_receiver,
-            output_sender,
+            OutputPipeline::new(
+                app_handle.clone(),
+                session_id.clone(),
+                route.clone(),
+                output_sender,
+                queued_bytes.clone(),
+                raw_mode.clone(),
+                shell_state.clone(),
+                sudo_autofill.clone(),
+                username.clone(),
+                triggers.clone(),
+                highlights.clone(),
+                notify_when_done.clone(),
+                input_sender.clone(),
+                saved_session_id.clone(),
+                sftp_handle.stats.clone(),
+                encoding.clone(),
+                environment.answerback,
+                guard.clone(),
+            ),
+        );
+
+        if let (ResumeAction::Offer(sessions), Some(h)) = (resume, &app_handle) {
+            let payload = ResumeAvailable {
+                session_id: session_id.0.clone(),
+                sessions,
+            };
+            route.emit(h, "resume-available", &payload);
+        }
+
+        // Capabilities are probed off the connect path
+        crate::capabilities::spawn_probe(
             app_handle.clone(),
             session_id.clone(),
+            sftp_handle.clone(),
+            stop_flag.clone(),
         );
 
         // 5. Spawn monitoring task
         let status_handle = Self::spawn_monitoring_task(
             app_handle,
             session_id.clone(),
+            route.clone(),
             sess_arc.clone(),
             stop_flag.clone(),
+            suspension.clone(),
             refresh_interval.clone(),
+            last_status.clone(),
+            alerts.clone(),
+            saved_session_id.clone(),
+            format!("{}@{}", username, ip),
         );
 
         // 6. Save session state
@@ -348,15 +2533,36 @@ impl SshManager {
             let mut sessions = sessions_arc
                 .write()
                 .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
-            sessions.insert(session_id.clone(), SshSession { ip, port, username });
+            sessions.insert(
+                session_id.clone(),
+                SshSession {
+                    ip,
+                    port,
+                    username,
+                    connected_addr: connected_addr.to_string(),
+                },
+            );
 
             let mut channels = channels_arc
                 .write()
                 .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
-            channels.insert(
+            let replaced = channels.insert(
                 session_id,
                 SshChannelInfo {
-                    receiver: Arc::new(tokio::sync::Mutex::new(output_receiver)),
+                    receiver: Arc::new(Mutex::new(output_receiver)),
+                    queued_bytes,
+                    raw_mode,
+                    read_only,
+                    shell_state,
+                    sudo_autofill,
+                    encoding,
+                    triggers,
+                    highlights,
+                    alerts,
+                    idle,
+                    guard,
+                    notify_when_done,
+                    suspension,
                     handle: Some(handle),
                     status_handle: Some(status_handle),
                     input_sender,
@@ -364,9 +2570,26 @@ impl SshManager {
                     next_seq,
                     initial_outputs,
                     refresh_interval,
+                    last_status,
+                    sftp_handle,
+                    saved_session_id,
                     sess_arc,
+                    server_banner,
+                    route,
+                    listeners,
                 },
             );
+            // A concurrent connect with the same id finished first; stop
+            // its tasks rather than leaving them running unreferenced
+            if let Some(mut old) = replaced {
+                old.stop_flag.store(true, Ordering::SeqCst);
+                if let Some(handle) = old.handle.take() {
+                    handle.abort();
+                }
+                if let Some(status_handle) = old.status_handle.take() {
+                    status_handle.abort();
+                }
+            }
         }
 
         Ok(())
@@ -376,27 +2599,42 @@ impl SshManager {
     fn register_input_listener(
         app_handle: &tauri::AppHandle,
         session_id: &SessionId,
-        input_sender: &mpsc::UnboundedSender<String>,
+        input_sender: &mpsc::UnboundedSender<InputCommand>,
+        read_only: &Arc<AtomicBool>,
+        idle: &Arc<IdleGuard>,
         stop_flag: &Arc<AtomicBool>,
-    ) {
-        let event_name = format!("ssh-input-{}", session_id.0);
+    ) -> tauri::EventId {
+        let sid = session_id.0.clone();
         let input_tx = input_sender.clone();
+        let read_only = read_only.clone();
+        let idle = idle.clone();
         let task_stop = stop_flag.clone();
 
-        app_handle.listen(&event_name, move |event: tauri::Event| {
-            if task_stop.load(Ordering::SeqCst) {
-                return;
-            }
-
-            #[derive(serde::Deserialize)]
-            struct InputPayload {
-                input: String,
-            }
+        transport::listen_session_event(
+            app_handle,
+            "ssh-input",
+            session_id,
+            move |payload: TerminalInput| {
+                if task_stop.load(Ordering::SeqCst) || read_only.load(Ordering::SeqCst) {
+                    return;
+                }
 
-            if let Ok(payload) = serde_json::from_str::<InputPayload>(event.payload()) {
-                let _ = input_tx.send(payload.input);
-            }
-        });
+                let bytes = match payload.data {
+                    Some(data) => match general_purpose::STANDARD.decode(data) {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            let message = format!("invalid base64 in ssh-input: {}", e);
+                            crate::error_bus::report("ssh", Some(&sid), message);
+                            return;
+                        }
+                    },
+                    None => payload.input.into_bytes(),
+                };
+                if idle.on_input() {
+                    let _ = input_tx.send(InputCommand::Data(bytes));
+                }
+            },
+        )
     }
 
     /// Registers event listener for terminal resize events
@@ -405,51 +2643,61 @@ impl SshManager {
         session_id: &SessionId,
         channel_arc: &Arc<tokio::sync::Mutex<ssh2::Channel>>,
         stop_flag: &Arc<AtomicBool>,
-    ) {
-        let resize_event_name = format!("ssh-resize-{}", session_id.0);
+    ) -> tauri::EventId {
+        let sid = session_id.0.clone();
         let task_channel = channel_arc.clone();
         let task_stop = stop_flag.clone();
 
-        app_handle.listen(&resize_event_name, move |event: tauri::Event| {
-            if task_stop.load(Ordering::SeqCst) {
-                return;
-            }
-
-            #[derive(serde::Deserialize)]
-            struct ResizePayload {
-                cols: u32,
-                rows: u32,
-            }
+        transport::listen_session_event(
+            app_handle,
+            "ssh-resize",
+            session_id,
+            move |payload: TerminalResize| {
+                if task_stop.load(Ordering::SeqCst) {
+                    return;
+                }
 
-            if let Ok(payload) = serde_json::from_str::<ResizePayload>(event.payload()) {
+                // Hidden or collapsed views report a zero size
+                if payload.cols == 0 || payload.rows == 0 {
+                    return;
+                }
                 let task_channel_clone = task_channel.clone();
+                let sid = sid.clone();
                 let _ = tokio::spawn(async move {
                     let mut ch = task_channel_clone.lock().await;
-                    let _ = ch.request_pty_size(payload.cols, payload.rows, None, None);
+                    if let Err(e) = ch.request_pty_size(payload.cols, payload.rows, None, None) {
+                        crate::error_bus::report(
+                            "ssh",
+                            Some(&sid),
+                            format!("resize failed: {}", e),
+                        );
+                    }
                 });
-            }
-        });
+            },
+        )
     }
 
     /// Spawns the background I/O task that processes SSH input/output
+    #[allow(clippy::too_many_arguments)]
     fn spawn_io_task(
         channel_arc: Arc<tokio::sync::Mutex<ssh2::Channel>>,
         sess_arc: Arc<tokio::sync::Mutex<Session>>,
         stop_flag: Arc<AtomicBool>,
+        suspension: Arc<Suspension>,
         next_seq: Arc<AtomicU64>,
-        initial_outputs: Arc<tokio::sync::Mutex<Vec<OutputChunk>>>,
-        mut input_receiver: mpsc::UnboundedReceiver<String>,
-        output_sender: mpsc::UnboundedSender<OutputChunk>,
-        app_handle: Option<tauri::AppHandle>,
-        session_id: SessionId,
+        initial_outputs: Arc<Mutex<Vec<OutputChunk>>>,
+        mut input_receiver: mpsc::UnboundedReceiver<InputCommand>,
+        mut pipeline: OutputPipeline,
     ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             let mut buffer = [0u8; SSH_BUFFER_SIZE];
-            let mut pending_output = String::new();
+            let mut stderr_buffer = [0u8; SSH_BUFFER_SIZE];
+            let mut pending_output: Vec<u8> = Vec::new();
             let mut last_emit = std::time::Instant::now();
             let mut seen_first_output = false;
             let initial_buffering_start = std::time::Instant::now();
             let mut in_initial_buffering = true;
+            let mut input_queue = InputQueue::default();
 
             loop {
                 if stop_flag.load(Ordering::SeqCst) {
@@ -458,29 +2706,65 @@ impl SshManager {
 
                 // Attempt non-blocking read from SSH channel
                 // We lock the session to ensure thread safety with monitoring task
-                let read_result = {
+                let (read_result, stderr_read) = {
                     let _sess_lock = sess_arc.lock().await;
                     let mut ch = channel_arc.lock().await;
-                    match ch.read(&mut buffer) {
-                        Ok(0) => Some(Err("Connection closed")),
+                    // `Err(true)` when the connection dropped, `Err(false)`
+                    // when the remote side closed it cleanly
+                    let read_result = match ch.read(&mut buffer) {
+                        Ok(0) => Some(Err(false)),
                         Ok(n) => Some(Ok(n)),
                         Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => None,
-                        Err(_) => Some(Err("Read error")),
-                    }
+                        Err(_) => Some(Err(true)),
+                    };
+                    // Errors are left to the stdout read, which ends the session
+                    let stderr_read = ch.stderr().read(&mut stderr_buffer).unwrap_or(0);
+                    (read_result, stderr_read)
                 };
 
                 match read_result {
                     Some(Ok(n)) => {
-                        pending_output.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                        pending_output.extend_from_slice(&buffer[..n]);
+                        pipeline.record_rate(n);
+                        pipeline.cap_pending(&mut pending_output);
                     }
-                    Some(Err(_)) => {
+                    Some(Err(dropped)) => {
                         stop_flag.store(true, Ordering::SeqCst);
+                        if dropped {
+                            pipeline.on_disconnected();
+                        }
                         break;
                     }
-                    None => {
-                        // No data available, yield to other tasks
-                        tokio::task::yield_now().await;
+                    None if stderr_read == 0 => {
+                        // No data available, yield to other tasks; a
+                        // suspended session waits before reading again
+                        if suspension.is_suspended() {
+                            suspension.pause(SUSPENDED_READ_INTERVAL).await;
+                        } else {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                    None => {}
+                }
+
+                // stderr is not batched; pending stdout goes first to keep
+                // the order in which the server sent both
+                if stderr_read > 0 {
+                    pipeline.record_rate(stderr_read);
+                    let mut chunks = Vec::with_capacity(2);
+                    if !pending_output.is_empty() {
+                        let seq = next_seq.fetch_add(1, Ordering::SeqCst);
+                        chunks.push(pipeline.emit(seq, std::mem::take(&mut pending_output)));
+                    }
+                    let seq = next_seq.fetch_add(1, Ordering::SeqCst);
+                    chunks.push(pipeline.emit_stderr(seq, stderr_buffer[..stderr_read].to_vec()));
+                    if in_initial_buffering {
+                        if let Ok(mut cache) = initial_outputs.lock() {
+                            cache.extend(chunks);
+                        }
                     }
+                    last_emit = std::time::Instant::now();
+                    seen_first_output = true;
                 }
 
                 // Check if initial buffering phase has ended
@@ -492,12 +2776,7 @@ impl SshManager {
                     // Flush any remaining pending output
                     if !pending_output.is_empty() {
                         let seq = next_seq.fetch_add(1, Ordering::SeqCst);
-                        let chunk = OutputChunk::new(seq, pending_output.clone());
-                        if let Some(h) = &app_handle {
-                            let _ = h.emit(&format!("ssh-output-{}", session_id.0), &chunk);
-                        }
-                        let _ = output_sender.send(chunk);
-                        pending_output.clear();
+                        pipeline.emit(seq, std::mem::take(&mut pending_output));
                         last_emit = std::time::Instant::now();
                         seen_first_output = true;
                     }
@@ -507,6 +2786,8 @@ impl SshManager {
                 let (size_threshold, time_threshold_ms) =
                     if in_initial_buffering && !seen_first_output {
                         (INITIAL_BATCH_SIZE_THRESHOLD, INITIAL_BATCH_TIME_MS)
+                    } else if pipeline.is_flooding() {
+                        (FLOOD_BATCH_SIZE_THRESHOLD, FLOOD_BATCH_TIME_MS)
                     } else {
                         (NORMAL_BATCH_SIZE_THRESHOLD, NORMAL_BATCH_TIME_MS)
                     };
@@ -516,63 +2797,102 @@ impl SshManager {
                         || last_emit.elapsed() > Duration::from_millis(time_threshold_ms))
                 {
                     let seq = next_seq.fetch_add(1, Ordering::SeqCst);
-                    let chunk = OutputChunk::new(seq, pending_output.clone());
+                    let chunk = pipeline.emit(seq, std::mem::take(&mut pending_output));
 
                     // Cache initial outputs for late-joining clients
                     if in_initial_buffering {
-                        let mut cache = initial_outputs.lock().await;
-                        cache.push(chunk.clone());
-                    }
-
-                    // Emit event to frontend
-                    if let Some(h) = &app_handle {
-                        let _ = h.emit(&format!("ssh-output-{}", session_id.0), &chunk);
+                        if let Ok(mut cache) = initial_outputs.lock() {
+                            cache.push(chunk);
+                        }
                     }
 
-                    let _ = output_sender.send(chunk);
-                    pending_output.clear();
                     last_emit = std::time::Instant::now();
                     seen_first_output = true;
                 }
 
-                // Process queued user input
+                // Process queued user input; input released by a confirmed
+                // command goes before anything typed since
+                let mut inputs = pipeline.released_input();
                 while let Ok(input) = input_receiver.try_recv() {
+                    inputs.extend(pipeline.guard_input(input));
+                }
+                for input in inputs {
+                    if let InputCommand::Data(data) = &input {
+                        pipeline.on_input(data);
+                    }
+                    input_queue.push(pipeline.encode_input(input));
+                }
+                if !input_queue.is_empty() {
                     let _sess_lock = sess_arc.lock().await;
                     let mut ch = channel_arc.lock().await;
-                    let _ = ch.write_all(input.as_bytes()).and_then(|_| ch.flush());
+                    if input_queue.write_to(&mut ch, &pipeline).is_err() {
+                        stop_flag.store(true, Ordering::SeqCst);
+                        drop(ch);
+                        pipeline.on_disconnected();
+                        break;
+                    }
                 }
             }
         })
     }
 
     /// Spawns the background monitoring task for server metrics
+    #[allow(clippy::too_many_arguments)]
     fn spawn_monitoring_task(
         app_handle: Option<tauri::AppHandle>,
         session_id: SessionId,
+        route: SessionRoute<OutputChunk>,
         sess_arc: Arc<tokio::sync::Mutex<Session>>,
         stop_flag: Arc<AtomicBool>,
+        suspension: Arc<Suspension>,
         refresh_interval: Arc<AtomicU64>,
+        last_status: Arc<RwLock<Option<ServerStatus>>>,
+        alerts: Arc<std::sync::Mutex<AlertEngine>>,
+        saved_session_id: Option<String>,
+        host: String,
     ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             // Initial readings for delta calculation (rx, tx, time)
             let mut last_net_read: Option<(f64, f64, std::time::Instant)> = None;
             let mut last_cpu_read: Option<(u64, u64)> = None; // (total, idle)
+            let mut thresholds = crate::notifications::ThresholdWatch::default();
+            let mut recorder = MetricsRecorder::default();
+            // History outlives the connection when it has a saved session
+            let history_id = saved_session_id
+                .clone()
+                .unwrap_or_else(|| session_id.0.clone());
+            let rtt = Arc::new(AtomicU32::new(0));
+            let probe = Self::spawn_latency_probe(sess_arc.clone(), stop_flag.clone(), rtt.clone());
 
             loop {
                 if stop_flag.load(Ordering::SeqCst) {
                     break;
                 }
+                // Metrics resume at once when the session is resumed; the
+                // latency probe keeps running and the connection alive
+                if suspension.is_suspended() {
+                    suspension.pause(SUSPENDED_CHECK_INTERVAL).await;
+                    continue;
+                }
+                // The metrics are read from /proc, which only Linux has
+                if crate::capabilities::cached(&session_id.0)
+                    .is_some_and(|c| !c.supports_monitoring())
+                {
+                    tracing::debug!("server metrics are not available on {}", host);
+                    break;
+                }
 
-                let start_time = std::time::Instant::now();
-                let status_res = {
+                let (status_res, command_ms) = {
                     let sess = sess_arc.lock().await;
-                    Self::fetch_server_status(&sess, last_cpu_read).await
+                    let start_time = std::time::Instant::now();
+                    let res = Self::fetch_server_status(&sess, last_cpu_read).await;
+                    (res, start_time.elapsed().as_millis() as u32)
                 };
-                let latency = start_time.elapsed().as_millis() as u32;
 
                 if let Ok((mut status, current_cpu_raw)) = status_res {
                     let now = std::time::Instant::now();
-                    status.latency = latency;
+                    status.latency = rtt.load(Ordering::SeqCst);
+                    status.command_ms = command_ms;
                     last_cpu_read = Some(current_cpu_raw);
 
                     // Calculate network speed
@@ -596,24 +2916,175 @@ impl SshManager {
                             status.net_down = rx_diff / duration;
                             status.net_up = tx_diff / duration;
                         }
-                    } else {
-                        status.net_down = 0.0;
-                        status.net_up = 0.0;
+                    } else {
+                        status.net_down = 0.0;
+                        status.net_up = 0.0;
+                    }
+
+                    last_net_read = Some((current_rx, current_tx, now));
+
+                    if let Ok(mut last) = last_status.write() {
+                        *last = Some(status.clone());
+                    }
+                    if let Some(sample) = recorder.add(&status, now_millis() as i64) {
+                        let history_id = history_id.clone();
+                        let recorded = tokio::task::spawn_blocking(move || {
+                            crate::db::record_metrics_sample(&history_id, &sample)
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(e.to_string()));
+                        if let Err(e) = recorded {
+                            let message = format!("failed to record metrics: {}", e);
+                            crate::error_bus::report("metrics", Some(&session_id.0), message);
+                        }
+                    }
+                    if let Some(h) = &app_handle {
+                        thresholds.check(h, &session_id.0, &status);
+                        route.emit(h, &format!("ssh-status-{}", session_id.0), &status);
+                        // Alerts are recorded in the database
+                        let fired = {
+                            let (alerts, h, sid, saved_id, host, status) = (
+                                alerts.clone(),
+                                h.clone(),
+                                session_id.0.clone(),
+                                saved_session_id.clone(),
+                                host.clone(),
+                                status.clone(),
+                            );
+                            tokio::task::spawn_blocking(move || {
+                                alerts
+                                    .lock()
+                                    .map(|mut engine| {
+                                        engine.check(&h, &sid, saved_id.as_deref(), &host, &status)
+                                    })
+                                    .unwrap_or_default()
+                            })
+                            .await
+                            .unwrap_or_default()
+                        };
+                        for event in fired {
+                            route.emit(h, &format!("ssh-alert-{}", session_id.0), &event);
+                        }
+                    }
+                } else {
+                    if let Err(e) = &status_res {
+                        // Failures while the connection closes are expected
+                        if !stop_flag.load(Ordering::SeqCst) {
+                            crate::error_bus::report("monitoring", Some(&session_id.0), e);
+                        }
+                    }
+                    if let Ok(mut last) = last_status.write() {
+                        *last = None;
+                    }
+                }
+
+                let interval = refresh_interval.load(Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(interval)).await;
+            }
+            probe.abort();
+            let _ = tokio::task::spawn_blocking(move || {
+                if let Ok(mut engine) = alerts.lock() {
+                    engine.resolve_all();
+                }
+            })
+            .await;
+        })
+    }
+
+    /// Spawns the task that warns about, then locks or disconnects, a
+    /// session left without user input for its idle timeout
+    fn spawn_idle_watch(
+        app_handle: tauri::AppHandle,
+        session_id: SessionId,
+        route: SessionRoute<OutputChunk>,
+        idle: Arc<IdleGuard>,
+        stop_flag: Arc<AtomicBool>,
+    ) {
+        tokio::spawn(async move {
+            let event = format!("ssh-idle-{}", session_id.0);
+            let emit = |state: &str, remaining_secs: u64| {
+                let payload = IdleEvent {
+                    session_id: session_id.0.clone(),
+                    state: state.to_string(),
+                    remaining_secs,
+                };
+                route.emit(&app_handle, &event, &payload);
+            };
+            let mut warned = false;
+            while !stop_flag.load(Ordering::SeqCst) {
+                tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+                // Settings are read on every check so changes apply at once
+                let policy = idle.policy(&crate::settings::current().security);
+                let Some(policy) = policy.filter(|_| !idle.is_locked()) else {
+                    warned = false;
+                    continue;
+                };
+                let idle_for = idle.idle_for();
+                if idle_for >= policy.timeout {
+                    warned = false;
+                    match policy.action {
+                        IdleAction::Lock => {
+                            idle.lock();
+                            emit("locked", 0);
+                        }
+                        IdleAction::Disconnect => {
+                            emit("disconnected", 0);
+                            let _ = app_handle.state::<SshManager>().disconnect_ssh(&session_id);
+                            break;
+                        }
                     }
-
-                    last_net_read = Some((current_rx, current_tx, now));
-
-                    if let Some(h) = &app_handle {
-                        let _ = h.emit(&format!("ssh-status-{}", session_id.0), &status);
+                } else if idle_for + policy.warning >= policy.timeout {
+                    if !warned {
+                        warned = true;
+                        emit("warning", (policy.timeout - idle_for).as_secs());
                     }
+                } else if warned {
+                    warned = false;
+                    emit("active", 0);
                 }
+            }
+        });
+    }
 
-                let interval = refresh_interval.load(Ordering::SeqCst);
-                tokio::time::sleep(Duration::from_millis(interval)).await;
+    /// Spawns the task measuring the network round trip every
+    /// `LATENCY_PROBE_INTERVAL`, independent of the status refresh rate
+    fn spawn_latency_probe(
+        sess_arc: Arc<tokio::sync::Mutex<Session>>,
+        stop_flag: Arc<AtomicBool>,
+        rtt: Arc<AtomicU32>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            while !stop_flag.load(Ordering::SeqCst) {
+                let probe = {
+                    let sess = sess_arc.lock().await;
+                    Self::probe_latency(&sess).await
+                };
+                if let Ok(ms) = probe {
+                    rtt.store(ms, Ordering::SeqCst);
+                }
+                tokio::time::sleep(LATENCY_PROBE_INTERVAL).await;
             }
         })
     }
 
+    /// Measures one round trip to the server: opening a channel is a single
+    /// request and reply and runs nothing remotely
+    async fn probe_latency(sess: &Session) -> Result<u32, SshError> {
+        let start_time = std::time::Instant::now();
+        let mut channel = loop {
+            match sess.channel_session() {
+                Ok(channel) => break channel,
+                Err(ref e) if e.code() == ssh2::ErrorCode::Session(-37) => {
+                    tokio::task::yield_now().await;
+                }
+                Err(e) => return Err(SshError::ChannelError(e.to_string())),
+            }
+        };
+        let rtt = start_time.elapsed().as_millis() as u32;
+        let _ = channel.close();
+        Ok(rtt)
+    }
+
     /// Fetches server performance metrics via a short-lived SSH channel
     async fn fetch_server_status(
         sess: &Session,
@@ -648,8 +3119,12 @@ impl SshManager {
         }
 
         let mut output = String::new();
+        let mut errors = Vec::new();
         loop {
             let mut buf = [0u8; 1024];
+            while let Ok(n @ 1..) = channel.stderr().read(&mut buf) {
+                push_limited(&mut errors, &buf[..n], MAX_STDERR_BYTES);
+            }
             match channel.read(&mut buf) {
                 Ok(0) => break,
                 Ok(n) => output.push_str(&String::from_utf8_lossy(&buf[..n])),
@@ -666,10 +3141,15 @@ impl SshManager {
             .filter(|s| !s.is_empty())
             .collect();
         if lines.len() < 6 {
-            return Err(SshError::OperationFailed(format!(
-                "Invalid status output format (lines: {})",
-                lines.len()
-            )));
+            let errors = String::from_utf8_lossy(&errors);
+            return Err(SshError::OperationFailed(match errors.trim() {
+                "" => format!("Invalid status output format (lines: {})", lines.len()),
+                errors => format!(
+                    "Invalid status output format (lines: {}): {}",
+                    lines.len(),
+                    errors
+                ),
+            }));
         }
 
         // Parse CPU
@@ -792,6 +3272,7 @@ impl SshManager {
                 net_down: net_down_raw,
                 net_up: net_up_raw,
                 latency: 0,
+                command_ms: 0,
                 load_avg,
                 uptime,
             },
@@ -810,8 +3291,14 @@ impl SshManager {
 
         if let Some(channel_info) = channels.get(session_id) {
             let mut outputs = Vec::new();
-            let mut receiver = channel_info.receiver.blocking_lock();
+            let mut receiver = channel_info
+                .receiver
+                .lock()
+                .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
             while let Ok(chunk) = receiver.try_recv() {
+                channel_info
+                    .queued_bytes
+                    .fetch_sub(chunk.size(), Ordering::SeqCst);
                 outputs.push(chunk);
             }
             Ok(outputs)
@@ -828,15 +3315,304 @@ impl SshManager {
             .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
 
         if let Some(channel_info) = channels.get(session_id) {
+            if channel_info.read_only.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            check_unlocked(channel_info)?;
             channel_info
                 .input_sender
-                .send(input)
+                .send(InputCommand::Data(input.into_bytes()))
                 .map_err(|_| SshError::ChannelError("Failed to send input".to_string()))
         } else {
             Err(SshError::SessionNotFound(session_id.0.clone()))
         }
     }
 
+    /// Sends raw (base64-encoded) bytes to a specific SSH session
+    pub fn send_ssh_bytes(&self, session_id: &SessionId, data: &str) -> Result<(), SshError> {
+        let bytes = general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| SshError::OperationFailed(format!("Invalid base64 input: {}", e)))?;
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let channel_info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        if channel_info.read_only.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        check_unlocked(channel_info)?;
+        channel_info
+            .input_sender
+            .send(InputCommand::Data(bytes))
+            .map_err(|_| SshError::ChannelError("Failed to send input".to_string()))
+    }
+
+    /// Switches a session between text and raw bytes output
+    pub fn set_raw_mode(&self, session_id: &SessionId, enabled: bool) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        info.raw_mode.store(enabled, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Makes a session read-only: user input is dropped while output keeps
+    /// streaming, e.g. to share a live view without stray keystrokes
+    pub fn set_session_readonly(
+        &self,
+        session_id: &SessionId,
+        read_only: bool,
+    ) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        info.read_only.store(read_only, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Enables or disables sudo password autofill for a session
+    ///
+    /// The stored password of the saved session the connection was opened
+    /// from is used, so a password is only ever typed into its own server.
+    pub fn set_sudo_autofill(&self, session_id: &SessionId, enabled: bool) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+
+        let value = if enabled {
+            Some(info.saved_session_id.clone().ok_or_else(|| {
+                SshError::OperationFailed(
+                    "A saved session is required for sudo autofill".to_string(),
+                )
+            })?)
+        } else {
+            None
+        };
+        let mut guard = info
+            .sudo_autofill
+            .write()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        *guard = value;
+        Ok(())
+    }
+
+    /// Arms (or disarms) a one-shot notification for the running command
+    ///
+    /// When the command finishes while no window is focused, a native
+    /// notification is shown and `ssh-notify-done-{sessionId}` is emitted.
+    /// Requires shell integration for command boundaries.
+    pub fn notify_when_done(&self, session_id: &SessionId, enabled: bool) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        info.notify_when_done.store(enabled, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Changes the character set of a running session: "auto", an encoding
+    /// label such as "gbk" or "latin1", or empty for UTF-8
+    pub fn set_session_encoding(
+        &self,
+        session_id: &SessionId,
+        label: &str,
+    ) -> Result<(), SshError> {
+        let setting = SessionEncoding::parse(label).map_err(SshError::OperationFailed)?;
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        *info
+            .encoding
+            .write()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))? = setting;
+        Ok(())
+    }
+
+    /// Reloads the output triggers of a running session from the database
+    ///
+    /// Call after triggers were added, edited or deleted. Global triggers
+    /// are always included; `saved_session_id` adds that session's own.
+    pub fn reload_triggers(
+        &self,
+        session_id: &SessionId,
+        saved_session_id: Option<String>,
+    ) -> Result<(), SshError> {
+        let rules = crate::db::load_triggers_for_session(saved_session_id.as_deref())
+            .map_err(SshError::OperationFailed)?;
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        let mut engine = info
+            .triggers
+            .lock()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        *engine = TriggerEngine::new(rules);
+        Ok(())
+    }
+
+    /// Reloads the alert rules of a running session from the database
+    ///
+    /// Call after alert rules were added, edited or deleted. Rules that
+    /// still exist keep their state. Global rules are always included;
+    /// `saved_session_id` adds that session's own.
+    pub fn reload_alert_rules(
+        &self,
+        session_id: &SessionId,
+        saved_session_id: Option<String>,
+    ) -> Result<(), SshError> {
+        let rules = crate::db::load_alert_rules_for_session(saved_session_id.as_deref())
+            .map_err(SshError::OperationFailed)?;
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        info.alerts
+            .lock()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+            .replace_rules(rules);
+        Ok(())
+    }
+
+    /// Runs `f` on the session's highlight engine
+    fn with_highlights<T>(
+        &self,
+        session_id: &SessionId,
+        f: impl FnOnce(&mut HighlightEngine) -> T,
+    ) -> Result<T, SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        let mut engine = info
+            .highlights
+            .lock()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        Ok(f(&mut engine))
+    }
+
+    /// Reloads the highlight rules of a running session from the database
+    ///
+    /// Counters of rules that still exist are kept. Global rules are always
+    /// included; `saved_session_id` adds that session's own.
+    pub fn reload_highlight_rules(
+        &self,
+        session_id: &SessionId,
+        saved_session_id: Option<String>,
+    ) -> Result<(), SshError> {
+        let rules = crate::db::load_highlight_rules_for_session(saved_session_id.as_deref())
+            .map_err(SshError::OperationFailed)?;
+        self.with_highlights(session_id, |engine| engine.replace_rules(rules))
+    }
+
+    /// Returns the match counters of the session's counting highlight rules
+    pub fn match_counters(&self, session_id: &SessionId) -> Result<Vec<MatchCounter>, SshError> {
+        self.with_highlights(session_id, |engine| engine.counters())
+    }
+
+    /// Resets the session's match counters to zero
+    pub fn reset_match_counters(&self, session_id: &SessionId) -> Result<(), SshError> {
+        self.with_highlights(session_id, |engine| engine.reset_counters())
+    }
+
+    /// Queues a paste that is written in flow-controlled slices
+    ///
+    /// When `confirm_threshold_bytes` is set and the text is larger, nothing
+    /// is sent and the outcome asks the caller to confirm first (by calling
+    /// again without a threshold). Progress is reported through
+    /// `ssh-paste-progress-{sessionId}` events.
+    pub fn paste_text(
+        &self,
+        session_id: &SessionId,
+        text: String,
+        confirm_threshold_bytes: Option<u64>,
+        bracketed: bool,
+    ) -> Result<PasteOutcome, SshError> {
+        let total_bytes = text.len() as u64;
+        if confirm_threshold_bytes.is_some_and(|t| total_bytes > t) {
+            return Ok(PasteOutcome {
+                paste_id: None,
+                total_bytes,
+                requires_confirmation: true,
+            });
+        }
+
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let channel_info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        // Nothing is sent to a read-only session; no paste id reports that
+        if channel_info.read_only.load(Ordering::SeqCst) {
+            return Ok(PasteOutcome {
+                paste_id: None,
+                total_bytes,
+                requires_confirmation: false,
+            });
+        }
+        check_unlocked(channel_info)?;
+
+        let data = if bracketed {
+            let text = strip_paste_delimiters(&text);
+            let mut data = Vec::with_capacity(text.len() + 12);
+            data.extend_from_slice(BRACKETED_PASTE_START);
+            data.extend_from_slice(text.as_bytes());
+            data.extend_from_slice(BRACKETED_PASTE_END);
+            data
+        } else {
+            text.into_bytes()
+        };
+        let paste_id = uuid::Uuid::new_v4().to_string();
+        let total_bytes = data.len() as u64;
+
+        channel_info
+            .input_sender
+            .send(InputCommand::Paste {
+                paste_id: paste_id.clone(),
+                data,
+            })
+            .map_err(|_| SshError::ChannelError("Failed to send input".to_string()))?;
+
+        Ok(PasteOutcome {
+            paste_id: Some(paste_id),
+            total_bytes,
+            requires_confirmation: false,
+        })
+    }
+
     /// Retrieves cached initial output (welcome banner, login prompts) for a session
     ///
     /// Useful for clients that connect after the session has started.
@@ -850,13 +3626,107 @@ impl SshManager {
             .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
 
         if let Some(channel_info) = channels.get(session_id) {
-            let outputs = channel_info.initial_outputs.blocking_lock().clone();
+            let outputs = channel_info
+                .initial_outputs
+                .lock()
+                .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+                .clone();
             Ok(outputs)
         } else {
             Err(SshError::SessionNotFound(session_id.0.clone()))
         }
     }
 
+    /// Returns the banner and MOTD the server showed when the session was
+    /// opened
+    pub fn get_session_banner(&self, session_id: &SessionId) -> Result<SessionBanner, SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        channels
+            .get(session_id)
+            .map(SshChannelInfo::banner)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))
+    }
+
+    /// Answers a command held by the command guard: `confirmed` sends it,
+    /// otherwise it is dropped and the line cancelled
+    pub fn confirm_guarded_command(
+        &self,
+        app_handle: &tauri::AppHandle,
+        session_id: &SessionId,
+        guard_id: &str,
+        confirmed: bool,
+    ) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let channel_info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        let guard = channel_info
+            .guard
+            .as_ref()
+            .ok_or_else(|| SshError::OperationFailed("Session has no command guard".to_string()))?;
+        let held = guard
+            .lock()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+            .confirm(guard_id, confirmed)
+            .map_err(SshError::OperationFailed)?;
+        // Input typed while waiting can hold the next command
+        if let Some(held) = held {
+            channel_info.route.emit(
+                app_handle,
+                &format!("ssh-command-guard-{}", session_id.0),
+                &held,
+            );
+        }
+        Ok(())
+    }
+
+    /// Unlocks a session locked after inactivity
+    pub fn unlock_ssh_session(
+        &self,
+        app_handle: &tauri::AppHandle,
+        session_id: &SessionId,
+    ) -> Result<(), SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let channel_info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))?;
+        if channel_info.idle.unlock() {
+            let payload = IdleEvent {
+                session_id: session_id.0.clone(),
+                state: "unlocked".to_string(),
+                remaining_secs: 0,
+            };
+            channel_info
+                .route
+                .emit(app_handle, &format!("ssh-idle-{}", session_id.0), &payload);
+        }
+        Ok(())
+    }
+
+    /// Cancels a connect in progress for `session_id`; its `connect_ssh`
+    /// returns `Cancelled`. Returns false when no connect is in progress.
+    pub fn cancel_connect(&self, session_id: &SessionId) -> Result<bool, SshError> {
+        let connecting = self
+            .connecting
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let Some(pending) = connecting.get(session_id) else {
+            return Ok(false);
+        };
+        pending.cancelled.store(true, Ordering::SeqCst);
+        pending.notify.notify_one();
+        Ok(true)
+    }
+
     /// Disconnects a specific SSH session and cleans up resources
     pub fn disconnect_ssh(&self, session_id: &SessionId) -> Result<(), SshError> {
         // Remove from channels and clean up task
@@ -869,6 +3739,19 @@ impl SshManager {
                 if let Some(status_handle) = info.status_handle.take() {
                     status_handle.abort();
                 }
+                if let Some(saved_id) = &info.saved_session_id {
+                    let traffic = info.sftp_handle.stats.snapshot();
+                    let banner = info.banner();
+                    if let Err(e) = crate::db::record_disconnect(
+                        saved_id,
+                        traffic.total_in,
+                        traffic.total_out,
+                        banner.server_banner.as_deref(),
+                        banner.motd.as_deref(),
+                    ) {
+                        tracing::warn!("{}", e);
+                    }
+                }
             }
         }
 
@@ -876,23 +3759,64 @@ impl SshManager {
         if let Ok(mut sessions) = self.sessions.write() {
             sessions.remove(session_id);
         }
-        println!("Disconnected SSH session: {}", session_id.0);
+        if let Ok(mut traces) = self.traces.write() {
+            traces.remove(session_id);
+        }
+        crate::watch::stop_session_watches(&session_id.0);
+        crate::capabilities::remove_session(&session_id.0);
+        crate::tunnel::close_session_tunnels(&session_id.0);
+        crate::output_index::remove_session(&session_id.0);
+        tracing::info!("disconnected SSH session {}", session_id.0);
         Ok(())
     }
 
-    /// Disconnects all active SSH sessions
-    pub fn disconnect_all(&self) {
-        // Collect all session IDs first to avoid holding locks
-        let session_ids: Vec<SessionId> = if let Ok(channels) = self.channels.read() {
-            channels.keys().cloned().collect()
-        } else {
-            Vec::new()
-        };
+    /// Returns the trace of how an open session was connected
+    pub fn get_connection_trace(&self, session_id: &SessionId) -> Option<ConnectionTrace> {
+        self.traces
+            .read()
+            .ok()
+            .and_then(|traces| traces.get(session_id).cloned())
+    }
 
-        for session_id in session_ids {
-            let _ = self.disconnect_ssh(&session_id);
-            println!("Disconnected SSH session: {}", session_id.0);
-        }
+    /// Returns the connection details of a session
+    pub fn get_session_info(&self, session_id: &SessionId) -> Result<SshSession, SshError> {
+        let sessions = self
+            .sessions
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        sessions
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| SshError::SessionNotFound(session_id.0.clone()))
+    }
+
+    /// Lists open connections with their latest monitoring reading
+    pub fn active_sessions(&self) -> Vec<ActiveSession> {
+        let Ok(channels) = self.channels.read() else {
+            return Vec::new();
+        };
+        let sessions = self.sessions.read().ok();
+        let mut active: Vec<ActiveSession> = channels
+            .iter()
+            .map(|(id, info)| {
+                let label = sessions
+                    .as_ref()
+                    .and_then(|s| s.get(id))
+                    .map(|s| format!("{}@{}", s.username, s.ip))
+                    .unwrap_or_else(|| id.0.clone());
+                ActiveSession {
+                    session_id: id.0.clone(),
+                    label,
+                    saved_session_id: info.saved_session_id.clone(),
+                    connected: !info.stop_flag.load(Ordering::SeqCst),
+                    read_only: info.read_only.load(Ordering::SeqCst),
+                    suspended: info.suspension.is_suspended(),
+                    status: info.last_status.read().ok().and_then(|s| s.clone()),
+                }
+            })
+            .collect();
+        active.sort_by(|a, b| a.label.cmp(&b.label));
+        active
     }
 
     /// Checks if a session exists
@@ -901,177 +3825,60 @@ impl SshManager {
         if let Ok(sessions) = self.sessions.read() {
             sessions.contains_key(session_id)
         } else {
-            false
-        }
-    }
-
-    /// Uploads a file via SFTP to the specified remote path.
-    /// This implementation runs in the background and emits progress events.
-    /// It uses chunked uploading and releases the session lock between chunks
-    /// to ensure the terminal remains responsive.
-    pub fn upload_file_sftp(
-        &self,
-        app_handle: tauri::AppHandle,
-        session_id: SessionId,
-        task_id: String,
-        local_path: String,
-        remote_path: String,
-    ) -> Result<(), SshError> {
-        let sess_arc = {
-            let channels = self
-                .channels
-                .read()
-                .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
-            let info = channels
-                .get(&session_id)
-                .ok_or_else(|| SshError::SessionNotFound(session_id.as_ref().to_string()))?;
-            info.sess_arc.clone()
-        };
-
-        // Perform the upload in background thread to avoid blocking the main thread
-        // We do NOT await this spawn to ensure true async behavior
-        std::thread::spawn(move || {
-            let sid = session_id.as_ref().to_string();
-            let upload_start = std::time::Instant::now();
-            
-            let result: Result<u64, SshError> = (|| {
-                let mut local_file = std::fs::File::open(&local_path).map_err(|e| {
-                    SshError::OperationFailed(format!("Failed to open local file {}: {}", local_path, e))
-                })?;
-
-                let total_bytes = local_file.metadata().map(|m| m.len()).unwrap_or(0);
-                
-                // 512KB chunks provide a good balance between throughput and terminal responsiveness
-                let mut buffer = [0u8; 1024 * 512];
-                let mut total_written: u64 = 0;
-                let mut is_first_chunk = true;
-
-                loop {
-                    // 1. Read a chunk from the local file
-                    let n = local_file.read(&mut buffer).map_err(|e| {
-                        SshError::OperationFailed(format!("Read local file failed: {}", e))
-                    })?;
-                    
-                    if n == 0 {
-                        break;
-                    }
-
-                    // 2. Acquire the session lock for this chunk
-                    let sess = sess_arc.blocking_lock();
-                    
-                    // Temporarily set to blocking for synchronous SFTP operations
-                    sess.set_blocking(true);
-
-                    let chunk_res = (|| {
-                        let sftp = sess.sftp().map_err(|e| {
-                            SshError::OperationFailed(format!("Failed to start SFTP: {}", e))
-                        })?;
-
-                        let flags = if is_first_chunk {
-                            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
-                        } else {
-                            OpenFlags::WRITE
-                        };
-
-                        let mut remote_file = sftp.open_mode(
-                            std::path::Path::new(&remote_path),
-                            flags,
-                            0o644,
-                            OpenType::File
-                        ).map_err(|e| {
-                            SshError::OperationFailed(format!("Failed to open remote file {}: {}", remote_path, e))
-                        })?;
-
-                        if !is_first_chunk {
-                            remote_file.seek(SeekFrom::Start(total_written)).map_err(|e| {
-                                SshError::OperationFailed(format!("Failed to seek remote file: {}", e))
-                            })?;
-                        }
-
-                        remote_file.write_all(&buffer[..n]).map_err(|e| {
-                            SshError::OperationFailed(format!("Failed to write to remote file: {}", e))
-                        })?;
-
-                        remote_file.flush().map_err(|e| {
-                            SshError::OperationFailed(format!("Failed to flush remote file: {}", e))
-                        })?;
+            false
+        }
+    }
 
-                        Ok(())
-                    })();
-
-                    // 3. CRITICAL: Restore non-blocking mode and release the lock
-                    sess.set_blocking(false);
-                    drop(sess);
-
-                    // Check for errors after releasing the lock
-                    chunk_res?;
-                    
-                    total_written += n as u64;
-                    is_first_chunk = false;
-
-                    // Calculate progress and speed
-                    let elapsed = upload_start.elapsed().as_secs_f64();
-                    let speed = if elapsed > 0.0 { total_written as f64 / elapsed } else { 0.0 };
-                    let progress = if total_bytes > 0 { (total_written as f64 / total_bytes as f64) * 100.0 } else { 0.0 };
-
-                    // Emit progress event
-                    let _ = app_handle.emit("upload-progress", UploadProgress {
-                        task_id: task_id.clone(),
-                        session_id: sid.clone(),
-                        progress,
-                        uploaded_bytes: total_written,
-                        total_bytes,
-                        status: "uploading".to_string(),
-                        message: format!("Uploading... ({:.1} MB/s)", speed / 1024.0 / 1024.0),
-                        speed,
-                        error: None,
-                    });
+    /// Returns the session handle used for SFTP and extra exec channels
+    pub fn session_handle(&self, session_id: &SessionId) -> Result<SessionHandle, SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.as_ref().to_string()))?;
+        Ok(info.sftp_handle.clone())
+    }
 
-                    // 4. Brief pause to give other tasks a chance to use the session
-                    // if they are waiting for the lock.
-                    std::thread::sleep(std::time::Duration::from_millis(5));
-                }
+    /// Returns the saved session a connection was opened from
+    pub fn saved_session_id(&self, session_id: &SessionId) -> Option<String> {
+        let channels = self.channels.read().ok()?;
+        channels.get(session_id)?.saved_session_id.clone()
+    }
 
-                Ok(total_bytes)
-            })();
+    /// Returns the bytes a session has transferred so far
+    pub fn session_stats(&self, session_id: &SessionId) -> Result<SessionTraffic, SshError> {
+        Ok(self.session_handle(session_id)?.stats.snapshot())
+    }
 
-            // Emit final status
-            match result {
-                Ok(total_bytes) => {
-                    let elapsed = upload_start.elapsed().as_secs_f64();
-                    let speed = if elapsed > 0.0 { total_bytes as f64 / elapsed } else { 0.0 };
-                    let _ = app_handle.emit("upload-progress", UploadProgress {
-                        task_id: task_id.clone(),
-                        session_id: sid,
-                        progress: 100.0,
-                        uploaded_bytes: total_bytes,
-                        total_bytes,
-                        status: "success".to_string(),
-                        message: "Upload completed successfully".to_string(),
-                        speed,
-                        error: None,
-                    });
-                }
-                Err(e) => {
-                    let _ = app_handle.emit("upload-progress", UploadProgress {
-                        task_id: task_id.clone(),
-                        session_id: sid,
-                        progress: 0.0,
-                        uploaded_bytes: 0,
-                        total_bytes: 0,
-                        status: "error".to_string(),
-                        message: format!("Upload failed: {}", e),
-                        speed: 0.0,
-                        error: Some(e.to_string()),
-                    });
-                }
-            }
-        });
+    /// Returns the handle of a connection opened from the given saved
+    /// session, if one is open
+    pub fn saved_session_handle(&self, saved_session_id: &str) -> Option<SessionHandle> {
+        let channels = self.channels.read().ok()?;
+        channels
+            .values()
+            .find(|info| info.saved_session_id.as_deref() == Some(saved_session_id))
+            .map(|info| info.sftp_handle.clone())
+    }
 
-        Ok(())
+    /// Returns the interactive shell's working directory, if it has been
+    /// reported through OSC 7
+    pub fn get_remote_cwd(&self, session_id: &SessionId) -> Result<Option<String>, SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.as_ref().to_string()))?;
+        Ok(info.shell_state.cwd())
     }
 
-    /// Probes the remote user's home or current directory without affecting the shell
+    /// Probes the remote user's current directory without affecting the shell
+    ///
+    /// Prefers the directory tracked from the shell's OSC 7 reports and falls
+    /// back to running `pwd` on a separate exec channel (usually `$HOME`).
     pub async fn probe_remote_path(&self, session_id: &SessionId) -> Result<String, SshError> {
         let sess_arc = {
             let channels = self
@@ -1081,6 +3888,9 @@ impl SshManager {
             let info = channels
                 .get(session_id)
                 .ok_or_else(|| SshError::SessionNotFound(session_id.as_ref().to_string()))?;
+            if let Some(cwd) = info.shell_state.cwd() {
+                return Ok(cwd);
+            }
             info.sess_arc.clone()
         };
 
@@ -1115,7 +3925,11 @@ impl SshManager {
     }
 
     /// Updates the monitoring refresh rate for a session
-    pub fn set_refresh_rate(&self, session_id: &SessionId, interval_ms: u64) -> Result<(), SshError> {
+    pub fn set_refresh_rate(
+        &self,
+        session_id: &SessionId,
+        interval_ms: u64,
+    ) -> Result<(), SshError> {
         let channels = self
             .channels
             .read()
@@ -1126,6 +3940,55 @@ impl SshManager {
         info.refresh_interval.store(interval_ms, Ordering::SeqCst);
         Ok(())
     }
+
+    /// Suspends or resumes a session; returns whether its state changed
+    pub fn set_suspended(&self, session_id: &SessionId, suspended: bool) -> Result<bool, SshError> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+        let info = channels
+            .get(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.as_ref().to_string()))?;
+        let changed = if suspended {
+            info.suspension.suspend()
+        } else {
+            info.suspension.resume()
+        };
+        if changed {
+            tracing::debug!(
+                "{} SSH session {}",
+                if suspended { "suspended" } else { "resumed" },
+                session_id.0
+            );
+        }
+        Ok(changed)
+    }
+}
+
+impl ConnectionManager for SshManager {
+    fn kind(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn session_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .channels
+            .read()
+            .map(|channels| channels.keys().map(|id| id.0.clone()).collect())
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+
+    fn session_route(&self, session_id: &SessionId) -> Option<SessionRoute<OutputChunk>> {
+        let channels = self.channels.read().ok()?;
+        channels.get(session_id).map(|info| info.route.clone())
+    }
+
+    fn disconnect(&self, session_id: &SessionId) -> Result<(), String> {
+        self.disconnect_ssh(session_id).map_err(|e| e.to_string())
+    }
 }
 
 // ============================================================================
@@ -1134,12 +3997,16 @@ impl SshManager {
 
 /// Establishes a new SSH connection
 ///
+/// Fails with `AlreadyConnected` when `sessionId` is still connected,
+/// unless `options.takeover` is set.
+///
 /// # Tauri Command: `connect_ssh`
 #[tauri::command]
-#[allow(non_snake_case)]
+#[allow(non_snake_case, clippy::too_many_arguments)]
 pub async fn connect_ssh(
     state: tauri::State<'_, SshManager>,
     app_handle: tauri::AppHandle,
+    window: tauri::WebviewWindow,
     sessionId: String,
     ip: String,
     port: u16,
@@ -1147,6 +4014,7 @@ pub async fn connect_ssh(
     password: String,
     cols: u32,
     rows: u32,
+    options: Option<ConnectOptions>,
 ) -> Result<(), SshError> {
     state
         .connect_ssh(
@@ -1158,10 +4026,39 @@ pub async fn connect_ssh(
             password,
             cols,
             rows,
+            ConnectOptions {
+                window: Some(window.label().to_string()),
+                ..options.unwrap_or_default()
+            },
         )
         .await
 }
 
+/// Returns the phase-by-phase trace of how an open session was connected.
+/// A failed attempt returns its trace in the error when `diagnostics` is set.
+///
+/// # Tauri Command: `get_connection_trace`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_connection_trace(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Option<ConnectionTrace> {
+    state.get_connection_trace(&SessionId::from(sessionId))
+}
+
+/// Returns connection details, including the address that was connected to
+///
+/// # Tauri Command: `get_session_info`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_session_info(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<SshSession, SshError> {
+    state.get_session_info(&SessionId::from(sessionId))
+}
+
 /// Retrieves cached initial output from a session
 ///
 /// # Tauri Command: `get_buffered_ssh_output`
@@ -1174,6 +4071,34 @@ pub fn get_buffered_ssh_output(
     state.get_buffered_ssh_output(&SessionId::from(sessionId))
 }
 
+/// Returns the identification banner the server sent before
+/// authentication and the message of the day printed before the first
+/// prompt, kept apart from the terminal output. Both are also stored in
+/// the saved session's connection history when it disconnects.
+///
+/// # Tauri Command: `get_session_banner`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_session_banner(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<SessionBanner, SshError> {
+    state.get_session_banner(&SessionId::from(sessionId))
+}
+
+/// Cancels a `connect_ssh` in progress, which then fails with
+/// "Connection attempt cancelled". Returns whether one was in progress.
+///
+/// # Tauri Command: `cancel_connect`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_connect(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<bool, SshError> {
+    state.cancel_connect(&SessionId::from(sessionId))
+}
+
 /// Disconnects an SSH session and releases resources
 ///
 /// # Tauri Command: `disconnect_ssh`
@@ -1211,6 +4136,230 @@ pub fn send_ssh_input(
     state.send_ssh_input(&SessionId::from(sessionId), input)
 }
 
+/// Sends raw bytes (base64-encoded) to an SSH session
+///
+/// # Tauri Command: `send_ssh_bytes`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn send_ssh_bytes(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    data: String,
+) -> Result<(), SshError> {
+    state.send_ssh_bytes(&SessionId::from(sessionId), &data)
+}
+
+/// Enables or disables raw bytes output for an SSH session
+///
+/// # Tauri Command: `set_raw_mode`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn set_raw_mode(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    enabled: bool,
+) -> Result<(), SshError> {
+    state.set_raw_mode(&SessionId::from(sessionId), enabled)
+}
+
+/// Drops or accepts user input for an SSH session; output keeps streaming
+///
+/// # Tauri Command: `set_session_readonly`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn set_session_readonly(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    readOnly: bool,
+) -> Result<(), SshError> {
+    state.set_session_readonly(&SessionId::from(sessionId), readOnly)
+}
+
+/// Toggles sudo password autofill from the credentials of the saved
+/// session the connection was opened from. Enabling asks for biometric
+/// confirmation when it is turned on for saved credentials.
+///
+/// # Tauri Command: `set_sudo_autofill`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn set_sudo_autofill(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    enabled: bool,
+) -> Result<(), SshError> {
+    if enabled {
+        tokio::task::spawn_blocking(|| crate::biometric::confirm("fill in sudo passwords"))
+            .await
+            .map_err(|e| SshError::TaskError(e.to_string()))?
+            .map_err(SshError::AuthenticationFailed)?;
+    }
+    state.set_sudo_autofill(&SessionId::from(sessionId), enabled)
+}
+
+/// Notifies when the session's current command finishes in the background
+///
+/// # Tauri Command: `notify_when_done`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn notify_when_done(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    enabled: Option<bool>,
+) -> Result<(), SshError> {
+    state.notify_when_done(&SessionId::from(sessionId), enabled.unwrap_or(true))
+}
+
+/// Switches the character encoding of a running session
+///
+/// # Tauri Command: `set_session_encoding`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn set_session_encoding(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    encoding: String,
+) -> Result<(), SshError> {
+    state.set_session_encoding(&SessionId::from(sessionId), &encoding)
+}
+
+/// Sends or cancels a command held by the command guard
+///
+/// # Tauri Command: `confirm_guarded_command`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn confirm_guarded_command(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    guardId: String,
+    confirmed: bool,
+) -> Result<(), SshError> {
+    state.confirm_guarded_command(
+        &app_handle,
+        &SessionId::from(sessionId),
+        &guardId,
+        confirmed,
+    )
+}
+
+/// Unlocks a session locked after inactivity
+///
+/// # Tauri Command: `unlock_ssh_session`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn unlock_ssh_session(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<(), SshError> {
+    state.unlock_ssh_session(&app_handle, &SessionId::from(sessionId))
+}
+
+/// Reloads a session's output triggers after they were changed
+///
+/// # Tauri Command: `reload_triggers`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn reload_triggers(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    savedSessionId: Option<String>,
+) -> Result<(), SshError> {
+    state.reload_triggers(&SessionId::from(sessionId), savedSessionId)
+}
+
+/// Reloads a session's alert rules after they were changed
+///
+/// # Tauri Command: `reload_alert_rules`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn reload_alert_rules(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    savedSessionId: Option<String>,
+) -> Result<(), SshError> {
+    state.reload_alert_rules(&SessionId::from(sessionId), savedSessionId)
+}
+
+/// Reloads a session's highlight rules after they were changed
+///
+/// # Tauri Command: `reload_highlight_rules`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn reload_highlight_rules(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    savedSessionId: Option<String>,
+) -> Result<(), SshError> {
+    state.reload_highlight_rules(&SessionId::from(sessionId), savedSessionId)
+}
+
+/// Returns how many output lines each counting highlight rule matched
+///
+/// # Tauri Command: `get_match_counters`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_match_counters(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<Vec<MatchCounter>, SshError> {
+    state.match_counters(&SessionId::from(sessionId))
+}
+
+/// Resets a session's match counters
+///
+/// # Tauri Command: `reset_match_counters`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn reset_match_counters(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<(), SshError> {
+    state.reset_match_counters(&SessionId::from(sessionId))
+}
+
+/// Pastes text into an SSH session with flow control and progress events
+///
+/// # Tauri Command: `paste_text`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn paste_text(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    text: String,
+    confirmThresholdBytes: Option<u64>,
+    bracketed: Option<bool>,
+) -> Result<PasteOutcome, SshError> {
+    state.paste_text(
+        &SessionId::from(sessionId),
+        text,
+        confirmThresholdBytes,
+        bracketed.unwrap_or(false),
+    )
+}
+
+/// Returns every open SSH session with its connection state and latest
+/// monitoring reading, so an overview can be painted without subscribing
+/// to each session's `ssh-status-{sessionId}` events
+///
+/// # Tauri Command: `get_all_session_statuses`
+#[tauri::command]
+pub fn get_all_session_statuses(state: tauri::State<'_, SshManager>) -> Vec<ActiveSession> {
+    state.active_sessions()
+}
+
+/// Returns the cumulative terminal and SFTP traffic of a session
+///
+/// # Tauri Command: `get_session_stats`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_session_stats(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<SessionTraffic, SshError> {
+    state.session_stats(&SessionId::from(sessionId))
+}
+
 /// Updates the SSH status refresh rate
 #[tauri::command]
 #[allow(non_snake_case)]
@@ -1222,28 +4371,128 @@ pub async fn set_ssh_status_refresh_rate(
     state.set_refresh_rate(&SessionId::from(sessionId), intervalMs)
 }
 
-/// Uploads a file to a remote server using SFTP
+/// Suspends a session whose tab is hidden: the server metrics stop being
+/// fetched and the channel is read less often, while the connection and
+/// its output stay intact. The frontend calls this when a tab is hidden
+/// and `resume_session` when it is shown again. Returns whether the
+/// session was running.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn suspend_session(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<bool, SshError> {
+    state.set_suspended(&SessionId::from(sessionId), true)
+}
+
+/// Resumes a suspended session; metrics are fetched and output read at
+/// full rate again at once. Returns whether the session was suspended.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn resume_session(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<bool, SshError> {
+    state.set_suspended(&SessionId::from(sessionId), false)
+}
+
+/// Queues an SFTP upload on the session's transfer queue. Progress,
+/// including the queue position while waiting, is reported through
+/// `upload-progress` events. `preserveAttributes` carries the file mode
+/// and modification time over and defaults to the
+/// `transfers.preserveAttributes` setting.
 ///
 /// # Tauri Command: `upload_file_sftp`
 #[tauri::command]
 #[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_file_sftp(
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, SshManager>,
+    transfers: tauri::State<'_, TransferManager>,
     sessionId: String,
     taskId: String,
     localPath: String,
     remotePath: String,
+    priority: Option<i32>,
+    preserveAttributes: Option<bool>,
+) -> Result<(), SshError> {
+    let session_id = SessionId::from(sessionId);
+    let handle = state.session_handle(&session_id)?;
+    if let Some(saved_id) = state.saved_session_id(&session_id) {
+        let path = remotePath.clone();
+        tokio::task::spawn_blocking(move || {
+            crate::sftp::remember_path(&saved_id, &path, "upload");
+            // The destination directory, on the server's POSIX paths
+            if let Some((dir, _)) = path.rsplit_once('/') {
+                let dir = if dir.is_empty() { "/" } else { dir };
+                crate::sftp::remember_path(&saved_id, dir, "dir");
+            }
+        });
+    }
+    transfers.enqueue(
+        app_handle,
+        session_id.as_ref(),
+        handle,
+        taskId,
+        TransferJob::Upload {
+            local_path: localPath,
+            remote_path: remotePath,
+            preserve: preserveAttributes
+                .unwrap_or_else(|| crate::settings::current().transfers.preserve_attributes),
+        },
+        priority.unwrap_or(0),
+    )
+}
+
+/// Queues an SFTP download on the session's transfer queue. Progress is
+/// reported through `download-progress` events; `preserveAttributes` works
+/// as for uploads.
+///
+/// # Tauri Command: `download_file_sftp`
+#[tauri::command]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub async fn download_file_sftp(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SshManager>,
+    transfers: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    taskId: String,
+    remotePath: String,
+    localPath: String,
+    priority: Option<i32>,
+    preserveAttributes: Option<bool>,
 ) -> Result<(), SshError> {
-    state.upload_file_sftp(
+    let session_id = SessionId::from(sessionId);
+    let handle = state.session_handle(&session_id)?;
+    transfers.enqueue(
         app_handle,
-        SessionId::from(sessionId),
+        session_id.as_ref(),
+        handle,
         taskId,
-        localPath,
-        remotePath,
+        TransferJob::Download {
+            remote_path: remotePath,
+            local_path: localPath,
+            preserve: preserveAttributes
+                .unwrap_or_else(|| crate::settings::current().transfers.preserve_attributes),
+        },
+        priority.unwrap_or(0),
     )
 }
 
+/// Returns the shell's tracked working directory (OSC 7), if known
+///
+/// # Tauri Command: `get_remote_cwd`
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn get_remote_cwd(
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+) -> Result<Option<String>, SshError> {
+    state.get_remote_cwd(&SessionId::from(sessionId))
+}
+
 /// Probes the current remote working directory
 #[tauri::command]
 #[allow(non_snake_case)]
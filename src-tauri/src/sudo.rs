@@ -0,0 +1,99 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Matches a sudo password prompt that ends the current output, e.g.
+/// `[sudo] password for alice: `. Anchored to the start of a line and the
+/// end of the buffer so echoed text in the middle of output never matches.
+static SUDO_PROMPT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|[\r\n])\[sudo\] password for ([a-z_][a-z0-9_.-]{0,31}\$?): ?$")
+        .expect("valid sudo prompt regex")
+});
+
+/// Printed by sudo after a rejected password
+const SUDO_REJECTED: &str = "Sorry, try again.";
+
+/// Characters of trailing output kept to detect prompts split across chunks
+const TAIL_LIMIT: usize = 256;
+
+/// Window after a fill during which a re-prompt or rejection means the
+/// stored password was wrong
+const REJECT_WINDOW: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// What the detector wants the I/O task to do after a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudoAction {
+    /// A prompt for the session user is waiting for the password
+    Fill,
+    /// The previously filled password was rejected; autofill is disabled
+    Rejected,
+}
+
+/// Payload of the `ssh-sudo-autofill-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SudoAutofillEvent {
+    pub session_id: String,
+    /// "filled", "rejected" or "unavailable"
+    pub status: String,
+}
+
+/// Detects sudo password prompts for the connected user.
+///
+/// The password is filled once per prompt. If sudo rejects it (or prompts
+/// again right away) the detector reports `Rejected` instead of filling
+/// again, so a wrong stored password can never loop.
+#[derive(Debug)]
+pub struct SudoPromptDetector {
+    username: String,
+    tail: String,
+    filled_at: Option<std::time::Instant>,
+}
+
+impl SudoPromptDetector {
+    pub fn new(username: String) -> Self {
+        Self {
+            username,
+            tail: String::new(),
+            filled_at: None,
+        }
+    }
+
+    fn recently_filled(&self) -> bool {
+        self.filled_at.is_some_and(|t| t.elapsed() < REJECT_WINDOW)
+    }
+
+    /// Feeds decoded output; returns an action when one is required
+    pub fn feed(&mut self, text: &str) -> Option<SudoAction> {
+        if self.recently_filled() && text.contains(SUDO_REJECTED) {
+            self.tail.clear();
+            self.filled_at = None;
+            return Some(SudoAction::Rejected);
+        }
+
+        self.tail.push_str(text);
+        if self.tail.len() > TAIL_LIMIT {
+            let mut cut = self.tail.len() - TAIL_LIMIT;
+            while !self.tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.tail.drain(..cut);
+        }
+
+        let is_session_user = SUDO_PROMPT
+            .captures(&self.tail)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str() == self.username)?;
+        self.tail.clear();
+        if !is_session_user {
+            return None;
+        }
+        if self.recently_filled() {
+            self.filled_at = None;
+            return Some(SudoAction::Rejected);
+        }
+        self.filled_at = Some(std::time::Instant::now());
+        Some(SudoAction::Fill)
+    }
+}
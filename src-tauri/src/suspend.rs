@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Suspend state of one session. While suspended, the monitoring task
+/// pauses and the I/O task polls the channel at a slower rate; the
+/// connection itself and the latency probe, which keeps it alive, carry on.
+#[derive(Debug, Default)]
+pub struct Suspension {
+    suspended: AtomicBool,
+    /// Wakes the paused tasks when the session is resumed
+    resumed: Notify,
+}
+
+impl Suspension {
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the state changed
+    pub fn suspend(&self) -> bool {
+        !self.suspended.swap(true, Ordering::SeqCst)
+    }
+
+    /// Returns whether the state changed
+    pub fn resume(&self) -> bool {
+        let changed = self.suspended.swap(false, Ordering::SeqCst);
+        self.resumed.notify_waiters();
+        changed
+    }
+
+    /// Sleeps for `duration` while suspended, returning early when the
+    /// session is resumed; returns at once when it is not suspended
+    pub async fn pause(&self, duration: Duration) {
+        let resumed = self.resumed.notified();
+        tokio::pin!(resumed);
+        // Registered before the flag is read, so a resume in between is
+        // not missed
+        resumed.as_mut().enable();
+        if !self.is_suspended() {
+            return;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = resumed => {}
+        }
+    }
+}
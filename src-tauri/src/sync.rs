@@ -0,0 +1,485 @@
+use crate::db::{ExportData, ExportSession, Group, Tag, Tombstone};
+use crate::encryption::{EncryptionManager, SensitiveData};
+use crate::settings::SyncSettings;
+use base64::{engine::general_purpose, Engine as _};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Emitter};
+
+/// Name of the library file in the sync folder or WebDAV collection
+const LIBRARY_FILE: &str = "nexashell-library.json";
+
+/// Format version of the library file
+const LIBRARY_VERSION: u32 = 1;
+
+/// Plaintext encrypted into `key_check` so a wrong passphrase is detected
+/// before anything is merged
+const KEY_CHECK: &str = "nexashell-sync";
+
+/// The synced library. Credentials are encrypted with the user's sync
+/// passphrase, never with the machine key.
+#[derive(Serialize, Deserialize)]
+struct SyncLibrary {
+    version: u32,
+    key_check: String,
+    sessions: Vec<ExportSession>,
+    groups: Vec<Group>,
+    tags: Vec<Tag>,
+    #[serde(default)]
+    deleted: Vec<Tombstone>,
+}
+
+/// Outcome of a sync, also emitted as `sync-completed`
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Records added or updated locally from the remote library
+    pub pulled: usize,
+    /// Local records that were newer than the remote copy
+    pub pushed: usize,
+    /// Local records removed because they were deleted elsewhere
+    pub deleted: usize,
+    /// Records changed on both sides since the last sync; the newer
+    /// `updated_at` won
+    pub conflicts: usize,
+    /// Whether the remote library was rewritten
+    pub uploaded: bool,
+}
+
+/// Sync configuration without secrets
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub settings: SyncSettings,
+    pub has_passphrase: bool,
+    pub has_webdav_password: bool,
+    pub last_synced_at: Option<String>,
+}
+
+/// Error returned when another device replaced the library file while
+/// this one was merging
+const REMOTE_CHANGED: &str = "The synced library changed during the sync; sync again";
+
+/// Revision of the library file when it was loaded, checked again before
+/// it is replaced so an upload from another device is not overwritten
+#[derive(Debug, PartialEq)]
+enum Revision {
+    /// Nothing was synced yet
+    Missing,
+    /// ETag, or modification time of a file in the sync folder
+    Known(String),
+    /// The WebDAV server sent no ETag; the upload is unconditional
+    Unknown,
+}
+
+/// Modification time of a file in the sync folder, as a revision
+fn file_revision(path: &std::path::Path) -> Result<Revision, String> {
+    match std::fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(modified) => Ok(Revision::Known(
+            modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+                .to_string(),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Revision::Missing),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Where the library file lives
+enum Remote {
+    Folder(PathBuf),
+    WebDav {
+        url: String,
+        authorization: Option<String>,
+    },
+}
+
+impl Remote {
+    fn from_settings(
+        settings: &SyncSettings,
+        webdav_password: Option<String>,
+    ) -> Result<Self, String> {
+        match settings.target.as_str() {
+            "folder" => {
+                let folder = settings
+                    .folder_path
+                    .as_deref()
+                    .filter(|p| !p.is_empty())
+                    .ok_or("No sync folder configured")?;
+                let folder = PathBuf::from(folder);
+                if !folder.is_dir() {
+                    return Err(format!("Sync folder not found: {}", folder.display()));
+                }
+                Ok(Self::Folder(folder.join(LIBRARY_FILE)))
+            }
+            "webdav" => {
+                let base = settings
+                    .webdav_url
+                    .as_deref()
+                    .filter(|u| !u.is_empty())
+                    .ok_or("No WebDAV URL configured")?;
+                let authorization = settings.webdav_username.as_ref().map(|user| {
+                    let pair = format!("{}:{}", user, webdav_password.unwrap_or_default());
+                    format!("Basic {}", general_purpose::STANDARD.encode(pair))
+                });
+                Ok(Self::WebDav {
+                    url: format!("{}/{}", base.trim_end_matches('/'), LIBRARY_FILE),
+                    authorization,
+                })
+            }
+            other => Err(format!("Unknown sync target: {}", other)),
+        }
+    }
+
+    /// Reads the library file and its revision; `None` when nothing was
+    /// synced yet
+    fn load(&self) -> Result<(Option<String>, Revision), String> {
+        match self {
+            Self::Folder(path) => {
+                // Taken before reading, so a write in between shows up as a
+                // changed revision rather than being lost
+                let revision = file_revision(path)?;
+                match std::fs::read_to_string(path) {
+                    Ok(json) => Ok((Some(json), revision)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        Ok((None, Revision::Missing))
+                    }
+                    Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+                }
+            }
+            Self::WebDav { url, authorization } => {
+                let mut request = ureq::get(url);
+                if let Some(auth) = authorization {
+                    request = request.set("Authorization", auth);
+                }
+                match request.call() {
+                    Ok(response) => {
+                        let revision = match response.header("ETag") {
+                            Some(etag) => Revision::Known(etag.to_string()),
+                            None => Revision::Unknown,
+                        };
+                        let json = response.into_string().map_err(|e| e.to_string())?;
+                        Ok((Some(json), revision))
+                    }
+                    Err(ureq::Error::Status(404, _)) => Ok((None, Revision::Missing)),
+                    Err(e) => Err(format!("WebDAV download failed: {}", e)),
+                }
+            }
+        }
+    }
+
+    /// Replaces the library file, failing with `REMOTE_CHANGED` when it is
+    /// no longer at `revision`
+    fn store(&self, json: &str, revision: &Revision) -> Result<(), String> {
+        match self {
+            Self::Folder(path) => {
+                if file_revision(path)? != *revision {
+                    return Err(REMOTE_CHANGED.to_string());
+                }
+                // Write then rename so sync clients never pick up a partial file
+                let staging = path.with_extension("json.tmp");
+                std::fs::write(&staging, json).map_err(|e| e.to_string())?;
+                std::fs::rename(&staging, path).map_err(|e| e.to_string())
+            }
+            Self::WebDav { url, authorization } => {
+                let mut request = ureq::put(url).set("Content-Type", "application/json");
+                if let Some(auth) = authorization {
+                    request = request.set("Authorization", auth);
+                }
+                request = match revision {
+                    Revision::Missing => request.set("If-None-Match", "*"),
+                    Revision::Known(etag) => request.set("If-Match", etag),
+                    Revision::Unknown => request,
+                };
+                match request.send_string(json) {
+                    Ok(_) => Ok(()),
+                    Err(ureq::Error::Status(412, _)) => Err(REMOTE_CHANGED.to_string()),
+                    Err(e) => Err(format!("WebDAV upload failed: {}", e)),
+                }
+            }
+        }
+    }
+}
+
+fn session_key(s: &ExportSession) -> (&str, &str) {
+    (&s.metadata.id, &s.metadata.updated_at)
+}
+
+fn group_key(g: &Group) -> (&str, &str) {
+    (&g.id, &g.updated_at)
+}
+
+fn tag_key(t: &Tag) -> (&str, &str) {
+    (&t.id, &t.updated_at)
+}
+
+/// Merged records of one kind
+struct Merged<T> {
+    records: Vec<T>,
+    /// Remote records to write locally
+    incoming: Vec<T>,
+    /// Local records to delete
+    deletions: Vec<Tombstone>,
+}
+
+/// Merges local and remote records by id. The newer `updated_at` wins, and
+/// a deletion wins over a record that was not changed after it.
+fn merge_records<T: Clone>(
+    kind: &str,
+    local: Vec<T>,
+    remote: Vec<T>,
+    key: fn(&T) -> (&str, &str),
+    tombstones: &HashMap<(String, String), String>,
+    last_synced_at: Option<&str>,
+    report: &mut SyncReport,
+) -> Merged<T> {
+    let changed_since_sync = |updated_at: &str| last_synced_at.is_none_or(|t| updated_at > t);
+    let mut records: BTreeMap<String, T> = BTreeMap::new();
+    let mut incoming: BTreeMap<String, T> = BTreeMap::new();
+    let mut remote_by_id: HashMap<String, T> = remote
+        .into_iter()
+        .map(|r| (key(&r).0.to_string(), r))
+        .collect();
+
+    let local_ids: HashSet<String> = local.iter().map(|r| key(r).0.to_string()).collect();
+    for record in local {
+        let id = key(&record).0.to_string();
+        match remote_by_id.remove(&id) {
+            Some(theirs) => {
+                let (ours_at, theirs_at) = (key(&record).1, key(&theirs).1);
+                if ours_at != theirs_at
+                    && changed_since_sync(ours_at)
+                    && changed_since_sync(theirs_at)
+                {
+                    report.conflicts += 1;
+                }
+                if theirs_at > ours_at {
+                    incoming.insert(id.clone(), theirs.clone());
+                    records.insert(id, theirs);
+                } else {
+                    if ours_at > theirs_at {
+                        report.pushed += 1;
+                    }
+                    records.insert(id, record);
+                }
+            }
+            None => {
+                report.pushed += 1;
+                records.insert(id, record);
+            }
+        }
+    }
+    for (id, theirs) in remote_by_id {
+        incoming.insert(id.clone(), theirs.clone());
+        records.insert(id, theirs);
+    }
+
+    let mut deletions = Vec::new();
+    records.retain(|id, record| {
+        let Some(deleted_at) = tombstones.get(&(kind.to_string(), id.clone())) else {
+            return true;
+        };
+        if key(record).1 > deleted_at.as_str() {
+            return true;
+        }
+        incoming.remove(id);
+        if local_ids.contains(id) {
+            deletions.push(Tombstone {
+                kind: kind.to_string(),
+                id: id.clone(),
+                deleted_at: deleted_at.clone(),
+            });
+        }
+        false
+    });
+    report.deleted += deletions.len();
+    report.pulled += incoming.len();
+
+    Merged {
+        records: records.into_values().collect(),
+        incoming: incoming.into_values().collect(),
+        deletions,
+    }
+}
+
+/// Runs one sync round. Blocking.
+fn sync(settings: &SyncSettings) -> Result<SyncReport, String> {
+    if !settings.enabled {
+        return Err("Sync is not enabled".to_string());
+    }
+    let secrets = crate::db::load_sync_secrets()?;
+    let passphrase = secrets
+        .passphrase
+        .ok_or("Set a sync passphrase before syncing")?;
+    let remote = Remote::from_settings(settings, secrets.webdav_password)?;
+
+    let (remote_json, revision) = remote.load()?;
+    let remote_library = match remote_json {
+        Some(json) => {
+            let library: SyncLibrary = serde_json::from_str(&json)
+                .map_err(|e| format!("Synced library is unreadable: {}", e))?;
+            if library.version > LIBRARY_VERSION {
+                return Err("Synced library was written by a newer NexaShell".to_string());
+            }
+            let check = EncryptionManager::decrypt_with_key(&library.key_check, &passphrase)
+                .map_err(|_| "Sync passphrase does not match the synced library".to_string())?;
+            if check.password.as_deref() != Some(KEY_CHECK) {
+                return Err("Sync passphrase does not match the synced library".to_string());
+            }
+            Some(library)
+        }
+        None => None,
+    };
+    let uploaded_before = remote_library.is_some();
+    let (local, local_tombstones) = crate::db::load_sync_snapshot(&passphrase)?;
+    let remote_library = remote_library.unwrap_or(SyncLibrary {
+        version: LIBRARY_VERSION,
+        key_check: String::new(),
+        sessions: Vec::new(),
+        groups: Vec::new(),
+        tags: Vec::new(),
+        deleted: Vec::new(),
+    });
+
+    // Union of deletions, keeping the latest time per record
+    let mut tombstones: HashMap<(String, String), String> = HashMap::new();
+    let mut tombstones_changed = false;
+    for (tombstone, from_remote) in local_tombstones
+        .into_iter()
+        .map(|t| (t, false))
+        .chain(remote_library.deleted.iter().cloned().map(|t| (t, true)))
+    {
+        let slot = tombstones
+            .entry((tombstone.kind, tombstone.id))
+            .or_insert_with(|| {
+                tombstones_changed |= !from_remote;
+                tombstone.deleted_at.clone()
+            });
+        if tombstone.deleted_at > *slot {
+            tombstones_changed |= !from_remote;
+            *slot = tombstone.deleted_at;
+        }
+    }
+
+    let mut report = SyncReport::default();
+    let last_synced_at = secrets.last_synced_at.as_deref();
+    let sessions = merge_records(
+        "session",
+        local.sessions,
+        remote_library.sessions,
+        session_key,
+        &tombstones,
+        last_synced_at,
+        &mut report,
+    );
+    let groups = merge_records(
+        "group",
+        local.groups,
+        remote_library.groups,
+        group_key,
+        &tombstones,
+        last_synced_at,
+        &mut report,
+    );
+    let tags = merge_records(
+        "tag",
+        local.tags,
+        remote_library.tags,
+        tag_key,
+        &tombstones,
+        last_synced_at,
+        &mut report,
+    );
+
+    let merged_tombstones: Vec<Tombstone> = tombstones
+        .into_iter()
+        .map(|((kind, id), deleted_at)| Tombstone {
+            kind,
+            id,
+            deleted_at,
+        })
+        .collect();
+    let deletions: Vec<Tombstone> = sessions
+        .deletions
+        .into_iter()
+        .chain(groups.deletions)
+        .chain(tags.deletions)
+        .collect();
+
+    crate::db::apply_sync_changes(
+        ExportData {
+            sessions: sessions.incoming,
+            groups: groups.incoming,
+            tags: tags.incoming,
+        },
+        &deletions,
+        &merged_tombstones,
+        &passphrase,
+    )?;
+    if report.pulled > 0 || report.deleted > 0 {
+        crate::config_history::schedule_commit();
+    }
+
+    if !uploaded_before || report.pushed > 0 || tombstones_changed {
+        let key_check = EncryptionManager::encrypt_with_key(
+            &SensitiveData {
+                password: Some(KEY_CHECK.to_string()),
+                key_passphrase: None,
+            },
+            &passphrase,
+        )?;
+        let library = SyncLibrary {
+            version: LIBRARY_VERSION,
+            key_check,
+            sessions: sessions.records,
+            groups: groups.records,
+            tags: tags.records,
+            deleted: merged_tombstones,
+        };
+        let json = serde_json::to_string_pretty(&library).map_err(|e| e.to_string())?;
+        remote.store(&json, &revision)?;
+        report.uploaded = true;
+    }
+    // Only once the remote holds the merged library, so local edits are
+    // not treated as synced after a failed upload
+    crate::db::mark_synced()?;
+    Ok(report)
+}
+
+/// Returns the sync settings and which secrets are stored.
+#[command]
+pub fn get_sync_status() -> Result<SyncStatus, String> {
+    let secrets = crate::db::load_sync_secrets()?;
+    Ok(SyncStatus {
+        settings: crate::settings::current().sync,
+        has_passphrase: secrets.passphrase.is_some(),
+        has_webdav_password: secrets.webdav_password.is_some(),
+        last_synced_at: secrets.last_synced_at,
+    })
+}
+
+/// Stores the sync passphrase and/or WebDAV password. Omitted values are
+/// kept, empty strings clear them. All devices must use the same passphrase.
+#[command]
+pub fn set_sync_secrets(
+    passphrase: Option<String>,
+    webdav_password: Option<String>,
+) -> Result<(), String> {
+    crate::db::save_sync_secrets(passphrase, webdav_password)
+}
+
+/// Syncs the session library with the configured folder or WebDAV
+/// endpoint and emits `sync-completed` so the frontend reloads.
+#[command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    let settings = crate::settings::current().sync;
+    let report = tokio::task::spawn_blocking(move || sync(&settings))
+        .await
+        .map_err(|e| e.to_string())??;
+    let _ = app.emit("sync-completed", &report);
+    Ok(report)
+}
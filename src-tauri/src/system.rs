@@ -1,4 +1,8 @@
-use tauri::{command, AppHandle, Window};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Emitter, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
 #[command]
 pub fn get_platform() -> String {
@@ -64,10 +68,265 @@ pub async fn read_file_preview(path: String) -> Result<String, String> {
     Ok(String::from_utf8_lossy(&buffer[..n]).to_string())
 }
 
+/// Bytes inspected to guess whether a file is text
+const TEXT_SNIFF_LEN: usize = 8 * 1024;
+
+/// Guesses from its first bytes whether a file is binary: NUL bytes or
+/// invalid UTF-8 mean binary
+fn looks_binary(path: &str) -> std::io::Result<bool> {
+    use std::io::Read;
+
+    let mut head = Vec::with_capacity(TEXT_SNIFF_LEN);
+    std::fs::File::open(path)?
+        .take(TEXT_SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    if head.contains(&0) {
+        return Ok(true);
+    }
+    // A multi-byte character cut off at the end of the sample is fine
+    Ok(matches!(std::str::from_utf8(&head), Err(e) if e.error_len().is_some()))
+}
+
+/// Size and pre-flight details of a local file: modification time (Unix
+/// seconds), permission bits (`null` on Windows), read-only flag and
+/// whether the content looks binary (`null` for directories)
 #[command]
 pub async fn get_file_size(path: String) -> Result<serde_json::Value, String> {
     use std::fs;
 
     let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
-    Ok(serde_json::json!({ "size": metadata.len() }))
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let binary = if metadata.is_file() {
+        looks_binary(&path).ok()
+    } else {
+        None
+    };
+    Ok(serde_json::json!({
+        "size": metadata.len(),
+        "modified": modified,
+        "mode": unix_mode(&metadata),
+        "readonly": metadata.permissions().readonly(),
+        "isDir": metadata.is_dir(),
+        "binary": binary,
+    }))
+}
+
+/// Payload of the `hash-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HashProgress {
+    pub path: String,
+    pub processed: u64,
+    pub total: u64,
+}
+
+/// Minimum pause between two `hash-progress` events
+const HASH_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+fn hash_file<D: sha2::Digest>(
+    app: &AppHandle,
+    path: &str,
+    mut digest: D,
+) -> Result<String, String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut processed = 0u64;
+    let mut last_emit = std::time::Instant::now();
+    loop {
+        let n = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        digest.update(&buffer[..n]);
+        processed += n as u64;
+        if last_emit.elapsed() >= HASH_PROGRESS_INTERVAL {
+            last_emit = std::time::Instant::now();
+            let _ = app.emit(
+                "hash-progress",
+                HashProgress {
+                    path: path.to_string(),
+                    processed,
+                    total,
+                },
+            );
+        }
+    }
+    let _ = app.emit(
+        "hash-progress",
+        HashProgress {
+            path: path.to_string(),
+            processed,
+            total,
+        },
+    );
+    Ok(digest
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Hashes a local file in chunks, reporting `hash-progress` events, and
+/// returns the lowercase hex digest. `algo` is "sha256" (default),
+/// "sha384" or "sha512".
+#[command]
+pub async fn hash_local_file(
+    app: AppHandle,
+    path: String,
+    algo: Option<String>,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        match algo.as_deref().unwrap_or("sha256").to_lowercase().as_str() {
+            "sha256" => hash_file(&app, &path, sha2::Sha256::default()),
+            "sha384" => hash_file(&app, &path, sha2::Sha384::default()),
+            "sha512" => hash_file(&app, &path, sha2::Sha512::default()),
+            other => Err(format!("Unsupported hash algorithm: {}", other)),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// One entry of a local directory listing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalEntry {
+    pub name: String,
+    pub path: String,
+    /// "file", "dir", "symlink" or "other"; symlinks are not followed
+    pub kind: String,
+    pub size: u64,
+    /// Unix seconds
+    pub modified: Option<u64>,
+    /// Permission bits; `None` on Windows
+    pub mode: Option<u32>,
+    pub hidden: bool,
+    /// Kind of what a symlink points to; `None` for dangling links
+    pub target_kind: Option<String>,
+}
+
+fn kind_name(file_type: std::fs::FileType) -> &'static str {
+    if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "dir"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+fn unix_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o7777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+fn is_hidden(name: &str, metadata: &std::fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        // FILE_ATTRIBUTE_HIDDEN
+        if metadata.file_attributes() & 0x2 != 0 {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    name.starts_with('.')
+}
+
+/// Lists a local directory sorted by name; an empty path lists the home
+/// directory. Entries that cannot be read are skipped.
+#[command]
+pub async fn list_local_dir(path: String) -> Result<Vec<LocalEntry>, String> {
+    let dir = if path.is_empty() {
+        dirs::home_dir().ok_or("Home directory not found")?
+    } else {
+        PathBuf::from(path)
+    };
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let kind = kind_name(metadata.file_type());
+        entries.push(LocalEntry {
+            hidden: is_hidden(&name, &metadata),
+            path: entry.path().to_string_lossy().into_owned(),
+            name,
+            kind: kind.to_string(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            mode: unix_mode(&metadata),
+            target_kind: (kind == "symlink")
+                .then(|| std::fs::metadata(entry.path()).ok())
+                .flatten()
+                .map(|target| kind_name(target.file_type()).to_string()),
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Creates a local directory; fails if it already exists
+#[command]
+pub async fn create_local_dir(path: String) -> Result<(), String> {
+    std::fs::create_dir(&path).map_err(|e| e.to_string())
+}
+
+/// Renames or moves a local file or directory without replacing an existing
+/// one
+#[command]
+pub async fn rename_local(from: String, to: String) -> Result<(), String> {
+    if Path::new(&to).symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", to));
+    }
+    std::fs::rename(&from, &to).map_err(|e| e.to_string())
+}
+
+/// Moves local files and directories to the system trash so deletions from
+/// the transfer view can be undone
+#[command]
+pub async fn delete_local_to_trash(paths: Vec<String>) -> Result<(), String> {
+    trash::delete_all(&paths).map_err(|e| e.to_string())
+}
+
+/// Shows a local file or directory in the system file manager
+#[command]
+pub async fn open_in_file_manager(path: String) -> Result<(), String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("{} does not exist", path));
+    }
+    tauri_plugin_opener::reveal_item_in_dir(&path).map_err(|e| e.to_string())
+}
+
+/// Writes text to the system clipboard, e.g. after the user confirmed a
+/// remote clipboard request
+#[command]
+pub fn write_clipboard(app: AppHandle, text: String) -> Result<(), String> {
+    app.clipboard().write_text(text).map_err(|e| e.to_string())
 }
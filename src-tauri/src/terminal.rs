@@ -1,10 +1,14 @@
+use crate::events::{TerminalInput, TerminalResize};
+use crate::multiwindow::SessionRoute;
+use crate::output_index::OutputIndexer;
+use crate::ssh::Utf8Decoder;
+use crate::transport::{self, ConnectionManager, OutputChunk, SessionId, SessionListeners};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
-use tauri::{Emitter, Listener};
 use thiserror::Error;
 use tokio::sync::mpsc;
 
@@ -36,36 +40,14 @@ const BATCH_TIME_MS: u64 = 20;
 // Data Structures
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
-pub struct SessionId(String);
-
-impl From<String> for SessionId {
-    fn from(s: String) -> Self {
-        SessionId(s)
-    }
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct OutputChunk {
-    pub seq: u64,
-    pub output: String,
-    pub ts: u128,
-}
-
-impl OutputChunk {
-    fn new(seq: u64, output: String) -> Self {
-        let ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis();
-        Self { seq, output, ts }
-    }
-}
-
 pub struct TerminalInfo {
     pub handle: Option<tokio::task::JoinHandle<()>>,
     pub input_sender: mpsc::UnboundedSender<String>,
     pub stop_flag: Arc<AtomicBool>,
+    /// Window the terminal's output is sent to, with output for replay
+    pub route: SessionRoute<OutputChunk>,
+    /// Input and resize listeners, removed when the terminal is
+    pub listeners: SessionListeners,
 }
 
 #[derive(Default)]
@@ -80,8 +62,30 @@ impl TerminalManager {
         session_id: SessionId,
         cols: u16,
         rows: u16,
+        window: &str,
     ) -> Result<(), TerminalError> {
+        // Reopening under the same id replaces the old terminal
+        let reopening = self
+            .channels
+            .read()
+            .map_err(|e| TerminalError::LockPoisoned(e.to_string()))?
+            .contains_key(&session_id);
+        if reopening {
+            self.disconnect_local(&session_id)?;
+        }
+
         let channels_arc = Arc::clone(&self.channels);
+        let settings = crate::settings::current().terminal;
+        let cols = if cols == 0 {
+            settings.default_cols
+        } else {
+            cols
+        };
+        let rows = if rows == 0 {
+            settings.default_rows
+        } else {
+            rows
+        };
 
         // 1. Setup PTY
         let pty_system = native_pty_system();
@@ -96,9 +100,13 @@ impl TerminalManager {
 
         // 2. Spawn shell
         #[cfg(target_os = "windows")]
-        let shell = "powershell.exe";
+        let shell = settings
+            .local_shell
+            .unwrap_or_else(|| "powershell.exe".to_string());
         #[cfg(not(target_os = "windows"))]
-        let shell = std::env::var("SHELL").unwrap_or_else(|_| "zsh".to_string());
+        let shell = settings
+            .local_shell
+            .unwrap_or_else(|| std::env::var("SHELL").unwrap_or_else(|_| "zsh".to_string()));
 
         let mut cmd = CommandBuilder::new(shell);
         cmd.env("TERM", "xterm-256color");
@@ -113,6 +121,7 @@ impl TerminalManager {
         let (input_sender, mut input_receiver) = mpsc::unbounded_channel::<String>();
         let stop_flag = Arc::new(AtomicBool::new(false));
         let next_seq = Arc::new(AtomicU64::new(1));
+        let route = SessionRoute::new(window);
 
         let reader = pair
             .master
@@ -125,9 +134,15 @@ impl TerminalManager {
 
         // 4. Register event listeners for user input
         let master = Arc::new(Mutex::new(pair.master));
+        let mut listeners = SessionListeners::default();
         if let Some(h) = &app_handle {
-            Self::register_input_listener(h, &session_id, &input_sender);
-            Self::register_resize_listener(h, &session_id, Arc::clone(&master));
+            listeners = SessionListeners::new(h);
+            listeners.push(Self::register_input_listener(h, &session_id, &input_sender));
+            listeners.push(Self::register_resize_listener(
+                h,
+                &session_id,
+                Arc::clone(&master),
+            ));
         }
 
         // 5. Spawn I/O tasks
@@ -136,10 +151,13 @@ impl TerminalManager {
         let mut reader_clone = reader;
         let stop_flag_reader = stop_flag.clone();
         let next_seq_reader = next_seq.clone();
+        let route_reader = route.clone();
 
         // Output Task
         let output_handle = tokio::task::spawn_blocking(move || {
             let mut buffer = [0u8; TERMINAL_BUFFER_SIZE];
+            let mut decoder = Utf8Decoder::default();
+            let mut index = OutputIndexer::for_session(&session_id_clone.0);
 
             loop {
                 if stop_flag_reader.load(Ordering::SeqCst) {
@@ -149,13 +167,23 @@ impl TerminalManager {
                 match reader_clone.read(&mut buffer) {
                     Ok(0) => break, // EOF
                     Ok(n) => {
+                        let output = decoder.decode(&buffer[..n]);
+                        if output.is_empty() {
+                            continue;
+                        }
                         let seq = next_seq_reader.fetch_add(1, Ordering::SeqCst);
-                        let output = String::from_utf8_lossy(&buffer[..n]).to_string();
-                        let chunk = OutputChunk::new(seq, output);
-
-                        if let Some(h) = &app_handle_clone {
-                            let _ = h.emit(&format!("ssh-output-{}", session_id_clone.0), &chunk);
+                        if let Some(index) = &mut index {
+                            index.feed(seq, &output);
                         }
+                        let chunk = OutputChunk::new(seq, output);
+                        let size = chunk.size();
+
+                        route_reader.emit_output(
+                            app_handle_clone.as_ref(),
+                            &format!("ssh-output-{}", session_id_clone.0),
+                            &chunk,
+                            size,
+                        );
                     }
                     Err(_) => break,
                 }
@@ -187,6 +215,8 @@ impl TerminalManager {
                     handle: Some(output_handle),
                     input_sender,
                     stop_flag,
+                    route,
+                    listeners,
                 },
             );
         }
@@ -198,45 +228,39 @@ impl TerminalManager {
         app_handle: &tauri::AppHandle,
         session_id: &SessionId,
         input_sender: &mpsc::UnboundedSender<String>,
-    ) {
-        let event_name = format!("ssh-input-{}", session_id.0);
+    ) -> tauri::EventId {
         let input_tx = input_sender.clone();
 
-        app_handle.listen(&event_name, move |event: tauri::Event| {
-            #[derive(Deserialize)]
-            struct InputPayload {
-                input: String,
-            }
-            if let Ok(payload) = serde_json::from_str::<InputPayload>(event.payload()) {
+        transport::listen_session_event(
+            app_handle,
+            "ssh-input",
+            session_id,
+            move |payload: TerminalInput| {
                 let _ = input_tx.send(payload.input);
-            }
-        });
+            },
+        )
     }
 
     fn register_resize_listener(
         app_handle: &tauri::AppHandle,
         session_id: &SessionId,
         master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
-    ) {
-        let resize_event_name = format!("ssh-resize-{}", session_id.0);
-
-        app_handle.listen(&resize_event_name, move |event: tauri::Event| {
-            #[derive(Deserialize)]
-            struct ResizePayload {
-                cols: u16,
-                rows: u16,
-            }
-            if let Ok(payload) = serde_json::from_str::<ResizePayload>(event.payload()) {
+    ) -> tauri::EventId {
+        transport::listen_session_event(
+            app_handle,
+            "ssh-resize",
+            session_id,
+            move |payload: TerminalResize| {
                 if let Ok(m) = master.lock() {
                     let _ = m.resize(PtySize {
-                        rows: payload.rows,
-                        cols: payload.cols,
+                        rows: payload.rows.try_into().unwrap_or(u16::MAX),
+                        cols: payload.cols.try_into().unwrap_or(u16::MAX),
                         pixel_width: 0,
                         pixel_height: 0,
                     });
                 }
-            }
-        });
+            },
+        )
     }
 
     pub fn disconnect_local(&self, session_id: &SessionId) -> Result<(), TerminalError> {
@@ -248,20 +272,53 @@ impl TerminalManager {
                 }
             }
         }
+        crate::output_index::remove_session(&session_id.0);
         Ok(())
     }
 }
 
+impl ConnectionManager for TerminalManager {
+    fn kind(&self) -> &'static str {
+        "local"
+    }
+
+    fn session_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .channels
+            .read()
+            .map(|channels| channels.keys().map(|id| id.0.clone()).collect())
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+
+    fn session_route(&self, session_id: &SessionId) -> Option<SessionRoute<OutputChunk>> {
+        let channels = self.channels.read().ok()?;
+        channels.get(session_id).map(|info| info.route.clone())
+    }
+
+    fn disconnect(&self, session_id: &SessionId) -> Result<(), String> {
+        self.disconnect_local(session_id).map_err(|e| e.to_string())
+    }
+}
+
 #[tauri::command]
 pub async fn connect_local(
     state: tauri::State<'_, TerminalManager>,
     app_handle: tauri::AppHandle,
+    window: tauri::WebviewWindow,
     sessionId: String,
     cols: u16,
     rows: u16,
 ) -> Result<(), TerminalError> {
     state
-        .connect_local(Some(app_handle), SessionId::from(sessionId), cols, rows)
+        .connect_local(
+            Some(app_handle),
+            SessionId::from(sessionId),
+            cols,
+            rows,
+            window.label(),
+        )
         .await
 }
 
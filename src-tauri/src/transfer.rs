@@ -0,0 +1,600 @@
+use crate::ssh::{SessionHandle, SessionId, SshError, SshManager, UploadProgress};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Transfers run at the same time per session unless changed
+const DEFAULT_CONCURRENCY: usize = 1;
+
+/// Upper bound for `set_transfer_concurrency`
+const MAX_CONCURRENCY: usize = 8;
+
+/// Work carried out by a queued transfer. `preserve` carries the file mode
+/// and modification time over.
+#[derive(Debug, Clone)]
+pub enum TransferJob {
+    Upload {
+        local_path: String,
+        remote_path: String,
+        preserve: bool,
+    },
+    Download {
+        remote_path: String,
+        local_path: String,
+        preserve: bool,
+    },
+}
+
+impl TransferJob {
+    /// Local and remote path
+    fn paths(&self) -> (&str, &str) {
+        match self {
+            TransferJob::Upload {
+                local_path,
+                remote_path,
+                ..
+            }
+            | TransferJob::Download {
+                local_path,
+                remote_path,
+                ..
+            } => (local_path, remote_path),
+        }
+    }
+
+    fn direction(&self) -> &'static str {
+        match self {
+            TransferJob::Upload { .. } => "upload",
+            TransferJob::Download { .. } => "download",
+        }
+    }
+
+    /// Event progress is reported through
+    fn event(&self) -> &'static str {
+        match self {
+            TransferJob::Upload { .. } => "upload-progress",
+            TransferJob::Download { .. } => "download-progress",
+        }
+    }
+
+    /// Capitalized name used in progress messages
+    fn label(&self) -> &'static str {
+        match self {
+            TransferJob::Upload { .. } => "Upload",
+            TransferJob::Download { .. } => "Download",
+        }
+    }
+}
+
+struct QueuedTransfer {
+    task_id: String,
+    priority: i32,
+    job: TransferJob,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Per-session queue. `pending` is kept in run order.
+struct SessionQueue {
+    app_handle: AppHandle,
+    handle: SessionHandle,
+    pending: Vec<QueuedTransfer>,
+    active: HashMap<String, (TransferJob, Arc<AtomicBool>, i32)>,
+    max_concurrent: usize,
+}
+
+/// A queued or running transfer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferInfo {
+    pub task_id: String,
+    pub session_id: String,
+    /// "upload" or "download"
+    pub direction: String,
+    /// "queued" or "running"
+    pub status: String,
+    /// 1-based queue position while queued
+    pub queue_position: Option<usize>,
+    pub priority: i32,
+    pub local_path: String,
+    pub remote_path: String,
+}
+
+/// Queues SFTP transfers per session so they don't all fight for the
+/// session lock. By default transfers run one at a time; higher priority
+/// transfers start first.
+#[derive(Default, Clone)]
+pub struct TransferManager {
+    queues: Arc<Mutex<HashMap<String, SessionQueue>>>,
+}
+
+fn lock_error<T>(e: std::sync::PoisonError<T>) -> SshError {
+    SshError::LockPoisoned(e.to_string())
+}
+
+fn transfer_event(
+    task_id: &str,
+    session_id: &str,
+    status: &str,
+    message: String,
+) -> UploadProgress {
+    UploadProgress {
+        task_id: task_id.to_string(),
+        session_id: session_id.to_string(),
+        progress: 0.0,
+        uploaded_bytes: 0,
+        total_bytes: 0,
+        status: status.to_string(),
+        message,
+        speed: 0.0,
+        error: None,
+        queue_position: None,
+    }
+}
+
+impl SessionQueue {
+    /// Inserts after every transfer of the same or higher priority
+    fn insert(&mut self, transfer: QueuedTransfer) {
+        let index = self
+            .pending
+            .iter()
+            .position(|t| t.priority < transfer.priority)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(index, transfer);
+    }
+
+    /// Reports the position of every waiting transfer
+    fn emit_positions(&self, session_id: &str) {
+        for (index, transfer) in self.pending.iter().enumerate() {
+            let mut event = transfer_event(
+                &transfer.task_id,
+                session_id,
+                "queued",
+                format!("Waiting in queue (#{})", index + 1),
+            );
+            event.queue_position = Some(index + 1);
+            let _ = self.app_handle.emit(transfer.job.event(), event);
+        }
+    }
+}
+
+impl TransferManager {
+    /// Adds a transfer to the session's queue and starts it when a slot is
+    /// free.
+    pub fn enqueue(
+        &self,
+        app_handle: AppHandle,
+        session_id: &str,
+        handle: SessionHandle,
+        task_id: String,
+        job: TransferJob,
+        priority: i32,
+    ) -> Result<(), SshError> {
+        {
+            let mut queues = self.queues.lock().map_err(lock_error)?;
+            let queue = queues
+                .entry(session_id.to_string())
+                .or_insert_with(|| SessionQueue {
+                    app_handle: app_handle.clone(),
+                    handle: handle.clone(),
+                    pending: Vec::new(),
+                    active: HashMap::new(),
+                    max_concurrent: DEFAULT_CONCURRENCY,
+                });
+            // A reconnect replaces the session handle
+            queue.app_handle = app_handle;
+            queue.handle = handle;
+            if queue.active.contains_key(&task_id)
+                || queue.pending.iter().any(|t| t.task_id == task_id)
+            {
+                return Err(SshError::OperationFailed(format!(
+                    "Transfer {} is already queued",
+                    task_id
+                )));
+            }
+            queue.insert(QueuedTransfer {
+                task_id,
+                priority,
+                job,
+                cancel: Arc::new(AtomicBool::new(false)),
+            });
+        }
+        self.pump(session_id)
+    }
+
+    /// Starts queued transfers while the session has free slots
+    fn pump(&self, session_id: &str) -> Result<(), SshError> {
+        let mut queues = self.queues.lock().map_err(lock_error)?;
+        let Some(queue) = queues.get_mut(session_id) else {
+            return Ok(());
+        };
+        while queue.active.len() < queue.max_concurrent && !queue.pending.is_empty() {
+            let transfer = queue.pending.remove(0);
+            queue.active.insert(
+                transfer.task_id.clone(),
+                (
+                    transfer.job.clone(),
+                    transfer.cancel.clone(),
+                    transfer.priority,
+                ),
+            );
+            let manager = self.clone();
+            let app_handle = queue.app_handle.clone();
+            let handle = queue.handle.clone();
+            let sid = session_id.to_string();
+            std::thread::spawn(move || {
+                run_transfer(&app_handle, &handle, &sid, &transfer);
+                if let Ok(mut queues) = manager.queues.lock() {
+                    if let Some(queue) = queues.get_mut(&sid) {
+                        queue.active.remove(&transfer.task_id);
+                    }
+                }
+                let _ = manager.pump(&sid);
+            });
+        }
+        queue.emit_positions(session_id);
+        Ok(())
+    }
+
+    /// Lists the session's running and queued transfers in run order
+    pub fn list(&self, session_id: &str) -> Result<Vec<TransferInfo>, SshError> {
+        let queues = self.queues.lock().map_err(lock_error)?;
+        let Some(queue) = queues.get(session_id) else {
+            return Ok(Vec::new());
+        };
+        let info = |task_id: &str, job: &TransferJob, priority, position: Option<usize>| {
+            let (local_path, remote_path) = job.paths();
+            TransferInfo {
+                task_id: task_id.to_string(),
+                session_id: session_id.to_string(),
+                direction: job.direction().to_string(),
+                status: if position.is_some() {
+                    "queued"
+                } else {
+                    "running"
+                }
+                .to_string(),
+                queue_position: position,
+                priority,
+                local_path: local_path.to_string(),
+                remote_path: remote_path.to_string(),
+            }
+        };
+        let mut list: Vec<TransferInfo> = queue
+            .active
+            .iter()
+            .map(|(task_id, (job, _, priority))| info(task_id, job, *priority, None))
+            .collect();
+        list.extend(
+            queue
+                .pending
+                .iter()
+                .enumerate()
+                .map(|(i, t)| info(&t.task_id, &t.job, t.priority, Some(i + 1))),
+        );
+        Ok(list)
+    }
+
+    /// Lists the running and queued transfers of every session
+    pub fn list_all(&self) -> Result<Vec<TransferInfo>, SshError> {
+        let session_ids: Vec<String> = self
+            .queues
+            .lock()
+            .map_err(lock_error)?
+            .keys()
+            .cloned()
+            .collect();
+        let mut list = Vec::new();
+        for session_id in session_ids {
+            list.extend(self.list(&session_id)?);
+        }
+        Ok(list)
+    }
+
+    /// Number of transfers running across all sessions
+    pub fn running_count(&self) -> usize {
+        self.queues
+            .lock()
+            .map(|queues| queues.values().map(|q| q.active.len()).sum())
+            .unwrap_or(0)
+    }
+
+    /// Drops every queued transfer and, with `running`, cancels the ones
+    /// in progress too. Used when the app quits.
+    pub fn cancel_all(&self, running: bool) {
+        let Ok(mut queues) = self.queues.lock() else {
+            return;
+        };
+        for queue in queues.values_mut() {
+            queue.pending.clear();
+            if running {
+                for (_, cancel, _) in queue.active.values() {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    /// Moves a queued transfer to `position` (1-based)
+    pub fn reorder(
+        &self,
+        session_id: &str,
+        task_id: &str,
+        position: usize,
+    ) -> Result<(), SshError> {
+        let mut queues = self.queues.lock().map_err(lock_error)?;
+        let queue = queues
+            .get_mut(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.to_string()))?;
+        let index = queue
+            .pending
+            .iter()
+            .position(|t| t.task_id == task_id)
+            .ok_or_else(|| {
+                SshError::OperationFailed(format!("Transfer {} is not queued", task_id))
+            })?;
+        let transfer = queue.pending.remove(index);
+        let target = position.saturating_sub(1).min(queue.pending.len());
+        queue.pending.insert(target, transfer);
+        queue.emit_positions(session_id);
+        Ok(())
+    }
+
+    /// Changes a queued transfer's priority and re-sorts it
+    pub fn set_priority(
+        &self,
+        session_id: &str,
+        task_id: &str,
+        priority: i32,
+    ) -> Result<(), SshError> {
+        let mut queues = self.queues.lock().map_err(lock_error)?;
+        let queue = queues
+            .get_mut(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.to_string()))?;
+        let index = queue
+            .pending
+            .iter()
+            .position(|t| t.task_id == task_id)
+            .ok_or_else(|| {
+                SshError::OperationFailed(format!("Transfer {} is not queued", task_id))
+            })?;
+        let mut transfer = queue.pending.remove(index);
+        transfer.priority = priority;
+        queue.insert(transfer);
+        queue.emit_positions(session_id);
+        Ok(())
+    }
+
+    /// Cancels a queued or running transfer
+    pub fn cancel(&self, session_id: &str, task_id: &str) -> Result<(), SshError> {
+        let mut queues = self.queues.lock().map_err(lock_error)?;
+        let queue = queues
+            .get_mut(session_id)
+            .ok_or_else(|| SshError::SessionNotFound(session_id.to_string()))?;
+        if let Some((_, cancel, _)) = queue.active.get(task_id) {
+            // The worker reports the cancellation when it stops
+            cancel.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        let index = queue
+            .pending
+            .iter()
+            .position(|t| t.task_id == task_id)
+            .ok_or_else(|| SshError::OperationFailed(format!("Transfer {} not found", task_id)))?;
+        let transfer = queue.pending.remove(index);
+        let _ = queue.app_handle.emit(
+            transfer.job.event(),
+            transfer_event(
+                task_id,
+                session_id,
+                "cancelled",
+                format!("{} cancelled", transfer.job.label()),
+            ),
+        );
+        queue.emit_positions(session_id);
+        Ok(())
+    }
+
+    /// Sets how many transfers of the session may run at once
+    pub fn set_concurrency(&self, session_id: &str, max_concurrent: usize) -> Result<(), SshError> {
+        {
+            let mut queues = self.queues.lock().map_err(lock_error)?;
+            if let Some(queue) = queues.get_mut(session_id) {
+                queue.max_concurrent = max_concurrent.clamp(1, MAX_CONCURRENCY);
+            }
+        }
+        self.pump(session_id)
+    }
+}
+
+/// Runs one transfer and reports its progress and outcome
+fn run_transfer(
+    app_handle: &AppHandle,
+    handle: &SessionHandle,
+    session_id: &str,
+    transfer: &QueuedTransfer,
+) {
+    let job = &transfer.job;
+    let task_id = &transfer.task_id;
+    let started = Instant::now();
+
+    let on_progress = |written, total| {
+        let elapsed = started.elapsed().as_secs_f64();
+        let speed = if elapsed > 0.0 {
+            written as f64 / elapsed
+        } else {
+            0.0
+        };
+        let progress = if total > 0 {
+            (written as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        };
+        let (status, verb) = match job {
+            TransferJob::Upload { .. } => ("uploading", "Uploading"),
+            TransferJob::Download { .. } => ("downloading", "Downloading"),
+        };
+        let _ = app_handle.emit(
+            job.event(),
+            UploadProgress {
+                progress,
+                uploaded_bytes: written,
+                total_bytes: total,
+                speed,
+                ..transfer_event(
+                    task_id,
+                    session_id,
+                    status,
+                    format!("{}... ({:.1} MB/s)", verb, speed / 1024.0 / 1024.0),
+                )
+            },
+        );
+    };
+    let result = match job {
+        TransferJob::Upload {
+            local_path,
+            remote_path,
+            preserve,
+        } => crate::ssh::sftp_upload(
+            handle,
+            local_path,
+            remote_path,
+            *preserve,
+            &transfer.cancel,
+            on_progress,
+        ),
+        TransferJob::Download {
+            remote_path,
+            local_path,
+            preserve,
+        } => crate::ssh::sftp_download(
+            handle,
+            remote_path,
+            local_path,
+            *preserve,
+            &transfer.cancel,
+            on_progress,
+        ),
+    };
+
+    let event = match result {
+        Ok(total_bytes) => {
+            let elapsed = started.elapsed().as_secs_f64();
+            UploadProgress {
+                progress: 100.0,
+                uploaded_bytes: total_bytes,
+                total_bytes,
+                speed: if elapsed > 0.0 {
+                    total_bytes as f64 / elapsed
+                } else {
+                    0.0
+                },
+                ..transfer_event(
+                    task_id,
+                    session_id,
+                    "success",
+                    format!("{} completed successfully", job.label()),
+                )
+            }
+        }
+        Err(_) if transfer.cancel.load(Ordering::SeqCst) => transfer_event(
+            task_id,
+            session_id,
+            "cancelled",
+            format!("{} cancelled", job.label()),
+        ),
+        Err(e) => UploadProgress {
+            error: Some(e.to_string()),
+            ..transfer_event(
+                task_id,
+                session_id,
+                "error",
+                format!("{} failed: {}", job.label(), e),
+            )
+        },
+    };
+    let saved_id = app_handle
+        .state::<SshManager>()
+        .saved_session_id(&SessionId::from(session_id));
+    if let Some(saved_id) = saved_id {
+        if let Err(e) = crate::db::record_transfer(
+            &saved_id,
+            job.direction(),
+            &event.status,
+            event.uploaded_bytes,
+        ) {
+            tracing::warn!("{}", e);
+        }
+    }
+    if event.status != "cancelled" {
+        crate::notifications::transfer_finished(
+            app_handle,
+            job.direction(),
+            job.paths().0,
+            event.error.as_deref(),
+        );
+    }
+    let _ = app_handle.emit(job.event(), event);
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Lists the session's running and queued transfers
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn list_transfers(
+    state: tauri::State<'_, TransferManager>,
+    sessionId: String,
+) -> Result<Vec<TransferInfo>, SshError> {
+    state.list(&sessionId)
+}
+
+/// Moves a queued transfer to a new 1-based queue position
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn reorder_transfer(
+    state: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    taskId: String,
+    position: usize,
+) -> Result<(), SshError> {
+    state.reorder(&sessionId, &taskId, position)
+}
+
+/// Changes the priority of a queued transfer; higher runs first
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn set_transfer_priority(
+    state: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    taskId: String,
+    priority: i32,
+) -> Result<(), SshError> {
+    state.set_priority(&sessionId, &taskId, priority)
+}
+
+/// Cancels a queued or running transfer
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_transfer(
+    state: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    taskId: String,
+) -> Result<(), SshError> {
+    state.cancel(&sessionId, &taskId)
+}
+
+/// Sets how many transfers may run at once on a session (1 serializes them)
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn set_transfer_concurrency(
+    state: tauri::State<'_, TransferManager>,
+    sessionId: String,
+    maxConcurrent: usize,
+) -> Result<(), SshError> {
+    state.set_concurrency(&sessionId, maxConcurrent)
+}
@@ -0,0 +1,222 @@
+use crate::highlights::HighlightSpan;
+use crate::links::LinkSpan;
+use crate::multiwindow::SessionRoute;
+use crate::ssh::SshManager;
+use crate::terminal::TerminalManager;
+use base64::{engine::general_purpose, Engine as _};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::{AppHandle, EventId, Listener, Manager};
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// Newtype pattern for type-safe session identifiers, shared by every
+/// transport
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct SessionId(pub(crate) String);
+
+impl From<String> for SessionId {
+    fn from(s: String) -> Self {
+        SessionId(s)
+    }
+}
+
+impl AsRef<str> for SessionId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Represents a chunk of output data from a terminal session
+///
+/// Each chunk has a monotonically increasing sequence number
+/// to enable reliable client-side buffering and deduplication.
+///
+/// In raw mode `output` is empty and `data` carries the exact bytes read
+/// from the channel, base64-encoded.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct OutputChunk {
+    pub seq: u64,
+    pub output: String,
+    pub ts: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// URLs and file paths found in `output`, when link detection is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<LinkSpan>>,
+    /// Ranges of `output` matched by the session's highlight rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<HighlightSpan>>,
+    /// Channel stream the chunk was read from
+    pub stream: OutputStream,
+}
+
+/// Stream of a shell channel. With a PTY the server merges stderr into
+/// stdout, so "stderr" chunks only appear without one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputChunk {
+    /// Creates a new output chunk with current timestamp
+    pub(crate) fn new(seq: u64, output: String) -> Self {
+        Self {
+            seq,
+            output,
+            ts: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            data: None,
+            links: None,
+            highlights: None,
+            stream: OutputStream::Stdout,
+        }
+    }
+
+    /// Creates a raw-mode chunk carrying base64-encoded bytes
+    pub(crate) fn raw(seq: u64, bytes: &[u8]) -> Self {
+        Self {
+            data: Some(general_purpose::STANDARD.encode(bytes)),
+            ..Self::new(seq, String::new())
+        }
+    }
+
+    /// Approximate payload size used for queue accounting
+    pub(crate) fn size(&self) -> usize {
+        self.output.len() + self.data.as_ref().map_or(0, |d| d.len())
+    }
+}
+
+// ============================================================================
+// Connection Managers
+// ============================================================================
+
+/// Common interface of the managers of terminal sessions (SSH, local PTY).
+/// Window routing, the tray and shutdown go through it, so a new transport
+/// only has to implement it and be added to `managers`.
+pub trait ConnectionManager: Send + Sync {
+    /// Kind reported to the frontend, e.g. "ssh" or "local"
+    fn kind(&self) -> &'static str;
+
+    /// Ids of the open sessions, sorted
+    fn session_ids(&self) -> Vec<String>;
+
+    /// Output route of an open session
+    fn session_route(&self, session_id: &SessionId) -> Option<SessionRoute<OutputChunk>>;
+
+    /// Closes a session and releases its resources
+    fn disconnect(&self, session_id: &SessionId) -> Result<(), String>;
+
+    /// Closes every open session
+    fn disconnect_all(&self) {
+        // Collect the ids first so no lock is held while disconnecting
+        for session_id in self.session_ids() {
+            let _ = self.disconnect(&SessionId(session_id));
+        }
+    }
+
+    /// Output routes of all open sessions, by session id
+    fn session_routes(&self) -> Vec<(String, SessionRoute<OutputChunk>)> {
+        self.session_ids()
+            .into_iter()
+            .filter_map(|id| {
+                let route = self.session_route(&SessionId(id.clone()))?;
+                Some((id, route))
+            })
+            .collect()
+    }
+}
+
+/// Every connection manager of the app
+pub fn managers(app: &AppHandle) -> Vec<&dyn ConnectionManager> {
+    vec![
+        app.state::<SshManager>().inner(),
+        app.state::<TerminalManager>().inner(),
+    ]
+}
+
+/// The manager that has `session_id` open, with the session's route
+pub fn find_session<'a>(
+    app: &'a AppHandle,
+    session_id: &SessionId,
+) -> Option<(&'a dyn ConnectionManager, SessionRoute<OutputChunk>)> {
+    managers(app)
+        .into_iter()
+        .find_map(|manager| Some((manager, manager.session_route(session_id)?)))
+}
+
+/// Closes a session of any kind
+pub fn disconnect(app: &AppHandle, session_id: &SessionId) -> Result<(), String> {
+    match find_session(app, session_id) {
+        Some((manager, _)) => manager.disconnect(session_id),
+        None => Err(format!("Session not found: {}", session_id.0)),
+    }
+}
+
+// ============================================================================
+// Frontend Events
+// ============================================================================
+
+/// The frontend event listeners of a session. They are removed when this
+/// is dropped, i.e. when the session leaves its manager's registry.
+#[derive(Default)]
+pub struct SessionListeners {
+    app_handle: Option<AppHandle>,
+    ids: Vec<EventId>,
+}
+
+impl SessionListeners {
+    pub fn new(app_handle: &AppHandle) -> Self {
+        Self {
+            app_handle: Some(app_handle.clone()),
+            ids: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, id: EventId) {
+        self.ids.push(id);
+    }
+}
+
+impl Drop for SessionListeners {
+    fn drop(&mut self) {
+        if let Some(app_handle) = &self.app_handle {
+            for id in self.ids.drain(..) {
+                app_handle.unlisten(id);
+            }
+        }
+    }
+}
+
+/// Listens for a frontend event of a session and hands its parsed payload
+/// to `handler`. Payloads that do not parse are reported on the error bus.
+pub fn listen_session_event<T, F>(
+    app_handle: &AppHandle,
+    event: &str,
+    session_id: &SessionId,
+    handler: F,
+) -> EventId
+where
+    T: DeserializeOwned,
+    F: Fn(T) + Send + 'static,
+{
+    let name = format!("{}-{}", event, session_id.0);
+    let sid = session_id.0.clone();
+    let event = event.to_string();
+    app_handle.listen(&name, move |e: tauri::Event| {
+        match serde_json::from_str::<T>(e.payload()) {
+            Ok(payload) => handler(payload),
+            Err(err) => {
+                let message = format!("invalid {} payload: {}", event, err);
+                crate::error_bus::report("events", Some(&sid), message);
+            }
+        }
+    })
+}
@@ -0,0 +1,297 @@
+use crate::notifications::{CPU_THRESHOLD, DISK_THRESHOLD};
+use crate::ssh::{ActiveSession, SshManager};
+use crate::terminal::TerminalManager;
+use crate::transport::ConnectionManager;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+/// Id of the tray icon
+const TRAY_ID: &str = "main";
+
+/// How often the session list is compared with the menu
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Bytes done and total of unfinished transfers, by task id
+static TRANSFERS: Lazy<Mutex<HashMap<String, (u64, u64)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Last snapshot shown in the menu
+static SHOWN: Lazy<Mutex<Option<TraySnapshot>>> = Lazy::new(|| Mutex::new(None));
+
+/// Payload of the `tray-open-session` event: the frontend should focus the
+/// session's tab
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraySessionEvent {
+    pub session_id: String,
+    /// "ssh" or "local"
+    pub kind: String,
+}
+
+/// What the menu shows; the menu is rebuilt only when this changes
+#[derive(Debug, Clone, PartialEq)]
+struct TraySnapshot {
+    /// (id, dot and label) of SSH sessions
+    ssh: Vec<(String, String)>,
+    local: Vec<String>,
+    /// Transfer count and overall percentage
+    transfers: Option<(usize, u32)>,
+}
+
+/// Status dot of a session: green when healthy, yellow past the CPU or disk
+/// threshold, white before the first reading, red once disconnected
+fn status_dot(session: &ActiveSession) -> &'static str {
+    match &session.status {
+        _ if !session.connected => "🔴",
+        None => "⚪",
+        Some(s) if s.cpu_usage > CPU_THRESHOLD || s.disk_usage > DISK_THRESHOLD => "🟡",
+        Some(_) => "🟢",
+    }
+}
+
+fn snapshot(app: &AppHandle) -> TraySnapshot {
+    let ssh = app
+        .state::<SshManager>()
+        .active_sessions()
+        .into_iter()
+        .map(|s| {
+            (
+                s.session_id.clone(),
+                format!("{} {}", status_dot(&s), s.label),
+            )
+        })
+        .collect();
+    let local = app.state::<TerminalManager>().session_ids();
+    let transfers = TRANSFERS.lock().ok().and_then(|t| {
+        if t.is_empty() {
+            return None;
+        }
+        let (done, total) = t
+            .values()
+            .fold((0u64, 0u64), |(d, t), (done, total)| (d + done, t + total));
+        let percent = (done * 100).checked_div(total).unwrap_or(0);
+        Some((t.len(), percent as u32))
+    });
+    TraySnapshot {
+        ssh,
+        local,
+        transfers,
+    }
+}
+
+fn transfer_summary(transfers: Option<(usize, u32)>) -> String {
+    match transfers {
+        None => "No transfers".to_string(),
+        Some((1, percent)) => format!("1 transfer · {}%", percent),
+        Some((count, percent)) => format!("{} transfers · {}%", count, percent),
+    }
+}
+
+/// Builds the menu for `snapshot` and puts it on the tray icon
+fn apply_menu(app: &AppHandle, snapshot: &TraySnapshot) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let menu = Menu::new(app)?;
+    if snapshot.ssh.is_empty() && snapshot.local.is_empty() {
+        menu.append(&MenuItem::with_id(
+            app,
+            "no-sessions",
+            "No active sessions",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    let entries = snapshot
+        .ssh
+        .iter()
+        .map(|(id, label)| ("ssh", id.clone(), label.clone()))
+        .chain(
+            snapshot
+                .local
+                .iter()
+                .enumerate()
+                .map(|(i, id)| ("local", id.clone(), format!("Local terminal {}", i + 1))),
+        );
+    for (kind, id, label) in entries {
+        let open = MenuItem::with_id(
+            app,
+            format!("open:{}:{}", kind, id),
+            "Open",
+            true,
+            None::<&str>,
+        )?;
+        let disconnect = MenuItem::with_id(
+            app,
+            format!("disconnect:{}:{}", kind, id),
+            "Disconnect",
+            true,
+            None::<&str>,
+        )?;
+        menu.append(&Submenu::with_id_and_items(
+            app,
+            format!("session:{}:{}", kind, id),
+            label,
+            true,
+            &[&open, &disconnect],
+        )?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "transfers",
+        transfer_summary(snapshot.transfers),
+        false,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        "Show NexaShell",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "quick-connect",
+        "Quick Connect…",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?)?;
+
+    let active = snapshot.ssh.len() + snapshot.local.len();
+    let tooltip = match (active, snapshot.transfers) {
+        (0, None) => "NexaShell".to_string(),
+        (n, None) => format!("NexaShell · {} active", n),
+        (n, transfers) => format!("NexaShell · {} active · {}", n, transfer_summary(transfers)),
+    };
+    tray.set_menu(Some(menu))?;
+    tray.set_tooltip(Some(tooltip.as_str()))?;
+    Ok(())
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "show" => show_main_window(app),
+        "quick-connect" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = crate::quickconnect::show_quick_connect(app).await;
+            });
+        }
+        "quit" => app.exit(0),
+        _ => {
+            let mut parts = id.splitn(3, ':');
+            let (Some(action), Some(kind), Some(session_id)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return;
+            };
+            let event = TraySessionEvent {
+                session_id: session_id.to_string(),
+                kind: kind.to_string(),
+            };
+            match action {
+                "open" => {
+                    show_main_window(app);
+                    let _ = app.emit("tray-open-session", &event);
+                }
+                "disconnect" => {
+                    let _ = crate::transport::disconnect(app, &session_id.to_string().into());
+                    let _ = app.emit("tray-session-closed", &event);
+                    refresh(app, true);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Tracks transfer progress events for the aggregate indicator
+fn track_transfers(app: &AppHandle) {
+    for event in ["upload-progress", "download-progress"] {
+        app.listen(event, |event: tauri::Event| {
+            #[derive(serde::Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Progress {
+                task_id: String,
+                status: String,
+                #[serde(default)]
+                uploaded_bytes: u64,
+                #[serde(default)]
+                total_bytes: u64,
+            }
+            let Ok(progress) = serde_json::from_str::<Progress>(event.payload()) else {
+                return;
+            };
+            let Ok(mut transfers) = TRANSFERS.lock() else {
+                return;
+            };
+            match progress.status.as_str() {
+                "success" | "error" | "cancelled" => {
+                    transfers.remove(&progress.task_id);
+                }
+                _ => {
+                    transfers.insert(
+                        progress.task_id,
+                        (progress.uploaded_bytes, progress.total_bytes),
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// Rebuilds the menu when the sessions or transfers changed
+fn refresh(app: &AppHandle, force: bool) {
+    let current = snapshot(app);
+    let Ok(mut shown) = SHOWN.lock() else {
+        return;
+    };
+    if !force && shown.as_ref() == Some(&current) {
+        return;
+    }
+    match apply_menu(app, &current) {
+        Ok(()) => *shown = Some(current),
+        Err(e) => tracing::warn!("failed to update menu: {}", e),
+    }
+}
+
+/// Creates the tray icon and keeps its menu current
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("NexaShell")
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    refresh(app, true);
+    track_transfers(app);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+            refresh(&app, false);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use crate::db::Trigger;
+use crate::lines::LineAssembler;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// What a fired trigger asks the I/O task to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Write the text to the channel as-is
+    Send(String),
+    /// Write the text followed by Enter
+    Run(String),
+    /// Only notify the frontend
+    Notify(String),
+}
+
+/// Payload of the `ssh-trigger-{sessionId}` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerFired {
+    pub session_id: String,
+    pub trigger_id: String,
+    pub name: String,
+    /// "send", "run" or "notify"
+    pub action: String,
+    /// Text of the line that matched
+    pub matched: String,
+    /// The trigger's action value (notification message or sent text)
+    pub message: String,
+}
+
+#[derive(Debug)]
+struct CompiledTrigger {
+    id: String,
+    name: String,
+    regex: Regex,
+    action: TriggerAction,
+    cooldown: Duration,
+    last_fired: Option<Instant>,
+}
+
+/// A trigger match reported by `TriggerEngine::feed`
+#[derive(Debug, Clone)]
+pub struct TriggerMatch {
+    pub action: TriggerAction,
+    pub event: TriggerFired,
+}
+
+/// Matches output lines, as assembled by `LineAssembler`, against the
+/// session's triggers.
+///
+/// Complete lines are matched once. The current unterminated line is also
+/// checked so prompts such as `Are you sure (yes/no)? ` fire before Enter,
+/// but each trigger fires at most once per line and never within its
+/// cooldown.
+#[derive(Debug, Default)]
+pub struct TriggerEngine {
+    triggers: Vec<CompiledTrigger>,
+    lines: LineAssembler,
+    /// Indices of triggers already fired on the current partial line
+    fired_on_line: Vec<usize>,
+}
+
+impl TriggerEngine {
+    /// Builds an engine from stored rules. Disabled rules and rules whose
+    /// pattern no longer compiles are skipped.
+    pub fn new(rules: Vec<Trigger>) -> Self {
+        let triggers = rules
+            .into_iter()
+            .filter(|t| t.enabled)
+            .filter_map(|t| {
+                let regex = Regex::new(&t.pattern).ok()?;
+                let action = match t.action.as_str() {
+                    "send" => TriggerAction::Send(t.action_value),
+                    "run" => TriggerAction::Run(t.action_value),
+                    "notify" => TriggerAction::Notify(t.action_value),
+                    _ => return None,
+                };
+                Some(CompiledTrigger {
+                    id: t.id,
+                    name: t.name,
+                    regex,
+                    action,
+                    cooldown: Duration::from_millis(t.cooldown_ms.max(0) as u64),
+                    last_fired: None,
+                })
+            })
+            .collect();
+        Self {
+            triggers,
+            ..Default::default()
+        }
+    }
+
+    /// Feeds decoded output and returns the triggers that fired
+    pub fn feed(&mut self, session_id: &str, text: &str) -> Vec<TriggerMatch> {
+        let mut matches = Vec::new();
+        if self.triggers.is_empty() {
+            return matches;
+        }
+
+        for line in self.lines.feed(text) {
+            self.evaluate(session_id, &line, &mut matches);
+            self.fired_on_line.clear();
+        }
+        let partial = self.lines.partial();
+        if !partial.is_empty() {
+            self.evaluate(session_id, &partial, &mut matches);
+        }
+        matches
+    }
+
+    fn evaluate(&mut self, session_id: &str, line: &str, matches: &mut Vec<TriggerMatch>) {
+        for (idx, trigger) in self.triggers.iter_mut().enumerate() {
+            if self.fired_on_line.contains(&idx) {
+                continue;
+            }
+            if trigger
+                .last_fired
+                .is_some_and(|t| t.elapsed() < trigger.cooldown)
+            {
+                continue;
+            }
+            if !trigger.regex.is_match(line) {
+                continue;
+            }
+            trigger.last_fired = Some(Instant::now());
+            self.fired_on_line.push(idx);
+            let (action, message) = match &trigger.action {
+                TriggerAction::Send(v) => ("send", v),
+                TriggerAction::Run(v) => ("run", v),
+                TriggerAction::Notify(v) => ("notify", v),
+            };
+            matches.push(TriggerMatch {
+                action: trigger.action.clone(),
+                event: TriggerFired {
+                    session_id: session_id.to_string(),
+                    trigger_id: trigger.id.clone(),
+                    name: trigger.name.clone(),
+                    action: action.to_string(),
+                    matched: line.to_string(),
+                    message: message.clone(),
+                },
+            });
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::ssh::{SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Pause between checks for new connections to a tunnel
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a connection waits for data from the local client before
+/// polling the SSH channel again
+const RELAY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Open tunnels by tunnel id
+static TUNNELS: Lazy<Mutex<HashMap<String, Tunnel>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Tunnel {
+    info: TunnelInfo,
+    /// Stops the listener and every connection relayed through it
+    stop: Arc<AtomicBool>,
+    app_handle: AppHandle,
+}
+
+impl Tunnel {
+    fn close(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.app_handle.emit("tunnel-closed", &self.info);
+    }
+}
+
+/// A local port forwarded to a service on or reachable from the server.
+/// Payload of the `tunnel-opened` and `tunnel-closed` events.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelInfo {
+    pub tunnel_id: String,
+    pub session_id: String,
+    /// Port on 127.0.0.1
+    pub local_port: u16,
+    /// Host the server connects to; 127.0.0.1 for its own services
+    pub remote_host: String,
+    pub remote_port: u16,
+    /// `http://localhost:<localPort>`, for offering to open it in a browser
+    pub url: String,
+}
+
+/// Accepts local connections until the tunnel is closed. Blocking.
+fn accept_loop(
+    listener: TcpListener,
+    handle: SessionHandle,
+    info: TunnelInfo,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((client, _)) => {
+                let handle = handle.clone();
+                let (host, port) = (info.remote_host.clone(), info.remote_port);
+                let stop = stop.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = relay(&handle, client, &host, port, &stop) {
+                        tracing::warn!("{}:{}: {}", host, port, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL_INTERVAL)
+            }
+            Err(e) => {
+                tracing::error!("accept failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Copies data both ways between a local client and a new direct-tcpip
+/// channel until either side closes. The session lock is only held while
+/// polling the channel, so the terminal keeps working. Blocking.
+fn relay(
+    handle: &SessionHandle,
+    mut client: TcpStream,
+    host: &str,
+    port: u16,
+    stop: &AtomicBool,
+) -> Result<(), SshError> {
+    let mut channel = handle.blocking(|sess| {
+        sess.channel_direct_tcpip(host, port, None)
+            .map_err(|e| SshError::ChannelError(format!("Forward failed: {}", e)))
+    })?;
+    let io_error = |e: std::io::Error| SshError::OperationFailed(e.to_string());
+    client
+        .set_nonblocking(false)
+        .and_then(|_| client.set_read_timeout(Some(RELAY_POLL_INTERVAL)))
+        .map_err(io_error)?;
+
+    let mut buffer = [0u8; 16 * 1024];
+    let mut upstream = Vec::new();
+    let mut client_eof = false;
+    let mut sent_eof = false;
+    let result = loop {
+        if stop.load(Ordering::SeqCst) {
+            break Ok(());
+        }
+        if !client_eof && upstream.is_empty() {
+            match client.read(&mut buffer) {
+                Ok(0) => client_eof = true,
+                Ok(n) => upstream.extend_from_slice(&buffer[..n]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => break Err(io_error(e)),
+            }
+        } else {
+            std::thread::sleep(RELAY_POLL_INTERVAL);
+        }
+
+        // The session is in non-blocking mode whenever the lock is free
+        let mut downstream = Vec::new();
+        let polled = {
+            let _sess = handle.sess.blocking_lock();
+            let mut polled = Ok(());
+            while !upstream.is_empty() {
+                match channel.write(&upstream) {
+                    Ok(n) => {
+                        upstream.drain(..n);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        polled = Err(e);
+                        break;
+                    }
+                }
+            }
+            if client_eof && upstream.is_empty() && !sent_eof {
+                sent_eof = channel.send_eof().is_ok();
+            }
+            loop {
+                match channel.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => downstream.extend_from_slice(&buffer[..n]),
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        polled = Err(e);
+                        break;
+                    }
+                }
+            }
+            polled.map(|_| channel.eof())
+        };
+        if !downstream.is_empty() {
+            if let Err(e) = client.write_all(&downstream) {
+                break Err(io_error(e));
+            }
+        }
+        match polled {
+            Ok(true) => break Ok(()),
+            Ok(false) => {}
+            Err(e) => break Err(io_error(e)),
+        }
+    };
+
+    let _ = client.shutdown(std::net::Shutdown::Both);
+    handle.blocking(|_| {
+        let _ = channel.close();
+    });
+    result
+}
+
+/// Open tunnels, optionally only those of one session
+pub fn open_tunnels(session_id: Option<&str>) -> Vec<TunnelInfo> {
+    let Ok(tunnels) = TUNNELS.lock() else {
+        return Vec::new();
+    };
+    tunnels
+        .values()
+        .filter(|t| session_id.is_none_or(|id| t.info.session_id == id))
+        .map(|t| t.info.clone())
+        .collect()
+}
+
+/// Closes the tunnels of a session; called when it disconnects
+pub fn close_session_tunnels(session_id: &str) {
+    let closed: Vec<Tunnel> = match TUNNELS.lock() {
+        Ok(mut tunnels) => {
+            let ids: Vec<String> = tunnels
+                .values()
+                .filter(|t| t.info.session_id == session_id)
+                .map(|t| t.info.tunnel_id.clone())
+                .collect();
+            ids.iter().filter_map(|id| tunnels.remove(id)).collect()
+        }
+        Err(_) => return,
+    };
+    closed.into_iter().for_each(Tunnel::close);
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Forwards a free local port to `remotePort` on the server (or on
+/// `remoteHost` as seen from it), e.g. a service found by
+/// `list_remote_listeners`. Emits `tunnel-opened` so the UI can offer to
+/// open the returned URL. The tunnel stays open until `close_tunnel` or
+/// the session disconnects.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn open_tunnel_to_service(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    remotePort: u16,
+    remoteHost: Option<String>,
+) -> Result<TunnelInfo, SshError> {
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|l| l.set_nonblocking(true).map(|_| l))
+        .map_err(|e| SshError::OperationFailed(format!("Failed to open a local port: {}", e)))?;
+    let local_port = listener
+        .local_addr()
+        .map_err(|e| SshError::OperationFailed(e.to_string()))?
+        .port();
+
+    let info = TunnelInfo {
+        tunnel_id: uuid::Uuid::new_v4().to_string(),
+        session_id: sessionId,
+        local_port,
+        remote_host: remoteHost
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "127.0.0.1".to_string()),
+        remote_port: remotePort,
+        url: format!("http://localhost:{}", local_port),
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    TUNNELS
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(
+            info.tunnel_id.clone(),
+            Tunnel {
+                info: info.clone(),
+                stop: stop.clone(),
+                app_handle: app_handle.clone(),
+            },
+        );
+    {
+        let info = info.clone();
+        std::thread::spawn(move || accept_loop(listener, handle, info, stop));
+    }
+    let _ = app_handle.emit("tunnel-opened", &info);
+    Ok(info)
+}
+
+/// Closes a tunnel and the connections going through it
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn close_tunnel(tunnelId: String) -> Result<(), SshError> {
+    let tunnel = TUNNELS
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .remove(&tunnelId);
+    if let Some(tunnel) = tunnel {
+        tunnel.close();
+    }
+    Ok(())
+}
+
+/// Lists open tunnels, optionally only those of one session
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn list_tunnels(sessionId: Option<String>) -> Result<Vec<TunnelInfo>, SshError> {
+    Ok(open_tunnels(sessionId.as_deref()))
+}
@@ -0,0 +1,329 @@
+use crate::ssh::{SessionHandle, SessionId, SshError, SshManager};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Quiet period after the last change before files are uploaded, so an
+/// editor saving several files at once results in one batch
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Longest a change waits while other changes keep arriving
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Running watches by watch id
+static WATCHES: Lazy<Mutex<HashMap<String, ActiveWatch>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct ActiveWatch {
+    info: WatchInfo,
+    /// Dropping the watcher ends the upload thread
+    _watcher: RecommendedWatcher,
+    stop: Arc<AtomicBool>,
+}
+
+/// A running `watch_and_upload`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchInfo {
+    pub watch_id: String,
+    pub session_id: String,
+    pub local_dir: String,
+    pub remote_dir: String,
+    pub ignore_patterns: Vec<String>,
+}
+
+/// Payload of the `watch-upload` event, one per changed file
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchUploadEvent {
+    pub watch_id: String,
+    pub session_id: String,
+    /// Path relative to the watched directory, `/`-separated
+    pub path: String,
+    pub remote_path: String,
+    /// "uploading", "uploaded" or "error"
+    pub status: String,
+    pub bytes: u64,
+    pub error: Option<String>,
+}
+
+/// Builds the matcher for ignore patterns such as `node_modules`,
+/// `*.swp` or `dist/**`
+fn ignore_set(patterns: &[String]) -> Result<GlobSet, SshError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            SshError::OperationFailed(format!("Invalid ignore pattern {:?}: {}", pattern, e))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| SshError::OperationFailed(e.to_string()))
+}
+
+/// A path is ignored when a pattern matches it, one of its parent
+/// directories or the name of one of them
+fn is_ignored(ignore: &GlobSet, rel: &str) -> bool {
+    Path::new(rel)
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| ignore.is_match(p) || p.file_name().is_some_and(|n| ignore.is_match(n)))
+}
+
+struct Uploader {
+    app_handle: AppHandle,
+    handle: SessionHandle,
+    info: WatchInfo,
+    local_dir: PathBuf,
+    ignore: GlobSet,
+    stop: Arc<AtomicBool>,
+}
+
+impl Uploader {
+    /// Collects changes until the debounce window passes, then uploads
+    /// them. Runs until the watcher is dropped.
+    fn run(&self, events: Receiver<notify::Result<notify::Event>>) {
+        let mut changed = BTreeSet::new();
+        let mut first_change: Option<Instant> = None;
+        loop {
+            let wait = match first_change {
+                Some(first) => DEBOUNCE.min(MAX_DELAY.saturating_sub(first.elapsed())),
+                None => DEBOUNCE,
+            };
+            match events.recv_timeout(wait) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        changed.extend(event.paths);
+                        first_change.get_or_insert_with(Instant::now);
+                    }
+                    if first_change.is_none_or(|first| first.elapsed() < MAX_DELAY) {
+                        continue;
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("{}", e);
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            first_change = None;
+            for path in std::mem::take(&mut changed) {
+                if self.stop.load(Ordering::SeqCst) {
+                    return;
+                }
+                self.upload(&path);
+            }
+        }
+    }
+
+    fn upload(&self, path: &Path) {
+        // Deleted files and directories are left alone; new directories
+        // are created with the first file in them
+        if !path.is_file() {
+            return;
+        }
+        let Ok(rel) = path.strip_prefix(&self.local_dir) else {
+            return;
+        };
+        let rel = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if rel.is_empty() || is_ignored(&self.ignore, &rel) {
+            return;
+        }
+        let remote_path = format!("{}/{}", self.info.remote_dir.trim_end_matches('/'), rel);
+        let mut event = WatchUploadEvent {
+            watch_id: self.info.watch_id.clone(),
+            session_id: self.info.session_id.clone(),
+            path: rel,
+            remote_path: remote_path.clone(),
+            status: "uploading".to_string(),
+            bytes: 0,
+            error: None,
+        };
+        let _ = self.app_handle.emit("watch-upload", &event);
+
+        let parent = remote_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let result = if parent.is_empty() {
+            Ok(())
+        } else {
+            crate::dirsync::remote_mkdir_all(&self.handle, parent)
+        }
+        .and_then(|_| {
+            crate::ssh::sftp_upload(
+                &self.handle,
+                &path.to_string_lossy(),
+                &remote_path,
+                true,
+                &self.stop,
+                |_, _| {},
+            )
+        });
+        match result {
+            Ok(bytes) => {
+                event.status = "uploaded".to_string();
+                event.bytes = bytes;
+            }
+            Err(e) => {
+                event.status = "error".to_string();
+                event.error = Some(e.to_string());
+            }
+        }
+        let _ = self.app_handle.emit("watch-upload", &event);
+    }
+}
+
+/// Running watches, optionally only those of one session
+pub fn running_watches(session_id: Option<&str>) -> Vec<WatchInfo> {
+    let Ok(watches) = WATCHES.lock() else {
+        return Vec::new();
+    };
+    watches
+        .values()
+        .filter(|w| session_id.is_none_or(|id| w.info.session_id == id))
+        .map(|w| w.info.clone())
+        .collect()
+}
+
+/// Starts uploading changes below `local_dir`, the canonical form of
+/// `info.local_dir`, as described on `watch_and_upload`. Returns the watch
+/// id.
+pub fn start_watch(
+    app_handle: AppHandle,
+    handle: SessionHandle,
+    info: WatchInfo,
+    local_dir: PathBuf,
+) -> Result<String, SshError> {
+    let ignore = ignore_set(&info.ignore_patterns)?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| SshError::OperationFailed(format!("Failed to start watcher: {}", e)))?;
+    watcher
+        .watch(&local_dir, RecursiveMode::Recursive)
+        .map_err(|e| {
+            SshError::OperationFailed(format!("Failed to watch {}: {}", info.local_dir, e))
+        })?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let uploader = Uploader {
+        app_handle,
+        handle,
+        info: info.clone(),
+        local_dir,
+        ignore,
+        stop: stop.clone(),
+    };
+    std::thread::spawn(move || uploader.run(rx));
+
+    let watch_id = info.watch_id.clone();
+    WATCHES
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?
+        .insert(
+            watch_id.clone(),
+            ActiveWatch {
+                info,
+                _watcher: watcher,
+                stop,
+            },
+        );
+    Ok(watch_id)
+}
+
+/// Stops a watch; an upload in progress is cancelled
+pub fn stop_watch(watch_id: &str) {
+    if let Ok(mut watches) = WATCHES.lock() {
+        if let Some(watch) = watches.remove(watch_id) {
+            watch.stop.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Stops the watches of a session; called when it disconnects
+pub fn stop_session_watches(session_id: &str) {
+    if let Ok(mut watches) = WATCHES.lock() {
+        watches.retain(|_, watch| {
+            let keep = watch.info.session_id != session_id;
+            if !keep {
+                watch.stop.store(true, Ordering::SeqCst);
+            }
+            keep
+        });
+    }
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Watches `localDir` and uploads files to the same relative path under
+/// `remoteDir` as they are created or modified. Changes are batched over a
+/// short debounce window; each upload is reported through `watch-upload`
+/// events. Deletions are not propagated. Paths matching one of
+/// `ignorePatterns` (globs such as `node_modules`, `*.swp` or `dist/**`,
+/// matched against the relative path and each of its parts) are skipped.
+/// The watch runs until `stop_watch_and_upload` or the session
+/// disconnects. Returns the watch id.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn watch_and_upload(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    sessionId: String,
+    localDir: String,
+    remoteDir: String,
+    ignorePatterns: Option<Vec<String>>,
+) -> Result<String, SshError> {
+    let handle = state.session_handle(&SessionId::from(sessionId.clone()))?;
+    let local_dir = std::fs::canonicalize(&localDir)
+        .ok()
+        .filter(|p| p.is_dir())
+        .ok_or_else(|| {
+            SshError::OperationFailed(format!("Local directory not found: {}", localDir))
+        })?;
+    start_watch(
+        app_handle,
+        handle,
+        WatchInfo {
+            watch_id: uuid::Uuid::new_v4().to_string(),
+            session_id: sessionId,
+            local_dir: localDir,
+            remote_dir: remoteDir,
+            ignore_patterns: ignorePatterns.unwrap_or_default(),
+        },
+        local_dir,
+    )
+}
+
+/// Stops a `watch_and_upload`; an upload in progress is cancelled
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn stop_watch_and_upload(watchId: String) -> Result<(), SshError> {
+    let mut watches = WATCHES
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(watch) = watches.remove(&watchId) {
+        watch.stop.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Lists running watches, optionally only those of one session
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn list_watch_uploads(sessionId: Option<String>) -> Result<Vec<WatchInfo>, SshError> {
+    Ok(running_watches(sessionId.as_deref()))
+}
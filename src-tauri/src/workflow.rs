@@ -0,0 +1,444 @@
+use crate::db::{Workflow, WorkflowStep};
+use crate::ssh::{SessionHandle, SessionId, SshError, SshManager};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Timeout of command and upload steps that set none
+const DEFAULT_STEP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Timeout of wait steps that set none
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Pause between two checks of a wait step
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Output of a step sent with its progress event at most
+const MAX_STEP_OUTPUT: usize = 16 * 1024;
+
+/// A running workflow: its cancellation flag and the prompts waiting for
+/// an answer, by `(session id, step index)`
+struct Run {
+    cancel: Arc<AtomicBool>,
+    prompts: HashMap<(String, usize), mpsc::Sender<bool>>,
+}
+
+/// Running workflows by run id
+static RUNNING: Lazy<Mutex<HashMap<String, Run>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `workflow-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowProgress {
+    pub run_id: String,
+    pub workflow_id: String,
+    pub session_id: String,
+    pub step_index: usize,
+    pub step_name: String,
+    /// "running", "success", "failed", "skipped" or "cancelled"
+    pub status: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Payload of the `workflow-prompt` event; answered with
+/// `answer_workflow_prompt`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowPrompt {
+    pub run_id: String,
+    pub session_id: String,
+    pub step_index: usize,
+    pub message: String,
+    /// Seconds before an unanswered prompt fails the step
+    pub timeout_secs: u64,
+}
+
+/// Payload of the `workflow-done` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowSummary {
+    pub run_id: String,
+    pub workflow_id: String,
+    /// Sessions that completed every step
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+    pub cancelled: Vec<String>,
+    /// Error of the failed step, by failed session
+    pub errors: HashMap<String, String>,
+}
+
+fn step_timeout(step: &WorkflowStep, default: Duration) -> Duration {
+    if step.timeout_secs > 0 {
+        Duration::from_secs(step.timeout_secs)
+    } else {
+        default
+    }
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_STEP_OUTPUT {
+        let mut cut = output.len() - MAX_STEP_OUTPUT;
+        while !output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        output.replace_range(..cut, "…");
+    }
+    output
+}
+
+/// Runs one step; returns its output. Blocking.
+fn run_step(
+    app_handle: &AppHandle,
+    run_id: &str,
+    session_id: &str,
+    index: usize,
+    step: &WorkflowStep,
+    handle: &SessionHandle,
+    cancel: &AtomicBool,
+) -> Result<Option<String>, String> {
+    match step.kind.as_str() {
+        "command" => {
+            let timeout = step_timeout(step, DEFAULT_STEP_TIMEOUT);
+            let output = handle
+                .exec_interruptible(&step.command, timeout, cancel)
+                .map_err(|e| e.to_string())?;
+            if output.timed_out {
+                return Err(format!("Timed out after {}s", timeout.as_secs()));
+            }
+            if output.exit_status != Some(0) {
+                return Err(output.error_message("Command"));
+            }
+            Ok(Some(truncate_output(output.stdout)))
+        }
+        "upload" => {
+            let timeout = step_timeout(step, DEFAULT_STEP_TIMEOUT);
+            // The upload has no timeout of its own; a watchdog stops it
+            let stop = AtomicBool::new(false);
+            let done = AtomicBool::new(false);
+            let started = Instant::now();
+            let result = std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    while !done.load(Ordering::SeqCst) {
+                        if cancel.load(Ordering::SeqCst) || started.elapsed() >= timeout {
+                            stop.store(true, Ordering::SeqCst);
+                            return;
+                        }
+                        std::thread::sleep(Duration::from_millis(200));
+                    }
+                });
+                let result = crate::ssh::sftp_upload(
+                    handle,
+                    &step.local_path,
+                    &step.remote_path,
+                    crate::settings::current().transfers.preserve_attributes,
+                    &stop,
+                    |_, _| {},
+                );
+                done.store(true, Ordering::SeqCst);
+                result
+            });
+            match result {
+                Ok(bytes) => Ok(Some(format!(
+                    "Uploaded {} bytes to {}",
+                    bytes, step.remote_path
+                ))),
+                Err(_) if started.elapsed() >= timeout && !cancel.load(Ordering::SeqCst) => {
+                    Err(format!("Timed out after {}s", timeout.as_secs()))
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        "wait" => {
+            let timeout = step_timeout(step, DEFAULT_WAIT_TIMEOUT);
+            let pattern = regex::Regex::new(&step.pattern).map_err(|e| e.to_string())?;
+            let started = Instant::now();
+            loop {
+                let remaining = timeout.saturating_sub(started.elapsed());
+                let output = handle
+                    .exec_interruptible(&step.command, remaining, cancel)
+                    .map_err(|e| e.to_string())?;
+                if let Some(m) = pattern.find(&output.stdout) {
+                    return Ok(Some(m.as_str().to_string()));
+                }
+                if started.elapsed() + WAIT_POLL_INTERVAL >= timeout {
+                    return Err(format!(
+                        "Output did not match {} within {}s",
+                        step.pattern,
+                        timeout.as_secs()
+                    ));
+                }
+                std::thread::sleep(WAIT_POLL_INTERVAL);
+                if cancel.load(Ordering::SeqCst) {
+                    return Err("Workflow cancelled".to_string());
+                }
+            }
+        }
+        "prompt" => {
+            let timeout = step_timeout(step, DEFAULT_STEP_TIMEOUT);
+            let (sender, receiver) = mpsc::channel();
+            if let Some(run) = RUNNING.lock().map_err(|e| e.to_string())?.get_mut(run_id) {
+                run.prompts.insert((session_id.to_string(), index), sender);
+            }
+            let _ = app_handle.emit(
+                "workflow-prompt",
+                &WorkflowPrompt {
+                    run_id: run_id.to_string(),
+                    session_id: session_id.to_string(),
+                    step_index: index,
+                    message: step.message.clone(),
+                    timeout_secs: timeout.as_secs(),
+                },
+            );
+            let started = Instant::now();
+            let answer = loop {
+                if cancel.load(Ordering::SeqCst) {
+                    break Err("Workflow cancelled".to_string());
+                }
+                if started.elapsed() >= timeout {
+                    break Err(format!("No answer within {}s", timeout.as_secs()));
+                }
+                match receiver.recv_timeout(Duration::from_millis(250)) {
+                    Ok(answer) => break Ok(answer),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        break Err("Prompt was withdrawn".to_string())
+                    }
+                }
+            };
+            if let Some(run) = RUNNING.lock().map_err(|e| e.to_string())?.get_mut(run_id) {
+                run.prompts.remove(&(session_id.to_string(), index));
+            }
+            match answer? {
+                true => Ok(None),
+                false => Err("Declined by the user".to_string()),
+            }
+        }
+        other => Err(format!("Unknown step kind: {}", other)),
+    }
+}
+
+/// Runs every step on one session until one fails. Returns "success",
+/// "failed" with the step's error, or "cancelled". Blocking.
+fn run_session(
+    app_handle: &AppHandle,
+    run_id: &str,
+    workflow: &Workflow,
+    session_id: &str,
+    handle: &SessionHandle,
+    cancel: &AtomicBool,
+) -> (&'static str, Option<String>) {
+    let progress = |index: usize, status: &str, output: Option<String>, error: Option<String>| {
+        let _ = app_handle.emit(
+            "workflow-progress",
+            &WorkflowProgress {
+                run_id: run_id.to_string(),
+                workflow_id: workflow.id.clone(),
+                session_id: session_id.to_string(),
+                step_index: index,
+                step_name: workflow.steps[index].name.clone(),
+                status: status.to_string(),
+                output,
+                error,
+            },
+        );
+    };
+
+    for (index, step) in workflow.steps.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            for rest in index..workflow.steps.len() {
+                progress(rest, "cancelled", None, None);
+            }
+            return ("cancelled", None);
+        }
+        progress(index, "running", None, None);
+        match run_step(app_handle, run_id, session_id, index, step, handle, cancel) {
+            Ok(output) => progress(index, "success", output, None),
+            Err(_) if cancel.load(Ordering::SeqCst) => {
+                progress(index, "cancelled", None, None);
+                for rest in index + 1..workflow.steps.len() {
+                    progress(rest, "cancelled", None, None);
+                }
+                return ("cancelled", None);
+            }
+            Err(e) => {
+                progress(index, "failed", None, Some(e.clone()));
+                for rest in index + 1..workflow.steps.len() {
+                    progress(rest, "skipped", None, None);
+                }
+                let label = if step.name.is_empty() {
+                    format!("Step {}", index + 1)
+                } else {
+                    step.name.clone()
+                };
+                return ("failed", Some(format!("{}: {}", label, e)));
+            }
+        }
+    }
+    ("success", None)
+}
+
+/// Registers a run so it can be cancelled and its prompts answered
+fn register_run() -> Result<(String, Arc<AtomicBool>), String> {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let cancel = Arc::new(AtomicBool::new(false));
+    RUNNING.lock().map_err(|e| e.to_string())?.insert(
+        run_id.clone(),
+        Run {
+            cancel: cancel.clone(),
+            prompts: HashMap::new(),
+        },
+    );
+    Ok((run_id, cancel))
+}
+
+/// Runs a registered workflow run on the given sessions, then emits
+/// `workflow-done`. Blocking.
+fn execute(
+    app_handle: &AppHandle,
+    run_id: &str,
+    workflow: &Workflow,
+    targets: &[(String, SessionHandle)],
+    cancel: &AtomicBool,
+) -> WorkflowSummary {
+    let outcomes = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for (session_id, handle) in targets {
+            let outcomes = &outcomes;
+            scope.spawn(move || {
+                let (status, error) =
+                    run_session(app_handle, run_id, workflow, session_id, handle, cancel);
+                // Stopping the run also closes the other sessions' running
+                // commands
+                if status == "failed" && workflow.abort_on_failure {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+                if let Ok(mut outcomes) = outcomes.lock() {
+                    outcomes.push((session_id.clone(), status, error));
+                }
+            });
+        }
+    });
+
+    if let Ok(mut running) = RUNNING.lock() {
+        running.remove(run_id);
+    }
+    let mut summary = WorkflowSummary {
+        run_id: run_id.to_string(),
+        workflow_id: workflow.id.clone(),
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+        cancelled: Vec::new(),
+        errors: HashMap::new(),
+    };
+    for (session_id, status, error) in outcomes.into_inner().unwrap_or_default() {
+        match status {
+            "success" => summary.succeeded.push(session_id),
+            "failed" => {
+                if let Some(error) = error {
+                    summary.errors.insert(session_id.clone(), error);
+                }
+                summary.failed.push(session_id);
+            }
+            _ => summary.cancelled.push(session_id),
+        }
+    }
+    let _ = app_handle.emit("workflow-done", &summary);
+    summary
+}
+
+/// Runs a workflow on sessions opened by the caller and waits for it to
+/// finish, e.g. for scheduled runs. Blocking.
+pub fn run_blocking(
+    app_handle: &AppHandle,
+    workflow: &Workflow,
+    targets: &[(String, SessionHandle)],
+) -> Result<WorkflowSummary, String> {
+    let (run_id, cancel) = register_run()?;
+    Ok(execute(app_handle, &run_id, workflow, targets, &cancel))
+}
+
+// ============================================================================
+// Tauri Command Handlers
+// ============================================================================
+
+/// Runs a workflow on connected sessions, each on its own worker, the
+/// steps of a session in order. A failing step stops its session; with
+/// the workflow's `abort_on_failure` it also cancels the others.
+///
+/// Returns the run id at once. Steps report `workflow-progress` events,
+/// prompt steps wait for `answer_workflow_prompt` after a `workflow-prompt`
+/// event, and `workflow-done` lists the outcome per session.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn run_workflow(
+    app_handle: AppHandle,
+    state: tauri::State<'_, SshManager>,
+    workflowId: String,
+    sessionIds: Vec<String>,
+) -> Result<String, SshError> {
+    let workflow = crate::db::load_workflow(&workflowId).map_err(SshError::OperationFailed)?;
+    if workflow.steps.is_empty() {
+        return Err(SshError::OperationFailed(
+            "The workflow has no steps".to_string(),
+        ));
+    }
+    if sessionIds.is_empty() {
+        return Err(SshError::OperationFailed(
+            "No sessions selected".to_string(),
+        ));
+    }
+    let targets = sessionIds
+        .into_iter()
+        .map(|id| {
+            let handle = state.session_handle(&SessionId::from(id.clone()))?;
+            Ok((id, handle))
+        })
+        .collect::<Result<Vec<_>, SshError>>()?;
+
+    let (run_id, cancel) = register_run().map_err(SshError::OperationFailed)?;
+    let id = run_id.clone();
+    std::thread::spawn(move || execute(&app_handle, &id, &workflow, &targets, &cancel));
+    Ok(run_id)
+}
+
+/// Answers a prompt step: `proceed` continues the session's workflow,
+/// otherwise the step fails
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn answer_workflow_prompt(
+    runId: String,
+    sessionId: String,
+    stepIndex: usize,
+    proceed: bool,
+) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    let sender = running
+        .get(&runId)
+        .and_then(|run| run.prompts.get(&(sessionId, stepIndex)))
+        .ok_or_else(|| SshError::OperationFailed("The prompt is no longer open".to_string()))?;
+    let _ = sender.send(proceed);
+    Ok(())
+}
+
+/// Cancels a running workflow. Running commands and uploads are stopped
+/// and the remaining steps are reported as cancelled.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub fn cancel_workflow(runId: String) -> Result<(), SshError> {
+    let running = RUNNING
+        .lock()
+        .map_err(|e| SshError::LockPoisoned(e.to_string()))?;
+    if let Some(run) = running.get(&runId) {
+        run.cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}